@@ -0,0 +1,93 @@
+use crate::{Bcd, ProxyGeometry, ProxyMesh};
+
+type Vec3 = [f32; 3];
+
+// Vectors shorter than this are considered degenerate.
+const EPSILON: f32 = 1e-6;
+
+const IDENTITY: [Vec3; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+#[inline]
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[inline]
+fn scale(a: Vec3, s: f32) -> Vec3 {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+#[inline]
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[inline]
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// Scales `a` to unit length, unless it is degenerate.
+fn normalize(a: Vec3) -> Option<Vec3> {
+    let len = dot(a, a).sqrt();
+    (len > EPSILON).then(|| scale(a, 1.0 / len))
+}
+
+impl ProxyMesh {
+    /// Recomputes the normals of all faces from their vertices.
+    ///
+    /// Normals follow the counter-clockwise winding order of the
+    /// face corners. Degenerate faces and faces with out of bounds
+    /// vertex indices keep their current normal.
+    pub fn recompute_normals(&mut self) {
+        for face in &mut self.faces {
+            let [a, b, c] = face.face.map(|i| self.vertices.get(i as usize).copied());
+            let (Some(a), Some(b), Some(c)) = (a, b, c) else {
+                continue;
+            };
+
+            if let Some(normal) = normalize(cross(sub(b, a), sub(c, a))) {
+                face.normal = normal;
+            }
+        }
+    }
+}
+
+impl ProxyGeometry {
+    /// Turns [`ProxyGeometry::rotation`] into a proper rotation matrix
+    /// with orthogonal unit rows and a determinant of `1`.
+    ///
+    /// The first row keeps its direction and the second row is made
+    /// orthogonal to it, while the third row is derived from both.
+    /// Degenerate matrices are reset to identity.
+    pub fn orthonormalize_rotation(&mut self) {
+        let [x, y, _] = self.rotation;
+
+        let rows = normalize(x).and_then(|x| {
+            let y = normalize(sub(y, scale(x, dot(x, y))))?;
+            Some([x, y, cross(x, y)])
+        });
+
+        self.rotation = rows.unwrap_or(IDENTITY);
+    }
+}
+
+impl Bcd {
+    /// Repairs geometry for writing by orthonormalizing the rotations
+    /// of all shapes and recomputing the normals of all meshes.
+    ///
+    /// This is useful for imported or hand-edited data, where small
+    /// inaccuracies would otherwise produce incorrect collisions.
+    pub fn fix_geometry(&mut self) {
+        for collision in &mut self.collisions {
+            collision.geometry.orthonormalize_rotation();
+            if let Some(mesh) = &mut collision.mesh {
+                mesh.recompute_normals();
+            }
+        }
+    }
+}
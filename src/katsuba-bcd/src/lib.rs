@@ -2,6 +2,9 @@
 //!
 //! As the name suggests, this format describes geometric collision
 //! shapes for zones and is used for physics.
+//!
+//! All coordinates are in world units of the game engine, using a
+//! right-handed coordinate system where the Z axis points up.
 
 #![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
 #![forbid(unsafe_code)]
@@ -17,23 +20,44 @@ use katsuba_utils::{
 };
 use serde::{Deserialize, Serialize};
 
+mod geometry;
+
+#[cfg(feature = "schema")]
+mod schema;
+#[cfg(feature = "schema")]
+pub use schema::schema;
+
 bitflags! {
-    /// Attribute flags encoded in [`Geometry`] objects.
+    /// Attribute flags encoded in [`ProxyGeometry`] objects.
+    ///
+    /// In JSON, these are represented as a string of flag names
+    /// separated by `|`, e.g. `"WALKABLE | HITSCAN"`.
     #[binrw]
     #[br(map = Self::from_bits_truncate)]
     #[bw(map = Self::bits)]
     #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
     pub struct CollisionFlags: u32 {
+        /// Solid geometry of a world object.
         const OBJECT = 1 << 0;
+        /// Ground that entities can walk on.
         const WALKABLE = 1 << 1;
+        /// Geometry that is tested by hitscan rays.
         const HITSCAN = 1 << 3;
+        /// Only collides with the local player.
         const LOCAL_PLAYER = 1 << 4;
+        /// A body of water.
         const WATER = 1 << 6;
+        /// Geometry of a client-side object.
         const CLIENT_OBJECT = 1 << 7;
+        /// A volume which fires events when entered.
         const TRIGGER = 1 << 8;
+        /// A volume with fog effects.
         const FOG = 1 << 9;
+        /// A volume of goo.
         const GOO = 1 << 10;
+        /// A fishing area.
         const FISH = 1 << 11;
+        /// A volume of muck.
         const MUCK = 1 << 12;
     }
 }
@@ -41,44 +65,80 @@ bitflags! {
 /// A face used to describe mesh [`ShapeData`].
 #[binrw]
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Face {
     /// The face vector.
+    ///
+    /// Indices into [`ProxyMesh::vertices`] for the three corners
+    /// of the triangle.
     pub face: [u32; 3],
     /// The normal vector.
+    ///
+    /// A unit vector perpendicular to the face, pointing outwards.
     pub normal: [f32; 3],
 }
 
 /// Extra parameters for the encoded geometric shape.
 #[binrw]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum GeomParams {
     /// Box-shaped geometry.
     #[brw(magic = 0_u32)]
-    Box { length: f32, width: f32, depth: f32 },
+    Box {
+        /// The extent along the X axis, in world units.
+        length: f32,
+        /// The extent along the Y axis, in world units.
+        width: f32,
+        /// The extent along the Z axis, in world units.
+        depth: f32,
+    },
 
     /// Ray-shaped geometry.
     #[brw(magic = 1_u32)]
     Ray {
+        /// The position of the ray origin.
         position: f32,
+        /// The direction of the ray.
         direction: f32,
+        /// The length of the ray, in world units.
         length: f32,
     },
 
     /// Sphere-shaped geometry.
     #[brw(magic = 2_u32)]
-    Sphere { radius: f32 },
+    Sphere {
+        /// The radius of the sphere, in world units.
+        radius: f32,
+    },
 
     /// Cylinder-shaped geometry.
     #[brw(magic = 3_u32)]
-    Cylinder { radius: f32, length: f32 },
+    Cylinder {
+        /// The radius of the cylinder, in world units.
+        radius: f32,
+        /// The length of the cylinder along its Z axis, in world units.
+        length: f32,
+    },
 
     /// Tube-shaped geometry.
     #[brw(magic = 4_u32)]
-    Tube { radius: f32, length: f32 },
+    Tube {
+        /// The radius of the tube, in world units.
+        radius: f32,
+        /// The length of the tube along its Z axis, in world units.
+        length: f32,
+    },
 
     /// Plane-shaped geometry.
     #[brw(magic = 5_u32)]
-    Plane { normal: [f32; 3], distance: f32 },
+    Plane {
+        /// The unit normal vector of the plane.
+        normal: [f32; 3],
+        /// The signed distance of the plane from the origin, in
+        /// world units.
+        distance: f32,
+    },
 
     /// Mesh geometry.
     #[brw(magic = 6_u32)]
@@ -88,6 +148,7 @@ pub enum GeomParams {
 /// Representation of any geometric shape.
 #[binrw]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ProxyGeometry {
     #[br(temp)]
     #[bw(calc = name.len() as u32)]
@@ -99,12 +160,18 @@ pub struct ProxyGeometry {
     pub name: String,
 
     /// The rotation matrix of the shape.
+    ///
+    /// This is a 3x3 matrix stored in row-major order.
     pub rotation: [[f32; 3]; 3],
 
     /// The location vector of the shape.
+    ///
+    /// The X, Y and Z coordinates of the shape's origin in world units.
     pub location: [f32; 3],
 
     /// The scaling factor of the shape.
+    ///
+    /// A uniform factor applied on all axes.
     pub scale: f32,
 
     #[br(temp)]
@@ -138,6 +205,7 @@ impl ProxyGeometry {
 /// Representation of an arbitrary mesh shape.
 #[binrw]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ProxyMesh {
     #[br(temp)]
     #[bw(calc = self.vertices.len() as u32)]
@@ -148,6 +216,9 @@ pub struct ProxyMesh {
     face_count: u32,
 
     /// A dynamic list of vertices in the mesh.
+    ///
+    /// Each vertex holds X, Y and Z coordinates in world units,
+    /// relative to the origin of the shape.
     #[br(count = vertex_count)]
     pub vertices: Vec<[f32; 3]>,
 
@@ -161,6 +232,7 @@ pub struct ProxyMesh {
 /// Describes a geometric shape and the associated metadata.
 #[binrw]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Collision {
     #[br(temp)]
     #[bw(calc = self.geometry.params_type())]
@@ -182,6 +254,7 @@ pub struct Collision {
 /// Representation of a BCD file.
 #[binrw]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Bcd {
     #[br(temp)]
     #[bw(calc = self.collisions.len() as u32)]
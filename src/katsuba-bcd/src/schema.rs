@@ -0,0 +1,43 @@
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, RootSchema, Schema, SchemaObject},
+    schema_for, JsonSchema,
+};
+
+use crate::{Bcd, CollisionFlags};
+
+/// Generates a JSON Schema for the serialized representation of
+/// [`Bcd`] files.
+///
+/// Doc comments on the types are included as descriptions, which
+/// document units and axis conventions of the data.
+pub fn schema() -> RootSchema {
+    schema_for!(Bcd)
+}
+
+impl JsonSchema for CollisionFlags {
+    fn schema_name() -> String {
+        "CollisionFlags".to_owned()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        let flags = Self::all()
+            .iter_names()
+            .map(|(name, flag)| format!("{name} = {:#x}", flag.bits()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some(format!(
+                    "Collision flags as names separated by `|`. Known flags: {flags}."
+                )),
+                examples: vec!["WALKABLE | HITSCAN".into()],
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
@@ -0,0 +1,76 @@
+use katsuba_bcd::{Face, GeomParams, ProxyGeometry, ProxyMesh};
+
+fn geometry(rotation: [[f32; 3]; 3]) -> ProxyGeometry {
+    ProxyGeometry {
+        name: "shape".to_owned(),
+        rotation,
+        location: [0.0; 3],
+        scale: 1.0,
+        material: String::new(),
+        params: GeomParams::Mesh,
+    }
+}
+
+fn face(face: [u32; 3], normal: [f32; 3]) -> Face {
+    Face { face, normal }
+}
+
+fn assert_close(actual: [[f32; 3]; 3], expected: [[f32; 3]; 3]) {
+    let close = actual
+        .iter()
+        .flatten()
+        .zip(expected.iter().flatten())
+        .all(|(a, e)| (a - e).abs() < 1e-5);
+    assert!(close, "{actual:?} != {expected:?}");
+}
+
+#[test]
+fn orthonormalize_skewed_basis() {
+    let mut geom = geometry([[0.0, 3.0, 0.0], [1.0, 1.0, 0.0], [5.0, 5.0, 5.0]]);
+    geom.orthonormalize_rotation();
+
+    // The first row keeps its direction and the third row follows
+    // from the other two, so the basis stays right-handed.
+    assert_close(
+        geom.rotation,
+        [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+    );
+}
+
+#[test]
+fn orthonormalize_degenerate_basis() {
+    let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    let mut geom = geometry([[0.0; 3], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+    geom.orthonormalize_rotation();
+    assert_eq!(geom.rotation, identity);
+
+    // Parallel rows leave nothing to derive the second row from.
+    let mut geom = geometry([[1.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+    geom.orthonormalize_rotation();
+    assert_eq!(geom.rotation, identity);
+}
+
+#[test]
+fn recompute_normals() {
+    let stale = [0.5, 0.5, 0.5];
+    let mut mesh = ProxyMesh {
+        vertices: vec![
+            [0.0, 0.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0],
+            [4.0, 0.0, 0.0],
+        ],
+        faces: vec![
+            face([0, 1, 2], stale),
+            face([0, 2, 1], stale),
+            // Collinear corners span no area.
+            face([0, 1, 3], stale),
+            face([0, 1, 9], stale),
+        ],
+    };
+    mesh.recompute_normals();
+
+    let normals: Vec<_> = mesh.faces.iter().map(|f| f.normal).collect();
+    assert_eq!(normals, [[0.0, 0.0, 1.0], [0.0, 0.0, -1.0], stale, stale]);
+}
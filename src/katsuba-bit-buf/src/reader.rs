@@ -39,6 +39,9 @@ fn cold() {}
 /// buffered bits, [`Self::invalidate_and_realign_ptr`] can help.
 #[derive(Debug)]
 pub struct BitReader<'a> {
+    // Pointer to the first byte in the spanned byte view.
+    start: *const u8,
+
     // Pointer to the next byte where the bit lookahead
     // buffer will be fetched from.
     ptr: *const u8,
@@ -69,6 +72,7 @@ impl<'a> BitReader<'a> {
         // SAFETY: All pointer arithmetic in bounds or one past the end.
         unsafe {
             Self {
+                start: ptr,
                 ptr,
                 safeguard: ptr.add(len.saturating_sub(7)),
                 end: ptr.add(len),
@@ -98,6 +102,43 @@ impl<'a> BitReader<'a> {
         (self.untouched_bytes() << 3) + self.remaining as usize
     }
 
+    /// Gets the number of bits consumed from the reader so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        // SAFETY: Byte pointers are derived from the same object,
+        // with `start <= end` being an invariant.
+        let total = unsafe { self.end.offset_from(self.start) as usize };
+        (total << 3) - self.remaining_bits()
+    }
+
+    /// Moves the reader to the bit at `position` from the start of
+    /// the data, discarding any buffered bits.
+    ///
+    /// This can move backwards as well as forwards.
+    pub fn seek(&mut self, position: usize) -> io::Result<()> {
+        // SAFETY: Byte pointers are derived from the same object.
+        let total = unsafe { self.end.offset_from(self.start) as usize };
+        if position > total << 3 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "attempted to seek out of bounds",
+            ));
+        }
+
+        // SAFETY: `position` was bounds-checked above.
+        self.ptr = unsafe { self.start.add(position >> 3) };
+        self.lookahead = 0;
+        self.remaining = 0;
+
+        let bits = (position & 7) as u32;
+        if bits != 0 {
+            self.refill_bits();
+            self.consume(bits)?;
+        }
+
+        Ok(())
+    }
+
     /// Gets the bits currently buffered in the reader.
     #[inline]
     pub fn buffered_bits(&self) -> u32 {
@@ -54,3 +54,27 @@ fn read_bits_and_alignment() -> io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn seek_and_position() -> io::Result<()> {
+    let mut buf = BitReader::new(&[0xFF, 0b1010_0000, 3]);
+
+    buf.refill_bits();
+    buf.consume(12)?;
+    assert_eq!(buf.position(), 12);
+
+    buf.seek(13)?;
+    assert_eq!(buf.position(), 13);
+    buf.refill_bits();
+    assert!(matches!(buf.peek(3)?, 0b101));
+
+    buf.seek(0)?;
+    buf.refill_bits();
+    assert!(matches!(buf.peek(u8::BITS)?, 0xFF));
+
+    buf.seek(24)?;
+    assert_eq!(buf.remaining_bits(), 0);
+    assert!(buf.seek(25).is_err());
+
+    Ok(())
+}
@@ -10,10 +10,9 @@
 #![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
 #![forbid(unsafe_code)]
 
-use std::io;
+use std::io::{self, Read};
 
 use base64::{prelude::BASE64_STANDARD, Engine};
-use byteorder::{ReadBytesExt, LE};
 use katsuba_utils::thiserror::{self, Error};
 use rsa::{
     pkcs1::DecodeRsaPrivateKey,
@@ -35,6 +34,26 @@ pub enum Error {
     /// Failed to decrypt the ClientSig chunks.
     #[error("{0}")]
     Rsa(#[from] rsa::Error),
+
+    /// A chunk or its size prefix was cut off by the end of data.
+    #[error("chunk {chunk} at offset {offset} is truncated")]
+    Truncated {
+        /// The index of the chunk.
+        chunk: usize,
+        /// The byte offset of the chunk's size prefix.
+        offset: u64,
+    },
+
+    /// A single chunk failed to decrypt.
+    #[error("failed to decrypt chunk {chunk} at offset {offset}: {source}")]
+    Chunk {
+        /// The index of the chunk.
+        chunk: usize,
+        /// The byte offset of the chunk's size prefix.
+        offset: u64,
+        /// The underlying decryption error.
+        source: rsa::Error,
+    },
 }
 
 /// A private key representation which implements cryptographic operations in
@@ -63,27 +82,153 @@ impl PrivateKey {
 
     /// Decrypts the contents of an encrypted `ClientSig.bin` file and returns
     /// a byte vector containing the plaintext data.
-    pub fn decrypt_sig(&self, mut data: &[u8]) -> Result<Vec<u8>, Error> {
+    pub fn decrypt_sig(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
         let mut out = Vec::with_capacity(data.len());
+        for chunk in self.decrypt_chunks(data) {
+            out.append(&mut chunk?);
+        }
+
+        Ok(out)
+    }
 
-        while !data.is_empty() {
-            // Read the chunk size and make sure we have the data.
-            let chunk_size = data.read_u32::<LE>()? as usize;
-            if data.len() < chunk_size {
-                return Err(Error::Io(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "not enough chunks in ClientSig",
-                )));
+    /// Decrypts an encrypted `ClientSig.bin` stream chunk by chunk.
+    ///
+    /// The returned iterator yields the plaintext of every chunk and
+    /// stops after the first error.
+    pub fn decrypt_chunks<R: Read>(&self, reader: R) -> DecryptChunks<'_, R> {
+        DecryptChunks {
+            key: self,
+            reader,
+            chunk: 0,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Wraps an encrypted `ClientSig.bin` stream in a [`Read`]er which
+    /// produces the plaintext data.
+    ///
+    /// Errors are reported as [`io::ErrorKind::InvalidData`] with the
+    /// underlying [`Error`] as their inner error.
+    pub fn decrypt_reader<R: Read>(&self, reader: R) -> DecryptReader<'_, R> {
+        DecryptReader {
+            chunks: self.decrypt_chunks(reader),
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+/// An iterator over the decrypted chunks of a ClientSig stream.
+///
+/// Created by [`PrivateKey::decrypt_chunks`].
+pub struct DecryptChunks<'a, R> {
+    key: &'a PrivateKey,
+    reader: R,
+    chunk: usize,
+    offset: u64,
+    done: bool,
+}
+
+impl<R: Read> DecryptChunks<'_, R> {
+    // Fills `buf` completely and returns `false` on a clean EOF
+    // before the first byte.
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, Error> {
+        let mut read = 0;
+        while read < buf.len() {
+            match self.reader.read(&mut buf[read..]) {
+                Ok(0) if read == 0 => return Ok(false),
+                Ok(0) => return Err(self.truncated()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
             }
+        }
 
-            // Decrypt the next chunk and append it to the output buffer.
-            let (chunk, remainder) = data.split_at(chunk_size);
-            let mut decrypted_chunk = self.0.decrypt(Oaep::new::<Sha1>(), chunk)?;
+        Ok(true)
+    }
 
-            out.append(&mut decrypted_chunk);
-            data = remainder;
+    fn truncated(&self) -> Error {
+        Error::Truncated {
+            chunk: self.chunk,
+            offset: self.offset,
         }
+    }
 
-        Ok(out)
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        let mut size = [0; 4];
+        if !self.read_exact_or_eof(&mut size)? {
+            return Ok(None);
+        }
+
+        let size = u32::from_le_bytes(size);
+        let mut chunk = Vec::new();
+        self.reader
+            .by_ref()
+            .take(size as u64)
+            .read_to_end(&mut chunk)?;
+        if chunk.len() != size as usize {
+            return Err(self.truncated());
+        }
+
+        let plaintext = self
+            .key
+            .0
+            .decrypt(Oaep::new::<Sha1>(), &chunk)
+            .map_err(|source| Error::Chunk {
+                chunk: self.chunk,
+                offset: self.offset,
+                source,
+            })?;
+
+        self.chunk += 1;
+        self.offset += 4 + size as u64;
+
+        Ok(Some(plaintext))
+    }
+}
+
+impl<R: Read> Iterator for DecryptChunks<'_, R> {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let res = self.next_chunk().transpose();
+        self.done = !matches!(res, Some(Ok(..)));
+        res
+    }
+}
+
+/// A [`Read`]er producing the plaintext of a ClientSig stream.
+///
+/// Created by [`PrivateKey::decrypt_reader`].
+pub struct DecryptReader<'a, R> {
+    chunks: DecryptChunks<'a, R>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> Read for DecryptReader<'_, R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            match self.chunks.next() {
+                Some(Ok(chunk)) => {
+                    self.buf = chunk;
+                    self.pos = 0;
+                }
+                Some(Err(Error::Io(e))) => return Err(e),
+                Some(Err(e)) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+                None => return Ok(0),
+            }
+        }
+
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+
+        Ok(n)
     }
 }
@@ -1,4 +1,10 @@
-use std::{env, io, option::IntoIter as OptionIter, path::PathBuf, thread};
+use std::{
+    env, fmt, io,
+    option::IntoIter as OptionIter,
+    path::{Path, PathBuf},
+    thread,
+    time::SystemTime,
+};
 
 use thiserror::Error;
 
@@ -31,6 +37,28 @@ fn available_threads() -> Result<usize, BadConfiguration> {
     }
 }
 
+/// Gets the number of worker threads executors are configured for.
+///
+/// This is taken from the `KATSUBA_WORKER_THREADS` environment
+/// variable and falls back to [`thread::available_parallelism`].
+/// Other parallel work may use it to respect the same limit.
+pub fn worker_threads() -> Result<usize, BadConfiguration> {
+    available_threads()
+}
+
+// The boxed closure behind an [`Inspector`].
+type InspectFn = Box<dyn FnOnce(&Path, &[u8]) + Send>;
+
+/// A callback which observes the contents of a file on the worker
+/// thread after it was successfully written.
+pub struct Inspector(InspectFn);
+
+impl fmt::Debug for Inspector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inspector").finish_non_exhaustive()
+    }
+}
+
 /// A task to carry out inside the executor.
 ///
 /// Tasks are constructed by the user and dispatched to the
@@ -44,6 +72,8 @@ pub struct Task {
     pub kind: TaskKind,
     /// The outcome of the operation, set after completion.
     pub result: io::Result<()>,
+
+    inspector: Option<Inspector>,
 }
 
 /// Types of I/O to process on the worker threads.
@@ -52,10 +82,12 @@ pub enum TaskKind {
     /// Creates a new file at the given path with specified contents.
     ///
     /// Optionally, on UNIX platforms, a file mode choice may be
-    /// respected by the platform.
+    /// respected by the platform. When a modification time is
+    /// given, it is applied after the contents were written.
     CreateFile {
         contents: Buffer<'static>,
         mode: u32,
+        mtime: Option<SystemTime>,
     },
 
     /// Creates a directory from the given path.
@@ -69,8 +101,13 @@ impl Task {
     pub fn create_file(path: PathBuf, contents: Buffer<'static>, mode: u32) -> Self {
         Self {
             path,
-            kind: TaskKind::CreateFile { contents, mode },
+            kind: TaskKind::CreateFile {
+                contents,
+                mode,
+                mtime: None,
+            },
             result: Ok(()),
+            inspector: None,
         }
     }
 
@@ -80,19 +117,64 @@ impl Task {
             path,
             kind: TaskKind::CreateDir,
             result: Ok(()),
+            inspector: None,
+        }
+    }
+
+    /// Sets the modification time of the file created by this task.
+    ///
+    /// This has no effect on tasks which do not create files.
+    pub fn with_mtime(mut self, time: SystemTime) -> Self {
+        if let TaskKind::CreateFile { mtime, .. } = &mut self.kind {
+            *mtime = Some(time);
+        }
+        self
+    }
+
+    /// Attaches a callback to a file creation task which gets to see
+    /// the written contents.
+    ///
+    /// This is useful for computing data like content hashes on the
+    /// worker threads. The callback is not invoked when the task fails
+    /// or when it does not create a file.
+    pub fn inspect_with<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&Path, &[u8]) + Send + 'static,
+    {
+        self.inspector = Some(Inspector(Box::new(f)));
+        self
+    }
+
+    // The number of bytes of memory owned by the task. Borrowed
+    // buffers are not accounted for since they are kept alive by the
+    // caller anyway.
+    pub(super) fn memory_size(&self) -> usize {
+        match &self.kind {
+            TaskKind::CreateFile { contents, .. } if !contents.is_borrowed() => contents.len(),
+            _ => 0,
         }
     }
 
     pub(super) fn process(&mut self) {
         match &mut self.kind {
-            TaskKind::CreateFile { contents, mode } => {
-                self.result = r#impl::write_file(&self.path, contents, *mode);
+            TaskKind::CreateFile {
+                contents,
+                mode,
+                mtime,
+            } => {
+                self.result = r#impl::write_file(&self.path, contents, *mode, *mtime);
+
+                if let (Ok(()), Some(inspector)) = (&self.result, self.inspector.take()) {
+                    (inspector.0)(&self.path, contents);
+                }
             }
 
             TaskKind::CreateDir => {
                 self.result = r#impl::create_dir(&self.path);
             }
         }
+
+        crate::metrics::record(self);
     }
 }
 
@@ -128,6 +210,22 @@ impl Executor {
         }
     }
 
+    /// Limits the total size of file contents held by pending tasks
+    /// to `budget` bytes.
+    ///
+    /// When the budget is exhausted, [`Executor::dispatch`] waits for
+    /// running tasks to release their buffers before it submits more.
+    /// A task exceeding the budget on its own is still executed once
+    /// no other tasks are pending.
+    ///
+    /// This only affects the threaded executor, which holds buffers
+    /// for many tasks at once. [`None`] disables the limit.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) {
+        if let Self::Threaded(t) = self {
+            t.set_memory_budget(budget);
+        }
+    }
+
     /// Requests an in-memory buffer for I/O from the executor.
     ///
     /// `f` takes a vector reference with capacity for at least `size`
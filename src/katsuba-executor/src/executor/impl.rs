@@ -2,13 +2,20 @@ use std::{
     fs,
     io::{self, Write},
     path::Path,
+    time::SystemTime,
 };
 
 /// Creates a new file in the filesystem.
 ///
 /// The file mode may be optionally respected on UNIX platforms,
-/// but is ignored everywhere else.
-pub fn write_file(path: &Path, contents: &[u8], _mode: u32) -> io::Result<()> {
+/// but is ignored everywhere else. The modification time is set
+/// after writing the contents, when given.
+pub fn write_file(
+    path: &Path,
+    contents: &[u8],
+    _mode: u32,
+    mtime: Option<SystemTime>,
+) -> io::Result<()> {
     let mut opts = fs::OpenOptions::new();
 
     #[cfg(unix)]
@@ -18,7 +25,13 @@ pub fn write_file(path: &Path, contents: &[u8], _mode: u32) -> io::Result<()> {
     }
 
     let mut file = opts.write(true).create(true).truncate(true).open(path)?;
-    file.write_all(contents)
+    file.write_all(contents)?;
+
+    if let Some(mtime) = mtime {
+        file.set_modified(mtime)?;
+    }
+
+    Ok(())
 }
 
 /// Creates a new directory in the filesystem.
@@ -1,6 +1,9 @@
 use std::{
     io,
-    sync::{mpsc, Arc},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
 };
 
 use enum_map::{enum_map, Enum, EnumMap};
@@ -69,6 +72,11 @@ pub struct Threaded {
     tx: mpsc::Sender<Notification>,
     rx: mpsc::Receiver<Notification>,
     memory_buckets: EnumMap<BucketSize, Bucket>,
+
+    // The number of bytes held by tasks which did not complete yet,
+    // and an optional upper bound for it.
+    in_flight: Arc<AtomicUsize>,
+    memory_budget: Option<usize>,
 }
 
 impl Threaded {
@@ -101,9 +109,26 @@ impl Threaded {
             tx,
             rx,
             memory_buckets,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            memory_budget: None,
         }
     }
 
+    pub(super) fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.memory_budget = budget;
+    }
+
+    // Whether a task holding `size` bytes may be executed now.
+    fn has_capacity(&self, size: usize) -> bool {
+        if self.pool.queued_count() >= QUEUE_THRESHOLD {
+            return false;
+        }
+
+        let in_flight = self.in_flight.load(Ordering::Acquire);
+        self.memory_budget
+            .is_none_or(|budget| in_flight == 0 || in_flight + size <= budget)
+    }
+
     fn find_bucket(&self, size: usize) -> BucketSize {
         let mut bucket_size = BucketSize::FourK;
         for (next_bucket_size, bucket) in &self.memory_buckets {
@@ -136,10 +161,19 @@ impl Threaded {
     }
 
     pub(super) fn execute(&self, mut task: Task) {
+        let size = task.memory_size();
+        self.in_flight.fetch_add(size, Ordering::AcqRel);
+
         let tx = self.tx.clone();
+        let in_flight = self.in_flight.clone();
         self.pool.execute(move || {
             task.process();
-            let _ = tx.send(Notification::Done(task.result));
+
+            // Release the task's buffer before we account for it.
+            let Task { result, .. } = task;
+            in_flight.fetch_sub(size, Ordering::AcqRel);
+
+            let _ = tx.send(Notification::Done(result));
         });
     }
 
@@ -175,7 +209,9 @@ impl Iterator for SubmitIterator<'_> {
     type Item = io::Result<()>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.threaded.pool.queued_count() < QUEUE_THRESHOLD {
+        let size = self.task.as_ref().map_or(0, Task::memory_size);
+
+        if self.threaded.has_capacity(size) {
             if let Some(t) = self.task.take() {
                 self.threaded.execute(t);
             }
@@ -187,7 +223,7 @@ impl Iterator for SubmitIterator<'_> {
                     return Some(t);
                 }
 
-                if self.threaded.pool.queued_count() < QUEUE_THRESHOLD {
+                if self.threaded.has_capacity(size) {
                     if let Some(t) = self.task.take() {
                         self.threaded.execute(t);
                     }
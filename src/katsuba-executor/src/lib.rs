@@ -29,3 +29,6 @@ pub use executor::*;
 
 mod memory;
 pub use memory::Buffer;
+
+mod metrics;
+pub use metrics::Metrics;
@@ -48,6 +48,12 @@ impl<'a> Buffer<'a> {
         Self(BufferInner::Cow(Cow::Owned(buf)))
     }
 
+    /// Whether the buffer borrows memory it does not own.
+    #[inline]
+    pub(crate) fn is_borrowed(&self) -> bool {
+        matches!(self.0, BufferInner::Cow(Cow::Borrowed(..)))
+    }
+
     /// Creates a buffer from an existing [`PoolRef`].
     #[inline]
     pub(crate) fn pooled(pr: PoolRef) -> Self {
@@ -0,0 +1,57 @@
+//! Process-wide counters for the work done by executors.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{Task, TaskKind};
+
+static FILES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+static BYTES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+static DIRS_CREATED: AtomicU64 = AtomicU64::new(0);
+static TASKS_FAILED: AtomicU64 = AtomicU64::new(0);
+
+/// A snapshot of the work done by all executors in the process.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// The number of files that were written.
+    pub files_written: u64,
+    /// The total number of bytes written to files.
+    pub bytes_written: u64,
+    /// The number of directory creation tasks that completed.
+    pub dirs_created: u64,
+    /// The number of tasks which failed.
+    pub tasks_failed: u64,
+}
+
+impl Metrics {
+    /// Takes a snapshot of the current counter values.
+    ///
+    /// Tasks still in flight on worker threads are not accounted for
+    /// until they complete.
+    pub fn snapshot() -> Self {
+        Self {
+            files_written: FILES_WRITTEN.load(Ordering::Relaxed),
+            bytes_written: BYTES_WRITTEN.load(Ordering::Relaxed),
+            dirs_created: DIRS_CREATED.load(Ordering::Relaxed),
+            tasks_failed: TASKS_FAILED.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Accounts for a processed task in the global counters.
+pub(crate) fn record(task: &Task) {
+    if task.result.is_err() {
+        TASKS_FAILED.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    match &task.kind {
+        TaskKind::CreateFile { contents, .. } => {
+            FILES_WRITTEN.fetch_add(1, Ordering::Relaxed);
+            BYTES_WRITTEN.fetch_add(contents.len() as u64, Ordering::Relaxed);
+        }
+
+        TaskKind::CreateDir => {
+            DIRS_CREATED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Crate for parsing language table (`.lang`) files.
+//!
+//! Language tables map string keys to localized text and are shipped
+//! in the `Locale` directory of the game's archives.
+//!
+//! # Format
+//!
+//! Tables are UTF-16LE text with an optional byte order mark and
+//! `\r\n` line endings. The first line is a header which starts with
+//! the table name, optionally followed by a `:` and further data.
+//!
+//! Every entry is then encoded in three lines: the key, a comment for
+//! translators, and the localized text. Other files refer to entries
+//! by their key prefixed with the table name, as in `Table_Key`.
+
+#![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
+#![forbid(unsafe_code)]
+
+use katsuba_utils::thiserror::{self, Error};
+use serde::{Deserialize, Serialize};
+
+// The byte order mark at the start of UTF-16LE text.
+const BOM: char = '\u{FEFF}';
+
+/// Errors that may occur when parsing language tables.
+#[derive(Debug, Error)]
+pub enum LangError {
+    /// The data is not valid UTF-16LE text.
+    #[error("language table is not valid UTF-16LE")]
+    Encoding,
+
+    /// The table has no header line naming it.
+    #[error("language table is missing its header")]
+    MissingHeader,
+
+    /// The last entry of the table is cut off.
+    #[error("entry '{0}' is truncated")]
+    Truncated(String),
+}
+
+/// A single entry in a [`LangTable`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LangEntry {
+    /// The key of the entry, without the table name.
+    pub key: String,
+    /// A comment describing the entry, often empty.
+    pub comment: String,
+    /// The localized text.
+    pub value: String,
+}
+
+/// A parsed language table.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LangTable {
+    /// The name of the table.
+    pub name: String,
+    /// The entries of the table, in file order.
+    pub entries: Vec<LangEntry>,
+}
+
+impl LangTable {
+    /// Parses a language table from the raw bytes of a file.
+    pub fn parse(data: &[u8]) -> Result<Self, LangError> {
+        if !data.len().is_multiple_of(2) {
+            return Err(LangError::Encoding);
+        }
+
+        let units = data
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]));
+        let text: String = char::decode_utf16(units)
+            .collect::<Result<_, _>>()
+            .map_err(|_| LangError::Encoding)?;
+
+        Self::parse_str(text.strip_prefix(BOM).unwrap_or(&text))
+    }
+
+    /// Parses a language table from already decoded text.
+    pub fn parse_str(text: &str) -> Result<Self, LangError> {
+        let mut lines = text.split("\r\n");
+
+        let header = lines.next().filter(|h| !h.is_empty());
+        let name = header
+            .and_then(|h| h.split(':').next())
+            .ok_or(LangError::MissingHeader)?
+            .to_owned();
+
+        let mut entries = Vec::new();
+        while let Some(key) = lines.next() {
+            // Tables usually end with a line break.
+            if key.is_empty() && lines.clone().all(str::is_empty) {
+                break;
+            }
+
+            let (Some(comment), Some(value)) = (lines.next(), lines.next()) else {
+                return Err(LangError::Truncated(key.to_owned()));
+            };
+
+            entries.push(LangEntry {
+                key: key.to_owned(),
+                comment: comment.to_owned(),
+                value: value.to_owned(),
+            });
+        }
+
+        Ok(Self { name, entries })
+    }
+
+    /// Gets the key under which other files refer to `entry`.
+    pub fn qualified_key(&self, entry: &LangEntry) -> String {
+        format!("{}_{}", self.name, entry.key)
+    }
+
+    /// Iterates over `(qualified key, text)` pairs of all entries.
+    pub fn iter_qualified(&self) -> impl Iterator<Item = (String, &str)> + '_ {
+        self.entries
+            .iter()
+            .map(|e| (self.qualified_key(e), e.value.as_str()))
+    }
+}
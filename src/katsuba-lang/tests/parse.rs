@@ -0,0 +1,45 @@
+use katsuba_lang::{LangError, LangTable};
+
+fn encode(text: &str) -> Vec<u8> {
+    "\u{FEFF}"
+        .encode_utf16()
+        .chain(text.encode_utf16())
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+#[test]
+fn parse_table() {
+    let data = encode("Spells:1\r\nFire\r\n\r\nFireball\r\nIce\r\nshort\r\nFrost\r\n");
+    let table = LangTable::parse(&data).unwrap();
+
+    assert_eq!(table.name, "Spells");
+    assert_eq!(table.entries.len(), 2);
+    assert_eq!(table.entries[1].comment, "short");
+
+    let pairs: Vec<_> = table.iter_qualified().collect();
+    assert_eq!(
+        pairs,
+        [
+            ("Spells_Fire".to_owned(), "Fireball"),
+            ("Spells_Ice".to_owned(), "Frost")
+        ]
+    );
+}
+
+#[test]
+fn truncated_entry() {
+    let data = encode("Spells\r\nFire\r\n");
+    assert!(matches!(
+        LangTable::parse(&data),
+        Err(LangError::Truncated(key)) if key == "Fire"
+    ));
+}
+
+#[test]
+fn invalid_encoding() {
+    assert!(matches!(
+        LangTable::parse(&[0x00, 0xD8, 0x41]),
+        Err(LangError::Encoding)
+    ));
+}
@@ -6,6 +6,8 @@
 #![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
 #![forbid(unsafe_code)]
 
+use std::collections::HashSet;
+
 use katsuba_utils::{
     binrw::{
         self, binrw,
@@ -13,9 +15,40 @@ use katsuba_utils::{
         BinReaderExt, BinResult, BinWriterExt,
     },
     binrw_ext::{read_string_list, write_string_list},
+    thiserror::{self, Error},
 };
 use serde::{Deserialize, Serialize};
 
+/// Errors found when validating navigation graphs.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ValidationError {
+    /// Multiple nodes share the same identifier.
+    #[error("node identifier {0} is used more than once")]
+    DuplicateId(u16),
+
+    /// A link references a node identifier which does not exist.
+    #[error("link {link} references unknown node {id}")]
+    DanglingLink {
+        /// The index of the link in [`NavigationGraph::links`].
+        link: usize,
+        /// The unknown node identifier.
+        id: u16,
+    },
+
+    /// The stored last identifier is lower than a node identifier.
+    #[error("last identifier {last_id} is lower than node identifier {id}")]
+    LastId {
+        /// The stored [`NavigationGraph::last_id`].
+        last_id: u16,
+        /// The highest node identifier in the graph.
+        id: u16,
+    },
+
+    /// A node has no corresponding zone name.
+    #[error("node {0} has no zone name")]
+    MissingZoneName(u16),
+}
+
 /// A navigation node in the zone.
 #[binrw]
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -41,13 +74,11 @@ pub struct NavigationLink {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct NavigationGraph {
     #[br(temp)]
-    #[bw(calc = self.nodes.iter().map(|n| n.id).max().unwrap_or(0))]
-    #[serde(skip)]
-    _last_id: u16,
+    #[bw(calc = self.last_id.unwrap_or_else(|| self.max_id()))]
+    raw_last_id: u16,
 
     #[br(temp)]
     #[bw(calc = self.nodes.len() as u32)]
-    #[serde(skip)]
     node_count: u32,
 
     /// The navigation nodes, representing the edges of the graph.
@@ -62,9 +93,51 @@ pub struct NavigationGraph {
     /// the graph.
     #[br(count = link_count)]
     pub links: Vec<NavigationLink>,
+
+    /// The last node identifier that was handed out.
+    ///
+    /// This is preserved from parsed files since it may be higher
+    /// than the identifiers still in use. When [`None`], the highest
+    /// node identifier is written instead.
+    #[br(calc = Some(raw_last_id))]
+    #[bw(ignore)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_id: Option<u16>,
 }
 
 impl NavigationGraph {
+    /// Gets the highest node identifier in the graph.
+    pub fn max_id(&self) -> u16 {
+        self.nodes.iter().map(|n| n.id).max().unwrap_or(0)
+    }
+
+    /// Checks the graph for consistency before it is written.
+    ///
+    /// Node identifiers must be unique and all links must reference
+    /// existing nodes.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut ids = HashSet::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            if !ids.insert(node.id) {
+                return Err(ValidationError::DuplicateId(node.id));
+            }
+        }
+
+        for (link, l) in self.links.iter().enumerate() {
+            for id in [l.first, l.second] {
+                if !ids.contains(&id) {
+                    return Err(ValidationError::DanglingLink { link, id });
+                }
+            }
+        }
+
+        let id = self.max_id();
+        match self.last_id {
+            Some(last_id) if last_id < id => Err(ValidationError::LastId { last_id, id }),
+            _ => Ok(()),
+        }
+    }
+
     /// Attempts to parse a NAV graph from a given [`Read`]er.
     pub fn parse<R: Read + Seek>(mut reader: R) -> BinResult<Self> {
         reader.read_le().map_err(Into::into)
@@ -96,6 +169,24 @@ pub struct ZoneNavigationGraph {
 }
 
 impl ZoneNavigationGraph {
+    /// Checks the graph for consistency before it is written.
+    ///
+    /// In addition to [`NavigationGraph::validate`], every node must
+    /// have a zone name.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.graph.validate()?;
+
+        match self
+            .graph
+            .nodes
+            .iter()
+            .find(|n| n.id as usize >= self.zone_names.len())
+        {
+            Some(n) => Err(ValidationError::MissingZoneName(n.id)),
+            None => Ok(()),
+        }
+    }
+
     /// Attempts to parse a zonenav graph from a given [`Read`]er.
     pub fn parse<R: Read + Seek>(mut reader: R) -> BinResult<Self> {
         reader.read_le().map_err(Into::into)
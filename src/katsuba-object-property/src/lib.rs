@@ -12,5 +12,7 @@
 
 pub mod serde;
 
+pub mod typed;
+
 pub mod value;
 pub use value::Value;
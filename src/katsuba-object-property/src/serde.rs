@@ -1,31 +1,60 @@
 //! Serialization support for ObjectProperty values.
 
-use std::{io, sync::Arc};
+use std::{collections::HashSet, io, sync::Arc};
 
 use bitflags::bitflags;
 use katsuba_types::{PropertyFlags, TypeList};
+
+use crate::value::Names;
 use katsuba_utils::{
-    libdeflater::{DecompressionError, Decompressor},
+    libdeflater::{CompressionError, Compressor, DecompressionError, Decompressor},
     thiserror::{self, Error},
 };
 
 mod de;
 
+mod diagnostic;
+use diagnostic::Diagnostics;
+pub use diagnostic::{error_placeholder, Diagnostic, Span};
+
 mod enum_variant;
 
+mod events;
+pub use events::*;
+
+mod leaf;
+pub use leaf::{LeafReader, LeafTypes, LeafWriter};
+
+mod locale;
+pub use locale::LocaleProvider;
+
 #[cfg(feature = "option-guessing")]
 mod guess;
+#[cfg(feature = "option-guessing")]
+pub use guess::{Confidence, GuessConfidence};
 
 mod object;
 
+mod pool;
+use pool::Pool;
+
 mod property;
 
+mod scan;
+pub use scan::*;
+
+mod ser;
+
 mod simple_data;
 
+#[cfg(feature = "trace")]
+pub mod trace;
+
 mod type_tag;
 pub use type_tag::*;
 
 mod utils;
+pub use utils::{read_bits, write_bits};
 
 /// Magic header for persistent object state shipped with the client.
 pub const BIND_MAGIC: &[u8] = b"BINd";
@@ -41,6 +70,10 @@ pub enum Error {
     #[error("{0}")]
     Decompress(#[from] DecompressionError),
 
+    /// Failed to compress a zlib object stream.
+    #[error("{0}")]
+    Compress(#[from] CompressionError),
+
     /// The deserialized object as a whole was a null value.
     #[error("root object must not be null")]
     NullRoot,
@@ -83,10 +116,44 @@ pub enum Error {
     #[error("overflowed object size while consuming data")]
     ObjectSizeMismatch,
 
+    /// The encoded bit size of an object is too small to even cover
+    /// the size prefix itself.
+    #[error("invalid object size of {0} bits")]
+    InvalidObjectSize(u32),
+
     /// When a delta-encoded property is missing from a stream which enforces
     /// its presence.
     #[error("missing delta value which must be present")]
     MissingDelta,
+
+    /// More data than byte padding was left unread after deserializing
+    /// the root object in [`Strictness::Strict`] mode.
+    #[error("{0} bits were left unread after deserialization")]
+    TrailingBits(usize),
+
+    /// A value to serialize cannot be represented by the type it is
+    /// stored as.
+    #[error("cannot serialize value as '{0}'")]
+    InvalidValue(String),
+
+    /// A value to serialize in shallow mode lacks a property which
+    /// must be present.
+    #[error("missing value for property '{0}'")]
+    MissingProperty(String),
+
+    /// An object to serialize has a member which is not a property
+    /// of its type.
+    #[error("unknown property '{0}' for object")]
+    UnknownPropertyName(String),
+
+    /// A deserialized value requested more memory than permitted by
+    /// the configured [`AllocationLimits`].
+    #[error("{what} of {requested} exceeds the limit of {limit}")]
+    LimitExceeded {
+        what: &'static str,
+        requested: usize,
+        limit: usize,
+    },
 }
 
 bitflags! {
@@ -110,8 +177,51 @@ bitflags! {
     }
 }
 
+/// How strictly to treat data that was left unread after an object
+/// was successfully deserialized.
+///
+/// Leftover data usually indicates a wrong serializer configuration
+/// or outdated type information.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Silently ignores leftover data.
+    #[default]
+    Lenient,
+    /// Logs a warning for leftover data.
+    Warn,
+    /// Fails with [`Error::TrailingBits`] for leftover data.
+    Strict,
+}
+
+/// Limits for allocations requested by length prefixes in the data.
+///
+/// Length prefixes in corrupt or malicious data may request huge
+/// allocations. Exceeding any of these limits fails deserialization
+/// with [`Error::LimitExceeded`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocationLimits {
+    /// The maximum length of a single string, in code units.
+    pub max_string_len: usize,
+    /// The maximum number of elements in a single container.
+    pub max_elements: usize,
+    /// The maximum number of bytes allocated for strings and
+    /// containers throughout a whole object, and for its
+    /// decompressed data.
+    pub max_total: usize,
+}
+
+impl Default for AllocationLimits {
+    fn default() -> Self {
+        Self {
+            max_string_len: 16 << 20,
+            max_elements: 16 << 20,
+            max_total: 1 << 30,
+        }
+    }
+}
+
 /// Serializer configuration which influences how data is interpreted.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct SerializerOptions {
     /// The [`SerializerFlags`] to use.
     pub flags: SerializerFlags,
@@ -136,6 +246,105 @@ pub struct SerializerOptions {
     ///
     /// Used by Pirate101.
     pub djb2_only: bool,
+    /// How to treat bits left unread after deserialization.
+    ///
+    /// Up to 7 bits of padding to the next byte boundary are
+    /// always accepted.
+    ///
+    /// Ignored during serialization.
+    pub strictness: Strictness,
+    /// Collapses dynamic containers holding exactly one element into
+    /// that element instead of emitting a list.
+    ///
+    /// This produces terser output, but loses the distinction between
+    /// a single value and a container of one.
+    ///
+    /// Ignored during serialization.
+    pub collapse_single_element: bool,
+    /// Annotates values from dynamic containers with the kind of
+    /// container they were stored in.
+    ///
+    /// See [`List::container`][crate::value::List::container] for
+    /// the resulting shape. Takes precedence over
+    /// [`SerializerOptions::collapse_single_element`].
+    ///
+    /// Ignored during serialization.
+    pub annotate_containers: bool,
+    /// Recovers from errors in individual properties instead of
+    /// failing the whole object.
+    ///
+    /// Failing values are replaced by an [`error_placeholder`] and
+    /// reported as [`Diagnostic`]s, which can be retrieved with
+    /// [`Serializer::take_diagnostics`]. Only supported in deep
+    /// mode, where properties encode their sizes.
+    ///
+    /// List elements carry no sizes to skip past, so an error in
+    /// a single element replaces the whole list property.
+    ///
+    /// Ignored during serialization.
+    pub tolerant: bool,
+    /// Restricts the properties of the root object to the given
+    /// names, dropping all others from the output.
+    ///
+    /// In deep mode, the values of dropped properties are skipped
+    /// without decoding them. Shallow mode has no property sizes,
+    /// so everything is still decoded there.
+    ///
+    /// Ignored during serialization.
+    pub only: Option<Arc<HashSet<std::string::String>>>,
+    /// Skips objects of the types with these names entirely.
+    ///
+    /// Skipped objects are consumed by their encoded size without
+    /// decoding them and yield [`Value::Empty`]. Only supported in
+    /// deep mode, where objects encode their sizes.
+    ///
+    /// Ignored during serialization.
+    pub skip_types: Option<Arc<HashSet<std::string::String>>>,
+    /// Limits for allocations during deserialization.
+    ///
+    /// Ignored during serialization.
+    pub limits: AllocationLimits,
+    /// Resolves the keys in properties flagged as `LOCALIZED` or
+    /// `STRING_KEY` to their localized text.
+    ///
+    /// Keys which cannot be resolved are kept as-is.
+    ///
+    /// Ignored during serialization.
+    pub locale: Option<Arc<dyn LocaleProvider>>,
+    /// Handlers for leaf types which are not built in.
+    ///
+    /// Registered types take precedence over builtin ones of the same
+    /// name.
+    pub leaf_types: Option<Arc<LeafTypes>>,
+    /// Emits enum variants as [`Value::NamedEnum`], holding both the
+    /// symbolic name and the integral value.
+    ///
+    /// Ignored during serialization.
+    pub enum_names: bool,
+    /// Captures properties which are missing from the type list as
+    /// [`Value::Unknown`] instead of failing, keyed by their hash.
+    ///
+    /// The serializer writes such values back unchanged in deep mode.
+    /// Only relevant in deep mode, where properties name themselves.
+    ///
+    /// Ignored during serialization.
+    pub preserve_unknown: bool,
+    /// Records the bit offset and length of the value of every
+    /// property as a [`Span`].
+    ///
+    /// The spans can be retrieved with [`Serializer::take_spans`].
+    ///
+    /// Ignored during serialization.
+    pub record_spans: bool,
+    /// Substitutes the type list defaults for omitted delta-encoded
+    /// properties in shallow mode.
+    ///
+    /// Such values are no longer distinguishable from present ones,
+    /// so serializing them back marks them present. Without this,
+    /// omitted properties are [`Value::Empty`] and round trip exactly.
+    ///
+    /// Ignored during serialization.
+    pub fill_defaults: bool,
 }
 
 impl Default for SerializerOptions {
@@ -148,26 +357,99 @@ impl Default for SerializerOptions {
             recursion_limit: i8::MAX,
             skip_unknown_types: false,
             djb2_only: false,
+            strictness: Strictness::Lenient,
+            collapse_single_element: false,
+            annotate_containers: false,
+            tolerant: false,
+            only: None,
+            skip_types: None,
+            limits: AllocationLimits::default(),
+            locale: None,
+            leaf_types: None,
+            enum_names: false,
+            preserve_unknown: false,
+            record_spans: false,
+            fill_defaults: false,
+        }
+    }
+}
+
+impl SerializerOptions {
+    /// Options for persistent game files, which carry their flags
+    /// in the data and use deep mode.
+    ///
+    /// This matches files starting with [`BIND_MAGIC`] after the
+    /// magic was stripped.
+    pub fn game_file() -> Self {
+        Self {
+            flags: SerializerFlags::STATEFUL_FLAGS,
+            shallow: false,
+            ..Default::default()
+        }
+    }
+
+    /// Options for shallow state as it is sent over the network,
+    /// filtered to transmitted properties.
+    pub fn network_shallow() -> Self {
+        Self {
+            flags: SerializerFlags::empty(),
+            property_mask: PropertyFlags::TRANSMIT | PropertyFlags::PRIVILEGED_TRANSMIT,
+            shallow: true,
+            ..Default::default()
+        }
+    }
+
+    /// Options for game files of Pirate101, which hashes all types
+    /// with djb2.
+    pub fn pirate101() -> Self {
+        Self {
+            djb2_only: true,
+            ..Self::game_file()
         }
     }
 }
 
 pub(super) struct ZlibParts {
     inflater: Decompressor,
+    // Only created when serializing compressed data.
+    deflater: Option<Compressor>,
 
     // Most of the time, only one of these will be in use.
+    //
+    // All scratch buffers are retained across calls, so processing
+    // many objects with one serializer does not reallocate them.
     scratch1: Vec<u8>,
     scratch2: Vec<u8>,
+    // The bits of the object being serialized.
+    bits: Vec<u8>,
+
+    // The length of the object data in the last configured input.
+    data_len: usize,
 }
 
 impl ZlibParts {
     pub fn new() -> Self {
         Self {
             inflater: Decompressor::new(),
+            deflater: None,
             scratch1: Vec::new(),
             scratch2: Vec::new(),
+            bits: Vec::new(),
+            data_len: 0,
         }
     }
+
+    // Gets the number of bytes retained in the scratch buffers.
+    pub fn capacity(&self) -> usize {
+        self.scratch1.capacity() + self.scratch2.capacity() + self.bits.capacity()
+    }
+
+    // Frees the retained scratch buffers.
+    pub fn clear(&mut self) {
+        self.scratch1 = Vec::new();
+        self.scratch2 = Vec::new();
+        self.bits = Vec::new();
+    }
 }
 
 /// The inner parts of the serializer state.
@@ -175,6 +457,16 @@ pub struct SerializerParts {
     /// The serializer configuration in use.
     pub options: SerializerOptions,
     pub(crate) types: Arc<TypeList>,
+    pub(crate) pool: Pool,
+    pub(crate) diagnostics: Diagnostics,
+    // Whether the next object is the root, which is subject to
+    // `SerializerOptions::only`.
+    pub(crate) root: bool,
+    // The bytes allocated for the current object so far, checked
+    // against `AllocationLimits::max_total`.
+    pub(crate) allocated: usize,
+    // Object member names shared between all deserialized values.
+    pub(crate) names: Names,
 }
 
 /// A serializer and deserializer for values in the ObjectProperty system.
@@ -185,6 +477,28 @@ pub struct Serializer {
 }
 
 impl SerializerParts {
+    // Whether the paths to the values being deserialized are tracked
+    // in the diagnostics.
+    #[inline]
+    pub(crate) fn tracks_paths(&self) -> bool {
+        self.options.tolerant || self.options.record_spans
+    }
+
+    // Accounts for an allocation of `bytes` against the total limit.
+    pub(crate) fn allocate(&mut self, bytes: usize) -> Result<(), Error> {
+        let limit = self.options.limits.max_total;
+        self.allocated = self.allocated.saturating_add(bytes);
+        if self.allocated > limit {
+            return Err(Error::LimitExceeded {
+                what: "total allocation",
+                requested: self.allocated,
+                limit,
+            });
+        }
+
+        Ok(())
+    }
+
     #[inline]
     pub(super) fn with_recursion_limit<F, T>(&mut self, f: F) -> Result<T, Error>
     where
@@ -192,6 +506,7 @@ impl SerializerParts {
     {
         self.options.recursion_limit -= 1;
         if self.options.recursion_limit < 0 {
+            self.options.recursion_limit += 1;
             return Err(Error::Recursion);
         }
 
@@ -1,23 +1,86 @@
-use std::sync::Arc;
+use std::{fs, path::Path, sync::Arc};
 
 use byteorder::{ReadBytesExt, LE};
 use katsuba_bit_buf::BitReader;
 use katsuba_types::TypeList;
 use katsuba_utils::libdeflater::Decompressor;
+use memmap2::Mmap;
 
 use super::*;
 use crate::Value;
 
+// The container formats a compressed stream may come in.
+pub(super) enum Framing {
+    Zlib,
+    #[cfg(feature = "extra-framings")]
+    Gzip,
+    #[cfg(feature = "extra-framings")]
+    Deflate,
+}
+
+impl Framing {
+    // Identifies the framing of a compressed stream by its header.
+    //
+    // Without the `extra-framings` feature, all streams are zlib.
+    pub(super) fn detect(data: &[u8]) -> Self {
+        #[cfg(feature = "extra-framings")]
+        {
+            match data {
+                [0x1F, 0x8B, ..] => Self::Gzip,
+
+                // The compression method is deflate and the header
+                // checksum holds.
+                &[cmf, flg, ..] if cmf & 0xF == 8 && u16::from_be_bytes([cmf, flg]) % 31 == 0 => {
+                    Self::Zlib
+                }
+
+                _ => Self::Deflate,
+            }
+        }
+
+        #[cfg(not(feature = "extra-framings"))]
+        {
+            let _ = data;
+            Self::Zlib
+        }
+    }
+}
+
+/// Decompresses a stream prefixed with its decompressed size.
+///
+/// The size is checked against [`AllocationLimits::max_total`] before
+/// anything is allocated for it.
+///
+/// With the `extra-framings` feature, gzip and raw deflate streams
+/// are accepted in addition to zlib. Streams which fail to decode as
+/// zlib are retried as raw deflate.
 #[inline]
 pub(super) fn zlib_decompress(
     inflater: &mut Decompressor,
     mut data: &[u8],
     out: &mut Vec<u8>,
+    limits: &AllocationLimits,
 ) -> Result<(), Error> {
     let size = data.read_u32::<LE>()? as usize;
+    utils::check_limit("decompressed size", size, limits.max_total)?;
     out.resize(size, 0);
 
-    let decompressed = inflater.zlib_decompress(data, out)?;
+    let decompressed = match Framing::detect(data) {
+        Framing::Zlib => {
+            let res = inflater.zlib_decompress(data, out);
+
+            // Raw deflate streams may start with bytes that happen to
+            // form a valid zlib header, so fall back to them.
+            #[cfg(feature = "extra-framings")]
+            let res = res.or_else(|_| inflater.deflate_decompress(data, out));
+
+            res?
+        }
+        #[cfg(feature = "extra-framings")]
+        Framing::Gzip => inflater.gzip_decompress(data, out)?,
+        #[cfg(feature = "extra-framings")]
+        Framing::Deflate => inflater.deflate_decompress(data, out)?,
+    };
     if decompressed != size {
         return Err(Error::DecompressedSizeMismatch {
             expected: size,
@@ -29,14 +92,16 @@ pub(super) fn zlib_decompress(
 }
 
 impl ZlibParts {
-    fn configure<'a>(
+    // Strips the framing described by `opts` from `data` and inflates
+    // compressed payloads into scratch.
+    pub(super) fn payload<'a>(
         &'a mut self,
         opts: &mut SerializerOptions,
         mut data: &'a [u8],
-    ) -> Result<BitReader<'a>, Error> {
+    ) -> Result<&'a [u8], Error> {
         // If the data is manually compressed, uncompress into scratch.
         if opts.manual_compression {
-            zlib_decompress(&mut self.inflater, data, &mut self.scratch1)?;
+            zlib_decompress(&mut self.inflater, data, &mut self.scratch1, &opts.limits)?;
             data = &self.scratch1;
         }
 
@@ -47,11 +112,20 @@ impl ZlibParts {
 
         // If the data is compressed, uncompress it into scratch.
         if opts.flags.contains(SerializerFlags::WITH_COMPRESSION) && data.read_u8()? != 0 {
-            zlib_decompress(&mut self.inflater, data, &mut self.scratch2)?;
+            zlib_decompress(&mut self.inflater, data, &mut self.scratch2, &opts.limits)?;
             data = &self.scratch2;
         }
 
-        Ok(BitReader::new(data))
+        self.data_len = data.len();
+        Ok(data)
+    }
+
+    fn configure<'a>(
+        &'a mut self,
+        opts: &mut SerializerOptions,
+        data: &'a [u8],
+    ) -> Result<BitReader<'a>, Error> {
+        self.payload(opts, data).map(BitReader::new)
     }
 }
 
@@ -66,9 +140,25 @@ impl Serializer {
                 "cannot skip unknown types in shallow mode",
             ));
         }
+        if options.shallow && options.skip_types.is_some() {
+            return Err(Error::BadConfig("cannot skip types in shallow mode"));
+        }
+        if options.shallow && options.tolerant {
+            return Err(Error::BadConfig(
+                "cannot recover from errors in shallow mode",
+            ));
+        }
 
         Ok(Self {
-            parts: SerializerParts { options, types },
+            parts: SerializerParts {
+                options,
+                types,
+                pool: Pool::default(),
+                diagnostics: Default::default(),
+                root: false,
+                allocated: 0,
+                names: Default::default(),
+            },
             zlib_parts: ZlibParts::new(),
         })
     }
@@ -101,6 +191,20 @@ impl Serializer {
         types: Arc<TypeList>,
         data: &[u8],
     ) -> Result<Self, Error> {
+        Self::with_guessed_options_and_confidence(opts, types, data).map(|(de, _)| de)
+    }
+
+    /// Like [`Serializer::with_guessed_options_from_base`], but also
+    /// reports how confident the guess for the options that can only
+    /// be found by trial deserialization is.
+    ///
+    /// These trials assume the data holds a [`PropertyClass`] object.
+    #[cfg(feature = "option-guessing")]
+    pub fn with_guessed_options_and_confidence(
+        opts: SerializerOptions,
+        types: Arc<TypeList>,
+        data: &[u8],
+    ) -> Result<(Self, GuessConfidence), Error> {
         super::guess::Guesser::new(opts, types).guess(data)
     }
 
@@ -108,12 +212,150 @@ impl Serializer {
     pub fn deserialize<T: TypeTag>(&mut self, data: &[u8]) -> Result<Value, Error> {
         let mut reader = self.zlib_parts.configure(&mut self.parts.options, data)?;
         log::info!("Deserializing object with config {:?}", self.parts.options);
+        self.parts.diagnostics.clear();
+        self.parts.root = true;
+        self.parts.allocated = 0;
 
         let value = object::deserialize::<T>(&mut self.parts, &mut reader)?;
         if let Value::Empty = value {
             return Err(Error::NullRoot);
         }
 
+        let remaining = reader.remaining_bits();
+        if remaining >= u8::BITS as usize {
+            match self.parts.options.strictness {
+                Strictness::Lenient => {}
+                Strictness::Warn => log::warn!("{remaining} bits were left unread"),
+                Strictness::Strict => return Err(Error::TrailingBits(remaining)),
+            }
+        }
+
         Ok(value)
     }
+
+    /// Walks the object in `data` as a stream of [`Event`]s without
+    /// building a [`Value`] tree.
+    ///
+    /// See [`Events`] for details.
+    pub fn events<'a, T: TypeTag>(&'a mut self, data: &'a [u8]) -> Result<Events<'a, T>, Error> {
+        let reader = self.zlib_parts.configure(&mut self.parts.options, data)?;
+        log::info!("Streaming object with config {:?}", self.parts.options);
+
+        let parts = SerializerParts {
+            options: self.parts.options.clone(),
+            types: self.parts.types.clone(),
+            pool: Pool::default(),
+            diagnostics: Default::default(),
+            root: false,
+            allocated: 0,
+            names: Default::default(),
+        };
+        Ok(Events::new(parts, &self.parts.types, reader))
+    }
+
+    /// Deserializes an object [`Value`] from the file at `path`.
+    ///
+    /// The file is memory-mapped instead of being read into a buffer,
+    /// so large objects are deserialized without holding a second copy
+    /// of their data. The file must not be modified concurrently.
+    ///
+    /// Like [`Serializer::deserialize`], this expects raw object data.
+    /// Files starting with [`BIND_MAGIC`] are not supported.
+    pub fn deserialize_file<T: TypeTag, P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<Value, Error> {
+        let file = fs::File::open(path)?;
+
+        // Empty files cannot be mapped on all platforms.
+        if file.metadata()?.len() == 0 {
+            return self.deserialize::<T>(&[]);
+        }
+
+        // SAFETY: The mapping does not outlive this function and is
+        // only ever read from. Concurrent modification of the file is
+        // documented as a user error above.
+        let mapping = unsafe { Mmap::map(&file)? };
+        self.deserialize::<T>(&mapping)
+    }
+
+    /// Deserializes every input in `batch` and lends the resulting
+    /// [`Value`]s to `f`, one at a time.
+    ///
+    /// Each value is recycled as soon as `f` returns, so that the
+    /// allocations of one object are reused for the next one without
+    /// the caller handing anything back. Inputs that fail to
+    /// deserialize are reported to `f` and do not end the batch.
+    pub fn deserialize_batch<'a, T, I, F>(&mut self, batch: I, mut f: F)
+    where
+        T: TypeTag,
+        I: IntoIterator<Item = &'a [u8]>,
+        F: FnMut(Result<&Value, Error>),
+    {
+        for data in batch {
+            match self.deserialize::<T>(data) {
+                Ok(value) => {
+                    f(Ok(&value));
+                    self.parts.pool.recycle(value);
+                }
+                Err(e) => f(Err(e)),
+            }
+        }
+    }
+
+    /// Gets the size in bytes of the object data in the last input,
+    /// after removing its framing and decompressing it.
+    pub fn data_len(&self) -> usize {
+        self.zlib_parts.data_len
+    }
+
+    /// Takes the [`Span`]s recorded by the last call to
+    /// [`Serializer::deserialize`] with
+    /// [`SerializerOptions::record_spans`] set.
+    pub fn take_spans(&mut self) -> Vec<Span> {
+        std::mem::take(&mut self.parts.diagnostics.spans)
+    }
+
+    /// Takes the [`Diagnostic`]s collected by the last call to
+    /// [`Serializer::deserialize`] in
+    /// [`SerializerOptions::tolerant`] mode.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.parts.diagnostics.list)
+    }
+
+    /// Hands a deserialized `value` that is no longer needed back to
+    /// the serializer.
+    ///
+    /// Its list allocations will be reused by subsequent calls to
+    /// [`Serializer::deserialize`], which reduces allocator churn when
+    /// processing a batch of objects. [`Serializer::deserialize_batch`]
+    /// does this automatically.
+    ///
+    /// Only lists are reused; the maps backing objects are freed.
+    /// This is purely an optimization; dropping values works just as
+    /// well.
+    pub fn recycle(&mut self, value: Value) {
+        self.parts.pool.recycle(value);
+    }
+
+    /// Frees all allocations held for reuse by [`Serializer::recycle`].
+    pub fn clear_pool(&mut self) {
+        self.parts.pool.clear();
+    }
+
+    /// Gets the number of bytes retained in the scratch buffers for
+    /// decompression and serialization.
+    pub fn scratch_capacity(&self) -> usize {
+        self.zlib_parts.capacity()
+    }
+
+    /// Frees the scratch buffers for decompression and serialization.
+    ///
+    /// These buffers are otherwise retained across calls, so that a
+    /// batch of objects does not reallocate them for every object.
+    /// Clearing them is useful after processing an unusually large
+    /// object.
+    pub fn clear_scratch(&mut self) {
+        self.zlib_parts.clear();
+    }
 }
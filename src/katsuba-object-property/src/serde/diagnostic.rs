@@ -0,0 +1,94 @@
+use std::fmt::Write;
+
+use crate::{
+    value::{CxxStr, Object},
+    Value,
+};
+
+/// An error in an individual property which was recovered from in
+/// [`SerializerOptions::tolerant`][super::SerializerOptions::tolerant]
+/// mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The path to the failing value, like `m_foo.m_bar[2]`.
+    pub path: String,
+    /// A description of the error.
+    pub reason: String,
+    /// The bit offset of the failing property in the object data,
+    /// after decompression.
+    pub offset: usize,
+}
+
+/// The location of a property value in the object data, as recorded
+/// with [`SerializerOptions::record_spans`][super::SerializerOptions::record_spans].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// The path to the property, like `m_foo.m_bar[2]`.
+    pub path: String,
+    /// The bit offset of the value in the object data, after
+    /// decompression.
+    pub offset: usize,
+    /// The length of the value in bits.
+    pub len: usize,
+}
+
+// Diagnostics collected during a single deserialization.
+#[derive(Default)]
+pub(crate) struct Diagnostics {
+    // The path to the value currently being deserialized. Only
+    // maintained in tolerant mode or when recording spans.
+    pub path: String,
+    pub list: Vec<Diagnostic>,
+    pub spans: Vec<Span>,
+}
+
+impl Diagnostics {
+    pub fn clear(&mut self) {
+        self.path.clear();
+        self.list.clear();
+        self.spans.clear();
+    }
+
+    pub fn push_key(&mut self, key: &str) -> usize {
+        let len = self.path.len();
+        if len != 0 {
+            self.path.push('.');
+        }
+        self.path.push_str(key);
+        len
+    }
+
+    pub fn push_index(&mut self, idx: usize) -> usize {
+        let len = self.path.len();
+        let _ = write!(self.path, "[{idx}]");
+        len
+    }
+
+    // Records a diagnostic for the current path and restores the path
+    // to `len`, discarding segments left over from the failed value.
+    pub fn record(&mut self, len: usize, reason: String, offset: usize) {
+        self.list.push(Diagnostic {
+            path: self.path.clone(),
+            reason,
+            offset,
+        });
+        self.path.truncate(len);
+    }
+}
+
+/// Creates the placeholder emitted in place of a value which failed to
+/// deserialize in tolerant mode.
+///
+/// This is an untyped object holding the error message under the
+/// `$__error` key.
+pub fn error_placeholder(reason: &str) -> Value {
+    let mut obj = Object {
+        inner: Default::default(),
+    };
+    obj.insert(
+        "$__error".into(),
+        Value::String(CxxStr(reason.as_bytes().to_vec())),
+    );
+
+    Value::Object { hash: 0, obj }
+}
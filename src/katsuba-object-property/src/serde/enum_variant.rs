@@ -1,4 +1,4 @@
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, BitWriter};
 use katsuba_types::Property;
 
 use super::{utils, Error, SerializerFlags, SerializerParts};
@@ -9,19 +9,86 @@ pub fn deserialize(
     property: &Property,
     reader: &mut BitReader<'_>,
 ) -> Result<Value, Error> {
-    if de
+    let (name, value) = if de
         .options
         .flags
         .contains(SerializerFlags::HUMAN_READABLE_ENUMS)
     {
         let raw = utils::read_string(reader, &de.options)?;
-        let value = std::str::from_utf8(raw)?;
-        property
-            .decode_enum_variant(value)
-            .map(Value::Enum)
-            .map_err(Into::into)
+        let name = std::str::from_utf8(raw)?;
+        (Some(name.into()), property.decode_enum_variant(name)?)
     } else {
-        let value = utils::read_bits(reader, u32::BITS)?;
-        Ok(Value::Enum(value as i64))
+        (None, utils::read_bits(reader, u32::BITS)? as i64)
+    };
+
+    Ok(variant(de, property, name, value))
+}
+
+/// Converts the default variant of `property` from the type list,
+/// given by name or by value, into a [`Value`].
+pub fn from_default(
+    de: &SerializerParts,
+    property: &Property,
+    default: &serde_json::Value,
+) -> Option<Value> {
+    let value = match default {
+        serde_json::Value::Number(n) => n.as_i64()?,
+        serde_json::Value::String(s) => property.decode_enum_variant(s).ok()?,
+        _ => return None,
+    };
+
+    Some(variant(de, property, None, value))
+}
+
+fn variant(
+    de: &SerializerParts,
+    property: &Property,
+    name: Option<crate::value::String>,
+    value: i64,
+) -> Value {
+    if !de.options.enum_names {
+        return Value::Enum(value);
+    }
+
+    // Names from the data are kept verbatim, others are looked up.
+    let name = name.or_else(|| property.encode_enum_variant(value).ok());
+    Value::NamedEnum { name, value }
+}
+
+pub fn serialize(
+    ser: &SerializerParts,
+    property: &Property,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    let invalid = || Error::InvalidValue(property.r#type.to_string());
+
+    // Variants may be given by name or by value.
+    let (name, variant) = match value.unshared() {
+        Value::String(s) => {
+            let name = std::str::from_utf8(&s.0)?;
+            (Some(name), property.decode_enum_variant(name)?)
+        }
+        v => (None, utils::as_u64(v).ok_or_else(invalid)? as i64),
+    };
+
+    if ser
+        .options
+        .flags
+        .contains(SerializerFlags::HUMAN_READABLE_ENUMS)
+    {
+        let encoded;
+        let name = match name {
+            Some(name) => name,
+            None => {
+                encoded = property.encode_enum_variant(variant)?;
+                encoded.as_str()
+            }
+        };
+
+        utils::write_string(writer, name.as_bytes(), &ser.options).ok_or_else(invalid)
+    } else {
+        utils::write_bits(writer, variant as u64, u32::BITS);
+        Ok(())
     }
 }
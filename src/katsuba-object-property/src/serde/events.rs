@@ -0,0 +1,387 @@
+use std::marker::PhantomData;
+
+use katsuba_bit_buf::BitReader;
+use katsuba_types::{Property, PropertyFlags, TypeDef, TypeList};
+
+use super::*;
+use crate::Value;
+
+/// An event produced by [`Events`] while walking serialized data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event<'a> {
+    /// Start of an object, followed by its properties.
+    ObjectStart {
+        /// The type hash of the object.
+        hash: u32,
+        /// The type name of the object.
+        name: &'a str,
+    },
+
+    /// End of the innermost open object.
+    ObjectEnd,
+
+    /// A property of the innermost open object.
+    ///
+    /// The events for its value follow.
+    Property {
+        /// The name of the property.
+        name: &'a str,
+    },
+
+    /// Start of a list with `len` elements.
+    ListStart {
+        /// The number of elements in the list.
+        len: usize,
+    },
+
+    /// End of the innermost open list.
+    ListEnd,
+
+    /// A leaf value, i.e. simple data or an enum variant, or the
+    /// default value of an omitted delta-encoded property with
+    /// [`SerializerOptions::fill_defaults`].
+    Value(Value),
+
+    /// A narrow string value.
+    ///
+    /// Borrows from the deserialized data instead of being copied into
+    /// a [`Value::String`].
+    Str(&'a [u8]),
+
+    /// A null object, an unknown or excluded one that was skipped, or
+    /// an omitted delta-encoded property without a filled default.
+    Null,
+}
+
+// Pending work on the explicit stack of an event reader.
+enum Frame<'a> {
+    // An open object and the position of its next property.
+    Object {
+        type_def: &'a TypeDef,
+        next: usize,
+        remaining: usize,
+    },
+    // The value of a property that is yet to be read.
+    Value(&'a Property),
+    // The value of an omitted delta-encoded property.
+    Default(&'a Property),
+    // An open list and its number of unread elements.
+    List {
+        property: &'a Property,
+        remaining: usize,
+    },
+    // A property size to validate once its value was read.
+    Check {
+        start: usize,
+        size: usize,
+    },
+}
+
+/// A pull-based deserializer producing [`Event`]s instead of a tree
+/// of [`Value`]s.
+///
+/// Only leaf values are ever materialized, so even enormous objects
+/// are walked in constant memory apart from the nesting depth. Narrow
+/// strings are not even copied, see [`Event::Str`].
+///
+/// Created by [`Serializer::events`]. Container shaping options and
+/// tolerant error recovery are not applied to events.
+pub struct Events<'a, T> {
+    parts: SerializerParts,
+    types: &'a TypeList,
+    reader: BitReader<'a>,
+    stack: Vec<Frame<'a>>,
+    depth: i8,
+    started: bool,
+    done: bool,
+    _tag: PhantomData<T>,
+}
+
+impl<'a, T: TypeTag> Events<'a, T> {
+    pub(super) fn new(parts: SerializerParts, types: &'a TypeList, reader: BitReader<'a>) -> Self {
+        Self {
+            parts,
+            types,
+            reader,
+            stack: Vec::new(),
+            depth: 0,
+            started: false,
+            done: false,
+            _tag: PhantomData,
+        }
+    }
+
+    /// Reads the next [`Event`] from the data.
+    ///
+    /// Returns [`None`] once the root object was fully read. After an
+    /// error, no more events are produced.
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>, Error> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let res = self.advance();
+        if !matches!(res, Ok(Some(_))) {
+            self.done = true;
+        }
+        res
+    }
+
+    /// Gets the number of bits of object data consumed so far.
+    ///
+    /// This excludes any framing around the object, such as stateful
+    /// flags or compression.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.reader.position()
+    }
+
+    fn advance(&mut self) -> Result<Option<Event<'a>>, Error> {
+        if !self.started {
+            self.started = true;
+            return match self.object()? {
+                Event::Null => Err(Error::NullRoot),
+                event => Ok(Some(event)),
+            };
+        }
+
+        loop {
+            let Some(frame) = self.stack.pop() else {
+                self.finish()?;
+                return Ok(None);
+            };
+
+            match frame {
+                Frame::Object {
+                    type_def,
+                    next,
+                    remaining,
+                } => {
+                    let property = if self.parts.options.shallow {
+                        self.next_property_shallow(type_def, next)?
+                    } else {
+                        self.next_property_deep(type_def, remaining)?
+                    };
+
+                    return match property {
+                        Some(property) => Ok(Some(Event::Property {
+                            name: &property.name,
+                        })),
+                        None => {
+                            self.depth -= 1;
+                            Ok(Some(Event::ObjectEnd))
+                        }
+                    };
+                }
+
+                Frame::Value(property) if property.dynamic => {
+                    let len = utils::read_container_length(
+                        &mut self.reader,
+                        self.parts
+                            .options
+                            .flags
+                            .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
+                    )?;
+                    utils::check_limit(
+                        "element count",
+                        len,
+                        self.parts.options.limits.max_elements,
+                    )?;
+
+                    self.stack.push(Frame::List {
+                        property,
+                        remaining: len,
+                    });
+                    return Ok(Some(Event::ListStart { len }));
+                }
+
+                Frame::Value(property) => return self.element(property).map(Some),
+
+                Frame::Default(property) => {
+                    return Ok(Some(match property::default_value(&self.parts, property) {
+                        Value::Empty => Event::Null,
+                        value => Event::Value(value),
+                    }));
+                }
+
+                Frame::List {
+                    property,
+                    remaining,
+                } => {
+                    if remaining == 0 {
+                        return Ok(Some(Event::ListEnd));
+                    }
+
+                    self.stack.push(Frame::List {
+                        property,
+                        remaining: remaining - 1,
+                    });
+                    return self.element(property).map(Some);
+                }
+
+                Frame::Check { start, size } => {
+                    let actual = start
+                        .checked_sub(self.reader.remaining_bits())
+                        .ok_or(Error::ObjectSizeMismatch)?;
+                    if size != actual {
+                        return Err(Error::PropertySizeMismatch {
+                            expected: size,
+                            actual,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // In shallow mode, we walk masked properties in order.
+    fn next_property_shallow(
+        &mut self,
+        type_def: &'a TypeDef,
+        next: usize,
+    ) -> Result<Option<&'a Property>, Error> {
+        let mask = self.parts.options.property_mask;
+        let Some((idx, property)) = type_def
+            .properties
+            .iter()
+            .enumerate()
+            .skip(next)
+            .find(|(_, p)| p.flags.contains(mask) && !p.flags.contains(PropertyFlags::DEPRECATED))
+        else {
+            return Ok(None);
+        };
+
+        let omitted = property.flags.contains(PropertyFlags::DELTA_ENCODE)
+            && !utils::read_bool(&mut self.reader)?;
+        if omitted
+            && self
+                .parts
+                .options
+                .flags
+                .contains(SerializerFlags::FORBID_DELTA_ENCODE)
+        {
+            return Err(Error::MissingDelta);
+        }
+
+        self.stack.push(Frame::Object {
+            type_def,
+            next: idx + 1,
+            remaining: 0,
+        });
+        self.stack.push(match omitted {
+            true => Frame::Default(property),
+            false => Frame::Value(property),
+        });
+        Ok(Some(property))
+    }
+
+    // In deep mode, the properties name themselves.
+    fn next_property_deep(
+        &mut self,
+        type_def: &'a TypeDef,
+        remaining: usize,
+    ) -> Result<Option<&'a Property>, Error> {
+        if remaining == 0 {
+            return Ok(None);
+        }
+
+        // The property size also counts padding bits to byte boundaries.
+        let start = self.reader.remaining_bits();
+        self.reader.realign_to_byte();
+
+        let size = utils::read_bits(&mut self.reader, u32::BITS)? as usize;
+        let hash = utils::read_bits(&mut self.reader, u32::BITS)? as u32;
+        let property = type_def
+            .properties
+            .iter()
+            .find(|p| p.hash == hash)
+            .ok_or(Error::UnknownProperty(hash))?;
+
+        let remaining = remaining
+            .checked_sub(size)
+            .ok_or(Error::ObjectSizeMismatch)?;
+
+        self.stack.push(Frame::Object {
+            type_def,
+            next: 0,
+            remaining,
+        });
+        self.stack.push(Frame::Check { start, size });
+        self.stack.push(Frame::Value(property));
+        Ok(Some(property))
+    }
+
+    // Reads a single non-list value of `property`.
+    fn element(&mut self, property: &'a Property) -> Result<Event<'a>, Error> {
+        // Leaf values are not retained, so only count them one at a time.
+        self.parts.allocated = 0;
+
+        if property.is_enum() {
+            return enum_variant::deserialize(&self.parts, property, &mut self.reader)
+                .map(Event::Value);
+        }
+
+        // Narrow strings are stored verbatim, so they can be borrowed.
+        if property.r#type == "std::string" {
+            return utils::read_string(&mut self.reader, &self.parts.options).map(Event::Str);
+        }
+
+        match simple_data::deserialize(&mut self.parts, &property.r#type, &mut self.reader) {
+            Some(res) => res.map(Event::Value),
+            None => self.object(),
+        }
+    }
+
+    // Reads the header of an object and opens it.
+    fn object(&mut self) -> Result<Event<'a>, Error> {
+        self.reader.realign_to_byte();
+
+        match T::identity(&mut self.reader, self.types) {
+            Ok(Some(type_def)) if object::is_skipped(&self.parts, type_def) => {
+                object::skip_object(&self.parts, &mut self.reader)?;
+                Ok(Event::Null)
+            }
+
+            Ok(Some(type_def)) => {
+                if self.depth >= self.parts.options.recursion_limit {
+                    return Err(Error::Recursion);
+                }
+
+                let remaining = object::read_bit_size(&self.parts, &mut self.reader)? as usize;
+                self.depth += 1;
+                self.stack.push(Frame::Object {
+                    type_def,
+                    next: 0,
+                    remaining,
+                });
+
+                Ok(Event::ObjectStart {
+                    hash: object::type_hash(&self.parts, type_def),
+                    name: &type_def.name,
+                })
+            }
+
+            Ok(None) => Ok(Event::Null),
+
+            Err(_) if self.parts.options.skip_unknown_types => {
+                object::skip_unknown(&self.parts, &mut self.reader)?;
+                Ok(Event::Null)
+            }
+
+            Err(e) => Err(e),
+        }
+    }
+
+    fn finish(&self) -> Result<(), Error> {
+        let remaining = self.reader.remaining_bits();
+        if remaining >= u8::BITS as usize {
+            match self.parts.options.strictness {
+                Strictness::Lenient => {}
+                Strictness::Warn => log::warn!("{remaining} bits were left unread"),
+                Strictness::Strict => return Err(Error::TrailingBits(remaining)),
+            }
+        }
+
+        Ok(())
+    }
+}
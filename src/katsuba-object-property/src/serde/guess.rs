@@ -1,7 +1,7 @@
 use std::{mem, sync::Arc};
 
 use byteorder::{ByteOrder, LE};
-use katsuba_types::TypeList;
+use katsuba_types::{PropertyFlags, TypeList};
 use once_cell::sync::Lazy;
 use regex::bytes::Regex;
 
@@ -34,6 +34,14 @@ fn maybe_serializer_flags(flags: Option<u32>) -> bool {
 fn maybe_zlib_stream(offset: usize, data: &[u8]) -> bool {
     static HEADERS: [&[u8]; 4] = [b"\x78\x01", b"\x78\x9c", b"\x78\xda", b"\x78\x5e"];
 
+    // Raw deflate streams have no header, so every stream with a size
+    // prefix deflate can achieve (at most ~1032:1) is worth a try.
+    if cfg!(feature = "extra-framings") {
+        let compressed = data.len().saturating_sub(offset);
+        return read_u32(offset - 4, data)
+            .is_some_and(|size| compressed > 0 && size as usize <= compressed * 1032);
+    }
+
     // Attempt to identify zlib streams cheaply by finding the magic header.
     data.get(offset..offset + 2)
         .map(|v| HEADERS.contains(&v))
@@ -74,12 +82,13 @@ fn zlib_decompress(
     inflater: &mut Decompressor,
     out: &mut Vec<u8>,
     data: &[u8],
+    limits: &AllocationLimits,
 ) -> Result<bool, Error> {
-    match de::zlib_decompress(inflater, data, out) {
+    match de::zlib_decompress(inflater, data, out, limits) {
         Ok(()) => Ok(true),
 
         // Assume this was a false positive stream.
-        Err(Error::Decompress(_)) => Ok(false),
+        Err(Error::Decompress(_) | Error::LimitExceeded { .. }) => Ok(false),
 
         Err(e) => Err(e),
     }
@@ -112,6 +121,70 @@ fn check_length_prefix_types(opts: &mut SerializerOptions, data: &[u8]) {
     }
 }
 
+/// How confident the guesser is in an option it picked by trial
+/// deserialization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Confidence {
+    /// The picked value is the only one that worked best.
+    Certain,
+    /// Other values worked just as well; the picked one is merely
+    /// the more common choice.
+    Ambiguous,
+    /// No value led to a successful deserialization.
+    Unknown,
+}
+
+/// The confidence in guessed options that cannot be inferred from
+/// the layout of the data alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GuessConfidence {
+    /// Confidence in [`SerializerOptions::property_mask`].
+    pub property_mask: Confidence,
+    /// Confidence in [`SerializerFlags::HUMAN_READABLE_ENUMS`].
+    pub human_readable_enums: Confidence,
+}
+
+// Property masks to try in shallow mode, in order of preference.
+const CANDIDATE_MASKS: &[PropertyFlags] = &[
+    PropertyFlags::TRANSMIT.union(PropertyFlags::PRIVILEGED_TRANSMIT),
+    PropertyFlags::TRANSMIT,
+    PropertyFlags::PRIVILEGED_TRANSMIT,
+    PropertyFlags::SAVE,
+    PropertyFlags::PERSIST,
+];
+
+// Scores a trial deserialization of `data` with the given options.
+//
+// Deserializations which consume the whole stream score highest,
+// followed by those which leave data unread.
+fn score(de: &mut Serializer, opts: &SerializerOptions, data: &[u8]) -> u8 {
+    de.parts.options = SerializerOptions {
+        strictness: Strictness::Strict,
+        ..opts.clone()
+    };
+
+    match de.deserialize::<PropertyClass>(data) {
+        Ok(value) => {
+            de.recycle(value);
+            2
+        }
+        Err(Error::TrailingBits(..)) => 1,
+        Err(_) => 0,
+    }
+}
+
+// Rates the confidence in the value `pick` picked for an option, given
+// the option values of all trials which scored best.
+fn confidence<T: PartialEq>(best: u8, pick: T, mut winners: impl Iterator<Item = T>) -> Confidence {
+    if best == 0 {
+        Confidence::Unknown
+    } else if winners.all(|v| v == pick) {
+        Confidence::Certain
+    } else {
+        Confidence::Ambiguous
+    }
+}
+
 pub struct Guesser {
     types: Arc<TypeList>,
     zlib: ZlibParts,
@@ -127,24 +200,38 @@ impl Guesser {
         }
     }
 
-    pub fn guess(mut self, data: &[u8]) -> Result<Serializer, Error> {
-        // We perform only a baseline guess -- a pass that identifies and bases
+    pub fn guess(mut self, data: &[u8]) -> Result<(Serializer, GuessConfidence), Error> {
+        // We start with a baseline guess -- a pass that identifies and bases
         // off unambiguous properties of serialized data under the assumption
         // the stream is valid.
         self.baseline_guess(data)?;
 
-        // What we don't know at this point:
-        //
-        // - Are enums compact or human-readable?
-        // - What is the utilized property filter mask?
-
-        Ok(Serializer {
+        let mut de = Serializer {
             parts: SerializerParts {
-                options: self.opts,
+                options: self.opts.clone(),
                 types: self.types,
+                pool: Pool::default(),
+                diagnostics: Default::default(),
+                root: false,
+                allocated: 0,
+                names: Default::default(),
             },
             zlib_parts: self.zlib,
-        })
+        };
+
+        // What we don't know at this point:
+        //
+        // - Are enums compact or human-readable?
+        // - What is the utilized property filter mask?
+        //
+        // So we try out candidates and see which ones consume the stream.
+        let confidence = trial_guess(
+            &mut de,
+            self.opts,
+            data.strip_prefix(BIND_MAGIC).unwrap_or(data),
+        );
+
+        Ok((de, confidence))
     }
 
     fn baseline_guess<'a>(&'a mut self, mut data: &'a [u8]) -> Result<(), Error> {
@@ -154,7 +241,12 @@ impl Guesser {
 
         // First, check if we're dealing with a compressed object.
         if maybe_zlib_stream(4, data)
-            && zlib_decompress(&mut self.zlib.inflater, &mut self.zlib.scratch1, data)?
+            && zlib_decompress(
+                &mut self.zlib.inflater,
+                &mut self.zlib.scratch1,
+                data,
+                &self.opts.limits,
+            )?
         {
             self.opts.manual_compression = true;
             data = &self.zlib.scratch1;
@@ -179,7 +271,12 @@ impl Guesser {
 
         if maybe_zlib_stream(5, data)
             && data.first() == Some(&1)
-            && zlib_decompress(&mut self.zlib.inflater, &mut self.zlib.scratch2, &data[1..])?
+            && zlib_decompress(
+                &mut self.zlib.inflater,
+                &mut self.zlib.scratch2,
+                &data[1..],
+                &self.opts.limits,
+            )?
         {
             self.opts.flags |= SerializerFlags::WITH_COMPRESSION;
             data = &self.zlib.scratch2;
@@ -235,3 +332,49 @@ impl Guesser {
         Ok(())
     }
 }
+
+fn trial_guess(de: &mut Serializer, base: SerializerOptions, data: &[u8]) -> GuessConfidence {
+    // Deep mode does not filter properties by mask.
+    let mut masks = vec![base.property_mask];
+    if base.shallow {
+        masks.extend(CANDIDATE_MASKS.iter().filter(|&&m| m != base.property_mask));
+    }
+
+    // Stateful flags determine the enum encoding by themselves.
+    let base_enums = base.flags.contains(SerializerFlags::HUMAN_READABLE_ENUMS);
+    let mut enums = vec![base_enums];
+    if !base.flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+        enums.push(!base_enums);
+    }
+
+    let mut trials = Vec::with_capacity(masks.len() * enums.len());
+    for &readable in &enums {
+        for &mask in &masks {
+            let mut opts = base.clone();
+            opts.property_mask = mask;
+            opts.flags
+                .set(SerializerFlags::HUMAN_READABLE_ENUMS, readable);
+
+            trials.push((score(de, &opts, data), mask, readable));
+        }
+    }
+
+    // Pick the first of the best scoring trials, in order of preference.
+    let best = trials.iter().map(|t| t.0).max().unwrap_or(0);
+    let (_, mask, readable) = trials.iter().find(|t| t.0 == best).copied().unwrap();
+    let winners = || trials.iter().filter(|t| t.0 == best);
+
+    de.parts.options = base;
+    if best > 0 {
+        de.parts.options.property_mask = mask;
+        de.parts
+            .options
+            .flags
+            .set(SerializerFlags::HUMAN_READABLE_ENUMS, readable);
+    }
+
+    GuessConfidence {
+        property_mask: confidence(best, mask, winners().map(|t| t.1)),
+        human_readable_enums: confidence(best, readable, winners().map(|t| t.2)),
+    }
+}
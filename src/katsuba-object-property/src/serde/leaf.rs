@@ -0,0 +1,83 @@
+use std::{collections::HashMap, fmt};
+
+use katsuba_bit_buf::{BitReader, BitWriter};
+
+use super::{Error, SerializerOptions};
+use crate::Value;
+
+/// Reads a value of a custom leaf type from the object data.
+pub type LeafReader =
+    dyn Fn(&mut BitReader<'_>, &SerializerOptions) -> Result<Value, Error> + Send + Sync;
+
+/// Writes a value of a custom leaf type to the object data.
+///
+/// Returns [`None`] when the value cannot be represented by the type.
+pub type LeafWriter =
+    dyn Fn(&mut BitWriter, &SerializerOptions, &Value) -> Option<()> + Send + Sync;
+
+pub(crate) struct LeafType {
+    pub bit_packed: bool,
+    pub read: Box<LeafReader>,
+    pub write: Box<LeafWriter>,
+}
+
+/// A registry of handlers for leaf types which are not built into
+/// the serializer.
+///
+/// See [`SerializerOptions::leaf_types`].
+#[derive(Default)]
+pub struct LeafTypes {
+    types: HashMap<String, LeafType>,
+}
+
+impl LeafTypes {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the handlers for the C++ type `name`, replacing any
+    /// previous ones.
+    ///
+    /// Values of types which are not `bit_packed` start on a byte
+    /// boundary in shallow mode, like most builtin leaf types do.
+    pub fn register<R, W>(
+        &mut self,
+        name: impl Into<String>,
+        bit_packed: bool,
+        read: R,
+        write: W,
+    ) -> &mut Self
+    where
+        R: Fn(&mut BitReader<'_>, &SerializerOptions) -> Result<Value, Error>
+            + Send
+            + Sync
+            + 'static,
+        W: Fn(&mut BitWriter, &SerializerOptions, &Value) -> Option<()> + Send + Sync + 'static,
+    {
+        self.types.insert(
+            name.into(),
+            LeafType {
+                bit_packed,
+                read: Box::new(read),
+                write: Box::new(write),
+            },
+        );
+        self
+    }
+
+    /// Checks if handlers for the type `name` are registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.types.contains_key(name)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&LeafType> {
+        self.types.get(name)
+    }
+}
+
+impl fmt::Debug for LeafTypes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.types.keys()).finish()
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+use katsuba_types::{Property, PropertyFlags};
+
+use crate::value::{CxxStr, Value};
+
+/// A source of localized strings for resolving string keys during
+/// deserialization.
+///
+/// See [`SerializerOptions::locale`](super::SerializerOptions::locale).
+pub trait LocaleProvider: fmt::Debug + Send + Sync {
+    /// Looks up the localized text for a qualified string key like
+    /// `Table_Key`.
+    fn lookup(&self, key: &str) -> Option<&str>;
+}
+
+/// Replaces the string keys in the `value` of `property` with their
+/// localized text, if it is flagged as holding any.
+///
+/// Keys without a translation are kept as-is.
+pub fn localize(locale: &dyn LocaleProvider, property: &Property, value: &mut Value) {
+    if !property
+        .flags
+        .intersects(PropertyFlags::LOCALIZED | PropertyFlags::STRING_KEY)
+    {
+        return;
+    }
+
+    localize_value(locale, value);
+}
+
+fn localize_value(locale: &dyn LocaleProvider, value: &mut Value) {
+    match value {
+        Value::String(CxxStr(key)) => {
+            let text = std::str::from_utf8(key).ok().and_then(|k| locale.lookup(k));
+            if let Some(text) = text {
+                *key = text.as_bytes().to_vec();
+            }
+        }
+
+        // Dynamic containers of keys are localized element-wise.
+        Value::List(list) => list.iter_mut().for_each(|v| localize_value(locale, v)),
+
+        _ => {}
+    }
+}
@@ -1,12 +1,15 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, BitWriter};
 use katsuba_types::{PropertyFlags, TypeDef};
 use katsuba_utils::{align::align_down, hash::djb2, hash::string_id};
 use smartstring::alias::String;
 
 use super::{property, utils, Error, SerializerFlags, SerializerParts, TypeTag};
-use crate::{value::Object, Value};
+use crate::{
+    value::{Name, Object},
+    Value,
+};
 
 pub fn deserialize<T: TypeTag>(
     de: &mut SerializerParts,
@@ -17,6 +20,13 @@ pub fn deserialize<T: TypeTag>(
 
         let types = de.types.clone();
         let res = match T::identity(reader, &types) {
+            // If the type is to be skipped, consume its bits unread.
+            Ok(Some(type_def)) if is_skipped(de, type_def) => {
+                log::debug!("Skipping object of type '{}'", type_def.name);
+                skip_object(de, reader)?;
+                Value::Empty
+            }
+
             // If a type definition exists, read the full object.
             Ok(Some(type_def)) => {
                 let object_size = read_bit_size(de, reader)? as usize;
@@ -29,21 +39,7 @@ pub fn deserialize<T: TypeTag>(
             // If no type definition exists but we're allowed to skip it,
             // consume the bits the object is supposed to occupy.
             Err(_) if de.options.skip_unknown_types => {
-                log::warn!("Encountered unknown type; skipping it");
-
-                let object_size = read_bit_size(de, reader)? as usize;
-                let aligned_object_size = align_down(object_size, u8::BITS as _);
-
-                // When skipping an object, we must make sure to consume
-                // exactly as many bits as specified or we might end up
-                // with property size mismatches.
-                //
-                // We first read the whole bytes out of the given bit size,
-                // then refill the buffer and consume only the remainder.
-                reader.read_bytes(utils::bits_to_bytes(aligned_object_size))?;
-                reader.refill_bits();
-                reader.consume((object_size - aligned_object_size) as u32)?;
-
+                skip_unknown(de, reader)?;
                 Value::Empty
             }
 
@@ -56,6 +52,53 @@ pub fn deserialize<T: TypeTag>(
     })
 }
 
+/// Consumes the remainder of an object with an unknown type, after
+/// its identity was read.
+pub fn skip_unknown(de: &SerializerParts, reader: &mut BitReader<'_>) -> Result<(), Error> {
+    log::warn!("Encountered unknown type; skipping it");
+    skip_object(de, reader)
+}
+
+/// Consumes the remainder of an object after its identity was read,
+/// without decoding it.
+pub fn skip_object(de: &SerializerParts, reader: &mut BitReader<'_>) -> Result<(), Error> {
+    let object_size = read_bit_size(de, reader)? as usize;
+    let aligned_object_size = align_down(object_size, u8::BITS as _);
+
+    // When skipping an object, we must make sure to consume
+    // exactly as many bits as specified or we might end up
+    // with property size mismatches.
+    //
+    // We first read the whole bytes out of the given bit size,
+    // then refill the buffer and consume only the remainder. The
+    // size prefix ends on a byte boundary, so the lookahead can be
+    // discarded before reading bytes past it.
+    reader.realign_to_byte();
+    reader.read_bytes(utils::bits_to_bytes(aligned_object_size))?;
+    reader.refill_bits();
+    reader.consume((object_size - aligned_object_size) as u32)?;
+
+    Ok(())
+}
+
+/// Computes the hash of an object with type `type_def`.
+#[inline]
+pub fn type_hash(de: &SerializerParts, type_def: &TypeDef) -> u32 {
+    match de.options.djb2_only {
+        true => djb2(type_def.name.as_bytes()),
+        false => string_id(type_def.name.as_bytes()),
+    }
+}
+
+/// Whether objects of type `type_def` are configured to be skipped.
+#[inline]
+pub fn is_skipped(de: &SerializerParts, type_def: &TypeDef) -> bool {
+    de.options
+        .skip_types
+        .as_ref()
+        .is_some_and(|types| types.contains(type_def.name.as_str()))
+}
+
 fn deserialize_properties<T: TypeTag>(
     de: &mut SerializerParts,
     object_size: usize,
@@ -64,27 +107,30 @@ fn deserialize_properties<T: TypeTag>(
 ) -> Result<Value, Error> {
     let mut inner = BTreeMap::new();
 
+    // Only the root object is subject to property projection.
+    let only = match std::mem::take(&mut de.root) {
+        true => de.options.only.clone(),
+        false => None,
+    };
+    let only = only.as_deref();
+
     if de.options.shallow {
-        deserialize_properties_shallow::<T>(&mut inner, de, type_def, reader)?;
+        deserialize_properties_shallow::<T>(&mut inner, de, only, type_def, reader)?;
     } else {
-        deserialize_properties_deep::<T>(&mut inner, de, object_size, type_def, reader)?;
+        deserialize_properties_deep::<T>(&mut inner, de, only, object_size, type_def, reader)?;
     }
 
-    let hash = match de.options.djb2_only {
-        true => djb2(type_def.name.as_bytes()),
-        false => string_id(type_def.name.as_bytes()),
-    };
-
     Ok(Value::Object {
-        hash,
+        hash: type_hash(de, type_def),
         obj: Object { inner },
     })
 }
 
 #[inline]
 fn deserialize_properties_shallow<T: TypeTag>(
-    obj: &mut BTreeMap<String, Value>,
+    obj: &mut BTreeMap<Name, Value>,
     de: &mut SerializerParts,
+    only: Option<&HashSet<std::string::String>>,
     type_def: &TypeDef,
     reader: &mut BitReader<'_>,
 ) -> Result<(), Error> {
@@ -95,18 +141,35 @@ fn deserialize_properties_shallow<T: TypeTag>(
         .iter()
         .filter(|p| p.flags.contains(mask) && !p.flags.contains(PropertyFlags::DEPRECATED))
     {
-        if property.flags.contains(PropertyFlags::DELTA_ENCODE)
-            && !utils::read_bool(reader)?
-            && de
+        if property.flags.contains(PropertyFlags::DELTA_ENCODE) && !utils::read_bool(reader)? {
+            if de
                 .options
                 .flags
                 .contains(SerializerFlags::FORBID_DELTA_ENCODE)
-        {
-            return Err(Error::MissingDelta);
+            {
+                return Err(Error::MissingDelta);
+            }
+
+            // Omitted values take their default from the type list.
+            if is_selected(only, &property.name) {
+                let value = property::default_value(de, property);
+                obj.insert(de.names.get(&property.name), value);
+            }
+            continue;
+        }
+
+        let len = de.diagnostics.path.len();
+        if de.tracks_paths() {
+            de.diagnostics.push_key(&property.name);
         }
 
+        // Without property sizes, unselected values must still be
+        // decoded to get past them.
         let value = property::deserialize::<T>(de, property, reader)?;
-        obj.insert(property.name.clone(), value);
+        de.diagnostics.path.truncate(len);
+        if is_selected(only, &property.name) {
+            obj.insert(de.names.get(&property.name), value);
+        }
     }
 
     Ok(())
@@ -114,8 +177,9 @@ fn deserialize_properties_shallow<T: TypeTag>(
 
 #[inline]
 fn deserialize_properties_deep<T: TypeTag>(
-    obj: &mut BTreeMap<String, Value>,
+    obj: &mut BTreeMap<Name, Value>,
     de: &mut SerializerParts,
+    only: Option<&HashSet<std::string::String>>,
     mut object_size: usize,
     type_def: &TypeDef,
     reader: &mut BitReader<'_>,
@@ -125,48 +189,286 @@ fn deserialize_properties_deep<T: TypeTag>(
         // Back up the current buffer length and read the property size.
         // This will also count padding bits to byte boundaries.
         let previous_buf_len = reader.remaining_bits();
+        let offset = reader.position();
         reader.realign_to_byte();
 
         let property_size = utils::read_bits(reader, u32::BITS)? as usize;
 
-        // Read the property's hash and find the object in type defs.
-        let property_hash = utils::read_bits(reader, u32::BITS)? as u32;
-        let property = type_def
-            .properties
-            .iter()
-            .find(|p| p.hash == property_hash)
-            .ok_or_else(|| Error::UnknownProperty(property_hash))?;
+        let res = deserialize_property_deep::<T>(
+            de,
+            only,
+            offset,
+            property_size,
+            previous_buf_len,
+            type_def,
+            reader,
+        );
+        match res {
+            Ok((name, Some(value))) => {
+                obj.insert(name, value);
+            }
+            Ok((_, None)) => {}
 
-        // Deserialize the property's value.
-        let value = property::deserialize::<T>(de, property, reader)?;
+            // In tolerant mode, errors within the property's size bounds
+            // are recorded and the rest of the property is skipped.
+            Err((property, len, e))
+                if de.options.tolerant && property_size >= u32::BITS as usize =>
+            {
+                reader.seek(offset + property_size)?;
 
-        // Validate the size expectations.
-        let actual_size = previous_buf_len - reader.remaining_bits();
-        if property_size != actual_size {
-            return Err(Error::PropertySizeMismatch {
-                expected: property_size,
-                actual: actual_size,
-            });
+                let reason = e.to_string();
+                if let Some(property) = property {
+                    obj.insert(de.names.get(property), super::error_placeholder(&reason));
+                }
+                de.diagnostics.record(len, reason, offset);
+            }
+
+            Err((_, _, e)) => return Err(e),
         }
 
         // Prepare for the next round of deserialization.
         object_size = object_size
             .checked_sub(property_size)
-            .ok_or_else(|| Error::ObjectSizeMismatch)?;
-
-        // Lastly, insert the property into the object.
-        obj.insert(property.name.clone(), value);
+            .ok_or(Error::ObjectSizeMismatch)?;
     }
 
     Ok(())
 }
 
+// Deserializes a single property after its size prefix, or skips it
+// when it is not selected for output.
+//
+// On failure, this returns the name of the property if known and the
+// length of the diagnostics path to restore alongside the error.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn deserialize_property_deep<'a, T: TypeTag>(
+    de: &mut SerializerParts,
+    only: Option<&HashSet<std::string::String>>,
+    offset: usize,
+    property_size: usize,
+    previous_buf_len: usize,
+    type_def: &'a TypeDef,
+    reader: &mut BitReader<'_>,
+) -> Result<(Name, Option<Value>), (Option<&'a String>, usize, Error)> {
+    let tracking = de.tracks_paths();
+    let len = de.diagnostics.path.len();
+
+    // Read the property's hash and find the object in type defs.
+    let property_hash = utils::read_bits(reader, u32::BITS).map_err(|e| (None, len, e))? as u32;
+    let Some(property) = type_def.properties.iter().find(|p| p.hash == property_hash) else {
+        if de.options.preserve_unknown {
+            return deserialize_unknown(
+                only,
+                property_hash,
+                property_size,
+                previous_buf_len,
+                reader,
+            )
+            .map_err(|e| (None, len, e));
+        }
+        if tracking {
+            de.diagnostics.push_key(&format!("{property_hash:#010x}"));
+        }
+        return Err((None, len, Error::UnknownProperty(property_hash)));
+    };
+
+    let fail = |e| (Some(&property.name), len, e);
+    if !is_selected(only, &property.name) {
+        // Corrupt sizes must not make us seek backwards.
+        let consumed = previous_buf_len - reader.remaining_bits();
+        if property_size < consumed {
+            return Err(fail(Error::PropertySizeMismatch {
+                expected: property_size,
+                actual: consumed,
+            }));
+        }
+
+        reader
+            .seek(offset + property_size)
+            .map_err(|e| fail(e.into()))?;
+        return Ok((de.names.get(&property.name), None));
+    }
+
+    if tracking {
+        de.diagnostics.push_key(&property.name);
+    }
+
+    // Deserialize the property's value.
+    let value = property::deserialize::<T>(de, property, reader).map_err(fail)?;
+
+    // Validate the size expectations.
+    let actual_size = previous_buf_len
+        .checked_sub(reader.remaining_bits())
+        .ok_or(Error::ObjectSizeMismatch)
+        .map_err(fail)?;
+    if property_size != actual_size {
+        return Err(fail(Error::PropertySizeMismatch {
+            expected: property_size,
+            actual: actual_size,
+        }));
+    }
+
+    if tracking {
+        de.diagnostics.path.truncate(len);
+    }
+    Ok((de.names.get(&property.name), Some(value)))
+}
+
+// Captures the raw bits of a property which is missing from the type
+// list, keyed by its hash.
+fn deserialize_unknown(
+    only: Option<&HashSet<std::string::String>>,
+    hash: u32,
+    property_size: usize,
+    previous_buf_len: usize,
+    reader: &mut BitReader<'_>,
+) -> Result<(Name, Option<Value>), Error> {
+    let consumed = previous_buf_len - reader.remaining_bits();
+    let len = property_size
+        .checked_sub(consumed)
+        .ok_or(Error::PropertySizeMismatch {
+            expected: property_size,
+            actual: consumed,
+        })?;
+
+    let name = Name::from(format!("{hash:#010x}"));
+    let bits = utils::read_raw_bits(reader, len)?;
+    if !is_selected(only, &name) {
+        return Ok((name, None));
+    }
+
+    Ok((name, Some(Value::Unknown { hash, len, bits })))
+}
+
+#[inline]
+fn is_selected(only: Option<&HashSet<std::string::String>>, name: &str) -> bool {
+    only.is_none_or(|only| only.contains(name))
+}
+
 #[inline]
 pub(crate) fn read_bit_size(
     de: &SerializerParts,
     reader: &mut BitReader<'_>,
 ) -> Result<u32, Error> {
-    (!de.options.shallow)
-        .then(|| Ok(utils::read_bits(reader, u32::BITS)? as u32 - u32::BITS))
-        .unwrap_or(Ok(0))
+    if de.options.shallow {
+        return Ok(0);
+    }
+
+    // The encoded size includes the size prefix itself, so corrupt
+    // data may hold values which are too small.
+    let size = utils::read_bits(reader, u32::BITS)? as u32;
+    size.checked_sub(u32::BITS)
+        .ok_or(Error::InvalidObjectSize(size))
+}
+
+pub fn serialize<T: TypeTag>(
+    ser: &mut SerializerParts,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    writer.realign_to_byte();
+
+    let (hash, obj) = match value.unshared() {
+        // Null pointers are encoded as just the null identity.
+        Value::Empty => {
+            T::write_identity(writer, 0);
+            return Ok(());
+        }
+        Value::Object { hash, obj } => (*hash, obj),
+        _ => return Err(Error::InvalidValue("object".to_owned())),
+    };
+
+    let types = ser.types.clone();
+    let type_def = types.0.get(&hash).ok_or(Error::UnknownType(hash))?;
+    log::debug!("Serializing object of type '{}' ({hash})", type_def.name);
+
+    // Unknown properties can only be written where properties name
+    // themselves.
+    let known = |(k, v): &(&Name, &Value)| {
+        type_def.properties.iter().any(|p| p.name == k.as_str())
+            || (!ser.options.shallow && matches!(v.unshared(), Value::Unknown { .. }))
+    };
+    if let Some((name, _)) = obj.iter().find(|e| !known(e)) {
+        return Err(Error::UnknownPropertyName(name.to_string()));
+    }
+
+    T::write_identity(writer, hash);
+    if ser.options.shallow {
+        serialize_properties_shallow::<T>(obj, ser, type_def, writer)
+    } else {
+        // The object size includes the size prefix itself.
+        writer.length_prefixed(|w| serialize_properties_deep::<T>(obj, ser, type_def, w))
+    }
+}
+
+#[inline]
+fn serialize_properties_shallow<T: TypeTag>(
+    obj: &Object,
+    ser: &mut SerializerParts,
+    type_def: &TypeDef,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    // In shallow mode, all masked properties must be written in order.
+    let mask = ser.options.property_mask;
+    for property in type_def
+        .properties
+        .iter()
+        .filter(|p| p.flags.contains(mask) && !p.flags.contains(PropertyFlags::DEPRECATED))
+    {
+        let value = obj
+            .get(property.name.as_str())
+            .ok_or_else(|| Error::MissingProperty(property.name.to_string()))?;
+
+        // Delta-encoded values are marked present unless empty and
+        // omitting them is allowed.
+        if property.flags.contains(PropertyFlags::DELTA_ENCODE) {
+            let present = ser
+                .options
+                .flags
+                .contains(SerializerFlags::FORBID_DELTA_ENCODE)
+                || !matches!(value.unshared(), Value::Empty);
+            utils::write_bool(writer, present);
+            if !present {
+                continue;
+            }
+        }
+
+        property::serialize::<T>(ser, property, value, writer)?;
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn serialize_properties_deep<T: TypeTag>(
+    obj: &Object,
+    ser: &mut SerializerParts,
+    type_def: &TypeDef,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    // In deep mode, the properties name themselves, so every property
+    // present in the object is written regardless of the mask.
+    for property in &type_def.properties {
+        let Some(value) = obj.get(property.name.as_str()) else {
+            continue;
+        };
+
+        // The property size includes the size prefix itself.
+        writer.length_prefixed(|w| {
+            utils::write_bits(w, property.hash as u64, u32::BITS);
+            property::serialize::<T>(ser, property, value, w)
+        })?;
+    }
+
+    // Preserved unknown properties are written back verbatim.
+    for value in obj.values() {
+        if let Value::Unknown { hash, len, bits } = value.unshared() {
+            writer.length_prefixed(|w| {
+                utils::write_bits(w, *hash as u64, u32::BITS);
+                utils::write_raw_bits(w, bits, *len);
+            });
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,86 @@
+use std::{mem, sync::Arc};
+
+use crate::Value;
+
+// The upper bound of list allocations kept around for reuse.
+const MAX_POOLED_VECS: usize = 4096;
+
+// Lists with a larger capacity than this are not worth keeping
+// around since they are rare and would pin a lot of memory.
+const MAX_POOLED_CAPACITY: usize = 1024;
+
+/// A pool of recycled allocations for deserialized values.
+///
+/// Deserialization takes list allocations out of the pool and falls
+/// back to the allocator when it is empty. The pool is refilled with
+/// the values of a batch once they were handed to the caller, with
+/// lists the deserializer discards itself and with values explicitly
+/// handed back through [`Pool::recycle`].
+///
+/// Only lists are pooled. Objects are backed by
+/// [`BTreeMap`][std::collections::BTreeMap]s whose node allocations
+/// cannot be reused, so only their children are recycled.
+#[derive(Default)]
+pub(crate) struct Pool {
+    vecs: Vec<Vec<Value>>,
+
+    // A reusable stack for walking recycled values.
+    stack: Vec<Value>,
+}
+
+impl Pool {
+    /// Takes a list allocation from the pool with at least `capacity`
+    /// free elements.
+    pub fn take_vec(&mut self, capacity: usize) -> Vec<Value> {
+        match self.vecs.pop() {
+            Some(mut v) => {
+                v.reserve(capacity);
+                v
+            }
+            None => Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Harvests the allocations from `value` for later reuse.
+    ///
+    /// Like [`Drop`] for values, this operates iteratively to avoid
+    /// stack overflows with deeply nested data.
+    pub fn recycle(&mut self, value: Value) {
+        let mut stack = mem::take(&mut self.stack);
+        stack.push(value);
+
+        while let Some(value) = stack.pop() {
+            match value {
+                Value::List(mut list) => {
+                    let mut inner = mem::take(&mut list.inner);
+                    stack.append(&mut inner);
+
+                    if self.vecs.len() < MAX_POOLED_VECS && inner.capacity() <= MAX_POOLED_CAPACITY
+                    {
+                        self.vecs.push(inner);
+                    }
+                }
+
+                Value::Object { mut obj, .. } => {
+                    stack.extend(mem::take(&mut obj.inner).into_values());
+                }
+
+                Value::Shared(shared) => {
+                    if let Ok(value) = Arc::try_unwrap(shared) {
+                        stack.push(value);
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        self.stack = stack;
+    }
+
+    /// Frees all pooled allocations.
+    pub fn clear(&mut self) {
+        self.vecs = Vec::new();
+        self.stack = Vec::new();
+    }
+}
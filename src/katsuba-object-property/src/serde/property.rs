@@ -1,4 +1,4 @@
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, BitWriter};
 use katsuba_types::Property;
 
 use super::*;
@@ -11,17 +11,66 @@ pub fn deserialize<T: TypeTag>(
 ) -> Result<Value, Error> {
     log::debug!("Deserializing value for property '{}'", property.name);
 
-    let value = if property.dynamic {
+    let offset = reader.position();
+    let mut value = if property.dynamic {
         deserialize_list::<T>(de, property, reader)?
     } else {
         deserialize_value::<T>(de, property, reader)?
     };
 
+    if de.options.record_spans {
+        let span = Span {
+            path: de.diagnostics.path.clone(),
+            offset,
+            len: reader.position() - offset,
+        };
+        de.diagnostics.spans.push(span);
+    }
+
+    if let Some(provider) = &de.options.locale {
+        locale::localize(&**provider, property, &mut value);
+    }
+
     log::trace!("Got '{value:?}'");
 
     Ok(value)
 }
 
+/// Gets the value of a delta-encoded `property` which was omitted
+/// from the data.
+///
+/// This is the default from the type list when
+/// [`SerializerOptions::fill_defaults`] is set, or [`Value::Empty`]
+/// otherwise or when there is none.
+pub fn default_value(de: &SerializerParts, property: &Property) -> Value {
+    let Some(default) = property
+        .default
+        .as_ref()
+        .filter(|_| de.options.fill_defaults)
+    else {
+        return Value::Empty;
+    };
+
+    let element = |v| match property.is_enum() {
+        true => enum_variant::from_default(de, property, v),
+        false => simple_data::from_default(&property.r#type, v),
+    };
+    let value = match default {
+        serde_json::Value::Array(values) if property.dynamic => values
+            .iter()
+            .map(element)
+            .collect::<Option<Vec<_>>>()
+            .map(|values| Value::List(List::new(values))),
+        v if !property.dynamic => element(v),
+        _ => None,
+    };
+
+    value.unwrap_or_else(|| {
+        log::warn!("Ignoring invalid default for property '{}'", property.name);
+        Value::Empty
+    })
+}
+
 fn deserialize_value<T: TypeTag>(
     de: &mut SerializerParts,
     property: &Property,
@@ -50,15 +99,110 @@ fn deserialize_list<T: TypeTag>(
             .flags
             .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
     )?;
-    let mut inner = Vec::with_capacity(len);
+    utils::check_limit("element count", len, de.options.limits.max_elements)?;
+    de.allocate(len.saturating_mul(std::mem::size_of::<Value>()))?;
 
-    de.with_recursion_limit(|de| {
-        for _ in 0..len {
-            inner.push(deserialize_value::<T>(de, property, reader)?);
+    // Every element occupies at least one bit, so this bounds the
+    // allocation for corrupt lengths.
+    let mut list = List::new(de.pool.take_vec(len.min(reader.remaining_bits())));
+
+    let res = de.with_recursion_limit(|de| {
+        let tracking = de.tracks_paths();
+        for idx in 0..len {
+            // Track element indices so that diagnostics point to the
+            // exact element that failed.
+            let path_len = if tracking {
+                de.diagnostics.push_index(idx)
+            } else {
+                0
+            };
+
+            list.push(deserialize_value::<T>(de, property, reader)?);
+
+            if tracking {
+                de.diagnostics.path.truncate(path_len);
+            }
         }
 
         Ok(())
-    })?;
+    });
+
+    match res {
+        Ok(()) if de.options.annotate_containers => {
+            list.container = Some(property.container);
+            Ok(Value::List(list))
+        }
+        Ok(()) if de.options.collapse_single_element && list.len() == 1 => {
+            let value = list.pop().unwrap();
+            de.pool.recycle(Value::List(list));
+            Ok(value)
+        }
+        Ok(()) => Ok(Value::List(list)),
+        Err(e) => {
+            // Keep the allocation of partially deserialized lists.
+            de.pool.recycle(Value::List(list));
+            Err(e)
+        }
+    }
+}
+
+pub fn serialize<T: TypeTag>(
+    ser: &mut SerializerParts,
+    property: &Property,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    log::debug!("Serializing value for property '{}'", property.name);
+
+    let value = value.unshared();
+    if property.dynamic {
+        serialize_list::<T>(ser, property, value, writer)
+    } else {
+        serialize_value::<T>(ser, property, value, writer)
+    }
+}
+
+fn serialize_value<T: TypeTag>(
+    ser: &mut SerializerParts,
+    property: &Property,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    if property.is_enum() {
+        enum_variant::serialize(ser, property, value, writer)
+    } else {
+        match simple_data::serialize(ser, &property.r#type, value, writer) {
+            Some(res) => res,
+            None => object::serialize::<T>(ser, value, writer),
+        }
+    }
+}
+
+fn serialize_list<T: TypeTag>(
+    ser: &mut SerializerParts,
+    property: &Property,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    // Containers with a single element may have been collapsed into
+    // that element on deserialization.
+    let elements = match value {
+        Value::List(list) => list.as_slice(),
+        value => std::slice::from_ref(value),
+    };
+
+    utils::write_container_length(
+        writer,
+        elements.len(),
+        ser.options
+            .flags
+            .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
+    )
+    .ok_or_else(|| Error::InvalidValue(property.r#type.to_string()))?;
+
+    for element in elements {
+        serialize_value::<T>(ser, property, element, writer)?;
+    }
 
-    Ok(Value::List(List { inner }))
+    Ok(())
 }
@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+
+use super::{Error, Serializer};
+
+/// A type hash referenced by serialized data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeReference {
+    /// The type hash.
+    pub hash: u32,
+    /// The bit offsets of all occurrences in the data.
+    pub offsets: Vec<usize>,
+}
+
+// Reads 32 bits starting at the given bit offset, in the LSB-first
+// order used by the bit reader.
+fn read_u32_at(data: &[u8], bit: usize) -> u32 {
+    let start = bit / 8;
+    let mut window = [0; 8];
+    let available = data.len().saturating_sub(start).min(8);
+    window[..available].copy_from_slice(&data[start..start + available]);
+
+    (u64::from_le_bytes(window) >> (bit % 8)) as u32
+}
+
+/// Scans serialized data for known type hashes without deserializing it.
+///
+/// `data` is first unwrapped according to the options of `de`, so
+/// that compressed payloads are inflated before they are scanned.
+/// Every 32-bit window is then compared against the hashes in the
+/// type list of `de`, either at every byte or, when `bit_aligned` is
+/// set, at every bit offset to also cover compact bit-packed streams.
+///
+/// This is a heuristic; random data may coincidentally match a hash,
+/// especially with `bit_aligned` scans over large inputs.
+pub fn scan_type_hashes(
+    de: &mut Serializer,
+    data: &[u8],
+    bit_aligned: bool,
+) -> Result<Vec<TypeReference>, Error> {
+    // Flags read from stateful data only apply to this scan.
+    let mut opts = de.parts.options.clone();
+    let data = de.zlib_parts.payload(&mut opts, data)?;
+    let types = &de.parts.types;
+
+    let bits = data.len() * 8;
+    let step = if bit_aligned { 1 } else { 8 };
+
+    let mut found: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+    for bit in (0..bits.saturating_sub(31)).step_by(step) {
+        let hash = read_u32_at(data, bit);
+        if types.0.contains_key(&hash) {
+            found.entry(hash).or_default().push(bit);
+        }
+    }
+
+    Ok(found
+        .into_iter()
+        .map(|(hash, offsets)| TypeReference { hash, offsets })
+        .collect())
+}
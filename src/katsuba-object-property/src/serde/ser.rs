@@ -0,0 +1,103 @@
+use katsuba_bit_buf::BitWriter;
+use katsuba_utils::libdeflater::{CompressionLvl, Compressor};
+
+use super::*;
+use crate::Value;
+
+#[inline]
+pub(super) fn zlib_compress(
+    deflater: &mut Compressor,
+    data: &[u8],
+    out: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let size = u32::try_from(data.len()).map_err(|_| Error::InvalidValue("object".to_owned()))?;
+    out.extend_from_slice(&size.to_le_bytes());
+
+    let start = out.len();
+    out.resize(start + deflater.zlib_compress_bound(data.len()), 0);
+    let compressed = deflater.zlib_compress(data, &mut out[start..])?;
+    out.truncate(start + compressed);
+
+    Ok(())
+}
+
+impl ZlibParts {
+    fn deflater(&mut self) -> &mut Compressor {
+        self.deflater
+            .get_or_insert_with(|| Compressor::new(CompressionLvl::default()))
+    }
+
+    // The inverse of `configure`, applying the framing around the
+    // serialized object bits.
+    fn finish(&mut self, opts: &SerializerOptions, data: &[u8]) -> Result<Vec<u8>, Error> {
+        // With manual compression, the framed data is only compressed
+        // again, so scratch memory is good enough for it.
+        let mut out = match opts.manual_compression {
+            true => std::mem::take(&mut self.scratch1),
+            false => Vec::new(),
+        };
+        out.clear();
+        out.reserve(data.len() + 5);
+
+        // If the serializer flags are stateful, they lead the data.
+        if opts.flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+            out.extend_from_slice(&opts.flags.bits().to_le_bytes());
+        }
+
+        // If the data is compressed, mark it as such and compress it.
+        if opts.flags.contains(SerializerFlags::WITH_COMPRESSION) {
+            out.push(1);
+            zlib_compress(self.deflater(), data, &mut out)?;
+        } else {
+            out.extend_from_slice(data);
+        }
+
+        // If the data is manually compressed, compress everything again.
+        if opts.manual_compression {
+            let mut framed = Vec::new();
+            let res = zlib_compress(self.deflater(), &out, &mut framed);
+            self.scratch1 = out;
+            res?;
+            out = framed;
+        }
+
+        Ok(out)
+    }
+}
+
+impl Serializer {
+    /// Serializes an object [`Value`] into binary state.
+    ///
+    /// This is the inverse of [`Serializer::deserialize`] with the same
+    /// configuration. With [`SerializerFlags::STATEFUL_FLAGS`], the
+    /// configured flags are written into the state.
+    ///
+    /// Values are matched against the type list leniently: integers,
+    /// floats and booleans convert into each other where lossless,
+    /// enums may be given by name, and leaf types like vectors may be
+    /// given as objects with their fields. Dynamic properties also
+    /// accept a single value in place of a one-element list.
+    ///
+    /// In shallow mode, every property selected by the mask must be
+    /// present. In deep mode, all present properties are written and
+    /// missing ones are omitted from the state.
+    pub fn serialize<T: TypeTag>(&mut self, value: &Value) -> Result<Vec<u8>, Error> {
+        if let Value::Empty = value.unshared() {
+            return Err(Error::NullRoot);
+        }
+
+        log::info!("Serializing object with config {:?}", self.parts.options);
+
+        // The bits are written into a buffer retained across calls.
+        let mut writer = BitWriter::from_vec(std::mem::take(&mut self.zlib_parts.bits));
+        let res = object::serialize::<T>(&mut self.parts, value, &mut writer);
+        writer.realign_to_byte();
+
+        let mut bits = writer.into_inner();
+        let res = res.and_then(|()| self.zlib_parts.finish(&self.parts.options, &bits));
+        bits.clear();
+        self.zlib_parts.bits = bits;
+
+        res
+    }
+}
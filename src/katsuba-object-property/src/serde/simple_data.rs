@@ -1,4 +1,4 @@
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, BitWriter};
 use phf::phf_map;
 
 use crate::value::*;
@@ -7,6 +7,9 @@ use super::{utils, Error, SerializerOptions, SerializerParts};
 
 type ReadCallback = fn(&mut BitReader<'_>, &SerializerOptions) -> Result<Value, Error>;
 
+// Returns `None` when the value cannot be represented by the type.
+type WriteCallback = fn(&mut BitWriter, &SerializerOptions, &Value) -> Option<()>;
+
 static DESERIALIZER_LUT: phf::Map<&'static str, (bool, ReadCallback)> = phf_map! {
     // Primitive C++ types
     "bool" => (true, |r, _| utils::read_bool(r).map(Value::Bool)),
@@ -88,15 +91,286 @@ static DESERIALIZER_LUT: phf::Map<&'static str, (bool, ReadCallback)> = phf_map!
 };
 
 pub fn deserialize(
-    de: &SerializerParts,
+    de: &mut SerializerParts,
     ty: &str,
     reader: &mut BitReader<'_>,
 ) -> Option<Result<Value, Error>> {
-    DESERIALIZER_LUT.get(ty).map(|(bits, f)| {
-        if de.options.shallow && !bits {
-            reader.realign_to_byte();
+    let shallow = de.options.shallow;
+    let value = match de.options.leaf_types.as_deref().and_then(|t| t.get(ty)) {
+        Some(leaf) => {
+            if shallow && !leaf.bit_packed {
+                reader.realign_to_byte();
+            }
+            (leaf.read)(reader, &de.options)
+        }
+        None => {
+            let (bits, f) = DESERIALIZER_LUT.get(ty)?;
+            if shallow && !bits {
+                reader.realign_to_byte();
+            }
+            f(reader, &de.options)
+        }
+    };
+
+    Some(value.and_then(|value| {
+        match &value {
+            Value::String(s) => de.allocate(s.0.len())?,
+            Value::WString(s) => de.allocate(s.0.len() * 2)?,
+            _ => {}
+        }
+
+        Ok(value)
+    }))
+}
+
+/// Converts a default value from the type list into a [`Value`] of
+/// the simple data type `ty`, if it can be represented.
+pub fn from_default(ty: &str, default: &serde_json::Value) -> Option<Value> {
+    use serde_json::Value as Json;
+
+    match (ty, default) {
+        ("bool", Json::Bool(b)) => Some(Value::Bool(*b)),
+        ("float" | "double", Json::Number(n)) => n.as_f64().map(Value::Float),
+        (
+            "char" | "short" | "int" | "long" | "bi2" | "bi3" | "bi4" | "bi5" | "bi6" | "bi7"
+            | "s24",
+            Json::Number(n),
+        ) => n.as_i64().map(Value::Signed),
+        (
+            "unsigned char" | "unsigned short" | "wchar_t" | "unsigned int" | "unsigned long"
+            | "unsigned __int64" | "gid" | "union gid" | "bui2" | "bui3" | "bui4" | "bui5" | "bui6"
+            | "bui7" | "u24",
+            Json::Number(n),
+        ) => n.as_u64().map(Value::Unsigned),
+        ("std::string", Json::String(s)) => Some(Value::String(CxxStr(s.as_bytes().to_vec()))),
+        ("std::wstring", Json::String(s)) => {
+            Some(Value::WString(CxxWStr(s.encode_utf16().collect())))
+        }
+        _ => None,
+    }
+}
+
+fn write_bits(w: &mut BitWriter, v: &Value, nbits: u32) -> Option<()> {
+    utils::write_bits(w, utils::as_u64(v)?, nbits);
+    Some(())
+}
+
+fn write_string(w: &mut BitWriter, opts: &SerializerOptions, v: &Value) -> Option<()> {
+    match v {
+        Value::String(s) => utils::write_string(w, &s.0, opts),
+        Value::WString(s) => {
+            let s = std::string::String::from_utf16(&s.0).ok()?;
+            utils::write_string(w, s.as_bytes(), opts)
+        }
+        _ => None,
+    }
+}
+
+fn write_wstring(w: &mut BitWriter, opts: &SerializerOptions, v: &Value) -> Option<()> {
+    match v {
+        Value::WString(s) => utils::write_wstring(w, &s.0, opts),
+        Value::String(s) => {
+            let s: Vec<u16> = std::str::from_utf8(&s.0).ok()?.encode_utf16().collect();
+            utils::write_wstring(w, &s, opts)
+        }
+        _ => None,
+    }
+}
+
+fn write_color(w: &mut BitWriter, v: &Value) -> Option<()> {
+    let Color { r, g, b, a } = match v {
+        Value::Color(c) => *c,
+        v => Color {
+            r: utils::as_u64(utils::field(v, "r")?)? as u8,
+            g: utils::as_u64(utils::field(v, "g")?)? as u8,
+            b: utils::as_u64(utils::field(v, "b")?)? as u8,
+            a: utils::as_u64(utils::field(v, "a")?)? as u8,
+        },
+    };
+
+    [r, g, b, a]
+        .into_iter()
+        .for_each(|c| utils::write_bits(w, c as u64, u8::BITS));
+    Some(())
+}
+
+fn write_floats<const N: usize>(w: &mut BitWriter, floats: Option<[f32; N]>) -> Option<()> {
+    floats?.into_iter().for_each(|f| utils::write_f32(w, f));
+    Some(())
+}
+
+fn write_ints<const N: usize>(w: &mut BitWriter, ints: Option<[i32; N]>) -> Option<()> {
+    ints?
+        .into_iter()
+        .for_each(|i| utils::write_bits(w, i as u32 as u64, u32::BITS));
+    Some(())
+}
+
+fn float_row(v: &Value, name: &str) -> Option<[f32; 3]> {
+    match utils::field(v, name)?.unshared() {
+        Value::List(row) if row.len() == 3 => Some([
+            utils::as_f64(&row[0])? as f32,
+            utils::as_f64(&row[1])? as f32,
+            utils::as_f64(&row[2])? as f32,
+        ]),
+        _ => None,
+    }
+}
+
+static SERIALIZER_LUT: phf::Map<&'static str, WriteCallback> = phf_map! {
+    // Primitive C++ types
+    "bool" => |w, _, v| {
+        utils::write_bool(w, utils::as_bool(v)?);
+        Some(())
+    },
+    "char" => |w, _, v| write_bits(w, v, i8::BITS),
+    "unsigned char" => |w, _, v| write_bits(w, v, u8::BITS),
+    "short" => |w, _, v| write_bits(w, v, i16::BITS),
+    "unsigned short" => |w, _, v| write_bits(w, v, u16::BITS),
+    "wchar_t" => |w, _, v| write_bits(w, v, u16::BITS),
+    "int" => |w, _, v| write_bits(w, v, i32::BITS),
+    "unsigned int" => |w, _, v| write_bits(w, v, u32::BITS),
+    "long" => |w, _, v| write_bits(w, v, i32::BITS),
+    "unsigned long" => |w, _, v| write_bits(w, v, u32::BITS),
+    "float" => |w, _, v| {
+        utils::write_f32(w, utils::as_f64(v)? as f32);
+        Some(())
+    },
+    "double" => |w, _, v| {
+        utils::write_u64(w, utils::as_f64(v)?.to_bits());
+        Some(())
+    },
+    "unsigned __int64" => |w, _, v| {
+        utils::write_u64(w, utils::as_u64(v)?);
+        Some(())
+    },
+    "gid" => |w, _, v| {
+        utils::write_u64(w, utils::as_u64(v)?);
+        Some(())
+    },
+    "union gid" => |w, _, v| {
+        utils::write_u64(w, utils::as_u64(v)?);
+        Some(())
+    },
+
+    // Bit integers
+    "bi2" => |w, _, v| write_bits(w, v, 2),
+    "bui2" => |w, _, v| write_bits(w, v, 2),
+    "bi3" => |w, _, v| write_bits(w, v, 3),
+    "bui3" => |w, _, v| write_bits(w, v, 3),
+    "bi4" => |w, _, v| write_bits(w, v, 4),
+    "bui4" => |w, _, v| write_bits(w, v, 4),
+    "bi5" => |w, _, v| write_bits(w, v, 5),
+    "bui5" => |w, _, v| write_bits(w, v, 5),
+    "bi6" => |w, _, v| write_bits(w, v, 6),
+    "bui6" => |w, _, v| write_bits(w, v, 6),
+    "bi7" => |w, _, v| write_bits(w, v, 7),
+    "bui7" => |w, _, v| write_bits(w, v, 7),
+    "s24" => |w, _, v| write_bits(w, v, 24),
+    "u24" => |w, _, v| write_bits(w, v, 24),
+
+    // Strings
+    "std::string" => write_string,
+    "std::wstring" => write_wstring,
+
+    // Miscellaneous leaf types that are not PropertyClasses
+    "class Color" => |w, _, v| write_color(w, v),
+    "class Vector3D" => |w, _, v| write_floats(w, match v {
+        Value::Vec3(v) => Some([v.x, v.y, v.z]),
+        v => (|| Some([
+            utils::field_f32(v, "x")?,
+            utils::field_f32(v, "y")?,
+            utils::field_f32(v, "z")?,
+        ]))(),
+    }),
+    "class Quaternion" => |w, _, v| write_floats(w, match v {
+        Value::Quat(q) => Some([q.x, q.y, q.z, q.w]),
+        v => (|| Some([
+            utils::field_f32(v, "x")?,
+            utils::field_f32(v, "y")?,
+            utils::field_f32(v, "z")?,
+            utils::field_f32(v, "w")?,
+        ]))(),
+    }),
+    "class Euler" => |w, _, v| write_floats(w, match v {
+        Value::Euler(e) => Some([e.pitch, e.roll, e.yaw]),
+        v => (|| Some([
+            utils::field_f32(v, "pitch")?,
+            utils::field_f32(v, "roll")?,
+            utils::field_f32(v, "yaw")?,
+        ]))(),
+    }),
+    "class Matrix3x3" => |w, _, v| {
+        let Matrix { i, j, k } = match v {
+            Value::Mat3x3(m) => **m,
+            v => Matrix {
+                i: float_row(v, "i")?,
+                j: float_row(v, "j")?,
+                k: float_row(v, "k")?,
+            },
+        };
+
+        write_floats(w, Some(i))?;
+        write_floats(w, Some(j))?;
+        write_floats(w, Some(k))
+    },
+    "class Size<int>" => |w, _, v| write_ints(w, match v {
+        Value::SizeInt(s) => Some([s.width, s.height]),
+        v => (|| Some([utils::field_i32(v, "width")?, utils::field_i32(v, "height")?]))(),
+    }),
+    "class Point<int>" => |w, _, v| write_ints(w, match v {
+        Value::PointInt(p) => Some([p.x, p.y]),
+        v => (|| Some([utils::field_i32(v, "x")?, utils::field_i32(v, "y")?]))(),
+    }),
+    "class Point<float>" => |w, _, v| write_floats(w, match v {
+        Value::PointFloat(p) => Some([p.x, p.y]),
+        v => (|| Some([utils::field_f32(v, "x")?, utils::field_f32(v, "y")?]))(),
+    }),
+    "class Rect<int>" => |w, _, v| write_ints(w, match v {
+        Value::RectInt(r) => Some([r.left, r.top, r.right, r.bottom]),
+        v => (|| Some([
+            utils::field_i32(v, "left")?,
+            utils::field_i32(v, "top")?,
+            utils::field_i32(v, "right")?,
+            utils::field_i32(v, "bottom")?,
+        ]))(),
+    }),
+    "class Rect<float>" => |w, _, v| write_floats(w, match v {
+        Value::RectFloat(r) => Some([r.left, r.top, r.right, r.bottom]),
+        v => (|| Some([
+            utils::field_f32(v, "left")?,
+            utils::field_f32(v, "top")?,
+            utils::field_f32(v, "right")?,
+            utils::field_f32(v, "bottom")?,
+        ]))(),
+    }),
+};
+
+pub fn serialize(
+    ser: &SerializerParts,
+    ty: &str,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Option<Result<(), Error>> {
+    let value = value.unshared();
+    let res = match ser.options.leaf_types.as_deref().and_then(|t| t.get(ty)) {
+        Some(leaf) => {
+            if ser.options.shallow && !leaf.bit_packed {
+                writer.realign_to_byte();
+            }
+            (leaf.write)(writer, &ser.options, value)
+        }
+        None => {
+            let f = SERIALIZER_LUT.get(ty)?;
+
+            // Mirror the realignment done by the deserializer above.
+            let bits = DESERIALIZER_LUT.get(ty).is_some_and(|(bits, _)| *bits);
+            if ser.options.shallow && !bits {
+                writer.realign_to_byte();
+            }
+            f(writer, &ser.options, value)
         }
+    };
 
-        f(reader, &de.options)
-    })
+    Some(res.ok_or_else(|| Error::InvalidValue(ty.to_owned())))
 }
@@ -0,0 +1,58 @@
+use std::{cell::RefCell, fmt};
+
+/// A primitive read from the object data, as passed to a trace sink.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Read<'a> {
+    /// An integer of `nbits` bits read at bit `position`.
+    Bits {
+        position: usize,
+        nbits: u32,
+        value: u64,
+    },
+    /// A run of bytes read at bit `position`.
+    Bytes { position: usize, data: &'a [u8] },
+}
+
+impl fmt::Display for Read<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bits {
+                position,
+                nbits,
+                value,
+            } => write!(f, "u{nbits} @ bit {position} = {value:#x}"),
+            Self::Bytes { position, data } => {
+                write!(f, "bytes[{}] @ bit {position} = ", data.len())?;
+                data.iter().try_for_each(|b| write!(f, "{b:02x}"))
+            }
+        }
+    }
+}
+
+/// A sink receiving every primitive read on the current thread.
+pub type Sink = Box<dyn FnMut(&Read<'_>)>;
+
+thread_local! {
+    static SINK: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+/// Installs `sink` for all deserializations on the current thread,
+/// returning the previously installed sink.
+///
+/// Passing [`None`] disables tracing again.
+pub fn set_sink(sink: Option<Sink>) -> Option<Sink> {
+    SINK.with(|s| s.replace(sink))
+}
+
+// Passes `read` to the sink of the current thread, if any.
+//
+// Reads done by the sink itself are not traced.
+pub(crate) fn record(read: Read<'_>) {
+    SINK.with(|s| {
+        if let Ok(mut sink) = s.try_borrow_mut() {
+            if let Some(sink) = sink.as_mut() {
+                sink(&read);
+            }
+        }
+    });
+}
@@ -1,5 +1,5 @@
-use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
-use katsuba_bit_buf::{utils::sign_extend, BitReader};
+use byteorder::{ByteOrder, LittleEndian};
+use katsuba_bit_buf::{utils::sign_extend, BitReader, BitWriter};
 use katsuba_utils::align::align_up;
 
 use super::{Error, SerializerFlags, SerializerOptions};
@@ -10,17 +10,41 @@ pub const fn bits_to_bytes(bits: usize) -> usize {
     align_up(bits, u8::BITS as _) >> 3
 }
 
+/// Reads an unsigned integer of up to 32 bits from `reader`.
 #[inline]
 pub fn read_bits(reader: &mut BitReader<'_>, nbits: u32) -> Result<u64, Error> {
     if reader.buffered_bits() < nbits {
         reader.refill_bits();
     }
 
+    #[cfg(feature = "trace")]
+    let position = reader.position();
+
     let v = reader.peek(nbits)?;
     reader.consume(nbits)?;
+
+    #[cfg(feature = "trace")]
+    super::trace::record(super::trace::Read::Bits {
+        position,
+        nbits,
+        value: v,
+    });
+
     Ok(v)
 }
 
+// Reads `len` bits verbatim, packed into bytes from the least
+// significant bit on.
+pub fn read_raw_bits(reader: &mut BitReader<'_>, len: usize) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(bits_to_bytes(len).min(reader.remaining_bits() / 8 + 1));
+    for start in (0..len).step_by(u8::BITS as usize) {
+        let nbits = (len - start).min(u8::BITS as usize) as u32;
+        out.push(read_bits(reader, nbits)? as u8);
+    }
+
+    Ok(out)
+}
+
 #[inline]
 pub fn read_signed_bits(reader: &mut BitReader<'_>, nbits: u32) -> Result<i64, Error> {
     let v = read_bits(reader, nbits)?;
@@ -30,10 +54,20 @@ pub fn read_signed_bits(reader: &mut BitReader<'_>, nbits: u32) -> Result<i64, E
 #[inline]
 pub fn read_u64(reader: &mut BitReader<'_>) -> Result<u64, Error> {
     reader.realign_to_byte();
-    reader
-        .read_bytes(8)
-        .map(LittleEndian::read_u64)
-        .map_err(Into::into)
+
+    #[cfg(feature = "trace")]
+    let position = reader.position();
+
+    let v = reader.read_bytes(8).map(LittleEndian::read_u64)?;
+
+    #[cfg(feature = "trace")]
+    super::trace::record(super::trace::Read::Bits {
+        position,
+        nbits: u64::BITS,
+        value: v,
+    });
+
+    Ok(v)
 }
 
 #[inline]
@@ -78,6 +112,19 @@ pub fn read_container_length(reader: &mut BitReader<'_>, compact: bool) -> Resul
     Ok(len)
 }
 
+#[inline]
+pub fn check_limit(what: &'static str, requested: usize, limit: usize) -> Result<(), Error> {
+    if requested > limit {
+        return Err(Error::LimitExceeded {
+            what,
+            requested,
+            limit,
+        });
+    }
+
+    Ok(())
+}
+
 #[inline]
 pub fn read_string<'a>(
     reader: &mut BitReader<'a>,
@@ -88,10 +135,20 @@ pub fn read_string<'a>(
         opts.flags
             .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
     )?;
+    check_limit("string length", len, opts.limits.max_string_len)?;
 
     if len != 0 {
         reader.realign_to_byte();
-        reader.read_bytes(len).map_err(Into::into)
+
+        #[cfg(feature = "trace")]
+        let position = reader.position();
+
+        let data = reader.read_bytes(len)?;
+
+        #[cfg(feature = "trace")]
+        super::trace::record(super::trace::Read::Bytes { position, data });
+
+        Ok(data)
     } else {
         Ok(&[])
     }
@@ -107,8 +164,9 @@ pub fn read_wstring(
         opts.flags
             .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
     )?;
+    check_limit("string length", len, opts.limits.max_string_len)?;
 
-    let mut out = Vec::with_capacity(len);
+    let mut out = Vec::with_capacity(len.min(reader.remaining_bits() / u16::BITS as usize));
     if len != 0 {
         reader.realign_to_byte();
         for _ in 0..len {
@@ -125,6 +183,9 @@ pub fn read_color(reader: &mut BitReader<'_>) -> Result<Color, Error> {
         reader.refill_bits();
     }
 
+    #[cfg(feature = "trace")]
+    let position = reader.position();
+
     let r = reader.peek(u8::BITS)? as u8;
     reader.consume(u8::BITS)?;
     let g = reader.peek(u8::BITS)? as u8;
@@ -134,63 +195,229 @@ pub fn read_color(reader: &mut BitReader<'_>) -> Result<Color, Error> {
     let a = reader.peek(u8::BITS)? as u8;
     reader.consume(u8::BITS)?;
 
+    #[cfg(feature = "trace")]
+    super::trace::record(super::trace::Read::Bits {
+        position,
+        nbits: u32::BITS,
+        value: u32::from_le_bytes([r, g, b, a]) as u64,
+    });
+
     Ok(Color { r, g, b, a })
 }
 
+// Floats in leaf types are read through the bit buffer, like they are
+// written. `BitReader::read_bytes` starts at the byte cursor, which is
+// ahead of the bits already buffered for `read_bits`, so it would skip
+// data after property headers in deep mode.
 #[inline]
-pub fn read_vec3(reader: &mut BitReader<'_>) -> Result<Vec3, Error> {
-    let mut data = reader.read_bytes(12)?;
+fn read_f32(reader: &mut BitReader<'_>) -> Result<f32, Error> {
+    read_bits(reader, u32::BITS).map(|v| f32::from_bits(v as u32))
+}
 
-    let x = data.read_f32::<LittleEndian>()?;
-    let y = data.read_f32::<LittleEndian>()?;
-    let z = data.read_f32::<LittleEndian>()?;
+#[inline]
+pub fn read_vec3(reader: &mut BitReader<'_>) -> Result<Vec3, Error> {
+    let x = read_f32(reader)?;
+    let y = read_f32(reader)?;
+    let z = read_f32(reader)?;
 
     Ok(Vec3 { x, y, z })
 }
 
 #[inline]
 pub fn read_quat(reader: &mut BitReader<'_>) -> Result<Quaternion, Error> {
-    let mut data = reader.read_bytes(16)?;
-
-    let x = data.read_f32::<LittleEndian>()?;
-    let y = data.read_f32::<LittleEndian>()?;
-    let z = data.read_f32::<LittleEndian>()?;
-    let w = data.read_f32::<LittleEndian>()?;
+    let x = read_f32(reader)?;
+    let y = read_f32(reader)?;
+    let z = read_f32(reader)?;
+    let w = read_f32(reader)?;
 
     Ok(Quaternion { x, y, z, w })
 }
 
 #[inline]
 pub fn read_euler(reader: &mut BitReader<'_>) -> Result<Euler, Error> {
-    let mut data = reader.read_bytes(12)?;
-
     // TODO: Is this order correct?
-    let pitch = data.read_f32::<LittleEndian>()?;
-    let roll = data.read_f32::<LittleEndian>()?;
-    let yaw = data.read_f32::<LittleEndian>()?;
+    let pitch = read_f32(reader)?;
+    let roll = read_f32(reader)?;
+    let yaw = read_f32(reader)?;
 
     Ok(Euler { pitch, roll, yaw })
 }
 
 #[inline]
 pub fn read_matrix(reader: &mut BitReader<'_>) -> Result<Matrix, Error> {
-    let mut data = reader.read_bytes(36)?;
-
-    let i = [
-        data.read_f32::<LittleEndian>()?,
-        data.read_f32::<LittleEndian>()?,
-        data.read_f32::<LittleEndian>()?,
-    ];
-    let j = [
-        data.read_f32::<LittleEndian>()?,
-        data.read_f32::<LittleEndian>()?,
-        data.read_f32::<LittleEndian>()?,
-    ];
-    let k = [
-        data.read_f32::<LittleEndian>()?,
-        data.read_f32::<LittleEndian>()?,
-        data.read_f32::<LittleEndian>()?,
-    ];
+    let i = [read_f32(reader)?, read_f32(reader)?, read_f32(reader)?];
+    let j = [read_f32(reader)?, read_f32(reader)?, read_f32(reader)?];
+    let k = [read_f32(reader)?, read_f32(reader)?, read_f32(reader)?];
 
     Ok(Matrix { i, j, k })
 }
+
+// Writes `len` bits as read by `read_raw_bits`.
+pub fn write_raw_bits(writer: &mut BitWriter, bits: &[u8], len: usize) {
+    for (idx, byte) in bits.iter().enumerate() {
+        let nbits = len
+            .saturating_sub(idx * u8::BITS as usize)
+            .min(u8::BITS as usize);
+        write_bits(writer, *byte as u64, nbits as u32);
+    }
+}
+
+/// Writes the low `nbits` bits of `value` to `writer`, with `nbits`
+/// being at most 32.
+#[inline]
+pub fn write_bits(writer: &mut BitWriter, value: u64, nbits: u32) {
+    debug_assert!(nbits <= u32::BITS);
+
+    if writer.remaining() < nbits {
+        writer.commit();
+    }
+
+    // After a commit, at least 49 bits are free in the buffer.
+    writer.offer(value, nbits).unwrap();
+}
+
+#[inline]
+pub fn write_u64(writer: &mut BitWriter, value: u64) {
+    writer.realign_to_byte();
+    writer.write_bytes(&value.to_le_bytes());
+}
+
+#[inline]
+pub fn write_bool(writer: &mut BitWriter, value: bool) {
+    write_bits(writer, value as u64, 1);
+}
+
+#[inline]
+pub fn write_compact_length(writer: &mut BitWriter, len: usize) -> Option<()> {
+    if len < 1 << (u8::BITS - 1) {
+        write_bool(writer, false);
+        write_bits(writer, len as u64, u8::BITS - 1);
+    } else if len < 1 << (u32::BITS - 1) {
+        write_bool(writer, true);
+        write_bits(writer, len as u64, u32::BITS - 1);
+    } else {
+        return None;
+    }
+
+    Some(())
+}
+
+#[inline]
+pub fn write_string_length(writer: &mut BitWriter, len: usize, compact: bool) -> Option<()> {
+    match compact {
+        true => write_compact_length(writer, len),
+        false => {
+            let len = u16::try_from(len).ok()?;
+            writer.realign_to_byte();
+            write_bits(writer, len as u64, u16::BITS);
+            Some(())
+        }
+    }
+}
+
+#[inline]
+pub fn write_container_length(writer: &mut BitWriter, len: usize, compact: bool) -> Option<()> {
+    match compact {
+        true => write_compact_length(writer, len),
+        false => {
+            let len = u32::try_from(len).ok()?;
+            writer.realign_to_byte();
+            write_bits(writer, len as u64, u32::BITS);
+            Some(())
+        }
+    }
+}
+
+#[inline]
+pub fn write_string(writer: &mut BitWriter, value: &[u8], opts: &SerializerOptions) -> Option<()> {
+    write_string_length(
+        writer,
+        value.len(),
+        opts.flags
+            .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
+    )?;
+
+    if !value.is_empty() {
+        writer.realign_to_byte();
+        writer.write_bytes(value);
+    }
+
+    Some(())
+}
+
+#[inline]
+pub fn write_wstring(
+    writer: &mut BitWriter,
+    value: &[u16],
+    opts: &SerializerOptions,
+) -> Option<()> {
+    write_string_length(
+        writer,
+        value.len(),
+        opts.flags
+            .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
+    )?;
+
+    if !value.is_empty() {
+        writer.realign_to_byte();
+        for &c in value {
+            write_bits(writer, c as u64, u16::BITS);
+        }
+    }
+
+    Some(())
+}
+
+#[inline]
+pub fn write_f32(writer: &mut BitWriter, value: f32) {
+    write_bits(writer, value.to_bits() as u64, u32::BITS);
+}
+
+// The following helpers coerce values into what a type expects.
+//
+// This is lenient about the exact variant so that values which went
+// through formats like JSON, where e.g. integers and floats are not
+// always distinguishable, can still be serialized.
+
+pub fn as_u64(value: &Value) -> Option<u64> {
+    match value.unshared() {
+        Value::Unsigned(v) => Some(*v),
+        Value::Signed(v) | Value::Enum(v) => Some(*v as u64),
+        Value::NamedEnum { value, .. } => Some(*value as u64),
+        Value::Bool(v) => Some(*v as u64),
+        Value::Float(v) if v.fract() == 0.0 => Some(*v as i64 as u64),
+        _ => None,
+    }
+}
+
+pub fn as_f64(value: &Value) -> Option<f64> {
+    match value.unshared() {
+        Value::Float(v) => Some(*v),
+        Value::Unsigned(v) => Some(*v as f64),
+        Value::Signed(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+
+pub fn as_bool(value: &Value) -> Option<bool> {
+    match value.unshared() {
+        Value::Bool(v) => Some(*v),
+        v => as_u64(v).map(|v| v != 0),
+    }
+}
+
+/// Gets the member `name` of an object value.
+pub fn field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    match value.unshared() {
+        Value::Object { obj, .. } => obj.get(name),
+        _ => None,
+    }
+}
+
+pub fn field_f32(value: &Value, name: &str) -> Option<f32> {
+    field(value, name).and_then(as_f64).map(|v| v as f32)
+}
+
+pub fn field_i32(value: &Value, name: &str) -> Option<i32> {
+    field(value, name).and_then(as_u64).map(|v| v as i32)
+}
@@ -0,0 +1,299 @@
+//! Typed deserialization of ObjectProperty values into Rust types.
+//!
+//! Instead of walking dynamic [`Value`]s, users can declare structs
+//! matching game classes and convert deserialized objects into them.
+//! Types implement [`ObjectProperty`], usually through the derive
+//! macro of the same name from the `derive` feature:
+//!
+//! ```ignore
+//! #[derive(ObjectProperty)]
+//! #[op(class = "class WizItemTemplate")]
+//! struct WizItemTemplate {
+//!     #[op(rename = "m_objectName")]
+//!     name: String,
+//!     #[op(rename = "m_templateID")]
+//!     template_id: u32,
+//! }
+//! ```
+//!
+//! Class and property names are checked against the [`TypeList`] at
+//! runtime, so structs which went out of date with the game fail to
+//! convert instead of silently missing data.
+
+use katsuba_types::TypeDef;
+pub use katsuba_types::TypeList;
+use katsuba_utils::{
+    hash::{djb2, string_id},
+    thiserror::{self, Error as ThisError},
+};
+
+use crate::{
+    serde::{self, Serializer, TypeTag},
+    value::*,
+    Value,
+};
+
+#[cfg(feature = "derive")]
+pub use katsuba_op_derive::ObjectProperty;
+
+/// Errors that may occur when converting values into Rust types.
+#[derive(Debug, ThisError)]
+pub enum Error {
+    /// Failed to deserialize the object in the first place.
+    #[error("{0}")]
+    Serializer(#[from] serde::Error),
+
+    /// The class of a Rust type is not part of the type list.
+    #[error("class '{0}' is not in the type list")]
+    UnknownClass(&'static str),
+
+    /// An object had a different class than the Rust type expects.
+    #[error("expected object of class '{expected}', got type with hash '{actual}'")]
+    ClassMismatch { expected: &'static str, actual: u32 },
+
+    /// A field refers to a property which is not part of its class.
+    #[error("class '{class}' has no property '{property}'")]
+    UnknownProperty {
+        class: std::string::String,
+        property: &'static str,
+    },
+
+    /// An object lacks a property which is required by a field.
+    #[error("missing value for property '{0}'")]
+    MissingProperty(&'static str),
+
+    /// A value cannot be represented by the requested Rust type.
+    #[error("value cannot be converted to '{0}'")]
+    InvalidValue(&'static str),
+
+    /// Failed to convert the value of a property.
+    #[error("property '{name}': {source}")]
+    Property {
+        name: &'static str,
+        source: Box<Error>,
+    },
+}
+
+/// A Rust type which corresponds to an ObjectProperty class.
+pub trait ObjectProperty: Sized {
+    /// The name of the class in the type list, e.g. `class Foo`.
+    const CLASS_NAME: &'static str;
+
+    /// Converts the members of an object with type `hash` into `Self`.
+    fn from_object(types: &TypeList, hash: u32, obj: Object) -> Result<Self, Error>;
+}
+
+/// A Rust type which can be converted from a dynamic [`Value`].
+pub trait FromValue: Sized {
+    /// Converts `value` into `Self`.
+    fn from_value(types: &TypeList, value: Value) -> Result<Self, Error>;
+
+    /// Produces a value for properties absent from an object, if the
+    /// type has a representation for that.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Looks up the type definition for `T` and checks that objects with
+/// type `hash` are instances of it.
+pub fn resolve<T: ObjectProperty>(types: &TypeList, hash: u32) -> Result<&TypeDef, Error> {
+    let name = T::CLASS_NAME;
+    let type_def = [string_id(name.as_bytes()), djb2(name.as_bytes())]
+        .into_iter()
+        .find_map(|h| types.0.get(&h).filter(|t| t.name == name))
+        .ok_or(Error::UnknownClass(name))?;
+
+    match types.0.get(&hash) {
+        Some(t) if t.name == name => Ok(type_def),
+        _ => Err(Error::ClassMismatch {
+            expected: name,
+            actual: hash,
+        }),
+    }
+}
+
+/// Takes the value of property `name` of `type_def` out of `obj` and
+/// converts it into `T`.
+pub fn field<T: FromValue>(
+    types: &TypeList,
+    type_def: &TypeDef,
+    obj: &mut Object,
+    name: &'static str,
+) -> Result<T, Error> {
+    if !type_def.properties.iter().any(|p| p.name == name) {
+        return Err(Error::UnknownProperty {
+            class: type_def.name.to_string(),
+            property: name,
+        });
+    }
+
+    match obj.remove(name) {
+        Some(value) => T::from_value(types, value).map_err(|e| Error::Property {
+            name,
+            source: Box::new(e),
+        }),
+        None => T::from_missing().ok_or(Error::MissingProperty(name)),
+    }
+}
+
+/// Converts an object `value` into `T`.
+///
+/// This is the [`FromValue`] implementation for types deriving
+/// [`ObjectProperty`].
+pub fn from_object_value<T: ObjectProperty>(types: &TypeList, value: Value) -> Result<T, Error> {
+    match value.into_unshared() {
+        Value::Object { hash, obj } => T::from_object(types, hash, obj),
+        _ => Err(Error::InvalidValue(T::CLASS_NAME)),
+    }
+}
+
+impl Serializer {
+    /// Deserializes an object from the given data and converts it
+    /// into `O`.
+    ///
+    /// See [`Serializer::deserialize`] for details.
+    pub fn deserialize_into<O: ObjectProperty, T: TypeTag>(
+        &mut self,
+        data: &[u8],
+    ) -> Result<O, Error> {
+        let value = self.deserialize::<T>(data)?;
+        let types = self.parts.types.clone();
+        from_object_value(&types, value)
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(_types: &TypeList, value: Value) -> Result<Self, Error> {
+        Ok(value)
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(types: &TypeList, value: Value) -> Result<Self, Error> {
+        match value.into_unshared() {
+            Value::Empty => Ok(None),
+            v => T::from_value(types, v).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: FromValue> FromValue for Box<T> {
+    fn from_value(types: &TypeList, value: Value) -> Result<Self, Error> {
+        T::from_value(types, value).map(Box::new)
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(types: &TypeList, value: Value) -> Result<Self, Error> {
+        match value.into_unshared() {
+            Value::List(list) => list.into_iter().map(|v| T::from_value(types, v)).collect(),
+            // Containers with a single element may have been collapsed
+            // into that element on deserialization.
+            v => T::from_value(types, v).map(|v| vec![v]),
+        }
+    }
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(_types: &TypeList, value: Value) -> Result<Self, Error> {
+                    let v = match value.into_unshared() {
+                        Value::Unsigned(v) => <$ty>::try_from(v).ok(),
+                        Value::Signed(v) | Value::Enum(v) => <$ty>::try_from(v).ok(),
+                        Value::NamedEnum { value, .. } => <$ty>::try_from(value).ok(),
+                        Value::Bool(v) => Some(v as $ty),
+                        _ => None,
+                    };
+                    v.ok_or(Error::InvalidValue(stringify!($ty)))
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+macro_rules! impl_float {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(_types: &TypeList, value: Value) -> Result<Self, Error> {
+                    match value.into_unshared() {
+                        Value::Float(v) => Ok(v as $ty),
+                        Value::Unsigned(v) => Ok(v as $ty),
+                        Value::Signed(v) => Ok(v as $ty),
+                        _ => Err(Error::InvalidValue(stringify!($ty))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_float!(f32, f64);
+
+impl FromValue for bool {
+    fn from_value(_types: &TypeList, value: Value) -> Result<Self, Error> {
+        match value.into_unshared() {
+            Value::Bool(v) => Ok(v),
+            Value::Unsigned(v @ (0 | 1)) => Ok(v == 1),
+            _ => Err(Error::InvalidValue("bool")),
+        }
+    }
+}
+
+impl FromValue for std::string::String {
+    fn from_value(_types: &TypeList, value: Value) -> Result<Self, Error> {
+        match value.into_unshared() {
+            Value::String(CxxStr(v)) => std::string::String::from_utf8(v).ok(),
+            Value::WString(CxxWStr(v)) => std::string::String::from_utf16(&v).ok(),
+            _ => None,
+        }
+        .ok_or(Error::InvalidValue("String"))
+    }
+}
+
+impl FromValue for Matrix {
+    fn from_value(_types: &TypeList, value: Value) -> Result<Self, Error> {
+        match value.into_unshared() {
+            Value::Mat3x3(v) => Ok(*v),
+            _ => Err(Error::InvalidValue("Matrix")),
+        }
+    }
+}
+
+macro_rules! impl_leaf {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(_types: &TypeList, value: Value) -> Result<Self, Error> {
+                    match value.into_unshared() {
+                        Value::$variant(v) => Ok(v),
+                        _ => Err(Error::InvalidValue(stringify!($ty))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_leaf! {
+    CxxStr => String,
+    CxxWStr => WString,
+    Color => Color,
+    Vec3 => Vec3,
+    Quaternion => Quat,
+    Euler => Euler,
+    Point<i32> => PointInt,
+    Point<f32> => PointFloat,
+    Size<i32> => SizeInt,
+    Rect<i32> => RectInt,
+    Rect<f32> => RectFloat,
+}
@@ -3,31 +3,62 @@
 //! Values have dynamic types and can be composed, at the cost of
 //! incurring memory and performance overhead.
 
+use std::sync::Arc;
+
 pub use smartstring::alias::String;
 
 mod color;
 pub use color::*;
 
+#[cfg(feature = "serde")]
+mod de;
+
+mod diff;
+pub use diff::*;
+
 mod drop;
 
+#[cfg(feature = "serde")]
+mod hashes;
+#[cfg(feature = "serde")]
+pub use hashes::*;
+
+mod intern;
+pub use intern::*;
+
 mod math;
 pub use math::*;
 
+mod name;
+pub use name::Name;
+pub(crate) use name::Names;
+
+mod links;
+pub use links::*;
+
 mod list;
 pub use list::*;
 
 mod object;
 pub use object::*;
 
+mod path;
+
 mod strings;
 pub use strings::*;
 
+mod table;
+pub use table::*;
+
 // TODO: Evaluate optimizations.
 
 /// A runtime value from the ObjectProperty system.
 ///
 /// Its type is dynamically assigned at runtime, which mandates
 /// appropriate checks for interpreting its contents.
+///
+/// With the `serde` feature, values can be serialized to and loaded
+/// back from self-describing formats such as JSON.
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(feature = "serde", serde(untagged))]
 #[derive(Clone, Debug, PartialEq)]
@@ -51,6 +82,17 @@ pub enum Value {
 
     /// An enum variant or bitflags.
     Enum(i64),
+    /// An enum variant or bitflags along with its symbolic name, as
+    /// produced with [`SerializerOptions::enum_names`].
+    ///
+    /// The name is [`None`] for variants which are unknown to the
+    /// type list.
+    ///
+    /// [`SerializerOptions::enum_names`]: crate::serde::SerializerOptions::enum_names
+    NamedEnum {
+        name: Option<String>,
+        value: i64,
+    },
 
     /// A homogenous list of elements.
     List(List),
@@ -82,4 +124,24 @@ pub enum Value {
     RectInt(Rect<i32>),
     /// A rectangle described by floating-point edges.
     RectFloat(Rect<f32>),
+
+    /// A value shared between multiple places, as produced by an
+    /// [`Interner`].
+    Shared(Arc<Value>),
+
+    /// The raw data of a property which is missing from the type
+    /// list, as produced with [`SerializerOptions::preserve_unknown`].
+    ///
+    /// [`SerializerOptions::preserve_unknown`]: crate::serde::SerializerOptions::preserve_unknown
+    Unknown {
+        /// The hash of the property.
+        #[cfg_attr(feature = "serde", serde(rename = "$__unknown"))]
+        hash: u32,
+        /// The length of the data in bits.
+        #[cfg_attr(feature = "serde", serde(rename = "$__len"))]
+        len: usize,
+        /// The data, packed from the least significant bit on.
+        #[cfg_attr(feature = "serde", serde(rename = "$__bits"))]
+        bits: Vec<u8>,
+    },
 }
@@ -0,0 +1,200 @@
+use std::{collections::BTreeMap, fmt};
+
+use katsuba_types::Container;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use super::{CxxStr, List, Name, Object, Value};
+
+/// Deserializes values in the shape produced by their `Serialize`
+/// implementation.
+///
+/// Maps with a `$__type` key become typed objects and maps holding
+/// `$__items` become annotated lists. Other maps become objects with
+/// a type hash of `0`, which the serializer accepts for leaf types
+/// like vectors. Annotations from [`WithHashes`][super::WithHashes]
+/// are ignored. Typing of everything else, e.g. whether a string is
+/// wide or an integer is an enum variant, is left to the serializer
+/// and the type list.
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an ObjectProperty value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Empty)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Empty)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Unsigned(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        // Keep non-negative integers consistent across formats.
+        Ok(match u64::try_from(v) {
+            Ok(v) => Value::Unsigned(v),
+            Err(_) => Value::Signed(v),
+        })
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(CxxStr(v.as_bytes().to_vec())))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(CxxStr(v.into_bytes())))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::String(CxxStr(v.to_vec())))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::String(CxxStr(v)))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut inner = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(value) = seq.next_element()? {
+            inner.push(value);
+        }
+
+        Ok(Value::List(List::new(inner)))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut inner = BTreeMap::<Name, Value>::new();
+        while let Some((key, value)) = map.next_entry::<std::string::String, Value>()? {
+            inner.insert(key.into(), value);
+        }
+
+        if let Some(items) = inner.remove("$__items") {
+            let container = match inner.remove("$__container") {
+                Some(Value::String(CxxStr(c))) => Some(container(&c)),
+                Some(_) => return Err(de::Error::custom("'$__container' must be a string")),
+                None => None,
+            };
+            let Value::List(mut list) = items else {
+                return Err(de::Error::custom("'$__items' must be a list"));
+            };
+
+            list.container = container;
+            return Ok(Value::List(list));
+        }
+
+        if let Some(hash) = inner.remove("$__unknown") {
+            return unknown(hash, &mut inner);
+        }
+
+        // Enums with names have exactly these two keys, whereas objects
+        // always carry a type hash.
+        if inner.len() == 2 {
+            if let (Some(name), Some(value)) = (inner.get("name"), inner.get("value")) {
+                let name = match name {
+                    Value::String(CxxStr(s)) => Some(
+                        std::str::from_utf8(s)
+                            .map_err(|_| de::Error::custom("enum name must be UTF-8"))?
+                            .into(),
+                    ),
+                    Value::Empty => None,
+                    _ => return Err(de::Error::custom("enum name must be a string")),
+                };
+                let value = match *value {
+                    Value::Unsigned(v) => v as i64,
+                    Value::Signed(v) => v,
+                    _ => return Err(de::Error::custom("enum value must be an integer")),
+                };
+
+                return Ok(Value::NamedEnum { name, value });
+            }
+        }
+
+        // Annotations written by `WithHashes` and bit spans are purely
+        // informational.
+        inner.remove("$__name");
+        inner.remove("$__hashes");
+        inner.remove("$__spans");
+
+        let hash = match inner.remove("$__type") {
+            Some(Value::Unsigned(hash)) => u32::try_from(hash)
+                .map_err(|_| de::Error::custom("'$__type' must be a type hash"))?,
+            Some(_) => return Err(de::Error::custom("'$__type' must be a type hash")),
+            None => 0,
+        };
+
+        Ok(Value::Object {
+            hash,
+            obj: Object { inner },
+        })
+    }
+}
+
+// Reads the fields of a `Value::Unknown` besides its hash.
+fn unknown<E: de::Error>(hash: Value, inner: &mut BTreeMap<Name, Value>) -> Result<Value, E> {
+    let (Value::Unsigned(hash), Some(Value::Unsigned(len)), Some(Value::List(bits))) =
+        (hash, inner.remove("$__len"), inner.remove("$__bits"))
+    else {
+        return Err(de::Error::custom("malformed unknown property"));
+    };
+
+    let bits = bits
+        .iter()
+        .map(|b| match b {
+            Value::Unsigned(b) => u8::try_from(*b).ok(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| de::Error::custom("'$__bits' must hold bytes"))?;
+
+    Ok(Value::Unknown {
+        hash: u32::try_from(hash).map_err(|_| de::Error::custom("bad property hash"))?,
+        len: len as usize,
+        bits,
+    })
+}
+
+fn container(name: &[u8]) -> Container {
+    match name {
+        b"Static" => Container::Static,
+        b"Vector" => Container::Vector,
+        b"List" => Container::List,
+        _ => Container::Other,
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt::Write;
+
+use super::{List, Object, Value};
+
+/// A single difference between two values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change<'a> {
+    /// A value only present in the second value.
+    Added(&'a Value),
+    /// A value only present in the first value.
+    Removed(&'a Value),
+    /// A value which differs between both values.
+    Changed(&'a Value, &'a Value),
+}
+
+impl Change<'_> {
+    /// Gets a short name for the kind of change.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Added(..) => "added",
+            Self::Removed(..) => "removed",
+            Self::Changed(..) => "changed",
+        }
+    }
+}
+
+impl Value {
+    /// Computes the structural differences from `self` to `other`.
+    ///
+    /// Every change is keyed by a path like `m_foo.m_bar[2]` relative
+    /// to the compared values, in the syntax of [`Value::get_path`].
+    /// Objects of different types are reported as changed as a whole.
+    pub fn diff<'a>(&'a self, other: &'a Value) -> Vec<(String, Change<'a>)> {
+        let mut out = Vec::new();
+        diff_values(&mut String::new(), self, other, &mut out);
+        out
+    }
+}
+
+/// Collects the differences between two values, keyed by a path
+/// like `m_foo.m_bar[2]` relative to the compared roots.
+///
+/// `path` is the prefix for all produced paths.
+pub fn diff_values<'a>(
+    path: &mut String,
+    a: &'a Value,
+    b: &'a Value,
+    out: &mut Vec<(String, Change<'a>)>,
+) {
+    match (a, b) {
+        (Value::Shared(a), _) => diff_values(path, a, b, out),
+        (_, Value::Shared(b)) => diff_values(path, a, b, out),
+        (Value::Object { hash: ha, obj: oa }, Value::Object { hash: hb, obj: ob }) if ha == hb => {
+            diff_objects(path, oa, ob, out)
+        }
+        (Value::List(la), Value::List(lb)) => diff_lists(path, la, lb, out),
+
+        _ if a != b => out.push((path.clone(), Change::Changed(a, b))),
+        _ => {}
+    }
+}
+
+/// Like [`diff_values`], but for the members of two objects.
+pub fn diff_objects<'a>(
+    path: &mut String,
+    a: &'a Object,
+    b: &'a Object,
+    out: &mut Vec<(String, Change<'a>)>,
+) {
+    let len = path.len();
+
+    for (key, va) in a {
+        push_key(path, key);
+        match b.get(key) {
+            Some(vb) => diff_values(path, va, vb, out),
+            None => out.push((path.clone(), Change::Removed(va))),
+        }
+        path.truncate(len);
+    }
+
+    for (key, vb) in b {
+        if !a.contains_key(key) {
+            push_key(path, key);
+            out.push((path.clone(), Change::Added(vb)));
+            path.truncate(len);
+        }
+    }
+}
+
+/// Like [`diff_values`], but for the elements of two lists.
+pub fn diff_lists<'a>(
+    path: &mut String,
+    a: &'a List,
+    b: &'a List,
+    out: &mut Vec<(String, Change<'a>)>,
+) {
+    let len = path.len();
+
+    for idx in 0..a.len().max(b.len()) {
+        let _ = write!(path, "[{idx}]");
+        match (a.get(idx), b.get(idx)) {
+            (Some(va), Some(vb)) => diff_values(path, va, vb, out),
+            (Some(va), None) => out.push((path.clone(), Change::Removed(va))),
+            (None, Some(vb)) => out.push((path.clone(), Change::Added(vb))),
+            (None, None) => unreachable!(),
+        }
+        path.truncate(len);
+    }
+}
+
+fn push_key(path: &mut String, key: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(key);
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use super::Value;
 
 /// Safely drops `value` in heap memory.
@@ -5,7 +7,7 @@ use super::Value;
 /// This avoids stack overflows with deeply nested types.
 pub fn safely(value: Value) {
     match value {
-        Value::List(..) | Value::Object { .. } => {}
+        Value::List(..) | Value::Object { .. } | Value::Shared(..) => {}
         _ => return,
     }
 
@@ -23,6 +25,12 @@ pub fn safely(value: Value) {
                     stack.push(child);
                 }
             }
+            Value::Shared(shared) => {
+                // Only the last owner is responsible for the contents.
+                if let Ok(value) = Arc::try_unwrap(shared) {
+                    stack.push(value);
+                }
+            }
             _ => (),
         }
     }
@@ -0,0 +1,95 @@
+use katsuba_types::TypeList;
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::{List, Value};
+
+/// Serializes a [`Value`] with the hashes of its types and properties.
+///
+/// Every object gets the name of its type under `"$__name"` and the
+/// hashes of its properties under `"$__hashes"`, keyed by property
+/// name. Both are skipped when values are loaded back, so annotated
+/// output can still be serialized again.
+pub struct WithHashes<'a> {
+    value: &'a Value,
+    types: &'a TypeList,
+}
+
+impl<'a> WithHashes<'a> {
+    /// Annotates `value` with hashes from the given type list.
+    pub fn new(value: &'a Value, types: &'a TypeList) -> Self {
+        Self { value, types }
+    }
+
+    fn child(&self, value: &'a Value) -> Self {
+        Self {
+            value,
+            types: self.types,
+        }
+    }
+}
+
+impl Serialize for WithHashes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.value {
+            Value::List(list) => match list.container {
+                Some(container) => {
+                    let mut map = serializer.serialize_map(Some(2))?;
+                    map.serialize_entry("$__container", container.as_str())?;
+                    map.serialize_entry("$__items", &Items(list, self))?;
+                    map.end()
+                }
+                None => Items(list, self).serialize(serializer),
+            },
+
+            Value::Object { hash, obj } => {
+                let type_def = self.types.0.get(hash);
+
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("$__type", hash)?;
+                if let Some(type_def) = type_def {
+                    map.serialize_entry("$__name", type_def.name.as_str())?;
+                    map.serialize_entry("$__hashes", &PropertyHashes(obj.keys(), type_def))?;
+                }
+                for (k, v) in obj.iter() {
+                    map.serialize_entry(k.as_str(), &self.child(v))?;
+                }
+                map.end()
+            }
+
+            Value::Shared(shared) => self.child(shared).serialize(serializer),
+
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+// The elements of a list, serialized with their hashes.
+struct Items<'a>(&'a List, &'a WithHashes<'a>);
+
+impl Serialize for Items<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for elem in self.0.iter() {
+            seq.serialize_element(&self.1.child(elem))?;
+        }
+        seq.end()
+    }
+}
+
+// The hashes of the named properties of a type.
+struct PropertyHashes<'a, I>(I, &'a katsuba_types::TypeDef);
+
+impl<'a, I> Serialize for PropertyHashes<'a, I>
+where
+    I: Iterator<Item = &'a super::Name> + Clone,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for name in self.0.clone() {
+            if let Some(property) = self.1.properties.iter().find(|p| p.name == name.as_str()) {
+                map.serialize_entry(name.as_str(), &property.hash)?;
+            }
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    mem,
+    sync::Arc,
+};
+
+use super::{Object, Value};
+
+// Objects nested deeper than this are not deduplicated, to avoid
+// stack overflows. This matches the default recursion limit of the
+// deserializer, so it only affects values built by hand.
+const MAX_DEPTH: usize = i8::MAX as usize;
+
+/// Structural deduplication of identical sub-objects in [`Value`]s.
+///
+/// Interning replaces every nested [`Value::Object`] with a
+/// [`Value::Shared`] node, so that structurally identical objects
+/// share a single allocation. Game files often contain thousands
+/// of equal default objects, which makes this a significant saving.
+///
+/// The same interner can be used for several values to share
+/// objects between all of them.
+///
+/// Objects nested deeper than the default recursion limit of the
+/// deserializer are still wrapped in [`Value::Shared`] nodes, but
+/// not deduplicated.
+#[derive(Default)]
+pub struct Interner {
+    buckets: HashMap<u64, Vec<Arc<Value>>>,
+}
+
+impl Interner {
+    /// Creates a new, empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the number of unique objects in the interner.
+    pub fn len(&self) -> usize {
+        self.buckets.values().map(Vec::len).sum()
+    }
+
+    /// Whether the interner holds no objects.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Deduplicates all sub-objects of `value`.
+    ///
+    /// The root itself is never replaced by a shared node.
+    pub fn intern(&mut self, value: Value) -> Value {
+        match value {
+            Value::Object { hash, mut obj } => {
+                self.visit_object(&mut obj, 0);
+                Value::Object { hash, obj }
+            }
+            value => self.visit(value, 0),
+        }
+    }
+
+    fn visit_object(&mut self, obj: &mut Object, depth: usize) {
+        for child in obj.values_mut() {
+            *child = self.visit(mem::replace(child, Value::Empty), depth + 1);
+        }
+    }
+
+    fn visit(&mut self, value: Value, depth: usize) -> Value {
+        match value {
+            // Too deep to intern, but hidden behind a unique shared node
+            // so that hashing and comparing the parents stays shallow.
+            value @ (Value::List(..) | Value::Object { .. }) if depth >= MAX_DEPTH => {
+                Value::Shared(Arc::new(value))
+            }
+
+            Value::List(mut list) => {
+                for elem in list.iter_mut() {
+                    *elem = self.visit(mem::replace(elem, Value::Empty), depth + 1);
+                }
+                Value::List(list)
+            }
+
+            Value::Object { hash, mut obj } => {
+                // Children are interned first so that comparing objects
+                // only needs pointer equality for nested shared nodes.
+                self.visit_object(&mut obj, depth);
+                Value::Shared(self.share(Value::Object { hash, obj }))
+            }
+
+            value => value,
+        }
+    }
+
+    fn share(&mut self, value: Value) -> Arc<Value> {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        hash_interned(&value, &mut hasher);
+
+        let bucket = self.buckets.entry(hasher.finish()).or_default();
+        match bucket.iter().find(|v| interned_eq(v, &value)) {
+            Some(shared) => shared.clone(),
+            None => {
+                let shared = Arc::new(value);
+                bucket.push(shared.clone());
+                shared
+            }
+        }
+    }
+}
+
+/// Deduplicates all sub-objects of `value` with a fresh [`Interner`].
+pub fn intern(value: Value) -> Value {
+    Interner::new().intern(value)
+}
+
+// Hashes a value whose children are already interned.
+//
+// Math types only contribute their kind; they are rare enough that
+// full comparisons on collisions are cheap.
+fn hash_interned<H: Hasher>(value: &Value, state: &mut H) {
+    mem::discriminant(value).hash(state);
+    match value {
+        Value::Unsigned(v) => v.hash(state),
+        Value::Signed(v) | Value::Enum(v) => v.hash(state),
+        Value::NamedEnum { value, .. } => value.hash(state),
+        Value::Float(v) => v.to_bits().hash(state),
+        Value::Bool(v) => v.hash(state),
+        Value::String(v) => v.0.hash(state),
+        Value::WString(v) => v.0.hash(state),
+
+        Value::List(list) => {
+            list.len().hash(state);
+            list.iter().for_each(|v| hash_interned(v, state));
+        }
+        Value::Object { hash, obj } => {
+            hash.hash(state);
+            for (k, v) in obj {
+                k.hash(state);
+                hash_interned(v, state);
+            }
+        }
+
+        Value::Shared(v) => Arc::as_ptr(v).hash(state),
+
+        _ => {}
+    }
+}
+
+// Compares two values whose children are already interned.
+fn interned_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Shared(a), Value::Shared(b)) => Arc::ptr_eq(a, b),
+        (Value::List(a), Value::List(b)) => {
+            a.container == b.container
+                && a.len() == b.len()
+                && a.iter().zip(b.iter()).all(|(a, b)| interned_eq(a, b))
+        }
+        (Value::Object { hash: ha, obj: a }, Value::Object { hash: hb, obj: b }) => {
+            ha == hb
+                && a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|((ka, va), (kb, vb))| ka == kb && interned_eq(va, vb))
+        }
+        (a, b) => a == b,
+    }
+}
+
+/// Serializes a [`Value`] with JSON references for shared objects.
+///
+/// The first occurrence of a [`Value::Shared`] object that is used
+/// more than once within the value gets an additional `"$id"` field. All subsequent
+/// occurrences are written as `{"$ref": id}` instead of repeating
+/// the object.
+#[cfg(feature = "serde")]
+pub struct WithReferences(pub Value);
+
+#[cfg(feature = "serde")]
+mod refs {
+    use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+    use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+    use super::WithReferences;
+    use crate::{value::List, Value};
+
+    #[derive(Default)]
+    struct Refs {
+        // How often each shared object occurs in the value.
+        //
+        // The strong count of the [`Arc`] is not enough, since an
+        // [`Interner`](super::Interner) keeps its own references.
+        counts: HashMap<*const Value, usize>,
+        ids: RefCell<HashMap<*const Value, usize>>,
+    }
+
+    impl Refs {
+        // Counts the occurrences of shared objects in `value`.
+        //
+        // The contents of each shared object are only walked once,
+        // and an explicit stack avoids overflows on deep values.
+        fn count(value: &Value) -> Self {
+            let mut counts = HashMap::new();
+
+            let mut stack = vec![value];
+            while let Some(value) = stack.pop() {
+                match value {
+                    Value::List(list) => stack.extend(list.iter()),
+                    Value::Object { obj, .. } => stack.extend(obj.values()),
+                    Value::Shared(shared) => {
+                        let count = counts.entry(Arc::as_ptr(shared)).or_insert(0);
+                        *count += 1;
+                        if *count == 1 {
+                            stack.push(shared);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+
+            Self {
+                counts,
+                ids: Default::default(),
+            }
+        }
+
+        fn is_repeated(&self, shared: &Arc<Value>) -> bool {
+            self.counts
+                .get(&Arc::as_ptr(shared))
+                .is_some_and(|&c| c > 1)
+        }
+    }
+
+    struct Node<'a> {
+        value: &'a Value,
+        refs: &'a Refs,
+    }
+
+    impl Serialize for WithReferences {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let refs = Refs::count(&self.0);
+            Node {
+                value: &self.0,
+                refs: &refs,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'a> Node<'a> {
+        fn child(&self, value: &'a Value) -> Self {
+            Self {
+                value,
+                refs: self.refs,
+            }
+        }
+    }
+
+    impl Serialize for Node<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self.value {
+                Value::List(list) => match list.container {
+                    Some(container) => {
+                        let items = Items {
+                            list,
+                            node: self.child(self.value),
+                        };
+
+                        let mut map = serializer.serialize_map(Some(2))?;
+                        map.serialize_entry("$__container", container.as_str())?;
+                        map.serialize_entry("$__items", &items)?;
+                        map.end()
+                    }
+                    None => Items {
+                        list,
+                        node: self.child(self.value),
+                    }
+                    .serialize(serializer),
+                },
+
+                Value::Object { hash, obj } => {
+                    let mut map = serializer.serialize_map(Some(obj.len() + 1))?;
+                    map.serialize_entry("$__type", hash)?;
+                    for (k, v) in obj {
+                        map.serialize_entry(k.as_str(), &self.child(v))?;
+                    }
+                    map.end()
+                }
+
+                Value::Shared(shared) if self.refs.is_repeated(shared) => {
+                    let (id, first) = {
+                        let mut ids = self.refs.ids.borrow_mut();
+                        let next = ids.len();
+                        let id = *ids.entry(Arc::as_ptr(shared)).or_insert(next);
+                        (id, id == next)
+                    };
+
+                    if first {
+                        serialize_tagged(serializer, id, self.child(shared))
+                    } else {
+                        let mut map = serializer.serialize_map(Some(1))?;
+                        map.serialize_entry("$ref", &id)?;
+                        map.end()
+                    }
+                }
+                Value::Shared(shared) => self.child(shared).serialize(serializer),
+
+                value => value.serialize(serializer),
+            }
+        }
+    }
+
+    // The elements of a list, serialized as a sequence of nodes.
+    struct Items<'a> {
+        list: &'a List,
+        node: Node<'a>,
+    }
+
+    impl Serialize for Items<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(self.list.len()))?;
+            for elem in self.list.iter() {
+                seq.serialize_element(&self.node.child(elem))?;
+            }
+            seq.end()
+        }
+    }
+
+    fn serialize_tagged<S: Serializer>(
+        serializer: S,
+        id: usize,
+        node: Node<'_>,
+    ) -> Result<S::Ok, S::Error> {
+        let Value::Object { hash, obj } = node.value else {
+            return node.serialize(serializer);
+        };
+
+        let mut map = serializer.serialize_map(Some(obj.len() + 2))?;
+        map.serialize_entry("$id", &id)?;
+        map.serialize_entry("$__type", hash)?;
+        for (k, v) in obj {
+            map.serialize_entry(k.as_str(), &node.child(v))?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,128 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use katsuba_types::{PropertyFlags, TypeList};
+
+use super::Value;
+
+/// The links between objects within a value, as found by
+/// [`Value::links`].
+///
+/// Objects are identified by their values of properties flagged
+/// `OBJECT_ID`, which properties flagged `REFERENCE_ID` refer to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Links {
+    /// The path of every identified object, keyed by its ID.
+    pub objects: BTreeMap<u64, String>,
+    /// The path of every reference, mapped to the path of the object
+    /// it refers to.
+    ///
+    /// References to IDs which are not within the value map to
+    /// [`None`]. Null references are left out.
+    pub references: BTreeMap<String, Option<String>>,
+}
+
+impl Links {
+    /// Resolves the reference at `path` to the path of the object it
+    /// refers to.
+    pub fn resolve(&self, path: &str) -> Option<&str> {
+        self.references.get(path)?.as_deref()
+    }
+}
+
+impl Value {
+    /// Collects the object IDs in `self` and resolves all references
+    /// between objects to their paths.
+    ///
+    /// Paths are relative to `self`, in the syntax of
+    /// [`Value::get_path`], with the root object at the empty path.
+    /// The type list determines which properties hold IDs.
+    pub fn links(&self, types: &TypeList) -> Links {
+        let mut walker = Walker {
+            types,
+            links: Links::default(),
+            references: Vec::new(),
+        };
+        walker.visit(&mut String::new(), self, PropertyFlags::empty());
+
+        let Walker {
+            mut links,
+            references,
+            ..
+        } = walker;
+        for (path, id) in references {
+            let target = links.objects.get(&id).cloned();
+            links.references.insert(path, target);
+        }
+        links
+    }
+}
+
+// Walks a value and collects IDs and the references to them.
+struct Walker<'t> {
+    types: &'t TypeList,
+    links: Links,
+    references: Vec<(String, u64)>,
+}
+
+impl Walker<'_> {
+    // Visits `value` at `path`, stored in a property with `flags`.
+    fn visit(&mut self, path: &mut String, value: &Value, flags: PropertyFlags) {
+        match value.unshared() {
+            Value::Object { hash, obj } => {
+                let Some(type_def) = self.types.0.get(hash) else {
+                    return;
+                };
+
+                let len = path.len();
+                for (key, v) in obj.iter() {
+                    let flags = type_def
+                        .properties
+                        .iter()
+                        .find(|p| p.name == key.as_str())
+                        .map_or(PropertyFlags::empty(), |p| p.flags);
+
+                    if flags.contains(PropertyFlags::OBJECT_ID) {
+                        if let Some(id) = as_id(v) {
+                            self.links.objects.insert(id, path.clone());
+                        }
+                    }
+
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                    self.visit(path, v, flags);
+                    path.truncate(len);
+                }
+            }
+
+            Value::List(list) => {
+                let len = path.len();
+                for (idx, v) in list.iter().enumerate() {
+                    let _ = write!(path, "[{idx}]");
+                    self.visit(path, v, flags);
+                    path.truncate(len);
+                }
+            }
+
+            v if flags.contains(PropertyFlags::REFERENCE_ID) => {
+                if let Some(id) = as_id(v) {
+                    self.references.push((path.clone(), id));
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+// Gets a non-null ID from a value, if it is one.
+fn as_id(value: &Value) -> Option<u64> {
+    let id = match value.unshared() {
+        Value::Unsigned(v) => *v,
+        Value::Signed(v) => *v as u64,
+        _ => return None,
+    };
+    (id != 0).then_some(id)
+}
@@ -4,18 +4,54 @@ use std::{
     ptr,
 };
 
+use katsuba_types::Container;
+
 use super::{drop, Value};
 
 /// A list of values with a non-recursive drop impl.
 ///
 /// A list can store arbitrary values in the ObjectProperty
 /// system, not necessarily being homogenous.
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
-#[cfg_attr(feature = "serde", serde(transparent))]
 #[derive(Clone, Debug, PartialEq)]
 pub struct List {
     /// The inner [`Value`]s of the list.
     pub inner: Vec<Value>,
+    /// The container kind the list was deserialized from, if the
+    /// serializer was configured to annotate it.
+    ///
+    /// Annotated lists serialize as an object holding the kind under
+    /// `$__container` and the elements under `$__items`.
+    pub container: Option<Container>,
+}
+
+impl List {
+    /// Creates a new list from its elements without annotation.
+    pub fn new(inner: Vec<Value>) -> Self {
+        Self {
+            inner,
+            container: None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for List {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        match self.container {
+            Some(container) => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("$__container", container.as_str())?;
+                map.serialize_entry("$__items", &self.inner)?;
+                map.end()
+            }
+            None => self.inner.serialize(serializer),
+        }
+    }
 }
 
 impl Drop for List {
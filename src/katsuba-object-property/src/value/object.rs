@@ -5,9 +5,7 @@ use std::{
     ptr,
 };
 
-use smartstring::alias::String;
-
-use super::{drop, Value};
+use super::{drop, Name, Value};
 
 /// Representation of an object in the ObjectProperty system.
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
@@ -15,7 +13,7 @@ use super::{drop, Value};
 #[derive(Clone, Debug, PartialEq)]
 pub struct Object {
     /// A mapping of class member names to their values.
-    pub inner: BTreeMap<String, Value>,
+    pub inner: BTreeMap<Name, Value>,
 }
 
 impl Drop for Object {
@@ -27,7 +25,7 @@ impl Drop for Object {
 }
 
 impl Deref for Object {
-    type Target = BTreeMap<String, Value>;
+    type Target = BTreeMap<Name, Value>;
 
     fn deref(&self) -> &Self::Target {
         &self.inner
@@ -41,8 +39,8 @@ impl DerefMut for Object {
 }
 
 impl IntoIterator for Object {
-    type Item = (String, Value);
-    type IntoIter = <BTreeMap<String, Value> as IntoIterator>::IntoIter;
+    type Item = (Name, Value);
+    type IntoIter = <BTreeMap<Name, Value> as IntoIterator>::IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
         let this = ManuallyDrop::new(self);
@@ -51,8 +49,8 @@ impl IntoIterator for Object {
 }
 
 impl<'a> IntoIterator for &'a Object {
-    type Item = (&'a String, &'a Value);
-    type IntoIter = <&'a BTreeMap<String, Value> as IntoIterator>::IntoIter;
+    type Item = (&'a Name, &'a Value);
+    type IntoIter = <&'a BTreeMap<Name, Value> as IntoIterator>::IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
@@ -60,8 +58,8 @@ impl<'a> IntoIterator for &'a Object {
 }
 
 impl<'a> IntoIterator for &'a mut Object {
-    type Item = (&'a String, &'a mut Value);
-    type IntoIter = <&'a mut BTreeMap<String, Value> as IntoIterator>::IntoIter;
+    type Item = (&'a Name, &'a mut Value);
+    type IntoIter = <&'a mut BTreeMap<Name, Value> as IntoIterator>::IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter_mut()
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use super::Value;
+
+// A single step in a value path.
+enum Segment<'a> {
+    Field(&'a str),
+    Index(usize),
+}
+
+// Splits `path` into its segments, yielding `None` for malformed ones.
+fn segments(path: &str) -> impl Iterator<Item = Option<Segment<'_>>> {
+    path.split('.').flat_map(|part| {
+        let (name, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+
+        let mut out = Vec::new();
+        if !name.is_empty() {
+            out.push(Some(Segment::Field(name)));
+        }
+        while !rest.is_empty() {
+            let Some((idx, tail)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) else {
+                out.push(None);
+                break;
+            };
+
+            out.push(idx.parse().ok().map(Segment::Index));
+            rest = tail;
+        }
+
+        // Parts must not be empty, as in `a..b`.
+        if out.is_empty() {
+            out.push(None);
+        }
+        out
+    })
+}
+
+impl Value {
+    /// Looks up a nested value by its path.
+    ///
+    /// Paths consist of property names separated by dots, where list
+    /// elements are selected by an index in brackets, e.g.
+    /// `m_behaviors[2].m_template.m_name`. Shared values are looked
+    /// through transparently.
+    ///
+    /// Returns [`None`] when the path is malformed or does not exist.
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        let mut current = self.unshared();
+        for segment in segments(path) {
+            current = match (segment?, current) {
+                (Segment::Field(name), Value::Object { obj, .. }) => obj.get(name)?,
+                (Segment::Index(idx), Value::List(list)) => list.get(idx)?,
+                _ => return None,
+            }
+            .unshared();
+        }
+
+        Some(current)
+    }
+
+    /// Resolves [`Value::Shared`] indirections to the value behind them.
+    pub fn unshared(&self) -> &Value {
+        let mut value = self;
+        while let Value::Shared(v) = value {
+            value = v;
+        }
+        value
+    }
+
+    /// Like [`Value::unshared`], but takes ownership of the value.
+    ///
+    /// The value behind a shared node is only cloned when it is still
+    /// shared with other places.
+    pub fn into_unshared(self) -> Value {
+        match self {
+            Value::Shared(v) => match Arc::try_unwrap(v) {
+                Ok(v) => v.into_unshared(),
+                Err(v) => v.unshared().clone(),
+            },
+            v => v,
+        }
+    }
+}
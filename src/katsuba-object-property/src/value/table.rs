@@ -0,0 +1,113 @@
+use std::{collections::HashMap, fmt::Write};
+
+use super::Value;
+
+/// A list of objects flattened into columns, as produced by
+/// [`Value::to_table`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table<'a> {
+    /// The value paths of the columns, relative to each object.
+    pub columns: Vec<String>,
+    /// The cells of every row, with [`None`] for columns that the
+    /// object of the row does not have.
+    pub rows: Vec<Vec<Option<&'a Value>>>,
+}
+
+// Collects the cells of one row into `cells`, adding new columns
+// as they are discovered.
+struct RowBuilder<'a, 't> {
+    columns: &'t mut Vec<String>,
+    index: &'t mut HashMap<String, usize>,
+    cells: Vec<Option<&'a Value>>,
+}
+
+impl<'a> RowBuilder<'a, '_> {
+    fn visit(&mut self, path: &mut String, value: &'a Value) {
+        let len = path.len();
+        match value.unshared() {
+            Value::Object { obj, .. } => {
+                for (key, v) in obj.iter() {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                    self.visit(path, v);
+                    path.truncate(len);
+                }
+            }
+
+            Value::List(list) => {
+                for (idx, v) in list.iter().enumerate() {
+                    let _ = write!(path, "[{idx}]");
+                    self.visit(path, v);
+                    path.truncate(len);
+                }
+            }
+
+            // Null objects don't contribute any columns.
+            Value::Empty => {}
+
+            value => {
+                let column = match self.index.get(path.as_str()) {
+                    Some(&column) => column,
+                    None => {
+                        self.columns.push(path.clone());
+                        self.index.insert(path.clone(), self.columns.len() - 1);
+                        self.columns.len() - 1
+                    }
+                };
+
+                if self.cells.len() <= column {
+                    self.cells.resize(column + 1, None);
+                }
+                self.cells[column] = Some(value);
+            }
+        }
+    }
+}
+
+impl Value {
+    /// Flattens a list of objects of the same type into a [`Table`]
+    /// with one column per leaf value path.
+    ///
+    /// Nested objects and lists are flattened with the same path
+    /// syntax as [`Value::get_path`]. Columns are ordered by their
+    /// first appearance.
+    ///
+    /// Returns [`None`] when the value is not a list of objects or
+    /// when the objects have different types.
+    pub fn to_table(&self) -> Option<Table<'_>> {
+        let Value::List(list) = self.unshared() else {
+            return None;
+        };
+
+        let mut columns = Vec::new();
+        let mut index = HashMap::new();
+        let mut rows = Vec::with_capacity(list.len());
+        let mut ty = None;
+
+        for element in list.iter() {
+            let Value::Object { hash, .. } = element.unshared() else {
+                return None;
+            };
+            if *ty.get_or_insert(*hash) != *hash {
+                return None;
+            }
+
+            let mut row = RowBuilder {
+                columns: &mut columns,
+                index: &mut index,
+                cells: Vec::new(),
+            };
+            row.visit(&mut String::new(), element);
+            rows.push(row.cells);
+        }
+
+        // Rows built before the last column was discovered are shorter.
+        for row in &mut rows {
+            row.resize(columns.len(), None);
+        }
+
+        Some(Table { columns, rows })
+    }
+}
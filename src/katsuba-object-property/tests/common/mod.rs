@@ -0,0 +1,102 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use katsuba_object_property::{
+    serde::{Serializer, SerializerOptions},
+    value::Object,
+    Value,
+};
+use katsuba_types::TypeList;
+use katsuba_utils::hash::string_id;
+
+/// The name of the class most tests deserialize.
+pub const TEST: &str = "class Test";
+
+/// The properties of [`TEST`] in [`types`].
+///
+/// `4242` is deliberately not the hash of any property, so tests can
+/// use it for properties unknown to the type list.
+pub const TEST_PROPERTIES: &str = r#"{
+    "m_values": { "type": "unsigned int", "id": 0, "flags": 31, "container": "List", "dynamic": true, "pointer": false, "hash": 5678 },
+    "m_count": { "type": "unsigned int", "id": 1, "flags": 31, "dynamic": false, "pointer": false, "hash": 9999 },
+    "m_name": { "type": "std::string", "id": 2, "flags": 31, "dynamic": false, "pointer": false, "hash": 4343 }
+}"#;
+
+/// Gets the type hash of the class `name`.
+pub fn hash(name: &str) -> u32 {
+    string_id(name.as_bytes())
+}
+
+/// Builds a type list from `(name, properties)` class definitions,
+/// where `properties` is the JSON object of property definitions.
+///
+/// Classes are keyed by the hashes the serializer computes for them.
+pub fn type_list(classes: &[(&str, &str)]) -> Arc<TypeList> {
+    let classes: Vec<_> = classes
+        .iter()
+        .map(|(name, properties)| {
+            let hash = hash(name);
+            format!(
+                r#""{hash}": {{
+                    "name": "{name}",
+                    "bases": ["PropertyClass"],
+                    "hash": {hash},
+                    "properties": {properties}
+                }}"#
+            )
+        })
+        .collect();
+
+    let types = format!(
+        r#"{{ "version": 2, "classes": {{ {} }} }}"#,
+        classes.join(",")
+    );
+    Arc::new(TypeList::from_str(&types).unwrap())
+}
+
+/// Builds the type list with [`TEST`] and its [`TEST_PROPERTIES`].
+pub fn types() -> Arc<TypeList> {
+    type_list(&[(TEST, TEST_PROPERTIES)])
+}
+
+/// Creates a serializer for [`types`] in shallow or deep mode.
+pub fn serializer(shallow: bool) -> Serializer {
+    serializer_with(SerializerOptions {
+        shallow,
+        ..Default::default()
+    })
+}
+
+/// Creates a serializer for [`types`] with `options`.
+pub fn serializer_with(options: SerializerOptions) -> Serializer {
+    Serializer::new(options, types()).unwrap()
+}
+
+/// Encodes an object of [`TEST`] from the words after its type hash.
+pub fn data(words: &[u32]) -> Vec<u8> {
+    std::iter::once(hash(TEST))
+        .chain(words.iter().copied())
+        .flat_map(u32::to_le_bytes)
+        .collect()
+}
+
+/// Builds an object value with the type `hash` from `members`.
+pub fn object(hash: u32, members: Vec<(&str, Value)>) -> Value {
+    Value::Object {
+        hash,
+        obj: Object {
+            inner: members.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+        },
+    }
+}
+
+/// Gets the members of `value`, which must be an object.
+pub fn members(value: &Value) -> &Object {
+    match value {
+        Value::Object { obj, .. } => obj,
+        v => panic!("expected object, got {v:?}"),
+    }
+}
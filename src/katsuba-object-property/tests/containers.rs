@@ -0,0 +1,74 @@
+use katsuba_object_property::{
+    serde::{PropertyClass, Serializer, SerializerOptions},
+    value::List,
+    Value,
+};
+use katsuba_types::Container;
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_values": { "type": "unsigned int", "id": 0, "flags": 31, "container": "Vector", "dynamic": true, "pointer": false, "hash": 5678 }
+}"#;
+
+// A shallow object holding a single element in `m_values`.
+const DATA: &[u32] = &[1, 42];
+
+fn deserialize_values(options: SerializerOptions) -> Value {
+    let mut de = Serializer::new(options, type_list(&[(TEST, PROPERTIES)])).unwrap();
+
+    let Value::Object { mut obj, .. } = de.deserialize::<PropertyClass>(&data(DATA)).unwrap()
+    else {
+        panic!("expected object");
+    };
+    obj.remove("m_values").unwrap()
+}
+
+#[test]
+fn single_element_as_array() {
+    let value = deserialize_values(SerializerOptions::default());
+    assert_eq!(value, Value::List(List::new(vec![Value::Unsigned(42)])));
+}
+
+#[test]
+fn single_element_as_scalar() {
+    let value = deserialize_values(SerializerOptions {
+        collapse_single_element: true,
+        ..Default::default()
+    });
+    assert_eq!(value, Value::Unsigned(42));
+}
+
+#[test]
+fn annotated_container() {
+    let value = deserialize_values(SerializerOptions {
+        annotate_containers: true,
+        collapse_single_element: true,
+        ..Default::default()
+    });
+
+    let Value::List(list) = value else {
+        panic!("expected list");
+    };
+    assert_eq!(list.container, Some(Container::Vector));
+    assert_eq!(list.inner, [Value::Unsigned(42)]);
+}
+
+#[test]
+fn member_names_are_shared() {
+    let types = type_list(&[(TEST, PROPERTIES)]);
+    let mut de = Serializer::new(SerializerOptions::default(), types).unwrap();
+
+    let key = |value: Value| {
+        let Value::Object { obj, .. } = value else {
+            panic!("expected object");
+        };
+        obj.keys().next().unwrap().clone()
+    };
+    let a = key(de.deserialize::<PropertyClass>(&data(DATA)).unwrap());
+    let b = key(de.deserialize::<PropertyClass>(&data(DATA)).unwrap());
+
+    assert_eq!(a, "m_values");
+    assert_eq!(a.as_ptr(), b.as_ptr());
+}
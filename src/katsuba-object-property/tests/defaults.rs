@@ -0,0 +1,71 @@
+use katsuba_object_property::{
+    serde::{PropertyClass, Serializer, SerializerOptions},
+    value::CxxStr,
+    Value,
+};
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_count": { "type": "unsigned int", "id": 0, "flags": 287, "dynamic": false, "pointer": false, "hash": 1, "default": 5 },
+    "m_name": { "type": "std::string", "id": 1, "flags": 287, "dynamic": false, "pointer": false, "hash": 2, "default": "none" },
+    "m_level": { "type": "int", "id": 2, "flags": 287, "dynamic": false, "pointer": false, "hash": 3 }
+}"#;
+
+fn serializer(fill_defaults: bool) -> Serializer {
+    let types = type_list(&[(TEST, PROPERTIES)]);
+    let options = SerializerOptions {
+        fill_defaults,
+        ..Default::default()
+    };
+
+    Serializer::new(options, types).unwrap()
+}
+
+#[test]
+fn omitted_values_take_defaults() {
+    let mut de = serializer(true);
+
+    // Only `m_level` is present, the other properties are omitted.
+    let mut data = data(&[]);
+    data.extend([0b100, 7, 0, 0, 0]);
+
+    let value = de.deserialize::<PropertyClass>(&data).unwrap();
+    let obj = members(&value);
+    assert_eq!(obj["m_count"], Value::Unsigned(5));
+    assert_eq!(obj["m_name"], Value::String(CxxStr(b"none".to_vec())));
+    assert_eq!(obj["m_level"], Value::Signed(7));
+}
+
+#[test]
+fn omitted_values_stay_empty() {
+    let mut de = serializer(false);
+
+    let mut data = data(&[]);
+    data.extend([0b100, 7, 0, 0, 0]);
+
+    let value = de.deserialize::<PropertyClass>(&data).unwrap();
+    let obj = members(&value);
+    assert_eq!(obj["m_count"], Value::Empty);
+    assert_eq!(obj["m_name"], Value::Empty);
+    assert_eq!(obj["m_level"], Value::Signed(7));
+
+    // Omitted properties are written back as omitted.
+    assert_eq!(de.serialize::<PropertyClass>(&value).unwrap(), data);
+}
+
+#[test]
+fn omitted_values_without_default() {
+    let mut de = serializer(true);
+
+    let mut data = data(&[]);
+    data.push(0);
+
+    let value = de.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(members(&value)["m_level"], Value::Empty);
+
+    // Empty values are omitted again.
+    let out = de.serialize::<PropertyClass>(&value).unwrap();
+    assert_eq!(de.deserialize::<PropertyClass>(&out).unwrap(), value);
+}
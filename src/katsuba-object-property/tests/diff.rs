@@ -0,0 +1,59 @@
+use katsuba_object_property::{
+    value::{Change, List},
+    Value,
+};
+
+mod common;
+use common::*;
+
+#[test]
+fn diff_paths() {
+    let a = object(
+        1,
+        vec![
+            ("m_id", Value::Unsigned(1)),
+            ("m_old", Value::Bool(true)),
+            (
+                "m_list",
+                Value::List(List::new(vec![Value::Unsigned(1), Value::Unsigned(2)])),
+            ),
+            ("m_child", object(2, vec![("m_name", Value::Unsigned(3))])),
+        ],
+    );
+    let b = object(
+        1,
+        vec![
+            ("m_id", Value::Unsigned(1)),
+            ("m_new", Value::Bool(false)),
+            ("m_list", Value::List(List::new(vec![Value::Unsigned(5)]))),
+            ("m_child", object(2, vec![("m_name", Value::Unsigned(4))])),
+        ],
+    );
+
+    let changes = a.diff(&b);
+    let summary: Vec<_> = changes
+        .iter()
+        .map(|(path, change)| (path.as_str(), change.kind()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("m_child.m_name", "changed"),
+            ("m_list[0]", "changed"),
+            ("m_list[1]", "removed"),
+            ("m_old", "removed"),
+            ("m_new", "added"),
+        ]
+    );
+    assert_eq!(changes[3].1, Change::Removed(&Value::Bool(true)));
+
+    assert!(a.diff(&a).is_empty());
+}
+
+#[test]
+fn diff_types() {
+    let a = object(1, vec![]);
+    let b = object(2, vec![]);
+
+    assert_eq!(a.diff(&b), [(String::new(), Change::Changed(&a, &b))]);
+}
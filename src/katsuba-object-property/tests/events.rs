@@ -0,0 +1,97 @@
+use katsuba_object_property::{
+    serde::{Event, PropertyClass, Serializer},
+    Value,
+};
+
+mod common;
+use common::*;
+
+fn collect<'a>(de: &'a mut Serializer, data: &'a [u8]) -> Vec<Event<'a>> {
+    let mut events = de.events::<PropertyClass>(data).unwrap();
+    let mut out = Vec::new();
+    while let Some(event) = events.next_event().unwrap() {
+        out.push(event);
+    }
+    out
+}
+
+#[test]
+fn shallow_events() {
+    let mut de = serializer(true);
+    let mut data = data(&[2, 1, 2, 7]);
+    data.extend([3, 0, b'a', b'b', b'c']);
+
+    let events = collect(&mut de, &data);
+    assert!(matches!(
+        events[0],
+        Event::ObjectStart {
+            name: "class Test",
+            ..
+        }
+    ));
+    assert_eq!(
+        events[1..],
+        [
+            Event::Property { name: "m_values" },
+            Event::ListStart { len: 2 },
+            Event::Value(Value::Unsigned(1)),
+            Event::Value(Value::Unsigned(2)),
+            Event::ListEnd,
+            Event::Property { name: "m_count" },
+            Event::Value(Value::Unsigned(7)),
+            Event::Property { name: "m_name" },
+            Event::Str(b"abc"),
+            Event::ObjectEnd,
+        ]
+    );
+
+    // Strings point into the input instead of being copied.
+    let Event::Str(s) = events[9] else {
+        panic!("expected string");
+    };
+    assert!(data.as_ptr_range().contains(&s.as_ptr()));
+}
+
+#[test]
+fn deep_events() {
+    let mut de = serializer(false);
+    let data = data(&[128, 96, 9999, 7]);
+
+    let events = collect(&mut de, &data);
+    assert_eq!(
+        events[1..],
+        [
+            Event::Property { name: "m_count" },
+            Event::Value(Value::Unsigned(7)),
+            Event::ObjectEnd,
+        ]
+    );
+}
+
+#[test]
+fn deep_events_size_mismatch() {
+    let mut de = serializer(false);
+    let data = data(&[128, 128, 9999, 7]);
+
+    let mut events = de.events::<PropertyClass>(&data).unwrap();
+    let res = std::iter::from_fn(|| events.next_event().transpose()).find_map(Result::err);
+    assert!(res.is_some());
+}
+
+#[test]
+fn event_positions() {
+    let mut de = serializer(true);
+    let mut data = data(&[2, 1, 2, 7]);
+    data.extend([0, 0]);
+
+    let mut events = de.events::<PropertyClass>(&data).unwrap();
+    let mut positions = Vec::new();
+    while events.next_event().unwrap().is_some() {
+        positions.push(events.position());
+    }
+
+    assert_eq!(
+        positions,
+        [32, 32, 64, 96, 128, 128, 128, 160, 160, 176, 176]
+    );
+}
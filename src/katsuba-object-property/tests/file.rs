@@ -0,0 +1,67 @@
+use std::fs;
+
+use katsuba_object_property::{
+    serde::{Error, PropertyClass},
+    value::{CxxStr, List},
+    Value,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::*;
+
+fn value() -> Value {
+    object(
+        hash(TEST),
+        vec![
+            (
+                "m_values",
+                Value::List(List::new(vec![Value::Unsigned(1), Value::Unsigned(2)])),
+            ),
+            ("m_count", Value::Unsigned(7)),
+            ("m_name", Value::String(CxxStr(b"abc".to_vec()))),
+        ],
+    )
+}
+
+#[test]
+fn round_trip() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("object.bin");
+
+    let mut ser = serializer(false);
+    fs::write(&path, ser.serialize::<PropertyClass>(&value()).unwrap()).unwrap();
+
+    let value = ser.deserialize_file::<PropertyClass, _>(&path).unwrap();
+    assert_eq!(value, self::value());
+}
+
+#[test]
+fn empty_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("empty.bin");
+    fs::write(&path, []).unwrap();
+
+    let res = serializer(false).deserialize_file::<PropertyClass, _>(&path);
+    assert!(matches!(res, Err(Error::Io(..))));
+}
+
+#[test]
+fn truncated_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("truncated.bin");
+
+    let mut ser = serializer(false);
+    let data = ser.serialize::<PropertyClass>(&value()).unwrap();
+    fs::write(&path, &data[..data.len() - 2]).unwrap();
+
+    assert!(ser.deserialize_file::<PropertyClass, _>(&path).is_err());
+}
+
+#[test]
+fn missing_file() {
+    let dir = TempDir::new().unwrap();
+
+    let res = serializer(false).deserialize_file::<PropertyClass, _>(dir.path().join("missing"));
+    assert!(matches!(res, Err(Error::Io(..))));
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "extra-framings")]
+
+use katsuba_object_property::{
+    serde::{PropertyClass, Serializer, SerializerOptions},
+    Value,
+};
+use katsuba_utils::libdeflater::{CompressionLvl, Compressor};
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_count": { "type": "unsigned int", "id": 0, "flags": 31, "dynamic": false, "pointer": false, "hash": 9999 }
+}"#;
+
+// Compresses an object with `compress` and prepends the size prefix.
+fn framed(compress: fn(&mut Compressor, &[u8], &mut [u8]) -> Option<usize>) -> Vec<u8> {
+    let mut deflater = Compressor::new(CompressionLvl::default());
+    let data = data(&[42]);
+
+    let mut out = vec![0; 4 + 256];
+    out[..4].copy_from_slice(&(data.len() as u32).to_le_bytes());
+    let len = compress(&mut deflater, &data, &mut out[4..]).unwrap();
+    out.truncate(4 + len);
+
+    out
+}
+
+fn deserialize(data: &[u8]) -> Value {
+    let options = SerializerOptions {
+        manual_compression: true,
+        ..Default::default()
+    };
+
+    let mut de = Serializer::new(options, type_list(&[(TEST, PROPERTIES)])).unwrap();
+    de.deserialize::<PropertyClass>(data).unwrap()
+}
+
+#[test]
+fn all_framings() {
+    let zlib = framed(|c, i, o| c.zlib_compress(i, o).ok());
+    let gzip = framed(|c, i, o| c.gzip_compress(i, o).ok());
+    let deflate = framed(|c, i, o| c.deflate_compress(i, o).ok());
+
+    let expected = deserialize(&zlib);
+    assert_eq!(deserialize(&gzip), expected);
+    assert_eq!(deserialize(&deflate), expected);
+}
+
+#[test]
+fn deflate_with_zlib_like_header() {
+    // A non-final stored block whose header and length bytes also
+    // form a valid zlib header, followed by an empty final block.
+    let mut object = data(&[42]);
+    object.resize(0x1D, 0);
+
+    let mut stream = vec![0x1D, 0, 0, 0, 0x08, 0x1D, 0x00, 0xE2, 0xFF];
+    stream.extend_from_slice(&object);
+    stream.extend([0x01, 0x00, 0x00, 0xFF, 0xFF]);
+
+    assert_eq!(
+        deserialize(&stream),
+        deserialize(&framed(|c, i, o| c.zlib_compress(i, o).ok()))
+    );
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "option-guessing")]
+
+use katsuba_object_property::{
+    serde::{Confidence, PropertyClass, Serializer, SerializerFlags},
+    Value,
+};
+use katsuba_types::PropertyFlags;
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_transmitted": { "type": "unsigned char", "id": 0, "flags": 24, "dynamic": false, "pointer": false, "hash": 1 },
+    "m_saved": { "type": "unsigned int", "id": 1, "flags": 1, "dynamic": false, "pointer": false, "hash": 2 }
+}"#;
+
+#[test]
+fn trial_property_mask() {
+    let types = type_list(&[(TEST, PROPERTIES)]);
+    let data = data(&[7]);
+
+    let (mut de, confidence) =
+        Serializer::with_guessed_options_and_confidence(Default::default(), types, &data).unwrap();
+
+    // Only the SAVE mask consumes the whole stream, but the enum
+    // encoding cannot be told apart without any enums.
+    assert_eq!(de.parts.options.property_mask, PropertyFlags::SAVE);
+    assert_eq!(confidence.property_mask, Confidence::Certain);
+    assert_eq!(confidence.human_readable_enums, Confidence::Ambiguous);
+    assert!(!de
+        .parts
+        .options
+        .flags
+        .contains(SerializerFlags::HUMAN_READABLE_ENUMS));
+
+    let value = de.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(value.get_path("m_saved"), Some(&Value::Unsigned(7)));
+}
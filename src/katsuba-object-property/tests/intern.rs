@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use katsuba_object_property::{
+    value::{Interner, List},
+    Value,
+};
+
+mod common;
+use common::*;
+
+fn child(id: u64) -> Value {
+    object(0x5678, vec![("m_id", Value::Unsigned(id))])
+}
+
+fn shared(value: &Value, key: &str) -> Arc<Value> {
+    match &members(value)[key] {
+        Value::Shared(shared) => shared.clone(),
+        v => panic!("expected shared value, got {v:?}"),
+    }
+}
+
+#[test]
+fn deduplicates_equal_objects() {
+    let mut interner = Interner::new();
+    let value = interner.intern(object(
+        0x1234,
+        vec![("m_a", child(1)), ("m_b", child(1)), ("m_c", child(2))],
+    ));
+
+    assert_eq!(interner.len(), 2);
+    assert!(Arc::ptr_eq(&shared(&value, "m_a"), &shared(&value, "m_b")));
+    assert!(!Arc::ptr_eq(&shared(&value, "m_a"), &shared(&value, "m_c")));
+    assert_eq!(*shared(&value, "m_c"), child(2));
+}
+
+#[test]
+fn shares_across_values() {
+    let mut interner = Interner::new();
+    let a = interner.intern(object(0x1234, vec![("m_a", child(1))]));
+    let b = interner.intern(object(0x1234, vec![("m_b", child(1))]));
+
+    assert_eq!(interner.len(), 1);
+    assert!(Arc::ptr_eq(&shared(&a, "m_a"), &shared(&b, "m_b")));
+}
+
+#[test]
+fn deep_values() {
+    let mut value = child(1);
+    for _ in 0..100_000 {
+        value = Value::List(List::new(vec![value]));
+    }
+
+    let value = katsuba_object_property::value::intern(object(0x1234, vec![("m_deep", value)]));
+    assert!(matches!(members(&value)["m_deep"], Value::List(..)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_references() {
+    use katsuba_object_property::value::WithReferences;
+    use serde_json::json;
+
+    let value = katsuba_object_property::value::intern(object(
+        0x1234,
+        vec![("m_a", child(1)), ("m_b", child(1)), ("m_c", child(2))],
+    ));
+
+    assert_eq!(
+        serde_json::to_value(WithReferences(value)).unwrap(),
+        json!({
+            "$__type": 0x1234,
+            "m_a": { "$id": 0, "$__type": 0x5678, "m_id": 1 },
+            "m_b": { "$ref": 0 },
+            "m_c": { "$__type": 0x5678, "m_id": 2 },
+        })
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_references_with_reused_interner() {
+    use katsuba_object_property::value::WithReferences;
+    use serde_json::json;
+
+    // The interner holds references of its own, which must not make
+    // objects used only once look repeated.
+    let mut interner = Interner::new();
+    let value = interner.intern(object(0x1234, vec![("m_a", child(1))]));
+
+    assert_eq!(
+        serde_json::to_value(WithReferences(value)).unwrap(),
+        json!({
+            "$__type": 0x1234,
+            "m_a": { "$__type": 0x5678, "m_id": 1 },
+        })
+    );
+}
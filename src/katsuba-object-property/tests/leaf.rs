@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use katsuba_object_property::{
+    serde::{self, LeafTypes, PropertyClass, Serializer, SerializerOptions},
+    Value,
+};
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_scale": { "type": "class Fixed", "id": 0, "flags": 31, "dynamic": false, "pointer": false, "hash": 1 },
+    "m_count": { "type": "unsigned int", "id": 1, "flags": 31, "dynamic": false, "pointer": false, "hash": 2 }
+}"#;
+
+// An 8.8 fixed-point number.
+fn leaf_types() -> LeafTypes {
+    let mut types = LeafTypes::new();
+    types.register(
+        "class Fixed",
+        false,
+        |r, _| {
+            let v = serde::read_bits(r, u16::BITS)? as u16 as i16;
+            Ok(Value::Float(v as f64 / 256.0))
+        },
+        |w, _, v| match v {
+            Value::Float(v) => {
+                serde::write_bits(w, (*v * 256.0) as i16 as u16 as u64, u16::BITS);
+                Some(())
+            }
+            _ => None,
+        },
+    );
+    types
+}
+
+#[test]
+fn custom_leaf_types() {
+    let options = SerializerOptions {
+        leaf_types: Some(Arc::new(leaf_types())),
+        ..Default::default()
+    };
+    let mut de = Serializer::new(options, type_list(&[(TEST, PROPERTIES)])).unwrap();
+
+    let mut data = data(&[]);
+    data.extend(0x0180u16.to_le_bytes());
+    data.extend(7u32.to_le_bytes());
+
+    let value = de.deserialize::<PropertyClass>(&data).unwrap();
+    let obj = members(&value);
+    assert_eq!(obj["m_scale"], Value::Float(1.5));
+    assert_eq!(obj["m_count"], Value::Unsigned(7));
+
+    assert_eq!(de.serialize::<PropertyClass>(&value).unwrap(), data);
+
+    // Values the writer rejects fail serialization.
+    let mut obj = obj.clone();
+    obj.insert("m_scale".into(), Value::Bool(true));
+    let invalid = Value::Object {
+        hash: hash(TEST),
+        obj,
+    };
+    assert!(de.serialize::<PropertyClass>(&invalid).is_err());
+}
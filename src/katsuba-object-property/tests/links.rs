@@ -0,0 +1,49 @@
+use katsuba_object_property::{value::List, Value};
+
+mod common;
+use common::*;
+
+const NODE: &str = "class Node";
+
+const PROPERTIES: &str = r#"{
+    "m_id": { "type": "gid", "id": 0, "flags": 16777247, "dynamic": false, "pointer": false, "hash": 10 },
+    "m_targets": { "type": "gid", "id": 1, "flags": 33554463, "dynamic": true, "pointer": false, "hash": 11 },
+    "m_children": { "type": "class Node", "id": 2, "flags": 31, "dynamic": true, "pointer": true, "hash": 12 }
+}"#;
+
+fn node(id: u64, targets: Vec<u64>, children: Vec<Value>) -> Value {
+    let targets = targets.into_iter().map(Value::Unsigned).collect();
+    object(
+        hash(NODE),
+        vec![
+            ("m_id", Value::Unsigned(id)),
+            ("m_targets", Value::List(List::new(targets))),
+            ("m_children", Value::List(List::new(children))),
+        ],
+    )
+}
+
+#[test]
+fn links() {
+    let types = type_list(&[(NODE, PROPERTIES)]);
+    let tree = node(
+        1,
+        vec![],
+        vec![node(2, vec![3, 0], vec![]), node(3, vec![1, 99], vec![])],
+    );
+
+    let links = tree.links(&types);
+    assert_eq!(links.objects[&1], "");
+    assert_eq!(links.objects[&2], "m_children[0]");
+    assert_eq!(links.objects[&3], "m_children[1]");
+
+    assert_eq!(
+        links.resolve("m_children[0].m_targets[0]"),
+        Some("m_children[1]")
+    );
+    assert_eq!(links.resolve("m_children[1].m_targets[0]"), Some(""));
+
+    // Null references are left out, unknown IDs are unresolved.
+    assert!(!links.references.contains_key("m_children[0].m_targets[1]"));
+    assert_eq!(links.references["m_children[1].m_targets[1]"], None);
+}
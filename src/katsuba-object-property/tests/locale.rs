@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use katsuba_object_property::{
+    serde::{LocaleProvider, PropertyClass, Serializer, SerializerOptions},
+    value::CxxStr,
+    Value,
+};
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_displayName": { "type": "std::string", "id": 0, "flags": 8388639, "dynamic": false, "pointer": false, "hash": 5678 },
+    "m_debugName": { "type": "std::string", "id": 1, "flags": 31, "dynamic": false, "pointer": false, "hash": 9999 }
+}"#;
+
+#[derive(Debug)]
+struct Strings;
+
+impl LocaleProvider for Strings {
+    fn lookup(&self, key: &str) -> Option<&str> {
+        (key == "Items_Sword").then_some("Sword of Light")
+    }
+}
+
+fn string(s: &str) -> Value {
+    Value::String(CxxStr(s.as_bytes().to_vec()))
+}
+
+#[test]
+fn resolve_string_keys() {
+    let options = SerializerOptions {
+        locale: Some(Arc::new(Strings)),
+        ..Default::default()
+    };
+    let mut de = Serializer::new(options, type_list(&[(TEST, PROPERTIES)])).unwrap();
+
+    let mut data = data(&[]);
+    for s in ["Items_Sword", "Items_Sword"] {
+        data.extend((s.len() as u16).to_le_bytes());
+        data.extend(s.as_bytes());
+    }
+
+    let Value::Object { obj, .. } = de.deserialize::<PropertyClass>(&data).unwrap() else {
+        panic!("expected object");
+    };
+
+    // Only properties flagged as holding string keys are resolved.
+    assert_eq!(obj["m_displayName"], string("Sword of Light"));
+    assert_eq!(obj["m_debugName"], string("Items_Sword"));
+}
@@ -0,0 +1,331 @@
+use std::{collections::HashSet, sync::Arc};
+
+use katsuba_object_property::{
+    serde::{
+        error_placeholder, AllocationLimits, Error, PropertyClass, Serializer, SerializerOptions,
+        Span, Strictness,
+    },
+    value::{CxxStr, List},
+    Value,
+};
+
+mod common;
+use common::*;
+
+#[test]
+fn object_size_smaller_than_prefix() {
+    let mut de = serializer(false);
+
+    let res = de.deserialize::<PropertyClass>(&data(&[10]));
+    assert!(matches!(res, Err(Error::InvalidObjectSize(10))));
+}
+
+#[test]
+fn property_larger_than_object() {
+    let mut de = serializer(false);
+
+    // The object claims 64 bits of properties, but the property
+    // itself claims more than that.
+    let res = de.deserialize::<PropertyClass>(&data(&[96, 128, 5678, 0]));
+    assert!(res.is_err());
+}
+
+#[test]
+fn huge_list_length() {
+    let mut de = serializer(true);
+
+    let res = de.deserialize::<PropertyClass>(&data(&[u32::MAX]));
+    assert!(matches!(
+        res,
+        Err(Error::LimitExceeded {
+            what: "element count",
+            ..
+        })
+    ));
+
+    // Lengths within the limits still fail on the missing data.
+    let mut de = serializer_with(SerializerOptions {
+        limits: AllocationLimits {
+            max_elements: usize::MAX,
+            max_total: usize::MAX,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let res = de.deserialize::<PropertyClass>(&data(&[u32::MAX]));
+    assert!(matches!(res, Err(Error::Io(..))));
+}
+
+// A complete object in shallow mode, followed by `trailing` bytes.
+fn with_trailing(trailing: &[u8]) -> Vec<u8> {
+    let mut data = data(&[2, 1, 2, 7]);
+    data.extend([3, 0, b'a', b'b', b'c']);
+    data.extend_from_slice(trailing);
+
+    data
+}
+
+fn deserialize_with(strictness: Strictness, data: &[u8]) -> Result<Value, Error> {
+    let mut de = serializer_with(SerializerOptions {
+        strictness,
+        ..Default::default()
+    });
+    de.deserialize::<PropertyClass>(data)
+}
+
+#[test]
+fn trailing_bits() {
+    let data = with_trailing(&[0xAA; 4]);
+
+    let expected = deserialize_with(Strictness::Lenient, &with_trailing(&[])).unwrap();
+    assert_eq!(
+        deserialize_with(Strictness::Lenient, &data).unwrap(),
+        expected
+    );
+    assert_eq!(deserialize_with(Strictness::Warn, &data).unwrap(), expected);
+    assert!(matches!(
+        deserialize_with(Strictness::Strict, &data),
+        Err(Error::TrailingBits(32))
+    ));
+}
+
+#[test]
+fn strict_without_trailing_bits() {
+    let res = deserialize_with(Strictness::Strict, &with_trailing(&[]));
+    assert!(res.is_ok());
+}
+
+#[test]
+fn huge_decompressed_size() {
+    let mut de = serializer_with(SerializerOptions {
+        manual_compression: true,
+        ..Default::default()
+    });
+
+    // The size prefix is checked before the stream is inflated.
+    let res = de.deserialize::<PropertyClass>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x78, 0x9C]);
+    assert!(matches!(
+        res,
+        Err(Error::LimitExceeded {
+            what: "decompressed size",
+            requested: 0xFFFF_FFFF,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn total_allocation_limit() {
+    let mut de = serializer_with(SerializerOptions {
+        limits: AllocationLimits {
+            max_total: 16,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let res = de.deserialize::<PropertyClass>(&data(&[2, 1, 2, 3]));
+    assert!(matches!(
+        res,
+        Err(Error::LimitExceeded {
+            what: "total allocation",
+            limit: 16,
+            ..
+        })
+    ));
+}
+
+// An object with a list whose length overflows its property, a
+// property unknown to the type list and a valid property.
+const DAMAGED: &[u32] = &[352, 128, 5678, 5, 1, 96, 4242, 0, 96, 9999, 7];
+
+#[test]
+fn tolerant_recovery() {
+    let mut de = serializer_with(SerializerOptions {
+        shallow: false,
+        tolerant: true,
+        ..Default::default()
+    });
+
+    let value = de.deserialize::<PropertyClass>(&data(DAMAGED)).unwrap();
+    let Value::Object { obj, .. } = &value else {
+        panic!("expected object, got {value:?}");
+    };
+    assert_eq!(obj.len(), 2);
+    assert_eq!(obj["m_count"], Value::Unsigned(7));
+    assert!(
+        matches!(&obj["m_values"], Value::Object { hash: 0, obj } if obj.contains_key("$__error"))
+    );
+
+    let diagnostics = de.take_diagnostics();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].path, "m_values");
+    assert_eq!(diagnostics[0].offset, 64);
+    assert_eq!(obj["m_values"], error_placeholder(&diagnostics[0].reason));
+    assert_eq!(diagnostics[1].path, "0x00001092");
+    assert_eq!(diagnostics[1].offset, 192);
+}
+
+#[test]
+fn tolerant_recovery_replaces_whole_lists() {
+    const PROPERTIES: &str = r#"{
+        "m_names": { "type": "std::string", "id": 0, "flags": 31, "container": "List", "dynamic": true, "pointer": false, "hash": 1 },
+        "m_count": { "type": "unsigned int", "id": 1, "flags": 31, "dynamic": false, "pointer": false, "hash": 2 }
+    }"#;
+
+    let types = type_list(&[(TEST, PROPERTIES)]);
+    let mut de = Serializer::new(
+        SerializerOptions {
+            shallow: false,
+            tolerant: true,
+            limits: AllocationLimits {
+                max_string_len: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        types,
+    )
+    .unwrap();
+
+    let names = ["ab", "too long", "cd"].map(|s| Value::String(CxxStr(s.into())));
+    let data = de
+        .serialize::<PropertyClass>(&object(
+            hash(TEST),
+            vec![
+                ("m_names", Value::List(List::new(names.into()))),
+                ("m_count", Value::Unsigned(7)),
+            ],
+        ))
+        .unwrap();
+
+    // List elements have no size prefix to skip past, so one bad
+    // element replaces the whole list.
+    let value = de.deserialize::<PropertyClass>(&data).unwrap();
+    let obj = members(&value);
+    assert_eq!(obj["m_count"], Value::Unsigned(7));
+
+    let diagnostics = de.take_diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].path, "m_names[1]");
+    assert_eq!(obj["m_names"], error_placeholder(&diagnostics[0].reason));
+}
+
+#[test]
+fn tolerant_config() {
+    let mut de = serializer(false);
+    assert!(de.deserialize::<PropertyClass>(&data(DAMAGED)).is_err());
+
+    let options = SerializerOptions {
+        shallow: true,
+        tolerant: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        Serializer::new(options, types()),
+        Err(Error::BadConfig(..))
+    ));
+}
+
+#[test]
+fn projection_skips_decode() {
+    let mut de = serializer_with(SerializerOptions {
+        shallow: false,
+        only: Some(Arc::new(HashSet::from(["m_count".to_owned()]))),
+        ..Default::default()
+    });
+
+    // The list length is bogus, but the list is never decoded.
+    let value = de
+        .deserialize::<PropertyClass>(&data(&[256, 128, 5678, u32::MAX, 0, 96, 9999, 7]))
+        .unwrap();
+    let Value::Object { obj, .. } = &value else {
+        panic!("expected object, got {value:?}");
+    };
+    assert_eq!(obj.len(), 1);
+    assert_eq!(obj["m_count"], Value::Unsigned(7));
+}
+
+#[test]
+fn preserve_unknown_property() {
+    let mut de = serializer_with(SerializerOptions {
+        shallow: false,
+        preserve_unknown: true,
+        ..Default::default()
+    });
+
+    let data = data(&[224, 96, 4242, 0xDEADBEEF, 96, 9999, 7]);
+    let value = de.deserialize::<PropertyClass>(&data).unwrap();
+    let Value::Object { obj, .. } = &value else {
+        panic!("expected object");
+    };
+
+    assert_eq!(obj["m_count"], Value::Unsigned(7));
+    assert_eq!(
+        obj["0x00001092"],
+        Value::Unknown {
+            hash: 4242,
+            len: 32,
+            bits: vec![0xEF, 0xBE, 0xAD, 0xDE],
+        }
+    );
+
+    // Unknown properties survive a round trip.
+    let data = de.serialize::<PropertyClass>(&value).unwrap();
+    assert_eq!(de.deserialize::<PropertyClass>(&data).unwrap(), value);
+}
+
+#[test]
+fn skip_types() {
+    let mut de = serializer_with(SerializerOptions {
+        shallow: false,
+        skip_types: Some(Arc::new(HashSet::from(["class Test".to_owned()]))),
+        ..Default::default()
+    });
+
+    // The bogus properties are never decoded, which leaves no root.
+    let res = de.deserialize::<PropertyClass>(&data(&[96, u32::MAX, u32::MAX]));
+    assert!(matches!(res, Err(Error::NullRoot)));
+
+    let options = SerializerOptions {
+        skip_types: Some(Arc::new(HashSet::new())),
+        ..Default::default()
+    };
+    assert!(matches!(
+        Serializer::new(options, types()),
+        Err(Error::BadConfig(..))
+    ));
+}
+
+#[test]
+fn record_spans() {
+    let mut de = serializer_with(SerializerOptions {
+        record_spans: true,
+        ..Default::default()
+    });
+
+    let mut data = data(&[2, 1, 2, 7]);
+    data.extend([0, 0]);
+
+    de.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(
+        de.take_spans(),
+        [
+            Span {
+                path: "m_values".to_owned(),
+                offset: 32,
+                len: 96,
+            },
+            Span {
+                path: "m_count".to_owned(),
+                offset: 128,
+                len: 32,
+            },
+            Span {
+                path: "m_name".to_owned(),
+                offset: 160,
+                len: 16,
+            },
+        ]
+    );
+}
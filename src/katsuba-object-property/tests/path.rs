@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use katsuba_object_property::{value::List, Value};
+
+mod common;
+use common::*;
+
+fn tree() -> Value {
+    let template = object(1, vec![("m_name", Value::Unsigned(42))]);
+    let behaviors = List::new(vec![
+        Value::Empty,
+        object(1, vec![("m_template", Value::Shared(Arc::new(template)))]),
+    ]);
+    let grid = List::new(vec![Value::List(List::new(vec![
+        Value::Unsigned(1),
+        Value::Unsigned(2),
+    ]))]);
+
+    object(
+        1,
+        vec![
+            ("m_behaviors", Value::List(behaviors)),
+            ("m_grid", Value::List(grid)),
+        ],
+    )
+}
+
+#[test]
+fn get_path() {
+    let tree = tree();
+
+    assert_eq!(
+        tree.get_path("m_behaviors[1].m_template.m_name"),
+        Some(&Value::Unsigned(42))
+    );
+    assert_eq!(tree.get_path("m_grid[0][1]"), Some(&Value::Unsigned(2)));
+    assert_eq!(tree.get_path("m_behaviors[0]"), Some(&Value::Empty));
+}
+
+#[test]
+fn get_path_missing() {
+    let tree = tree();
+
+    assert_eq!(tree.get_path("m_behaviors[2]"), None);
+    assert_eq!(tree.get_path("m_behaviors.m_template"), None);
+    assert_eq!(tree.get_path("m_grid[0"), None);
+    assert_eq!(tree.get_path("m_grid..x"), None);
+    assert_eq!(tree.get_path(""), None);
+}
+
+#[test]
+fn to_table() {
+    let list = Value::List(List::new(vec![
+        object(1, vec![("m_id", Value::Unsigned(1))]),
+        object(
+            1,
+            vec![
+                ("m_id", Value::Unsigned(2)),
+                ("m_tags", Value::List(List::new(vec![Value::Signed(-1)]))),
+            ],
+        ),
+    ]));
+
+    let table = list.to_table().unwrap();
+    assert_eq!(table.columns, ["m_id", "m_tags[0]"]);
+    assert_eq!(
+        table.rows,
+        [
+            vec![Some(&Value::Unsigned(1)), None],
+            vec![Some(&Value::Unsigned(2)), Some(&Value::Signed(-1))],
+        ]
+    );
+
+    assert_eq!(tree().to_table(), None);
+}
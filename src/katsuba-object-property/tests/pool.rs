@@ -0,0 +1,69 @@
+use katsuba_object_property::{
+    serde::{PropertyClass, Serializer},
+    Value,
+};
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_values": { "type": "unsigned int", "id": 0, "flags": 31, "container": "List", "dynamic": true, "pointer": false, "hash": 5678 }
+}"#;
+
+fn values_capacity(value: &Value) -> usize {
+    match members(value).get("m_values") {
+        Some(Value::List(list)) => list.inner.capacity(),
+        v => panic!("expected list, got {v:?}"),
+    }
+}
+
+#[test]
+fn recycled_lists_are_reused() {
+    let mut de = Serializer::new(Default::default(), type_list(&[(TEST, PROPERTIES)])).unwrap();
+
+    let mut words = vec![64];
+    words.extend(0..64);
+    let large = de.deserialize::<PropertyClass>(&data(&words)).unwrap();
+    assert!(values_capacity(&large) >= 64);
+
+    // Without recycling, a small list gets a fresh allocation.
+    let small = de.deserialize::<PropertyClass>(&data(&[1, 42])).unwrap();
+    assert!(values_capacity(&small) < 64);
+
+    de.recycle(large);
+    let small = de.deserialize::<PropertyClass>(&data(&[1, 42])).unwrap();
+    assert!(values_capacity(&small) >= 64);
+}
+
+#[test]
+fn clear_pool_drops_recycled_lists() {
+    let mut de = Serializer::new(Default::default(), type_list(&[(TEST, PROPERTIES)])).unwrap();
+
+    let mut words = vec![64];
+    words.extend(0..64);
+    let large = de.deserialize::<PropertyClass>(&data(&words)).unwrap();
+
+    de.recycle(large);
+    de.clear_pool();
+    let small = de.deserialize::<PropertyClass>(&data(&[1, 42])).unwrap();
+    assert!(values_capacity(&small) < 64);
+}
+
+#[test]
+fn batches_reuse_lists() {
+    let mut de = Serializer::new(Default::default(), type_list(&[(TEST, PROPERTIES)])).unwrap();
+
+    let mut words = vec![64];
+    words.extend(0..64);
+    let (large, small, broken) = (data(&words), data(&[1, 42]), data(&[]));
+
+    let mut capacities = Vec::new();
+    let batch = [&large[..], &broken[..], &small[..]];
+    de.deserialize_batch::<PropertyClass, _, _>(batch, |res| {
+        capacities.push(res.ok().map(values_capacity));
+    });
+
+    assert!(capacities[0].unwrap() >= 64);
+    assert_eq!(capacities[1], None);
+    assert!(capacities[2].unwrap() >= 64);
+}
@@ -0,0 +1,60 @@
+use katsuba_object_property::serde::{self, Error, SerializerFlags, SerializerOptions};
+use katsuba_utils::libdeflater::{CompressionLvl, Compressor};
+
+mod common;
+use common::*;
+
+// Compresses `data` with zlib and prepends the size prefix.
+fn compressed(data: &[u8]) -> Vec<u8> {
+    let mut deflater = Compressor::new(CompressionLvl::default());
+
+    let mut out = vec![0; 4 + deflater.zlib_compress_bound(data.len())];
+    out[..4].copy_from_slice(&(data.len() as u32).to_le_bytes());
+    let len = deflater.zlib_compress(data, &mut out[4..]).unwrap();
+    out.truncate(4 + len);
+
+    out
+}
+
+fn scan(options: SerializerOptions, data: &[u8]) -> Result<Vec<(u32, Vec<usize>)>, Error> {
+    let mut de = serializer_with(options);
+    let refs = serde::scan_type_hashes(&mut de, data, false)?;
+
+    Ok(refs.into_iter().map(|r| (r.hash, r.offsets)).collect())
+}
+
+#[test]
+fn raw_data() {
+    let blob = [data(&[1, 2]), data(&[3])].concat();
+
+    assert_eq!(
+        scan(SerializerOptions::default(), &blob).unwrap(),
+        [(hash(TEST), vec![0, 96])]
+    );
+}
+
+#[test]
+fn compressed_data() {
+    let blob = data(&[7, 7, 7, 7]);
+
+    let manual = SerializerOptions {
+        manual_compression: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        scan(manual.clone(), &compressed(&blob)).unwrap(),
+        [(hash(TEST), vec![0])]
+    );
+
+    let flagged = SerializerOptions {
+        flags: SerializerFlags::WITH_COMPRESSION,
+        ..Default::default()
+    };
+    let framed = [&[1][..], &compressed(&blob)].concat();
+    assert_eq!(scan(flagged, &framed).unwrap(), [(hash(TEST), vec![0])]);
+
+    // Truncated streams fail instead of being scanned as they are.
+    let mut truncated = compressed(&blob);
+    truncated.truncate(truncated.len() / 2);
+    assert!(scan(manual, &truncated).is_err());
+}
@@ -0,0 +1,265 @@
+use std::sync::Arc;
+
+use katsuba_object_property::{
+    serde::{Error, PropertyClass, Serializer, SerializerFlags, SerializerOptions},
+    value::{CxxStr, CxxWStr, List, Vec3},
+    Value,
+};
+use katsuba_types::TypeList;
+
+mod common;
+use common::*;
+
+const ITEM: &str = "class Item";
+
+const PROPERTIES: &str = r#"{
+    "m_id": { "type": "unsigned int", "id": 0, "flags": 31, "dynamic": false, "pointer": false, "hash": 1 },
+    "m_name": { "type": "std::string", "id": 1, "flags": 31, "dynamic": false, "pointer": false, "hash": 2 },
+    "m_scale": { "type": "float", "id": 2, "flags": 31, "dynamic": false, "pointer": false, "hash": 3 },
+    "m_visible": { "type": "bool", "id": 3, "flags": 31, "dynamic": false, "pointer": false, "hash": 4 },
+    "m_kind": { "type": "enum Kind", "id": 4, "flags": 31, "dynamic": false, "pointer": false, "hash": 5,
+                "enum_options": { "KIND_A": 1, "KIND_B": 2 } },
+    "m_pos": { "type": "class Vector3D", "id": 5, "flags": 31, "dynamic": false, "pointer": false, "hash": 6 },
+    "m_tags": { "type": "std::wstring", "id": 6, "flags": 31, "dynamic": true, "pointer": false, "hash": 7 },
+    "m_child": { "type": "class Item", "id": 7, "flags": 31, "dynamic": false, "pointer": true, "hash": 8 }
+}"#;
+
+fn types() -> Arc<TypeList> {
+    type_list(&[(ITEM, PROPERTIES)])
+}
+
+fn item_object(members: Vec<(&str, Value)>) -> Value {
+    object(hash(ITEM), members)
+}
+
+fn item() -> Value {
+    let child = item_object(vec![
+        ("m_id", Value::Unsigned(2)),
+        ("m_name", Value::String(CxxStr(b"child".to_vec()))),
+        ("m_scale", Value::Float(0.25)),
+        ("m_visible", Value::Bool(false)),
+        ("m_kind", Value::Enum(1)),
+        (
+            "m_pos",
+            Value::Vec3(Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            }),
+        ),
+        ("m_tags", Value::List(List::new(vec![]))),
+        ("m_child", Value::Empty),
+    ]);
+
+    item_object(vec![
+        ("m_id", Value::Unsigned(1)),
+        ("m_name", Value::String(CxxStr(b"parent".to_vec()))),
+        ("m_scale", Value::Float(1.5)),
+        ("m_visible", Value::Bool(true)),
+        ("m_kind", Value::Enum(2)),
+        (
+            "m_pos",
+            Value::Vec3(Vec3 {
+                x: 1.0,
+                y: -2.0,
+                z: 3.5,
+            }),
+        ),
+        (
+            "m_tags",
+            Value::List(List::new(vec![
+                Value::WString(CxxWStr("a".encode_utf16().collect())),
+                Value::WString(CxxWStr("bc".encode_utf16().collect())),
+            ])),
+        ),
+        ("m_child", child),
+    ])
+}
+
+fn round_trip(options: SerializerOptions) {
+    let mut serializer = Serializer::new(options.clone(), types()).unwrap();
+    let data = serializer.serialize::<PropertyClass>(&item()).unwrap();
+
+    let mut de = Serializer::new(options, types()).unwrap();
+    let value = de.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(value, item());
+}
+
+#[test]
+fn round_trip_shallow() {
+    round_trip(SerializerOptions::default());
+}
+
+#[test]
+fn round_trip_deep() {
+    round_trip(SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn round_trip_game_file() {
+    round_trip(SerializerOptions::game_file());
+}
+
+#[test]
+fn round_trip_configured() {
+    round_trip(SerializerOptions {
+        flags: SerializerFlags::STATEFUL_FLAGS
+            | SerializerFlags::COMPACT_LENGTH_PREFIXES
+            | SerializerFlags::HUMAN_READABLE_ENUMS
+            | SerializerFlags::WITH_COMPRESSION,
+        shallow: false,
+        manual_compression: true,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn round_trip_reused_serializer() {
+    let options = SerializerOptions {
+        flags: SerializerFlags::STATEFUL_FLAGS | SerializerFlags::WITH_COMPRESSION,
+        shallow: false,
+        manual_compression: true,
+        ..Default::default()
+    };
+    let mut serializer = Serializer::new(options, types()).unwrap();
+
+    // Buffers retained from earlier calls must not leak into later ones.
+    let data = serializer.serialize::<PropertyClass>(&item()).unwrap();
+    for _ in 0..3 {
+        let value = serializer.deserialize::<PropertyClass>(&data).unwrap();
+        assert_eq!(value, item());
+        assert_eq!(serializer.serialize::<PropertyClass>(&value).unwrap(), data);
+    }
+
+    serializer.clear_scratch();
+    assert_eq!(serializer.scratch_capacity(), 0);
+    assert_eq!(
+        serializer.serialize::<PropertyClass>(&item()).unwrap(),
+        data
+    );
+}
+
+#[test]
+fn scratch_reused_across_calls() {
+    let options = SerializerOptions {
+        flags: SerializerFlags::WITH_COMPRESSION,
+        shallow: false,
+        manual_compression: true,
+        ..Default::default()
+    };
+    let mut serializer = Serializer::new(options, types()).unwrap();
+
+    let data = serializer.serialize::<PropertyClass>(&item()).unwrap();
+    serializer.deserialize::<PropertyClass>(&data).unwrap();
+    let capacity = serializer.scratch_capacity();
+    assert_ne!(capacity, 0);
+
+    // Neither call needs more room than the first, so nothing grows.
+    serializer.serialize::<PropertyClass>(&item()).unwrap();
+    serializer.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(serializer.scratch_capacity(), capacity);
+
+    // The pool is separate and leaves the scratch buffers alone.
+    serializer.clear_pool();
+    assert_eq!(serializer.scratch_capacity(), capacity);
+}
+
+#[test]
+fn lenient_values() {
+    // Values as they come back from JSON, with enums by name and
+    // vectors as plain objects.
+    let Value::Object { hash, mut obj } = item() else {
+        unreachable!()
+    };
+    obj.insert("m_kind".into(), Value::String(CxxStr(b"KIND_B".to_vec())));
+    obj.insert("m_scale".into(), Value::Unsigned(2));
+    obj.insert(
+        "m_pos".into(),
+        item_object(vec![
+            ("x", Value::Float(1.0)),
+            ("y", Value::Signed(-2)),
+            ("z", Value::Float(3.5)),
+        ]),
+    );
+
+    let mut serializer = Serializer::new(SerializerOptions::default(), types()).unwrap();
+    let data = serializer
+        .serialize::<PropertyClass>(&Value::Object { hash, obj })
+        .unwrap();
+
+    let value = serializer.deserialize::<PropertyClass>(&data).unwrap();
+    let Value::Object { obj, .. } = value else {
+        panic!("expected object");
+    };
+    assert_eq!(obj["m_kind"], Value::Enum(2));
+    assert_eq!(obj["m_scale"], Value::Float(2.0));
+    assert_eq!(
+        obj["m_pos"],
+        Value::Vec3(Vec3 {
+            x: 1.0,
+            y: -2.0,
+            z: 3.5
+        })
+    );
+}
+
+#[test]
+fn enum_names() {
+    let options = SerializerOptions {
+        enum_names: true,
+        ..Default::default()
+    };
+    let mut serializer = Serializer::new(options, types()).unwrap();
+
+    let Value::Object { hash, mut obj } = item() else {
+        unreachable!()
+    };
+    obj.insert("m_kind".into(), Value::Enum(7));
+    let data = serializer
+        .serialize::<PropertyClass>(&Value::Object { hash, obj })
+        .unwrap();
+
+    let Value::Object { obj, .. } = serializer.deserialize::<PropertyClass>(&data).unwrap() else {
+        panic!("expected object");
+    };
+    let Value::Object { obj: child, .. } = &obj["m_child"] else {
+        panic!("expected object");
+    };
+
+    // Unknown variants keep their value.
+    assert_eq!(
+        obj["m_kind"],
+        Value::NamedEnum {
+            name: None,
+            value: 7
+        }
+    );
+    assert_eq!(
+        child["m_kind"],
+        Value::NamedEnum {
+            name: Some("KIND_A".into()),
+            value: 1
+        }
+    );
+
+    // Named variants serialize back to the same data.
+    let value = Value::Object { hash, obj };
+    assert_eq!(serializer.serialize::<PropertyClass>(&value).unwrap(), data);
+}
+
+#[test]
+fn missing_property() {
+    let Value::Object { hash, mut obj } = item() else {
+        unreachable!()
+    };
+    obj.remove("m_name");
+
+    let mut serializer = Serializer::new(SerializerOptions::default(), types()).unwrap();
+    assert!(matches!(
+        serializer.serialize::<PropertyClass>(&Value::Object { hash, obj }),
+        Err(Error::MissingProperty(name)) if name == "m_name"
+    ));
+}
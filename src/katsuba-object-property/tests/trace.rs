@@ -0,0 +1,41 @@
+#![cfg(feature = "trace")]
+
+use std::{cell::RefCell, rc::Rc};
+
+use katsuba_object_property::serde::{trace, PropertyClass, Serializer, SerializerOptions};
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_count": { "type": "unsigned int", "id": 0, "flags": 31, "dynamic": false, "pointer": false, "hash": 1 },
+    "m_name": { "type": "std::string", "id": 1, "flags": 31, "dynamic": false, "pointer": false, "hash": 2 }
+}"#;
+
+#[test]
+fn trace_reads() {
+    let types = type_list(&[(TEST, PROPERTIES)]);
+    let mut de = Serializer::new(SerializerOptions::default(), types).unwrap();
+
+    let mut data = data(&[7]);
+    data.extend(2u16.to_le_bytes());
+    data.extend(b"hi");
+
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let sink = lines.clone();
+    trace::set_sink(Some(Box::new(move |read: &trace::Read<'_>| {
+        sink.borrow_mut().push(read.to_string())
+    })));
+    de.deserialize::<PropertyClass>(&data).unwrap();
+    trace::set_sink(None);
+
+    assert_eq!(
+        *lines.borrow(),
+        [
+            format!("u32 @ bit 0 = {:#x}", hash(TEST)),
+            "u32 @ bit 32 = 0x7".to_owned(),
+            "u16 @ bit 64 = 0x2".to_owned(),
+            "bytes[2] @ bit 80 = 6869".to_owned(),
+        ]
+    );
+}
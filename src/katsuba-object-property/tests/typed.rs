@@ -0,0 +1,119 @@
+use katsuba_object_property::{
+    typed::{Error, FromValue},
+    value::{CxxStr, List, Vec3},
+    Value,
+};
+use katsuba_op_derive::ObjectProperty;
+
+mod common;
+use common::*;
+
+#[derive(Debug, PartialEq, ObjectProperty)]
+#[op(class = "class Item")]
+struct Item {
+    #[op(rename = "m_id")]
+    id: u32,
+    #[op(rename = "m_name")]
+    name: String,
+    m_scale: f32,
+    #[op(rename = "m_kind")]
+    kind: i32,
+    #[op(rename = "m_pos")]
+    pos: Vec3,
+    #[op(rename = "m_tags")]
+    tags: Vec<u8>,
+    #[op(rename = "m_child")]
+    child: Option<Box<Item>>,
+    #[op(skip)]
+    extra: bool,
+}
+
+#[derive(Debug, ObjectProperty)]
+#[op(class = "class Item")]
+struct Outdated {
+    #[op(rename = "m_templateID")]
+    _template_id: u32,
+}
+
+const ITEM: &str = "class Item";
+
+const PROPERTIES: &str = r#"{
+    "m_id": { "type": "unsigned int", "id": 0, "flags": 31, "dynamic": false, "pointer": false, "hash": 1 },
+    "m_name": { "type": "std::string", "id": 1, "flags": 31, "dynamic": false, "pointer": false, "hash": 2 },
+    "m_scale": { "type": "float", "id": 2, "flags": 31, "dynamic": false, "pointer": false, "hash": 3 },
+    "m_kind": { "type": "enum Kind", "id": 3, "flags": 31, "dynamic": false, "pointer": false, "hash": 4,
+                "enum_options": { "KIND_A": 1, "KIND_B": 2 } },
+    "m_pos": { "type": "class Vector3D", "id": 4, "flags": 31, "dynamic": false, "pointer": false, "hash": 5 },
+    "m_tags": { "type": "unsigned char", "id": 5, "flags": 31, "dynamic": true, "pointer": false, "hash": 6 },
+    "m_child": { "type": "class Item", "id": 6, "flags": 31, "dynamic": false, "pointer": true, "hash": 7 }
+}"#;
+
+fn item_object(members: Vec<(&str, Value)>) -> Value {
+    object(hash(ITEM), members)
+}
+
+fn item(child: Value) -> Value {
+    item_object(vec![
+        ("m_id", Value::Unsigned(7)),
+        ("m_name", Value::String(CxxStr(b"sword".to_vec()))),
+        ("m_scale", Value::Float(0.5)),
+        ("m_kind", Value::Enum(2)),
+        (
+            "m_pos",
+            Value::Vec3(Vec3 {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            }),
+        ),
+        (
+            "m_tags",
+            Value::List(List::new(vec![Value::Unsigned(1), Value::Unsigned(2)])),
+        ),
+        ("m_child", child),
+    ])
+}
+
+fn expected(child: Option<Box<Item>>) -> Item {
+    Item {
+        id: 7,
+        name: "sword".to_owned(),
+        m_scale: 0.5,
+        kind: 2,
+        pos: Vec3 {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        },
+        tags: vec![1, 2],
+        child,
+        extra: false,
+    }
+}
+
+#[test]
+fn derive_nested() {
+    let types = type_list(&[(ITEM, PROPERTIES)]);
+    let item = Item::from_value(&types, item(item(Value::Empty))).unwrap();
+
+    assert_eq!(item, expected(Some(Box::new(expected(None)))));
+}
+
+#[test]
+fn derive_errors() {
+    let types = type_list(&[(ITEM, PROPERTIES)]);
+
+    let err = Outdated::from_value(&types, item(Value::Empty)).unwrap_err();
+    assert!(matches!(err, Error::UnknownProperty { property, .. } if property == "m_templateID"));
+
+    let err =
+        Item::from_value(&types, item_object(vec![("m_id", Value::Unsigned(1))])).unwrap_err();
+    assert!(matches!(err, Error::MissingProperty("m_name")));
+
+    let mut bad = item(Value::Empty);
+    if let Value::Object { obj, .. } = &mut bad {
+        obj.insert("m_id".into(), Value::Signed(-1));
+    }
+    let err = Item::from_value(&types, bad).unwrap_err();
+    assert!(matches!(err, Error::Property { name: "m_id", .. }));
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "serde")]
+
+use katsuba_object_property::{
+    value::{CxxStr, List, WithHashes},
+    Value,
+};
+use katsuba_types::Container;
+
+mod common;
+use common::*;
+
+#[test]
+fn json_round_trip() {
+    let mut tags = List::new(vec![Value::Unsigned(1), Value::Signed(-2)]);
+    tags.container = Some(Container::Vector);
+
+    let value = object(
+        0x1234,
+        vec![
+            ("m_name", Value::String(CxxStr(b"item".to_vec()))),
+            ("m_scale", Value::Float(0.5)),
+            ("m_visible", Value::Bool(true)),
+            ("m_tags", Value::List(tags)),
+            ("m_plain", Value::List(List::new(vec![Value::Empty]))),
+            (
+                "m_child",
+                object(0x5678, vec![("m_id", Value::Unsigned(3))]),
+            ),
+        ],
+    );
+
+    let json = serde_json::to_string(&value).unwrap();
+    let loaded: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded, value);
+}
+
+#[test]
+fn json_untyped_objects() {
+    let loaded: Value = serde_json::from_str(r#"{ "x": 1.5, "y": 2 }"#).unwrap();
+    assert_eq!(
+        loaded,
+        object(0, vec![("x", Value::Float(1.5)), ("y", Value::Unsigned(2))])
+    );
+
+    assert!(serde_json::from_str::<Value>(r#"{ "$__type": "foo" }"#).is_err());
+    assert!(serde_json::from_str::<Value>(r#"{ "$__items": 1 }"#).is_err());
+}
+
+#[test]
+fn json_with_hashes() {
+    let types = type_list(&[(
+        TEST,
+        r#"{ "m_id": { "type": "unsigned int", "id": 0, "flags": 31, "dynamic": false, "pointer": false, "hash": 42 } }"#,
+    )]);
+    let value = object(hash(TEST), vec![("m_id", Value::Unsigned(3))]);
+
+    let json = serde_json::to_value(WithHashes::new(&value, &types)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "$__type": hash(TEST),
+            "$__name": "class Test",
+            "$__hashes": { "m_id": 42 },
+            "m_id": 3
+        })
+    );
+
+    let loaded: Value = serde_json::from_value(json).unwrap();
+    assert_eq!(loaded, value);
+}
@@ -0,0 +1,57 @@
+use katsuba_object_property::{
+    serde::{PropertyClass, Serializer, SerializerOptions},
+    value::Vec3,
+    Value,
+};
+
+mod common;
+use common::*;
+
+const PROPERTIES: &str = r#"{
+    "m_flag": { "type": "bool", "id": 0, "flags": 31, "dynamic": false, "pointer": false, "hash": 1 },
+    "m_pos": { "type": "class Vector3D", "id": 1, "flags": 31, "dynamic": false, "pointer": false, "hash": 2 }
+}"#;
+
+fn serializer() -> Serializer {
+    let options = SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    };
+    Serializer::new(options, type_list(&[(TEST, PROPERTIES)])).unwrap()
+}
+
+// A deep object with a bool followed by a vector. The vector starts
+// right after bits consumed through the bit buffer.
+fn fixture() -> Vec<u8> {
+    // The object size and the 65 bits of `m_flag`.
+    let mut data = data(&[264, 65, 1]);
+    data.push(0b1);
+
+    // `m_pos`, counting the 7 padding bits after `m_flag`.
+    data.extend(167_u32.to_le_bytes());
+    data.extend(2_u32.to_le_bytes());
+    for f in [1.0_f32, 2.0, 3.0] {
+        data.extend(f.to_le_bytes());
+    }
+
+    data
+}
+
+#[test]
+fn vector_after_bit_packed_value() {
+    let mut de = serializer();
+
+    let value = de.deserialize::<PropertyClass>(&fixture()).unwrap();
+    let obj = members(&value);
+    assert_eq!(obj["m_flag"], Value::Bool(true));
+    assert_eq!(
+        obj["m_pos"],
+        Value::Vec3(Vec3 {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0
+        })
+    );
+
+    assert_eq!(de.serialize::<PropertyClass>(&value).unwrap(), fixture());
+}
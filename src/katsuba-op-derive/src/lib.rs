@@ -0,0 +1,121 @@
+//! Derive macro for deserializing ObjectProperty objects into Rust
+//! structs.
+//!
+//! See `katsuba_object_property::typed` for the traits implemented by
+//! the generated code.
+
+#![deny(rust_2018_idioms)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Derives `ObjectProperty` and `FromValue` for a struct with named
+/// fields.
+///
+/// The struct is matched against the class `class <Name>` by default,
+/// which can be changed with `#[op(class = "...")]`. Every field is
+/// read from the property of the same name unless it is renamed with
+/// `#[op(rename = "...")]`. Fields marked `#[op(skip)]` are filled in
+/// with their [`Default`] value.
+#[proc_macro_derive(ObjectProperty, attributes(op))]
+pub fn derive_object_property(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    let mut class = format!("class {ident}");
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("op")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("class") {
+                class = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("unsupported container attribute"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "ObjectProperty can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "ObjectProperty can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut inits = Vec::with_capacity(fields.len());
+    for field in fields {
+        let name = field.ident.as_ref().unwrap();
+
+        let mut property = name.to_string();
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("op")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    property = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported field attribute"))
+                }
+            })?;
+        }
+
+        inits.push(if skip {
+            quote!(#name: ::core::default::Default::default())
+        } else {
+            quote!(#name: __typed::field(types, type_def, &mut obj, #property)?)
+        });
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        const _: () = {
+            use ::katsuba_object_property::typed as __typed;
+
+            impl #impl_generics __typed::ObjectProperty for #ident #ty_generics #where_clause {
+                const CLASS_NAME: &'static str = #class;
+
+                #[allow(unused_mut, unused_variables)]
+                fn from_object(
+                    types: &__typed::TypeList,
+                    hash: u32,
+                    mut obj: ::katsuba_object_property::value::Object,
+                ) -> ::core::result::Result<Self, __typed::Error> {
+                    let type_def = __typed::resolve::<Self>(types, hash)?;
+                    ::core::result::Result::Ok(Self {
+                        #(#inits,)*
+                    })
+                }
+            }
+
+            impl #impl_generics __typed::FromValue for #ident #ty_generics #where_clause {
+                fn from_value(
+                    types: &__typed::TypeList,
+                    value: ::katsuba_object_property::Value,
+                ) -> ::core::result::Result<Self, __typed::Error> {
+                    __typed::from_object_value(types, value)
+                }
+            }
+        };
+    })
+}
@@ -0,0 +1,224 @@
+//! Reusable processing pipelines for KingsIsle formats.
+//!
+//! This crate implements the logic behind Katsuba's commands in a
+//! form that is independent of any command-line or FFI types, so
+//! that all frontends share the same behavior.
+
+#![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
+#![forbid(unsafe_code)]
+
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use katsuba_object_property::{serde, Value};
+use katsuba_types::TypeList;
+use katsuba_utils::thiserror::{self, Error};
+
+/// Errors that may occur when running a pipeline.
+#[derive(Debug, Error)]
+pub enum PipelineError {
+    /// An I/O error occurred while reading input or writing output.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    /// No type list files were provided.
+    #[error("at least one type list is required for deserialization")]
+    NoTypeLists,
+
+    /// A type list file could not be loaded.
+    #[error("failed to load type list at '{}': {source}", path.display())]
+    TypeList {
+        /// The path to the type list file.
+        path: PathBuf,
+        /// The underlying error.
+        source: katsuba_types::Error,
+    },
+
+    /// Deserialization of ObjectProperty state failed.
+    #[error("{0}")]
+    Deserialize(#[from] serde::Error),
+
+    /// Writing the JSON representation of a value failed.
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+
+    /// Walking an input directory failed.
+    #[error("{0}")]
+    Walk(#[from] walkdir::Error),
+}
+
+/// Reads all the given type list files and merges them into a single
+/// [`TypeList`].
+///
+/// Entries from later files take precedence over earlier ones.
+pub fn merge_type_lists<P: AsRef<Path>>(paths: &[P]) -> Result<TypeList, PipelineError> {
+    let (first, rest) = paths.split_first().ok_or(PipelineError::NoTypeLists)?;
+
+    let mut list = load_type_list(first.as_ref())?;
+    for path in rest {
+        list.merge(load_type_list(path.as_ref())?);
+    }
+
+    Ok(list)
+}
+
+fn load_type_list(path: &Path) -> Result<TypeList, PipelineError> {
+    fs::File::open(path)
+        .map_err(katsuba_types::Error::from)
+        .and_then(|f| TypeList::from_reader(BufReader::new(f)))
+        .map_err(|source| PipelineError::TypeList {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+/// The result of running a [`DeserializeJob`] over a directory.
+#[derive(Debug, Default)]
+pub struct DirReport {
+    /// Paths to the JSON files that were written.
+    pub written: Vec<PathBuf>,
+    /// Input files which could not be processed, with their errors.
+    pub failed: Vec<(PathBuf, PipelineError)>,
+}
+
+/// Deserializes ObjectProperty state and converts it to JSON.
+///
+/// This is the library equivalent of `katsuba op de`. Inputs which
+/// start with [`serde::BIND_MAGIC`] are game files and are always
+/// deserialized in deep mode with [`serde::SerializerFlags::STATEFUL_FLAGS`],
+/// regardless of the configured options.
+pub struct DeserializeJob {
+    de: serde::Serializer,
+    options: serde::SerializerOptions,
+    // File contents, retained across calls to `deserialize_file`.
+    buf: Vec<u8>,
+}
+
+impl DeserializeJob {
+    /// Creates a new job with the given serializer configuration.
+    pub fn new(
+        options: serde::SerializerOptions,
+        types: Arc<TypeList>,
+    ) -> Result<Self, PipelineError> {
+        Ok(Self {
+            de: serde::Serializer::new(options.clone(), types)?,
+            options,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Deserializes a single object from its serialized bytes.
+    pub fn deserialize(&mut self, data: &[u8]) -> Result<Value, PipelineError> {
+        self.de.parts.options = self.options.clone();
+
+        let data = match data.strip_prefix(serde::BIND_MAGIC) {
+            Some(data) => {
+                self.de.parts.options.shallow = false;
+                self.de.parts.options.flags = serde::SerializerFlags::STATEFUL_FLAGS;
+                data
+            }
+            None => data,
+        };
+
+        self.de
+            .deserialize::<serde::PropertyClass>(data)
+            .map_err(Into::into)
+    }
+
+    /// Takes the diagnostics of the last deserialized object.
+    ///
+    /// These are only collected when the job was created with
+    /// [`serde::SerializerOptions::tolerant`] set.
+    pub fn take_diagnostics(&mut self) -> Vec<serde::Diagnostic> {
+        self.de.take_diagnostics()
+    }
+
+    /// Takes the bit spans of the properties in the last deserialized
+    /// object.
+    ///
+    /// These are only recorded when the job was created with
+    /// [`serde::SerializerOptions::record_spans`] set.
+    pub fn take_spans(&mut self) -> Vec<serde::Span> {
+        self.de.take_spans()
+    }
+
+    /// Gets the size in bytes of the last deserialized object data,
+    /// after decompressing it.
+    pub fn data_len(&self) -> usize {
+        self.de.data_len()
+    }
+
+    /// Deserializes the file at `path`.
+    ///
+    /// The buffer for the file contents is reused between calls.
+    pub fn deserialize_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Value, PipelineError> {
+        let mut buf = std::mem::take(&mut self.buf);
+        buf.clear();
+
+        let res = match fs::File::open(path).and_then(|mut f| f.read_to_end(&mut buf)) {
+            Ok(_) => self.deserialize(&buf),
+            Err(e) => Err(e.into()),
+        };
+        self.buf = buf;
+
+        res
+    }
+
+    /// Deserializes the file at `input` and writes its JSON form to
+    /// the file at `output`.
+    pub fn run_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        input: P,
+        output: Q,
+    ) -> Result<(), PipelineError> {
+        let value = self.deserialize_file(input)?;
+
+        let mut writer = BufWriter::new(fs::File::create(output)?);
+        serde_json::to_writer(&mut writer, &value)?;
+        writer.flush()?;
+
+        self.de.recycle(value);
+        Ok(())
+    }
+
+    /// Deserializes all files with the given `extension` in the `input`
+    /// directory tree and mirrors them as JSON files into `output`.
+    ///
+    /// A failure to process an individual file does not abort the job;
+    /// such errors are collected into the returned [`DirReport`].
+    pub fn run_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        input: P,
+        output: Q,
+        extension: &str,
+    ) -> Result<DirReport, PipelineError> {
+        let (input, output) = (input.as_ref(), output.as_ref());
+        let mut report = DirReport::default();
+
+        for entry in walkdir::WalkDir::new(input) {
+            let entry = entry?;
+            let path = entry.path();
+            if !entry.file_type().is_file() || path.extension().is_none_or(|e| e != extension) {
+                continue;
+            }
+
+            // `WalkDir` only yields paths inside of `input`.
+            let relative = path.strip_prefix(input).unwrap();
+            let out = output.join(relative).with_extension("json");
+            if let Some(parent) = out.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            match self.run_file(path, &out) {
+                Ok(()) => report.written.push(out),
+                Err(e) => report.failed.push((path.to_owned(), e)),
+            }
+        }
+
+        Ok(report)
+    }
+}
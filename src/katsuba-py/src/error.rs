@@ -1,4 +1,5 @@
 use katsuba_object_property::serde::Error as OpError;
+use katsuba_pipeline::PipelineError;
 use katsuba_wad::ArchiveError;
 use pyo3::prelude::*;
 
@@ -17,3 +18,10 @@ pub fn wad_to_py_err(err: ArchiveError) -> PyErr {
         e => KatsubaError::new_err(format!("{e}")),
     }
 }
+
+pub fn pipeline_to_py_err(err: PipelineError) -> PyErr {
+    match err {
+        PipelineError::Io(e) => e.into(),
+        e => KatsubaError::new_err(format!("{e}")),
+    }
+}
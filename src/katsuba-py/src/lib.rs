@@ -7,6 +7,7 @@
 
 mod error;
 mod op;
+mod quick;
 mod utils;
 mod wad;
 
@@ -22,12 +23,14 @@ pub fn katsuba(py: Python<'_>, module: &PyModule) -> PyResult<()> {
 
     // Declare all the submodules in the package.
     let op = PyModule::new(py, "op")?;
+    let quick = PyModule::new(py, "quick")?;
     let utils = PyModule::new(py, "utils")?;
     let wad = PyModule::new(py, "wad")?;
 
     // Enable `from katsuba_py.x import A` imports.
     let locals = [
         ("op", op.to_object(py)),
+        ("quick", quick.to_object(py)),
         ("utils", utils.to_object(py)),
         ("wad", wad.to_object(py)),
     ]
@@ -36,6 +39,7 @@ pub fn katsuba(py: Python<'_>, module: &PyModule) -> PyResult<()> {
         r#"
 import sys
 sys.modules['katsuba.op'] = op
+sys.modules['katsuba.quick'] = quick
 sys.modules['katsuba.utils'] = utils
 sys.modules['katsuba.wad'] = wad
 "#,
@@ -47,6 +51,10 @@ sys.modules['katsuba.wad'] = wad
     op::katsuba_op(op)?;
     module.add_submodule(op)?;
 
+    // Register katsuba_py.quick module.
+    quick::katsuba_quick(quick)?;
+    module.add_submodule(quick)?;
+
     // Register katsuba_py.utils module.
     utils::katsuba_utils(utils)?;
     module.add_submodule(utils)?;
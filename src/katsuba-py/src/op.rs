@@ -4,7 +4,7 @@ use katsuba_object_property::{
     serde::{self, SerializerFlags},
     Value,
 };
-use pyo3::{prelude::*, types::PyType};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyType};
 
 use crate::{error, KatsubaError};
 
@@ -18,7 +18,7 @@ pub use leaf_types::*;
 
 #[derive(Clone)]
 #[pyclass(module = "katsuba.op")]
-pub struct TypeList(Arc<katsuba_types::TypeList>);
+pub struct TypeList(pub(crate) Arc<katsuba_types::TypeList>);
 
 #[pymethods]
 impl TypeList {
@@ -36,9 +36,14 @@ impl TypeList {
             .map(|v| Self(Arc::new(v)))
             .map_err(|e| KatsubaError::new_err(e.to_string()))
     }
+
+    #[classmethod]
+    pub fn builtin(_cls: &PyType) -> Self {
+        Self(Arc::new(katsuba_types::TypeList::builtin()))
+    }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Default)]
 #[pyclass(module = "katsuba.op")]
 pub struct SerializerOptions(serde::SerializerOptions);
 
@@ -66,7 +71,7 @@ impl SerializerOptions {
 
     #[setter]
     pub fn set_property_mask(&mut self, new: u32) {
-        self.0.property_mask = katsuba_types::PropertyFlags::from_bits_truncate(new);
+        self.0.property_mask = katsuba_types::PropertyFlags::from_bits_retain(new);
     }
 
     #[getter]
@@ -118,6 +123,46 @@ impl SerializerOptions {
     pub fn set_djb2_only(&mut self, new: bool) {
         self.0.djb2_only = new;
     }
+
+    #[getter]
+    pub fn get_collapse_single_element(&self) -> bool {
+        self.0.collapse_single_element
+    }
+
+    #[setter]
+    pub fn set_collapse_single_element(&mut self, new: bool) {
+        self.0.collapse_single_element = new;
+    }
+
+    #[getter]
+    pub fn get_annotate_containers(&self) -> bool {
+        self.0.annotate_containers
+    }
+
+    #[setter]
+    pub fn set_annotate_containers(&mut self, new: bool) {
+        self.0.annotate_containers = new;
+    }
+
+    #[getter]
+    pub fn get_strictness(&self) -> &'static str {
+        match self.0.strictness {
+            serde::Strictness::Lenient => "lenient",
+            serde::Strictness::Warn => "warn",
+            serde::Strictness::Strict => "strict",
+        }
+    }
+
+    #[setter]
+    pub fn set_strictness(&mut self, new: &str) -> PyResult<()> {
+        self.0.strictness = match new {
+            "lenient" => serde::Strictness::Lenient,
+            "warn" => serde::Strictness::Warn,
+            "strict" => serde::Strictness::Strict,
+            _ => return Err(PyValueError::new_err(format!("unknown strictness '{new}'"))),
+        };
+        Ok(())
+    }
 }
 
 #[pyclass(module = "katsuba.op")]
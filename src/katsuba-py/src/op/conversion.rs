@@ -35,6 +35,7 @@ pub unsafe fn value_to_python(base: Arc<Value>, value: &Value, py: Python<'_>) -
 
         Value::Unsigned(v) => v.into_py(py),
         Value::Signed(v) | Value::Enum(v) => v.into_py(py),
+        Value::NamedEnum { value, .. } => value.into_py(py),
         Value::Float(v) => v.into_py(py),
         Value::Bool(v) => v.into_py(py),
 
@@ -43,6 +44,8 @@ pub unsafe fn value_to_python(base: Arc<Value>, value: &Value, py: Python<'_>) -
 
         Value::List(v) => unsafe { LazyList::new(base, v).into_py(py) },
         Value::Object { hash, obj } => unsafe { LazyObject::new(base, *hash, obj).into_py(py) },
+        Value::Shared(v) => unsafe { value_to_python(base, v, py) },
+        Value::Unknown { bits, .. } => bits.as_slice().into_py(py),
 
         Value::Color(v) => {
             let Color { r, g, b, a } = *v;
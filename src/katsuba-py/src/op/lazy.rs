@@ -1,13 +1,92 @@
-use std::{ptr::NonNull, sync::Arc};
+use std::{fmt::Write, ptr::NonNull, sync::Arc};
 
-use katsuba_object_property::value::{List, Object, Value};
+use katsuba_object_property::value::{diff_lists, diff_objects, Change, List, Object, Value};
 use pyo3::{
+    basic::CompareOp,
     exceptions::{PyIndexError, PyKeyError},
     prelude::*,
+    types::{PyDict, PyList, PyTuple},
 };
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::KatsubaError;
 
 use super::conversion::value_to_python;
 
+// The maximum number of elements to show in a `__repr__` preview.
+const PREVIEW_ITEMS: usize = 8;
+
+fn preview(value: &Value, out: &mut String) {
+    let _ = match value {
+        Value::Shared(v) => return preview(v, out),
+        Value::Empty => write!(out, "None"),
+        Value::Unsigned(v) => write!(out, "{v}"),
+        Value::Signed(v) | Value::Enum(v) => write!(out, "{v}"),
+        Value::NamedEnum { value, .. } => write!(out, "{value}"),
+        Value::Float(v) => write!(out, "{v}"),
+        Value::Bool(true) => write!(out, "True"),
+        Value::Bool(false) => write!(out, "False"),
+        Value::String(v) => write!(out, "b'{v}'"),
+        Value::WString(v) => write!(out, "'{v}'"),
+        Value::List(v) if v.is_empty() => write!(out, "[]"),
+        Value::List(..) => write!(out, "[...]"),
+        Value::Object { .. } => write!(out, "{{...}}"),
+        other => write!(out, "{other:?}"),
+    };
+}
+
+fn preview_more(len: usize, out: &mut String) {
+    if len > PREVIEW_ITEMS {
+        let _ = write!(out, ", ... (+{} more)", len - PREVIEW_ITEMS);
+    }
+}
+
+fn compare(py: Python<'_>, eq: Option<bool>, op: CompareOp) -> PyObject {
+    match (eq, op) {
+        (Some(eq), CompareOp::Eq) => eq.into_py(py),
+        (Some(eq), CompareOp::Ne) => (!eq).into_py(py),
+        _ => py.NotImplemented(),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T, pretty: bool) -> PyResult<String> {
+    let res = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+
+    res.map_err(|e| KatsubaError::new_err(e.to_string()))
+}
+
+// SAFETY: Values in `changes` must be derived from `a` or `b` as
+// described by the respective variant.
+unsafe fn changes_to_python(
+    py: Python<'_>,
+    a: &Arc<Value>,
+    b: &Arc<Value>,
+    changes: Vec<(String, Change<'_>)>,
+) -> Vec<(String, &'static str, PyObject, PyObject)> {
+    changes
+        .into_iter()
+        .map(|(path, change)| {
+            let kind = change.kind();
+            let (old, new) = match change {
+                Change::Added(v) => (py.None(), unsafe { value_to_python(b.clone(), v, py) }),
+                Change::Removed(v) => (unsafe { value_to_python(a.clone(), v, py) }, py.None()),
+                Change::Changed(va, vb) => unsafe {
+                    (
+                        value_to_python(a.clone(), va, py),
+                        value_to_python(b.clone(), vb, py),
+                    )
+                },
+            };
+
+            (path, kind, old, new)
+        })
+        .collect()
+}
+
 #[derive(Clone)]
 #[pyclass(module = "katsuba.op")]
 pub struct LazyList(Arc<Value>, NonNull<List>);
@@ -23,11 +102,46 @@ impl LazyList {
         // SAFETY: Constructor ensures our list is fine and we never get a mut ref.
         unsafe { self.1.as_ref() }
     }
+
+    fn equals(&self, py: Python<'_>, other: &PyAny) -> PyResult<Option<bool>> {
+        let list = self.get_ref();
+
+        if let Ok(other) = other.extract::<PyRef<'_, LazyList>>() {
+            return Ok(Some(list == other.get_ref()));
+        }
+
+        let items = if let Ok(other) = other.downcast::<PyList>() {
+            other.as_sequence()
+        } else if let Ok(other) = other.downcast::<PyTuple>() {
+            other.as_sequence()
+        } else {
+            return Ok(None);
+        };
+
+        if list.len() != items.len()? {
+            return Ok(Some(false));
+        }
+
+        for (idx, value) in list.iter().enumerate() {
+            let ours = unsafe { value_to_python(self.0.clone(), value, py) };
+            if !ours.as_ref(py).eq(items.get_item(idx)?)? {
+                return Ok(Some(false));
+            }
+        }
+
+        Ok(Some(true))
+    }
 }
 
 // SAFETY: Raw pointers are never exposed for mutation.
 unsafe impl Send for LazyList {}
 
+impl Serialize for LazyList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get_ref().serialize(serializer)
+    }
+}
+
 #[pymethods]
 impl LazyList {
     pub fn __iter__(slf: PyRef<'_, Self>) -> PyResult<Py<LazyListIter>> {
@@ -51,6 +165,51 @@ impl LazyList {
             .map(|v| unsafe { value_to_python(self.0.clone(), v, py) })
             .ok_or_else(|| PyIndexError::new_err("list index out of range"))
     }
+
+    pub fn __repr__(&self) -> String {
+        let list = self.get_ref();
+
+        let mut out = String::from("LazyList([");
+        for (idx, value) in list.iter().take(PREVIEW_ITEMS).enumerate() {
+            if idx != 0 {
+                out.push_str(", ");
+            }
+            preview(value, &mut out);
+        }
+        preview_more(list.len(), &mut out);
+        out.push_str("])");
+
+        out
+    }
+
+    pub fn __richcmp__(&self, py: Python<'_>, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        self.equals(py, other).map(|eq| compare(py, eq, op))
+    }
+
+    /// Computes the differences to `other` as a list of
+    /// `(path, kind, old, new)` tuples.
+    pub fn diff(
+        &self,
+        py: Python<'_>,
+        other: PyRef<'_, LazyList>,
+    ) -> Vec<(String, &'static str, PyObject, PyObject)> {
+        let mut changes = Vec::new();
+        diff_lists(
+            &mut String::new(),
+            self.get_ref(),
+            other.get_ref(),
+            &mut changes,
+        );
+
+        // SAFETY: Changes are derived from the lists we passed in.
+        unsafe { changes_to_python(py, &self.0, &other.0, changes) }
+    }
+
+    /// Serializes the list to a JSON string.
+    #[pyo3(signature = (pretty=false))]
+    pub fn to_json(&self, pretty: bool) -> PyResult<String> {
+        to_json(self, pretty)
+    }
 }
 
 #[pyclass(module = "katsuba.op")]
@@ -88,6 +247,35 @@ impl LazyObject {
         // SAFETY: Constructor ensures our list is fine and we never get a mut ref.
         unsafe { self.2.as_ref() }
     }
+
+    fn equals(&self, py: Python<'_>, other: &PyAny) -> PyResult<Option<bool>> {
+        let obj = self.get_ref();
+
+        if let Ok(other) = other.extract::<PyRef<'_, LazyObject>>() {
+            return Ok(Some(self.1 == other.1 && obj == other.get_ref()));
+        }
+
+        let Ok(dict) = other.downcast::<PyDict>() else {
+            return Ok(None);
+        };
+
+        if obj.len() != dict.len() {
+            return Ok(Some(false));
+        }
+
+        for (key, value) in obj {
+            let Some(theirs) = dict.get_item(key.as_str()) else {
+                return Ok(Some(false));
+            };
+
+            let ours = unsafe { value_to_python(self.0.clone(), value, py) };
+            if !ours.as_ref(py).eq(theirs)? {
+                return Ok(Some(false));
+            }
+        }
+
+        Ok(Some(true))
+    }
 }
 
 #[pymethods]
@@ -118,7 +306,82 @@ impl LazyObject {
         obj.get(key)
             .map(|v| unsafe { value_to_python(self.0.clone(), v, py) })
     }
+
+    pub fn __repr__(&self) -> String {
+        let obj = self.get_ref();
+
+        let mut out = format!("LazyObject(type_hash={:#x}, {{", self.1);
+        for (idx, (key, value)) in obj.iter().take(PREVIEW_ITEMS).enumerate() {
+            if idx != 0 {
+                out.push_str(", ");
+            }
+            let _ = write!(out, "'{key}': ");
+            preview(value, &mut out);
+        }
+        preview_more(obj.len(), &mut out);
+        out.push_str("})");
+
+        out
+    }
+
+    pub fn __richcmp__(&self, py: Python<'_>, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        self.equals(py, other).map(|eq| compare(py, eq, op))
+    }
+
+    /// Computes the differences to `other` as a list of
+    /// `(path, kind, old, new)` tuples.
+    ///
+    /// Paths are relative to this object, e.g. `m_foo.m_bar[2]`.
+    pub fn diff(
+        &self,
+        py: Python<'_>,
+        other: PyRef<'_, LazyObject>,
+    ) -> Vec<(String, &'static str, PyObject, PyObject)> {
+        // Objects of different types are considered entirely different.
+        if self.1 != other.1 {
+            return vec![(
+                String::new(),
+                "changed",
+                self.clone().into_py(py),
+                other.clone().into_py(py),
+            )];
+        }
+
+        let mut changes = Vec::new();
+        diff_objects(
+            &mut String::new(),
+            self.get_ref(),
+            other.get_ref(),
+            &mut changes,
+        );
+
+        // SAFETY: Changes are derived from the objects we passed in.
+        unsafe { changes_to_python(py, &self.0, &other.0, changes) }
+    }
+
+    /// Serializes the object to a JSON string.
+    ///
+    /// The type hash is stored in the `$__type` key, like in the
+    /// output of the `katsuba op de` command.
+    #[pyo3(signature = (pretty=false))]
+    pub fn to_json(&self, pretty: bool) -> PyResult<String> {
+        to_json(self, pretty)
+    }
 }
 
 // SAFETY: Raw pointers are never exposed for mutation.
 unsafe impl Send for LazyObject {}
+
+impl Serialize for LazyObject {
+    // Matches the representation of `Value::Object`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let obj = self.get_ref();
+
+        let mut map = serializer.serialize_map(Some(obj.len() + 1))?;
+        map.serialize_entry("$__type", &self.1)?;
+        for (key, value) in obj {
+            map.serialize_entry(key.as_str(), value)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use katsuba_object_property::serde;
+use katsuba_pipeline::DeserializeJob;
+use katsuba_wad::{
+    extract,
+    progress::{NoProgress, Progress},
+};
+use pyo3::prelude::*;
+
+use crate::{error, op, wad, KatsubaError};
+
+/// Type lists accepted by the helpers: either an already loaded
+/// list, or paths to the files to load.
+#[derive(FromPyObject)]
+enum Types {
+    List(op::TypeList),
+    Path(PathBuf),
+    Paths(Vec<PathBuf>),
+}
+
+impl Types {
+    fn load(self) -> PyResult<Arc<katsuba_types::TypeList>> {
+        let paths = match self {
+            Self::List(list) => return Ok(list.0),
+            Self::Path(path) => vec![path],
+            Self::Paths(paths) => paths,
+        };
+
+        katsuba_pipeline::merge_type_lists(&paths)
+            .map(Arc::new)
+            .map_err(error::pipeline_to_py_err)
+    }
+}
+
+fn create_parent(path: &Path) -> PyResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+/// Extracts all files in the archive at `path` into the directory
+/// `out`, returning the number of files written.
+///
+/// Unpatched files without data are skipped. When given, `progress`
+/// is called with `(entry, bytes_done, bytes_total)` after every
+/// extracted file.
+#[pyfunction]
+#[pyo3(signature = (path, out, progress = None))]
+fn unpack_wad(
+    py: Python<'_>,
+    path: PathBuf,
+    out: PathBuf,
+    progress: Option<PyObject>,
+) -> PyResult<usize> {
+    let archive = katsuba_wad::Archive::open_mmap(path).map_err(error::wad_to_py_err)?;
+
+    // Exceptions raised by the callback cannot interrupt extraction,
+    // so we keep the first one and raise it afterwards.
+    let mut callback_err = None;
+    let written = match progress {
+        Some(callback) => {
+            let mut sink = |p: &Progress<'_>| {
+                if callback_err.is_none() {
+                    let args = (p.entry, p.bytes_done, p.bytes_total);
+                    callback_err = callback.call1(py, args).err();
+                }
+            };
+            extract::extract_all(&archive, &out, &mut sink)
+        }
+        None => extract::extract_all(&archive, &out, &mut NoProgress),
+    }
+    .map_err(error::wad_to_py_err)?;
+
+    match callback_err {
+        Some(e) => Err(e),
+        None => Ok(written),
+    }
+}
+
+/// Deserializes the files matching `pattern` in the archive at `path`
+/// and writes them as JSON files into the directory `out`.
+///
+/// `types` is either a `TypeList` or one or more paths to type list
+/// files. Files which cannot be deserialized do not abort the
+/// conversion; they are returned with their errors instead.
+#[pyfunction]
+#[pyo3(signature = (path, types, out, pattern = "**/*.xml"))]
+fn wad_to_json(
+    path: PathBuf,
+    types: Types,
+    out: PathBuf,
+    pattern: &str,
+) -> PyResult<BTreeMap<String, String>> {
+    let archive = katsuba_wad::Archive::open_mmap(path).map_err(error::wad_to_py_err)?;
+    let mut job = DeserializeJob::new(serde::SerializerOptions::default(), types.load()?)
+        .map_err(error::pipeline_to_py_err)?;
+
+    let mut failed = BTreeMap::new();
+    let files = archive
+        .iter_glob(pattern)
+        .map_err(|e| KatsubaError::new_err(format!("{e:?}")))?;
+    for (name, file) in files {
+        if file.is_unpatched {
+            continue;
+        }
+
+        let contents = wad::extract_file_contents(&archive, file)?;
+        let value = match job.deserialize(&contents) {
+            Ok(value) => value,
+            Err(e) => {
+                failed.insert(name.clone(), e.to_string());
+                continue;
+            }
+        };
+
+        let out = out.join(name).with_extension("json");
+        create_parent(&out)?;
+
+        let mut writer = BufWriter::new(fs::File::create(out)?);
+        serde_json::to_writer(&mut writer, &value)
+            .map_err(|e| error::pipeline_to_py_err(e.into()))?;
+        writer.flush()?;
+    }
+
+    Ok(failed)
+}
+
+pub fn katsuba_quick(m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(unpack_wad, m)?)?;
+    m.add_function(wrap_pyfunction!(wad_to_json, m)?)?;
+
+    Ok(())
+}
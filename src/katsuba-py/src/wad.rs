@@ -5,7 +5,7 @@ use pyo3::{exceptions::PyKeyError, prelude::*, types::PyType};
 
 use crate::{error, op, KatsubaError};
 
-fn extract_file_contents<'a>(
+pub(crate) fn extract_file_contents<'a>(
     archive: &'a katsuba_wad::Archive,
     file: &katsuba_wad::types::File,
 ) -> PyResult<Cow<'a, [u8]>> {
@@ -11,6 +11,11 @@
 //! This crate generally tries to implement every format version a
 //! recent release of wiztype offers to produce.
 //!
+//! # Built-in Types
+//!
+//! With the `builtin` feature, a minimal type list for a few stable
+//! core classes is compiled into the crate. See [`TypeList::builtin`].
+//!
 //! [wiztype]: https://github.com/wizspoil/wiztype
 
 #![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
@@ -69,6 +74,23 @@ impl TypeList {
         serde_json::from_str(data).map_err(Into::into)
     }
 
+    /// Gets a minimal type list of stable core engine classes which
+    /// is compiled into the crate.
+    ///
+    /// This covers just enough to decode common structures like the
+    /// `TemplateManifest.xml` file without a full type dump. Merge it
+    /// with a dump from the client for anything beyond that.
+    #[cfg(feature = "builtin")]
+    pub fn builtin() -> Self {
+        static BUILTIN: std::sync::OnceLock<TypeList> = std::sync::OnceLock::new();
+
+        BUILTIN
+            .get_or_init(|| {
+                Self::from_str(include_str!("builtin.json")).expect("built-in type list is valid")
+            })
+            .clone()
+    }
+
     /// Merges all entries from `other` into `self`.
     pub fn merge(&mut self, mut other: TypeList) {
         self.0.reserve(other.0.len());
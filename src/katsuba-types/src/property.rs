@@ -5,7 +5,7 @@ use katsuba_utils::{
     hash,
     thiserror::{self, Error},
 };
-use serde::{de::Error, Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use smartstring::alias::String;
 
 use super::StringOrInt;
@@ -24,6 +24,10 @@ pub enum EncodingError {
 
 bitflags! {
     /// The configuration bits for [`Property`] values.
+    ///
+    /// Bits which are not known to this crate are retained as-is, so
+    /// flags from newer client versions survive a round trip. They
+    /// can be queried with [`PropertyFlags::unknown_bits`].
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     #[repr(transparent)]
     pub struct PropertyFlags: u32 {
@@ -61,6 +65,52 @@ bitflags! {
     }
 }
 
+impl PropertyFlags {
+    /// Gets the bits set in `self` which do not correspond to any
+    /// known flag.
+    #[inline]
+    pub fn unknown_bits(&self) -> u32 {
+        self.bits() & !Self::all().bits()
+    }
+}
+
+impl Serialize for PropertyFlags {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Serialize the raw bits to keep unknown ones intact.
+        serializer.serialize_u32(self.bits())
+    }
+}
+
+/// The kind of container a property stores its value in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Container {
+    /// A single value stored inline.
+    #[default]
+    Static,
+    /// A contiguous, dynamically sized array of values.
+    Vector,
+    /// A linked list of values.
+    List,
+    /// A container kind not known to this crate.
+    #[serde(other)]
+    Other,
+}
+
+impl Container {
+    /// Gets the name of the container kind as used in type lists.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Static => "Static",
+            Self::Vector => "Vector",
+            Self::List => "List",
+            Self::Other => "Other",
+        }
+    }
+}
+
 /// A property that represents a member of a class.
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
 pub struct Property {
@@ -74,6 +124,12 @@ pub struct Property {
     /// The associated property flag mask.
     #[serde(deserialize_with = "deserialize_property_flags")]
     pub flags: PropertyFlags,
+    /// The kind of container the property's value is stored in.
+    ///
+    /// Type lists which do not provide this information default to
+    /// [`Container::Static`].
+    #[serde(default)]
+    pub container: Container,
     /// Whether the property's storage is dynamically allocated.
     pub dynamic: bool,
     /// A combined hash of the property's name and of its type.
@@ -81,6 +137,10 @@ pub struct Property {
     /// A mapping of all enum options defined on a property.
     #[serde(default)]
     pub enum_options: HashMap<String, StringOrInt>,
+    /// The value the property takes when it is omitted from
+    /// delta-encoded state, if the type list provides one.
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
 }
 
 impl Property {
@@ -163,7 +223,5 @@ fn deserialize_property_flags<'de, D>(deserializer: D) -> Result<PropertyFlags,
 where
     D: Deserializer<'de>,
 {
-    let flags = u32::deserialize(deserializer)?;
-    PropertyFlags::from_bits(flags)
-        .ok_or_else(|| D::Error::custom(format!("unknown property flags: {:?}", flags)))
+    u32::deserialize(deserializer).map(PropertyFlags::from_bits_retain)
 }
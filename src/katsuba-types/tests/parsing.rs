@@ -45,3 +45,47 @@ fn query_properties() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn retain_unknown_property_flags() -> Result<(), Error> {
+    let list = TypeList::from_str(
+        r#"{
+            "version": 2,
+            "classes": {
+                "1": {
+                    "name": "class Future",
+                    "properties": {
+                        "m_value": {
+                            "type": "int",
+                            "id": 0,
+                            "flags": 2147483656,
+                            "dynamic": false,
+                            "hash": 1
+                        }
+                    }
+                }
+            }
+        }"#,
+    )?;
+
+    let property = &list.0[&1].properties[0];
+    assert!(property.flags.contains(PropertyFlags::TRANSMIT));
+    assert_eq!(property.flags.bits(), 0x8000_0008);
+    assert_eq!(property.flags.unknown_bits(), 0x8000_0000);
+
+    Ok(())
+}
+
+#[cfg(feature = "builtin")]
+#[test]
+fn builtin_types() {
+    let list = TypeList::builtin();
+
+    let hash = katsuba_utils::hash::string_id(b"class TemplateManifest");
+    let manifest = list.0.get(&hash).unwrap();
+    assert_eq!(manifest.properties[0].name, "m_serializedTemplates");
+    assert_eq!(
+        manifest.properties[0].type_hash(),
+        katsuba_utils::hash::string_id(b"class TemplateLocation*")
+    );
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     fs,
     io::{self, Read},
     mem,
@@ -13,7 +13,16 @@ use katsuba_utils::{
 };
 use memmap2::{Mmap, MmapOptions};
 
-use crate::{glob, types as wad_types};
+use crate::{crc, glob, types as wad_types};
+
+mod cache;
+pub use cache::*;
+
+mod options;
+pub use options::*;
+
+mod stats;
+pub use stats::*;
 
 /// Errors that may occur when working with KIWAD archives.
 #[derive(Debug, Error)]
@@ -33,6 +42,25 @@ pub enum ArchiveError {
     /// CRC validation of an archive file failed.
     #[error("{0}")]
     Crc(#[from] wad_types::CrcMismatch),
+
+    /// The file journal does not agree with the archive contents.
+    #[error("inconsistent archive journal: {0}")]
+    Inconsistent(String),
+
+    /// Opening the archive did not complete within the configured
+    /// [`RetryPolicy::timeout`].
+    #[error("timed out opening archive after {attempts} attempts: {source}")]
+    TimedOut {
+        /// The number of attempts that were made.
+        attempts: u32,
+        /// The error of the last attempt.
+        source: io::Error,
+    },
+
+    /// An HTTP request for a remote archive failed.
+    #[cfg(feature = "http")]
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] Box<ureq::Error>),
 }
 
 impl From<binrw::Error> for ArchiveError {
@@ -63,7 +91,7 @@ impl Archive {
     ///
     /// See [`Archive::open_heap`] for further details.
     pub fn heap(file: fs::File) -> Result<Self, ArchiveError> {
-        HeapArchive::new(file).map(|a| Self(ArchiveInner::Heap(a)))
+        HeapArchive::new(file, true, None).map(|a| Self(ArchiveInner::Heap(a)))
     }
 
     /// Creates an archive on the heap from a pre-allocated buffer holding
@@ -71,7 +99,7 @@ impl Archive {
     ///
     /// See [`Archive::open_heap`] for further details.
     pub fn from_vec(buf: Vec<u8>) -> Result<Self, ArchiveError> {
-        HeapArchive::from_vec(buf, 0o666).map(|a| Self(ArchiveInner::Heap(a)))
+        HeapArchive::from_vec(buf, 0o666, true, None).map(|a| Self(ArchiveInner::Heap(a)))
     }
 
     /// Opens a file at the given `path` and operates on it from
@@ -92,7 +120,7 @@ impl Archive {
     ///
     /// See [`Archive::open_mmap`] for further details.
     pub fn mmap(file: fs::File) -> Result<Self, ArchiveError> {
-        MemoryMappedArchive::new(file).map(|a| Self(ArchiveInner::MemoryMapped(a)))
+        MemoryMappedArchive::new(file, true, None).map(|a| Self(ArchiveInner::MemoryMapped(a)))
     }
 
     /// Opens a file at the given `path` and operates on it from
@@ -132,6 +160,14 @@ impl Archive {
         }
     }
 
+    #[inline]
+    fn journal_mut(&mut self) -> &mut Journal {
+        match &mut self.0 {
+            ArchiveInner::MemoryMapped(a) => &mut a.journal,
+            ArchiveInner::Heap(a) => &mut a.journal,
+        }
+    }
+
     #[inline]
     pub(crate) fn raw_archive(&self) -> &[u8] {
         match &self.0 {
@@ -169,11 +205,105 @@ impl Archive {
         glob::GlobIter::new(self, pattern)
     }
 
+    /// Builds an iterator over `(path, file)` pairs in the archive where
+    /// the path satisfies a combination of UNIX glob patterns.
+    ///
+    /// Patterns starting with `!` exclude matching paths. See
+    /// [`glob::Matcher::many`] for details.
+    #[inline]
+    pub fn iter_glob_many<S: AsRef<str>>(
+        &self,
+        patterns: &[S],
+    ) -> Result<glob::GlobIter<'_>, glob::GlobError> {
+        glob::GlobIter::many(self, patterns)
+    }
+
+    /// Enables or disables case-insensitive lookup of file names.
+    ///
+    /// When enabled, [`Archive::file_raw`] falls back to comparing
+    /// ASCII case-folded names when there is no exact match. If the
+    /// archive stores multiple names which only differ in casing, the
+    /// one that sorts first is found.
+    ///
+    /// This is disabled by default.
+    pub fn set_case_insensitive(&mut self, enable: bool) {
+        self.journal_mut().set_case_insensitive(enable);
+    }
+
+    /// Whether case-insensitive lookup of file names is enabled.
+    ///
+    /// See [`Archive::set_case_insensitive`] for details.
+    #[inline]
+    pub fn is_case_insensitive(&self) -> bool {
+        self.journal().folded.is_some()
+    }
+
     /// Gets the raw contents of an archived file by its string name.
     pub fn file_raw(&self, name: &str) -> Option<&wad_types::File> {
         self.journal().find(name)
     }
 
+    /// Extracts the raw file contents out of the archive and checks
+    /// them against the stored CRC.
+    ///
+    /// This is meant for archives opened without upfront verification
+    /// through [`OpenOptions::verify_crcs`]. Unpatched files are
+    /// detected here and reported as [`None`].
+    pub fn verified_file_contents(
+        &self,
+        file: &wad_types::File,
+    ) -> Result<Option<&[u8]>, ArchiveError> {
+        if file.is_unpatched {
+            return Ok(None);
+        }
+
+        let data = file
+            .extract(self.raw_archive())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+        let actual = crc::hash(data);
+        if actual == file.crc {
+            Ok(Some(data))
+        } else if wad_types::is_unpatched_file(data) {
+            Ok(None)
+        } else {
+            Err(wad_types::CrcMismatch {
+                expected: file.crc,
+                actual,
+            }
+            .into())
+        }
+    }
+
+    /// Checks the archive for consistency.
+    ///
+    /// This validates that the header agrees with the number of
+    /// journal entries, that no two entries share a name, and that
+    /// the data of every file lies within the archive and matches
+    /// its CRC.
+    pub fn verify(&self) -> Result<(), ArchiveError> {
+        let header = self.header();
+        if header.file_count as usize != self.len() {
+            return Err(ArchiveError::Inconsistent(format!(
+                "header declares {} files, but journal has {} unique entries",
+                header.file_count,
+                self.len()
+            )));
+        }
+
+        let raw = self.raw_archive();
+        for (name, file) in self.files() {
+            if file.extract(raw).is_none() {
+                return Err(ArchiveError::Inconsistent(format!(
+                    "data of '{name}' is out of bounds"
+                )));
+            }
+            self.verified_file_contents(file)?;
+        }
+
+        Ok(())
+    }
+
     /// Extracts the raw file contents out of the archive.
     pub fn file_contents(&self, file: &wad_types::File) -> Option<&[u8]> {
         if file.is_unpatched {
@@ -191,6 +321,10 @@ pub(crate) struct Journal {
     pub header: wad_types::Header,
     // The file permissions on UNIX systems.
     mode: u32,
+    // Case-folded file names mapped to their names in `inner`.
+    //
+    // Only built when case-insensitive lookup is requested.
+    folded: Option<HashMap<String, String>>,
 }
 
 impl Journal {
@@ -203,14 +337,32 @@ impl Journal {
                 flags: None,
             },
             mode,
+            folded: None,
         }
     }
 
     pub fn insert(&mut self, mut file: wad_types::File) {
         let name = mem::take(&mut file.name);
+        if let Some(folded) = &mut self.folded {
+            folded
+                .entry(name.to_ascii_lowercase())
+                .or_insert_with(|| name.clone());
+        }
         self.inner.insert(name, file);
     }
 
+    fn set_case_insensitive(&mut self, enable: bool) {
+        self.folded = enable.then(|| {
+            let mut folded = HashMap::with_capacity(self.inner.len());
+            for name in self.inner.keys() {
+                folded
+                    .entry(name.to_ascii_lowercase())
+                    .or_insert_with(|| name.clone());
+            }
+            folded
+        });
+    }
+
     fn build_from(&mut self, archive: wad_types::Archive) {
         let wad_types::Archive { header, files } = archive;
 
@@ -219,7 +371,10 @@ impl Journal {
     }
 
     fn find(&self, file: &str) -> Option<&wad_types::File> {
-        self.inner.get(file)
+        self.inner.get(file).or_else(|| {
+            let name = self.folded.as_ref()?.get(&file.to_ascii_lowercase())?;
+            self.inner.get(name)
+        })
     }
 }
 
@@ -242,7 +397,11 @@ struct MemoryMappedArchive {
 }
 
 impl MemoryMappedArchive {
-    fn new(file: fs::File) -> Result<Self, ArchiveError> {
+    fn new(
+        file: fs::File,
+        verify: bool,
+        cache: Option<&JournalCache>,
+    ) -> Result<Self, ArchiveError> {
         let mut this = Self {
             // SAFETY: We own the file and keep it around until the mapping
             // is closed; see comments in `MemoryMappedArchive` above.
@@ -256,8 +415,7 @@ impl MemoryMappedArchive {
         };
 
         // Parse the archive and build the file journal.
-        let mut archive = wad_types::Archive::parse(io::Cursor::new(&this.mapping))?;
-        archive.verify_crcs(&this.mapping)?;
+        let archive = load_journal(&this.mapping, verify, cache)?;
         this.journal.build_from(archive);
 
         Ok(this)
@@ -266,7 +424,7 @@ impl MemoryMappedArchive {
     fn open<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
         // Attempt to open the file at the given path.
         let file = fs::File::open(path)?;
-        Self::new(file)
+        Self::new(file, true, None)
     }
 }
 
@@ -279,22 +437,30 @@ struct HeapArchive {
 }
 
 impl HeapArchive {
-    fn new(mut file: fs::File) -> Result<Self, ArchiveError> {
+    fn new(
+        mut file: fs::File,
+        verify: bool,
+        cache: Option<&JournalCache>,
+    ) -> Result<Self, ArchiveError> {
         let mut buf = Vec::new();
         file.read_to_end(&mut buf)?;
 
-        Self::from_vec(buf, file_mode(&file))
+        Self::from_vec(buf, file_mode(&file), verify, cache)
     }
 
-    fn from_vec(buf: Vec<u8>, mode: u32) -> Result<Self, ArchiveError> {
+    fn from_vec(
+        buf: Vec<u8>,
+        mode: u32,
+        verify: bool,
+        cache: Option<&JournalCache>,
+    ) -> Result<Self, ArchiveError> {
         let mut this = Self {
             journal: Journal::new(mode),
             data: buf.into_boxed_slice(),
         };
 
         // Parse the archive and build the file journal.
-        let mut archive = wad_types::Archive::parse(io::Cursor::new(&this.data))?;
-        archive.verify_crcs(&this.data)?;
+        let archive = load_journal(&this.data, verify, cache)?;
         this.journal.build_from(archive);
 
         Ok(this)
@@ -302,10 +468,39 @@ impl HeapArchive {
 
     fn open<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
         let file = fs::File::open(path)?;
-        Self::new(file)
+        Self::new(file, true, None)
     }
 }
 
+// Parses the journal of the raw archive bytes, optionally verifying
+// CRCs. Goes through `cache` first when one is given.
+fn load_journal(
+    raw: &[u8],
+    verify: bool,
+    cache: Option<&JournalCache>,
+) -> Result<wad_types::Archive, ArchiveError> {
+    let mut archive = match cache.and_then(|c| c.load(raw)) {
+        Some(archive) => archive,
+        None => {
+            let mut reader = io::Cursor::new(raw);
+            let archive = wad_types::Archive::parse(&mut reader)?;
+
+            if let Some(cache) = cache {
+                // Failing to cache the journal is not worth failing over.
+                let _ = cache.store(raw, reader.position() as usize, &archive);
+            }
+
+            archive
+        }
+    };
+
+    if verify {
+        archive.verify_crcs(raw)?;
+    }
+
+    Ok(archive)
+}
+
 fn file_mode(_f: &fs::File) -> u32 {
     match () {
         #[cfg(unix)]
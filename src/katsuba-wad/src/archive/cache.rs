@@ -0,0 +1,206 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{crc, types as wad_types};
+
+// Magic bytes and format version of cache entries.
+const MAGIC: &[u8; 4] = b"KJC\x02";
+
+// The number of leading archive bytes hashed into the cache key.
+const KEY_PREFIX: usize = 64 * 1024;
+
+/// An on-disk cache of parsed archive journals.
+///
+/// Entries are keyed by the archive size and a CRC over its leading
+/// bytes, which includes the header. They are validated against a
+/// CRC of the full journal on load, so stale entries are never used
+/// for archives whose journal changed.
+///
+/// Only the parsed journal is cached. CRC verification and the
+/// detection of unpatched files depend on the file data and are
+/// redone every time an archive is opened.
+///
+/// The cache is best-effort: failures to read or write entries fall
+/// back to parsing the archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JournalCache {
+    dir: PathBuf,
+}
+
+impl JournalCache {
+    /// Creates a cache storing its entries in `dir`.
+    ///
+    /// The directory is created when the first entry is written.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Creates a cache in the platform's user cache directory.
+    ///
+    /// This is `$XDG_CACHE_HOME/katsuba` or `~/.cache/katsuba` on
+    /// UNIX systems and `%LOCALAPPDATA%\katsuba` on Windows. Returns
+    /// [`None`] when the location cannot be determined.
+    pub fn default_location() -> Option<Self> {
+        let base = if cfg!(windows) {
+            env::var_os("LOCALAPPDATA").map(PathBuf::from)
+        } else {
+            env::var_os("XDG_CACHE_HOME")
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|h| Path::new(&h).join(".cache")))
+        };
+
+        base.map(|b| Self::new(b.join("katsuba")))
+    }
+
+    /// Gets the directory where entries are stored.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, raw: &[u8]) -> PathBuf {
+        let prefix = &raw[..raw.len().min(KEY_PREFIX)];
+        let name = format!("{:016x}-{:08x}.journal", raw.len(), crc::hash(prefix));
+        self.dir.join(name)
+    }
+
+    /// Looks up the journal of the raw archive bytes `raw`.
+    pub(crate) fn load(&self, raw: &[u8]) -> Option<wad_types::Archive> {
+        let entry = fs::read(self.entry_path(raw)).ok()?;
+        let (archive, journal_len, journal_crc) = decode(&entry)?;
+
+        let journal = raw.get(..journal_len)?;
+        if crc::hash(journal) != journal_crc {
+            return None;
+        }
+
+        Some(archive)
+    }
+
+    /// Stores the journal of `raw`, which ends at `journal_len`.
+    pub(crate) fn store(
+        &self,
+        raw: &[u8],
+        journal_len: usize,
+        archive: &wad_types::Archive,
+    ) -> io::Result<()> {
+        let data = encode(archive, journal_len, crc::hash(&raw[..journal_len]));
+        let path = self.entry_path(raw);
+
+        // Write to a temporary file first so concurrent readers never
+        // observe partial entries.
+        fs::create_dir_all(&self.dir)?;
+        let tmp = path.with_extension(format!("tmp{}", process::id()));
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+    }
+}
+
+fn encode(archive: &wad_types::Archive, journal_len: usize, crc: u32) -> Vec<u8> {
+    let header = &archive.header;
+    let mut out = Vec::with_capacity(32 + archive.files.len() * 32);
+
+    out.extend_from_slice(MAGIC);
+    out.push(header.flags.is_some() as u8);
+    out.extend_from_slice(&(journal_len as u64).to_le_bytes());
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(&header.version.to_le_bytes());
+    out.extend_from_slice(&header.file_count.to_le_bytes());
+    out.push(header.flags.unwrap_or(0));
+    out.extend_from_slice(&(archive.files.len() as u32).to_le_bytes());
+
+    for file in &archive.files {
+        out.extend_from_slice(&file.offset.to_le_bytes());
+        out.extend_from_slice(&file.uncompressed_size.to_le_bytes());
+        out.extend_from_slice(&file.compressed_size.to_le_bytes());
+        out.extend_from_slice(&file.crc.to_le_bytes());
+        out.push(file.compressed as u8);
+        out.extend_from_slice(&(file.name.len() as u32).to_le_bytes());
+        out.extend_from_slice(file.name.as_bytes());
+    }
+
+    out
+}
+
+// A cursor for decoding entries which fails on truncated data.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.bytes(8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+    }
+}
+
+fn decode(entry: &[u8]) -> Option<(wad_types::Archive, usize, u32)> {
+    let mut r = Reader(entry);
+    if r.bytes(MAGIC.len())? != MAGIC {
+        return None;
+    }
+
+    let has_flags = r.u8()? != 0;
+    let journal_len = usize::try_from(r.u64()?).ok()?;
+    let crc = r.u32()?;
+    let version = r.u32()?;
+    let file_count = r.u32()?;
+    let flags = r.u8()?;
+    let len = r.u32()? as usize;
+
+    // Bound the allocation by what the entry could possibly hold.
+    let mut files = Vec::with_capacity(len.min(entry.len() / 21));
+    for _ in 0..len {
+        let offset = r.u32()?;
+        let uncompressed_size = r.u32()?;
+        let compressed_size = r.u32()?;
+        let file_crc = r.u32()?;
+        let compressed = r.u8()? != 0;
+        let name_len = r.u32()? as usize;
+        let name = std::str::from_utf8(r.bytes(name_len)?).ok()?;
+
+        files.push(wad_types::File {
+            offset,
+            uncompressed_size,
+            compressed_size,
+            compressed,
+            crc: file_crc,
+            is_unpatched: false,
+            name: name.to_owned(),
+        });
+    }
+
+    let archive = wad_types::Archive {
+        header: wad_types::Header {
+            version,
+            file_count,
+            flags: has_flags.then_some(flags),
+        },
+        files,
+    };
+
+    Some((archive, journal_len, crc))
+}
@@ -0,0 +1,236 @@
+use std::{
+    fs,
+    io::{self, Read, Seek},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    file_mode, Archive, ArchiveError, ArchiveInner, HeapArchive, JournalCache, MemoryMappedArchive,
+};
+
+/// A policy for retrying failed I/O operations when opening archives.
+///
+/// This is useful when archives are stored on network shares where
+/// transient errors are more common than on local disks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+
+    /// The delay before the first retry.
+    ///
+    /// It is doubled after every failed attempt.
+    pub initial_backoff: Duration,
+
+    /// The upper bound for delays between attempts.
+    pub max_backoff: Duration,
+
+    /// The total time budget for opening an archive, if any.
+    ///
+    /// The deadline is checked between attempts, so a single blocking
+    /// I/O operation may still exceed it.
+    pub timeout: Option<Duration>,
+}
+
+impl RetryPolicy {
+    /// A policy which does not retry failed operations.
+    pub const NEVER: Self = Self {
+        max_attempts: 1,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        timeout: None,
+    };
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            timeout: None,
+        }
+    }
+}
+
+// Whether an I/O error may go away when retrying the operation.
+fn is_transient(e: &io::Error) -> bool {
+    !matches!(
+        e.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::PermissionDenied
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::InvalidData
+            | io::ErrorKind::Unsupported
+            | io::ErrorKind::OutOfMemory
+    )
+}
+
+struct Retrier {
+    policy: RetryPolicy,
+    start: Instant,
+    attempts: u32,
+    backoff: Duration,
+}
+
+impl Retrier {
+    fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy,
+            start: Instant::now(),
+            attempts: 0,
+            backoff: policy.initial_backoff,
+        }
+    }
+
+    fn run<T>(&mut self, mut f: impl FnMut() -> io::Result<T>) -> Result<T, ArchiveError> {
+        loop {
+            self.attempts += 1;
+
+            let e = match f() {
+                Ok(v) => return Ok(v),
+                Err(e) => e,
+            };
+
+            if !is_transient(&e) || self.attempts >= self.policy.max_attempts {
+                return Err(ArchiveError::Io(e));
+            }
+
+            if let Some(timeout) = self.policy.timeout {
+                if self.start.elapsed() + self.backoff > timeout {
+                    return Err(ArchiveError::TimedOut {
+                        attempts: self.attempts,
+                        source: e,
+                    });
+                }
+            }
+
+            thread::sleep(self.backoff);
+            self.backoff = (self.backoff * 2).min(self.policy.max_backoff);
+        }
+    }
+}
+
+/// Options and flags for configuring how an [`Archive`] is opened.
+///
+/// This follows the builder pattern of [`std::fs::OpenOptions`].
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    retry: RetryPolicy,
+    case_insensitive: bool,
+    verify_crcs: bool,
+    cache: Option<JournalCache>,
+}
+
+impl OpenOptions {
+    /// Creates a blank set of options with the default behavior of
+    /// [`Archive::open_heap`] and [`Archive::open_mmap`].
+    pub fn new() -> Self {
+        Self {
+            retry: RetryPolicy::NEVER,
+            case_insensitive: false,
+            verify_crcs: true,
+            cache: None,
+        }
+    }
+
+    /// Sets the policy for retrying I/O operations that fail.
+    ///
+    /// In heap mode, a failed read resumes from where the previous
+    /// attempt stopped. In mmap mode, only opening the file is retried
+    /// since errors while accessing the mapping cannot be recovered.
+    pub fn retry(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Sets whether file names should be looked up case-insensitively.
+    ///
+    /// See [`Archive::set_case_insensitive`] for details.
+    pub fn case_insensitive(&mut self, enable: bool) -> &mut Self {
+        self.case_insensitive = enable;
+        self
+    }
+
+    /// Sets whether the CRCs of all files should be verified when the
+    /// archive is opened.
+    ///
+    /// This is enabled by default. When disabled, opening is faster
+    /// but unpatched files are not detected. Callers should then use
+    /// [`Archive::verified_file_contents`] to check files on access.
+    pub fn verify_crcs(&mut self, enable: bool) -> &mut Self {
+        self.verify_crcs = enable;
+        self
+    }
+
+    /// Sets a cache for reusing parsed journals of unchanged archives.
+    ///
+    /// This is disabled by default. See [`JournalCache`] for details.
+    pub fn journal_cache(&mut self, cache: Option<JournalCache>) -> &mut Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Creates an archive by mapping the open file into memory.
+    ///
+    /// See [`Archive::mmap`] for details. No retries are made.
+    pub fn mmap(&self, file: fs::File) -> Result<Archive, ArchiveError> {
+        MemoryMappedArchive::new(file, self.verify_crcs, self.cache.as_ref())
+            .map(|a| self.finish(Archive(ArchiveInner::MemoryMapped(a))))
+    }
+
+    /// Creates an archive on the heap from a buffer holding the
+    /// archive contents.
+    ///
+    /// See [`Archive::from_vec`] for details.
+    pub fn from_vec(&self, buf: Vec<u8>) -> Result<Archive, ArchiveError> {
+        HeapArchive::from_vec(buf, 0o666, self.verify_crcs, self.cache.as_ref())
+            .map(|a| self.finish(Archive(ArchiveInner::Heap(a))))
+    }
+
+    fn finish(&self, mut archive: Archive) -> Archive {
+        if self.case_insensitive {
+            archive.set_case_insensitive(true);
+        }
+        archive
+    }
+
+    /// Opens the archive at `path` and operates on it from heap memory.
+    ///
+    /// See [`Archive::open_heap`] for details.
+    pub fn open_heap<P: AsRef<Path>>(&self, path: P) -> Result<Archive, ArchiveError> {
+        let path = path.as_ref();
+        let mut retrier = Retrier::new(self.retry);
+
+        let mut buf = Vec::new();
+        let mode = retrier.run(|| {
+            let mut file = fs::File::open(path)?;
+            file.seek(io::SeekFrom::Start(buf.len() as u64))?;
+            file.read_to_end(&mut buf)?;
+
+            Ok(file_mode(&file))
+        })?;
+
+        HeapArchive::from_vec(buf, mode, self.verify_crcs, self.cache.as_ref())
+            .map(|a| self.finish(Archive(ArchiveInner::Heap(a))))
+    }
+
+    /// Opens the archive at `path` and operates on it from a memory
+    /// mapping.
+    ///
+    /// See [`Archive::open_mmap`] for details.
+    pub fn open_mmap<P: AsRef<Path>>(&self, path: P) -> Result<Archive, ArchiveError> {
+        let path = path.as_ref();
+        let file = Retrier::new(self.retry).run(|| fs::File::open(path))?;
+
+        self.mmap(file)
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
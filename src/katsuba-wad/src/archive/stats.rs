@@ -0,0 +1,116 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use super::Archive;
+use crate::types as wad_types;
+
+/// Aggregated sizes of a group of archive files.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SizeStats {
+    /// The number of files in the group.
+    pub count: usize,
+    /// The total size of the data stored in the archive.
+    pub stored_size: u64,
+    /// The total uncompressed size of the files.
+    pub uncompressed_size: u64,
+}
+
+impl SizeStats {
+    fn add(&mut self, file: &wad_types::File) {
+        self.count += 1;
+        self.stored_size += file.size() as u64;
+        self.uncompressed_size += file.uncompressed_size as u64;
+    }
+
+    /// Gets the ratio of stored to uncompressed size of the group.
+    ///
+    /// Empty groups have a ratio of `1.0`.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.uncompressed_size == 0 {
+            return 1.0;
+        }
+
+        self.stored_size as f64 / self.uncompressed_size as f64
+    }
+}
+
+/// A single file in [`ArchiveStats::largest`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EntryStats {
+    /// The path of the file in the archive.
+    pub name: String,
+    /// The size of the data stored in the archive.
+    pub stored_size: u64,
+    /// The uncompressed size of the file.
+    pub uncompressed_size: u64,
+}
+
+/// Statistics about the files in an [`Archive`].
+///
+/// Obtained through [`Archive::stats`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ArchiveStats {
+    /// The sizes of all files in the archive.
+    pub total: SizeStats,
+    /// The number of unpatched files without data.
+    pub unpatched: usize,
+    /// The sizes of files grouped by their lowercase extension.
+    ///
+    /// Files without an extension are grouped under an empty string.
+    pub extensions: BTreeMap<String, SizeStats>,
+    /// The files with the largest stored size in descending order,
+    /// up to [`ArchiveStats::LARGEST_ENTRIES`].
+    pub largest: Vec<EntryStats>,
+}
+
+impl ArchiveStats {
+    /// The maximum number of entries in [`ArchiveStats::largest`].
+    pub const LARGEST_ENTRIES: usize = 10;
+}
+
+// Gets the lowercase extension of the file name at the end of `path`.
+fn extension(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => ext.to_ascii_lowercase(),
+        _ => String::new(),
+    }
+}
+
+impl Archive {
+    /// Computes statistics about the files in the archive.
+    ///
+    /// This only inspects the journal and does not touch any data.
+    pub fn stats(&self) -> ArchiveStats {
+        let mut stats = ArchiveStats::default();
+        let mut by_size = Vec::with_capacity(self.len());
+
+        for (name, file) in self.files() {
+            stats.total.add(file);
+            stats.unpatched += file.is_unpatched as usize;
+            stats
+                .extensions
+                .entry(extension(name))
+                .or_default()
+                .add(file);
+
+            by_size.push((name, file));
+        }
+
+        // Stable sorting keeps files with equal sizes ordered by name.
+        by_size.sort_by_key(|(_, file)| Reverse(file.size()));
+        stats.largest = by_size
+            .into_iter()
+            .take(ArchiveStats::LARGEST_ENTRIES)
+            .map(|(name, file)| EntryStats {
+                name: name.clone(),
+                stored_size: file.size() as u64,
+                uncompressed_size: file.uncompressed_size as u64,
+            })
+            .collect();
+
+        stats
+    }
+}
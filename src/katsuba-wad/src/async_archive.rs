@@ -0,0 +1,106 @@
+//! Asynchronous access to KIWAD archives on the tokio runtime.
+
+use std::{io, path::Path, sync::Arc};
+
+use tokio::task;
+
+use crate::{types as wad_types, Archive, ArchiveError, Inflater, OpenOptions};
+
+// Runs a CPU-bound closure on tokio's blocking thread pool.
+async fn run_blocking<T, F>(f: F) -> Result<T, ArchiveError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ArchiveError> + Send + 'static,
+{
+    task::spawn_blocking(f).await.map_err(io::Error::other)?
+}
+
+/// A KIWAD archive for use from asynchronous code.
+///
+/// The archive file is read with non-blocking I/O. Parsing it and
+/// decompressing files is CPU-bound work, which is moved to tokio's
+/// blocking thread pool so that it does not stall the runtime.
+///
+/// The archive is reference-counted and can be cheaply cloned into
+/// concurrent tasks.
+#[derive(Clone)]
+pub struct AsyncArchive {
+    inner: Arc<Archive>,
+}
+
+impl AsyncArchive {
+    /// Opens the archive at `path` into heap memory.
+    ///
+    /// See [`Archive::open_heap`] for details.
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+        Self::open_with(path, OpenOptions::new()).await
+    }
+
+    /// Opens the archive at `path` into heap memory with the given
+    /// options.
+    ///
+    /// [`OpenOptions::retry`] is not respected here.
+    pub async fn open_with<P: AsRef<Path>>(
+        path: P,
+        options: OpenOptions,
+    ) -> Result<Self, ArchiveError> {
+        let data = tokio::fs::read(path).await?;
+        let archive = run_blocking(move || options.from_vec(data)).await?;
+
+        Ok(Self::from(archive))
+    }
+
+    /// Gets the underlying synchronous [`Archive`].
+    ///
+    /// Its methods for querying metadata are cheap and can be used
+    /// from asynchronous code as well.
+    #[inline]
+    pub fn archive(&self) -> &Archive {
+        &self.inner
+    }
+
+    /// Gets the journal entry of a file by its name.
+    #[inline]
+    pub fn file_raw(&self, name: &str) -> Option<&wad_types::File> {
+        self.inner.file_raw(name)
+    }
+
+    /// Reads the decompressed contents of the file `name`.
+    ///
+    /// Returns [`None`] if the archive has no such file or if it is
+    /// unpatched.
+    pub async fn read_entry(&self, name: &str) -> Result<Option<Vec<u8>>, ArchiveError> {
+        let Some(file) = self.inner.file_raw(name) else {
+            return Ok(None);
+        };
+        if file.is_unpatched {
+            return Ok(None);
+        }
+
+        if !file.compressed {
+            return Ok(self.inner.file_contents(file).map(<[u8]>::to_vec));
+        }
+
+        let archive = self.inner.clone();
+        let file = file.clone();
+        run_blocking(move || {
+            let Some(contents) = archive.file_contents(&file) else {
+                return Ok(None);
+            };
+
+            let mut inflater = Inflater::new();
+            inflater.decompress(contents, file.uncompressed_size as usize)?;
+
+            Ok(Some(inflater.into_inner()))
+        })
+        .await
+    }
+}
+
+impl From<Archive> for AsyncArchive {
+    fn from(archive: Archive) -> Self {
+        Self {
+            inner: Arc::new(archive),
+        }
+    }
+}
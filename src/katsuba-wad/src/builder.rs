@@ -1,7 +1,9 @@
 use std::{
+    collections::HashMap,
     ffi::OsStr,
     fs::File,
-    io::{self, BufWriter, Seek, Write},
+    io::{self, BufWriter, Read, Seek, Write},
+    mem,
     path::Path,
 };
 
@@ -12,10 +14,17 @@ use katsuba_utils::{
 };
 use tempfile::tempfile_in;
 
-use crate::{crc, deflater::Deflater, types as wad_types};
+use crate::{
+    crc,
+    deflater::{CompressionLevel, CompressionStrategy, Deflater, StreamDeflater},
+    progress::{Progress, ProgressSink},
+    types as wad_types,
+};
 
 const ALWAYS_UNCOMPRESSED: &[&str] = &["mp3", "ogg"];
 
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
 /// Errors that may occur when assembling KIWAD archives.
 #[derive(Debug, Error)]
 pub enum BuilderError {
@@ -49,11 +58,35 @@ impl From<binrw::Error> for BuilderError {
     }
 }
 
+// Whether a file at `path` is always stored uncompressed because
+// of its format.
+fn is_always_uncompressed(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ALWAYS_UNCOMPRESSED.contains(&ext))
+}
+
 #[inline(always)]
 fn checked_u32(x: usize) -> Result<u32, BuilderError> {
     u32::try_from(x).or(Err(BuilderError::TooLarge))
 }
 
+// Reads from `reader` until `buf` is full or the input ends, returning
+// the number of bytes read.
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(filled)
+}
+
 struct BuilderState {
     // The raw archive structure we're building. This is what we will
     // serialize in the end, sans the actual file contents.
@@ -66,6 +99,11 @@ struct BuilderState {
     // The offset of the next file's data in the archive. This does
     // not respect the size of the journal yet.
     next_file_offset: u32,
+
+    // An optional sink to report every added file to, along with the
+    // number of uncompressed bytes added so far.
+    progress: Option<Box<dyn ProgressSink + Send>>,
+    bytes_done: u64,
 }
 
 impl BuilderState {
@@ -83,11 +121,28 @@ impl BuilderState {
             journal_size: archive.binary_size(),
             archive,
             next_file_offset: 0,
+            progress: None,
+            bytes_done: 0,
         }
     }
 
-    fn intern_file(&mut self, record: wad_types::File, data: &[u8]) -> Result<(), BuilderError> {
+    fn intern_file(
+        &mut self,
+        record: wad_types::File,
+        data_len: usize,
+    ) -> Result<(), BuilderError> {
         let record_size = record.binary_size();
+        self.bytes_done += record.uncompressed_size as u64;
+
+        if let Some(sink) = &mut self.progress {
+            sink.update(&Progress {
+                entry: &record.name,
+                entries_done: self.archive.files.len() + 1,
+                entries_total: None,
+                bytes_done: self.bytes_done,
+                bytes_total: None,
+            });
+        }
 
         // Add the file record to the archive journal.
         self.archive.files.push(record);
@@ -97,26 +152,213 @@ impl BuilderState {
         self.journal_size += record_size;
         self.next_file_offset = self
             .next_file_offset
-            .checked_add(checked_u32(data.len())?)
+            .checked_add(checked_u32(data_len)?)
             .ok_or(BuilderError::TooLarge)?;
 
         Ok(())
     }
 
-    fn patch_file_offsets(&mut self) -> Result<(), BuilderError> {
+    // Produces the final journal with absolute data offsets, sorted
+    // in ascending path order to maintain compatibility with
+    // KingsIsle's official sorting order.
+    fn finalized_journal(&self) -> Result<wad_types::Archive, BuilderError> {
+        let mut archive = self.archive.clone();
+
         let journal_size = checked_u32(self.journal_size)?;
-        for file in &mut self.archive.files {
+        for file in &mut archive.files {
             file.offset = file
                 .offset
                 .checked_add(journal_size)
                 .ok_or(BuilderError::TooLarge)?;
         }
 
+        archive.files.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(archive)
+    }
+}
+
+// Compares the data at `offset` in the blob cache with `data`.
+fn cache_eq(cache: &mut BufWriter<File>, offset: u32, data: &[u8]) -> io::Result<bool> {
+    cache.seek(io::SeekFrom::Start(offset as u64))?;
+
+    let mut buf = vec![0; data.len().min(READ_CHUNK_SIZE)];
+    let mut eq = true;
+    for expected in data.chunks(READ_CHUNK_SIZE) {
+        let actual = &mut buf[..expected.len()];
+        cache.get_mut().read_exact(actual)?;
+
+        if actual != expected {
+            eq = false;
+            break;
+        }
+    }
+
+    cache.seek(io::SeekFrom::End(0))?;
+    Ok(eq)
+}
+
+// Compares two ranges of `len` bytes in the blob cache.
+fn cache_ranges_eq(cache: &mut BufWriter<File>, a: u32, b: u32, len: u32) -> io::Result<bool> {
+    let mut buf_a = vec![0; (len as usize).min(READ_CHUNK_SIZE)];
+    let mut buf_b = buf_a.clone();
+
+    let mut pos = 0;
+    let mut eq = true;
+    while pos < len {
+        let n = ((len - pos) as usize).min(READ_CHUNK_SIZE);
+
+        cache.seek(io::SeekFrom::Start((a + pos) as u64))?;
+        cache.get_mut().read_exact(&mut buf_a[..n])?;
+        cache.seek(io::SeekFrom::Start((b + pos) as u64))?;
+        cache.get_mut().read_exact(&mut buf_b[..n])?;
+
+        if buf_a[..n] != buf_b[..n] {
+            eq = false;
+            break;
+        }
+        pos += n as u32;
+    }
+
+    cache.seek(io::SeekFrom::End(0))?;
+    Ok(eq)
+}
+
+// A temporary file we use as a blob cache for file data. This allows
+// us to buffer big amounts of data without having to keep them in
+// memory.
+//
+// Data is deduplicated on insertion: when a file's stored data is
+// identical to that of a previous file, both records will share it.
+struct BlobCache {
+    file: BufWriter<File>,
+
+    // Offsets of the data in the cache, keyed by CRC and size.
+    offsets: HashMap<(u32, u32), Vec<u32>>,
+}
+
+impl BlobCache {
+    fn new(file: File) -> Self {
+        Self {
+            file: BufWriter::new(file),
+            offsets: HashMap::new(),
+        }
+    }
+
+    fn find<F>(&mut self, key: (u32, u32), mut eq: F) -> io::Result<Option<u32>>
+    where
+        F: FnMut(&mut BufWriter<File>, u32) -> io::Result<bool>,
+    {
+        // CRCs may collide, so candidates are compared byte by byte.
+        if let Some(offsets) = self.offsets.get(&key) {
+            for &offset in offsets {
+                if eq(&mut self.file, offset)? {
+                    return Ok(Some(offset));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn add(
+        &mut self,
+        state: &mut BuilderState,
+        mut record: wad_types::File,
+        data: &[u8],
+    ) -> Result<(), BuilderError> {
+        let key = (record.crc, checked_u32(data.len())?);
+        if let Some(offset) = self.find(key, |f, offset| cache_eq(f, offset, data))? {
+            record.offset = offset;
+            return state.intern_file(record, 0);
+        }
+
+        record.offset = state.next_file_offset;
+        self.offsets.entry(key).or_default().push(record.offset);
+
+        state.intern_file(record, data.len())?;
+        self.file.write_all(data)?;
+
         Ok(())
     }
 
-    fn sort_journal(&mut self) {
-        self.archive.files.sort_by(|a, b| a.name.cmp(&b.name));
+    // Like `add`, but for data which was already streamed to the end
+    // of the cache. It is truncated again if it's a duplicate.
+    fn add_streamed(
+        &mut self,
+        state: &mut BuilderState,
+        mut record: wad_types::File,
+        len: usize,
+    ) -> Result<(), BuilderError> {
+        let start = state.next_file_offset;
+        let key = (record.crc, checked_u32(len)?);
+        let size = key.1;
+
+        if let Some(offset) = self.find(key, |f, offset| cache_ranges_eq(f, offset, start, size))? {
+            self.file.flush()?;
+            self.file.get_mut().set_len(start as u64)?;
+            self.file.seek(io::SeekFrom::End(0))?;
+
+            record.offset = offset;
+            return state.intern_file(record, 0);
+        }
+
+        record.offset = start;
+        self.offsets.entry(key).or_default().push(start);
+
+        state.intern_file(record, len)
+    }
+}
+
+/// A file with its data prepared for storing in an archive.
+///
+/// Preparation does the expensive compression work up front and
+/// independently of an [`ArchiveBuilder`], so many files can be
+/// prepared on different threads. The results are then added with
+/// [`ArchiveBuilder::add_prepared`] in the desired order.
+pub struct PreparedFile {
+    name: String,
+    uncompressed_size: u32,
+    compressed: bool,
+    crc: u32,
+    data: Vec<u8>,
+}
+
+impl PreparedFile {
+    /// Prepares a file to be stored uncompressed.
+    pub fn uncompressed(name: impl AsRef<Path>, contents: Vec<u8>) -> Result<Self, BuilderError> {
+        Ok(Self {
+            name: name.as_ref().to_string_lossy().to_string(),
+            uncompressed_size: checked_u32(contents.len())?,
+            compressed: false,
+            crc: crc::hash(&contents),
+            data: contents,
+        })
+    }
+
+    /// Prepares a file to be stored compressed by `deflater`.
+    ///
+    /// Like with [`ArchiveBuilder::add_file_compressed`], files smaller
+    /// than `min_compressed_size` and formats which are always stored
+    /// uncompressed are not compressed.
+    pub fn compressed(
+        name: impl AsRef<Path>,
+        contents: Vec<u8>,
+        deflater: &mut Deflater,
+        min_compressed_size: usize,
+    ) -> Result<Self, BuilderError> {
+        let path = name.as_ref();
+        if contents.len() < min_compressed_size || is_always_uncompressed(path) {
+            return Self::uncompressed(path, contents);
+        }
+
+        let compressed = deflater.compress(&contents)?;
+        Ok(Self {
+            name: path.to_string_lossy().to_string(),
+            uncompressed_size: checked_u32(contents.len())?,
+            compressed: true,
+            crc: crc::hash(compressed),
+            data: compressed.to_vec(),
+        })
     }
 }
 
@@ -128,6 +370,9 @@ impl BuilderState {
 ///
 /// Thus, consumers of the API only need to keep one archive file
 /// at a time in memory.
+///
+/// Files with identical stored data are deduplicated, so their data
+/// is only written to the archive once.
 pub struct ArchiveBuilder {
     // The progressive archive state.
     state: BuilderState,
@@ -135,14 +380,24 @@ pub struct ArchiveBuilder {
     // The zlib deflater to handle file compression, one at a time.
     deflater: Deflater,
 
+    // The compression level to use unless overridden per file.
+    level: CompressionLevel,
+
+    // Files smaller than this many bytes are stored uncompressed.
+    min_compressed_size: usize,
+
+    // The deflater for files streamed from readers, created on first use.
+    stream_deflater: Option<StreamDeflater>,
+
+    // A reusable buffer for chunks of data streamed from readers.
+    read_buf: Vec<u8>,
+
     // The output archive file we are writing to.
     outfile: BufWriter<File>,
 
-    // A temporary file we use as a blob cache for compressed data.
-    // This allows us to buffer big amounts of data without having
-    // to keep them in memory. The file will be appended to `outfile`
+    // The blob cache for file data. It will be appended to `outfile`
     // before it is deleted.
-    blob_cache: BufWriter<File>,
+    blob_cache: BlobCache,
 }
 
 impl ArchiveBuilder {
@@ -154,20 +409,91 @@ impl ArchiveBuilder {
     ///
     /// `flags` will be ignored on `version < 2`.
     pub fn new<P: AsRef<Path>>(version: u32, flags: u8, out: P) -> Result<Self, BuilderError> {
+        Self::with_level(version, flags, CompressionLevel::default(), out)
+    }
+
+    /// Creates a new archive builder like [`ArchiveBuilder::new`], but
+    /// with a custom default compression `level` for added files.
+    pub fn with_level<P: AsRef<Path>>(
+        version: u32,
+        flags: u8,
+        level: CompressionLevel,
+        out: P,
+    ) -> Result<Self, BuilderError> {
         let out = out.as_ref();
         let parent = out.parent().ok_or(BuilderError::Path)?;
 
         let outfile = File::create(out).map(BufWriter::new)?;
-        let blob_cache = tempfile_in(parent).map(BufWriter::new)?;
+        let blob_cache = tempfile_in(parent).map(BlobCache::new)?;
 
         Ok(Self {
             state: BuilderState::new(version, flags),
-            deflater: Deflater::new(),
+            deflater: Deflater::with_level(level),
+            level,
+            min_compressed_size: 0,
+            stream_deflater: None,
+            read_buf: Vec::new(),
             outfile,
             blob_cache,
         })
     }
 
+    /// Sets the minimum size in bytes for files to be compressed.
+    ///
+    /// Smaller files passed to [`ArchiveBuilder::add_file_compressed`]
+    /// will be stored uncompressed instead. Defaults to `0`.
+    pub fn set_min_compressed_size(&mut self, size: usize) {
+        self.min_compressed_size = size;
+    }
+
+    /// Gets the minimum size in bytes for files to be compressed.
+    pub fn min_compressed_size(&self) -> usize {
+        self.min_compressed_size
+    }
+
+    /// Sets the compression strategy for all compressed files added
+    /// from now on. Defaults to [`CompressionStrategy::Default`].
+    ///
+    /// See [`CompressionStrategy`] for details.
+    pub fn set_compression_strategy(&mut self, strategy: CompressionStrategy) {
+        self.deflater.set_strategy(strategy);
+    }
+
+    /// Gets the compression strategy for compressed files.
+    pub fn compression_strategy(&self) -> CompressionStrategy {
+        self.deflater.strategy()
+    }
+
+    /// Sets a sink which is notified of every file added to the
+    /// archive.
+    ///
+    /// Since the builder does not know how many files will be added,
+    /// the reported totals are always [`None`].
+    pub fn set_progress<S: ProgressSink + Send + 'static>(&mut self, sink: S) {
+        self.state.progress = Some(Box::new(sink));
+    }
+
+    /// Adds a file that was prepared ahead of time to the archive.
+    ///
+    /// See [`PreparedFile`] for details.
+    pub fn add_prepared(&mut self, file: PreparedFile) -> Result<(), BuilderError> {
+        let record = wad_types::File {
+            offset: 0,
+            uncompressed_size: file.uncompressed_size,
+            compressed_size: if file.compressed {
+                checked_u32(file.data.len())?
+            } else {
+                u32::MAX
+            },
+            compressed: file.compressed,
+            crc: file.crc,
+            is_unpatched: false,
+            name: file.name,
+        };
+
+        self.blob_cache.add(&mut self.state, record, &file.data)
+    }
+
     /// Adds an uncompressed file to the archive.
     ///
     /// `name` is a relative path to the start of the archive where the
@@ -178,7 +504,7 @@ impl ArchiveBuilder {
         contents: &[u8],
     ) -> Result<(), BuilderError> {
         let record = wad_types::File {
-            offset: self.state.next_file_offset,
+            offset: 0,
             uncompressed_size: checked_u32(contents.len())?,
             compressed_size: u32::MAX,
             compressed: false,
@@ -187,10 +513,7 @@ impl ArchiveBuilder {
             name: name.as_ref().to_string_lossy().to_string(),
         };
 
-        self.state.intern_file(record, contents)?;
-        self.blob_cache.write_all(contents)?;
-
-        Ok(())
+        self.blob_cache.add(&mut self.state, record, contents)
     }
 
     /// Adds a compressed file to the archive.
@@ -203,23 +526,33 @@ impl ArchiveBuilder {
         &mut self,
         name: impl AsRef<Path>,
         contents: &[u8],
+    ) -> Result<(), BuilderError> {
+        self.add_file_compressed_with(name, contents, self.level)
+    }
+
+    /// Adds a compressed file to the archive with an explicit compression
+    /// `level` that overrides the builder's default.
+    ///
+    /// See [`ArchiveBuilder::add_file_compressed`] for details.
+    pub fn add_file_compressed_with(
+        &mut self,
+        name: impl AsRef<Path>,
+        contents: &[u8],
+        level: CompressionLevel,
     ) -> Result<(), BuilderError> {
         let path = name.as_ref();
 
         // Check if the given file path ends with a file that is conditionally
-        // uncompressed. In that case, we just delegate to `add_file`.
-        if path
-            .extension()
-            .and_then(OsStr::to_str)
-            .map(|ext| ALWAYS_UNCOMPRESSED.contains(&ext))
-            .unwrap_or(false)
-        {
+        // uncompressed or if it's too small to be worth compressing. In that
+        // case, we just delegate to `add_file`.
+        if contents.len() < self.min_compressed_size || is_always_uncompressed(path) {
             return self.add_file(name, contents);
         }
 
+        self.deflater.set_level(level);
         let compressed = self.deflater.compress(contents)?;
         let record = wad_types::File {
-            offset: self.state.next_file_offset,
+            offset: 0,
             uncompressed_size: checked_u32(contents.len())?,
             compressed_size: checked_u32(compressed.len())?,
             compressed: true,
@@ -228,10 +561,176 @@ impl ArchiveBuilder {
             name: path.to_string_lossy().to_string(),
         };
 
-        self.state.intern_file(record, compressed)?;
-        self.blob_cache.write_all(compressed)?;
+        self.blob_cache.add(&mut self.state, record, compressed)
+    }
 
-        Ok(())
+    /// Adds a file to the archive by reading its contents from `reader`.
+    ///
+    /// `name` is a relative path to the start of the archive where the
+    /// file will be located.
+    ///
+    /// Files are streamed into the archive in fixed-size chunks, and
+    /// `compressed` files are deflated chunk by chunk on the way. The
+    /// same rules as for [`ArchiveBuilder::add_file_compressed`] apply
+    /// to them, though their compressed data may differ from it.
+    ///
+    /// When reading fails midway, the builder is left in an unspecified
+    /// state and should be discarded.
+    pub fn add_file_from_reader<R: Read>(
+        &mut self,
+        name: impl AsRef<Path>,
+        reader: R,
+        compressed: bool,
+    ) -> Result<(), BuilderError> {
+        let path = name.as_ref();
+
+        let mut buf = mem::take(&mut self.read_buf);
+        let res = match compressed && !is_always_uncompressed(path) {
+            true => self.stream_compressed(path, reader, &mut buf),
+            false => self.stream_uncompressed(path, reader, &mut buf),
+        };
+        self.read_buf = buf;
+
+        res
+    }
+
+    fn stream_uncompressed<R: Read>(
+        &mut self,
+        path: &Path,
+        mut reader: R,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), BuilderError> {
+        buf.resize(READ_CHUNK_SIZE, 0);
+
+        let mut hasher = crc::CrcHasher::new();
+        let mut size = 0;
+
+        loop {
+            let read = read_chunk(&mut reader, buf)?;
+            if read == 0 {
+                break;
+            }
+
+            hasher.update(&buf[..read]);
+            self.blob_cache.file.write_all(&buf[..read])?;
+            size += read;
+        }
+
+        let record = wad_types::File {
+            offset: 0,
+            uncompressed_size: checked_u32(size)?,
+            compressed_size: u32::MAX,
+            compressed: false,
+            crc: hasher.finalize(),
+            is_unpatched: false,
+            name: path.to_string_lossy().to_string(),
+        };
+
+        self.blob_cache.add_streamed(&mut self.state, record, size)
+    }
+
+    fn stream_compressed<R: Read>(
+        &mut self,
+        path: &Path,
+        mut reader: R,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), BuilderError> {
+        // The first chunk decides whether the file is large enough to
+        // be compressed, so it must be able to hold that many bytes.
+        let chunk_size = READ_CHUNK_SIZE.max(self.min_compressed_size);
+        buf.resize(chunk_size + READ_CHUNK_SIZE, 0);
+        let (chunk, out) = buf.split_at_mut(chunk_size);
+
+        let mut read = read_chunk(&mut reader, chunk)?;
+        if read < self.min_compressed_size {
+            return self.add_file(path, &chunk[..read]);
+        }
+
+        let strategy = self.deflater.strategy();
+        let deflater = self.stream_deflater.get_or_insert_with(StreamDeflater::new);
+        deflater.reset(self.level, strategy);
+
+        let mut hasher = crc::CrcHasher::new();
+        let mut size = 0;
+        let mut compressed_size = 0;
+
+        let file = &mut self.blob_cache.file;
+        let mut sink = |data: &[u8]| {
+            hasher.update(data);
+            compressed_size += data.len();
+            file.write_all(data)
+        };
+
+        loop {
+            // A chunk that is not full marks the end of the input.
+            let finish = read < chunk.len();
+            deflater.compress(&chunk[..read], finish, out, &mut sink)?;
+            size += read;
+
+            if finish {
+                break;
+            }
+            read = read_chunk(&mut reader, chunk)?;
+        }
+
+        let record = wad_types::File {
+            offset: 0,
+            uncompressed_size: checked_u32(size)?,
+            compressed_size: checked_u32(compressed_size)?,
+            compressed: true,
+            crc: hasher.finalize(),
+            is_unpatched: false,
+            name: path.to_string_lossy().to_string(),
+        };
+
+        self.blob_cache
+            .add_streamed(&mut self.state, record, compressed_size)
+    }
+
+    /// Adds a file with its data exactly as stored in another archive.
+    ///
+    /// `file` describes the stored `data`, which is copied byte for
+    /// byte without a decompression and compression cycle. This makes
+    /// repacking and merging archives considerably faster.
+    ///
+    /// `name` takes the place of [`wad_types::File::name`], which is
+    /// empty for files obtained from an [`Archive`][crate::Archive].
+    /// The sizes and the CRC in `file` are not validated against
+    /// `data`.
+    pub fn add_raw_entry(
+        &mut self,
+        name: impl AsRef<Path>,
+        file: &wad_types::File,
+        data: &[u8],
+    ) -> Result<(), BuilderError> {
+        let record = wad_types::File {
+            offset: 0,
+            uncompressed_size: file.uncompressed_size,
+            compressed_size: file.compressed_size,
+            compressed: file.compressed,
+            crc: file.crc,
+            is_unpatched: false,
+            name: name.as_ref().to_string_lossy().to_string(),
+        };
+
+        self.blob_cache.add(&mut self.state, record, data)
+    }
+
+    /// Computes the journal of the archive as [`ArchiveBuilder::finish`]
+    /// would write it for the files added so far, without writing
+    /// anything to the output.
+    ///
+    /// The returned journal has its final data offsets and order, and
+    /// fails with [`BuilderError::TooLarge`] when the archive would
+    /// exceed the limits of the format.
+    pub fn plan(&self) -> Result<wad_types::Archive, BuilderError> {
+        self.state.finalized_journal()
+    }
+
+    /// Gets the size in bytes of the final archive file for the files
+    /// added so far.
+    pub fn planned_size(&self) -> u64 {
+        self.state.journal_size as u64 + self.state.next_file_offset as u64
     }
 
     /// Finalizes the archive building and writes all data to the
@@ -239,17 +738,13 @@ impl ArchiveBuilder {
     ///
     /// The temporary blob cache will be deleted by the OS after this.
     pub fn finish(mut self) -> Result<(), BuilderError> {
-        self.state.patch_file_offsets()?;
-
-        // Sort files in ascending path order to maintain compatibility
-        // with KingsIsle's official sorting order.
-        self.state.sort_journal();
+        let journal = self.state.finalized_journal()?;
 
         // Serialize the KIWAD header and file journal, then merge
         // the blob cache to the end of the output file.
-        self.state.archive.write(&mut self.outfile)?;
+        journal.write(&mut self.outfile)?;
         {
-            let mut blob_cache = match self.blob_cache.into_inner() {
+            let mut blob_cache = match self.blob_cache.file.into_inner() {
                 Ok(f) => f,
                 Err(e) => return Err(BuilderError::Io(e.into_error())),
             };
@@ -258,6 +753,10 @@ impl ArchiveBuilder {
             io::copy(&mut blob_cache, &mut self.outfile)?;
         }
 
+        if let Some(sink) = &mut self.state.progress {
+            sink.finish();
+        }
+
         Ok(())
     }
 }
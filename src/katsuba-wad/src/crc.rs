@@ -1,11 +1,48 @@
 //! CRC32 calculation for integrity-checking uncompressed
 //! archive files.
+//!
+//! The implementation selects a hardware-accelerated routine at
+//! runtime where available (PCLMULQDQ on x86, the CRC32 extension
+//! on AArch64) and falls back to slice-by-16 tables otherwise.
 
 use crc32fast::Hasher;
 
 /// Computes the CRC32 of `data`, as encoded in KIWAD archives.
 pub fn hash(data: &[u8]) -> u32 {
-    let mut hasher = Hasher::new_with_initial(u32::MAX);
+    let mut hasher = CrcHasher::new();
     hasher.update(data);
-    hasher.finalize() ^ u32::MAX
+    hasher.finalize()
+}
+
+/// An incremental hasher for KIWAD CRCs.
+///
+/// Feeding data in multiple [`CrcHasher::update`] calls produces
+/// the same result as a single call to [`hash`] over all of it.
+#[derive(Clone)]
+pub struct CrcHasher(Hasher);
+
+impl CrcHasher {
+    /// Creates a new hasher with no data processed yet.
+    #[inline]
+    pub fn new() -> Self {
+        Self(Hasher::new_with_initial(u32::MAX))
+    }
+
+    /// Processes more `data` into the CRC.
+    #[inline]
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Consumes the hasher and produces the final CRC value.
+    #[inline]
+    pub fn finalize(self) -> u32 {
+        self.0.finalize() ^ u32::MAX
+    }
+}
+
+impl Default for CrcHasher {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -1,4 +1,92 @@
+use std::io;
+
 use katsuba_utils::libdeflater::{CompressionError, CompressionLvl, Compressor};
+use miniz_oxide::{
+    deflate::{
+        core::{create_comp_flags_from_zip_params, CompressorOxide},
+        stream::deflate,
+    },
+    DataFormat, MZFlush, MZStatus,
+};
+
+/// The zlib compression level to use for archive files.
+///
+/// Levels range from `0` (no compression) to `12` (best ratio);
+/// higher levels trade compression speed for smaller output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CompressionLevel(u8);
+
+impl CompressionLevel {
+    /// The fastest level which still compresses data.
+    pub const FASTEST: Self = Self(1);
+
+    /// The level with the best compression ratio.
+    pub const BEST: Self = Self(12);
+
+    /// Creates a compression level from its numeric value.
+    ///
+    /// Returns [`None`] for levels outside of `0..=12`.
+    pub const fn new(level: u8) -> Option<Self> {
+        if level <= Self::BEST.0 {
+            Some(Self(level))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the numeric value of this level.
+    #[inline]
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+
+    fn to_lvl(self) -> CompressionLvl {
+        // Range is validated on construction.
+        CompressionLvl::new(self.0 as i32).unwrap()
+    }
+}
+
+impl Default for CompressionLevel {
+    fn default() -> Self {
+        Self::BEST
+    }
+}
+
+/// The strategy the compressor uses to find repetitions in the data.
+///
+/// Strategies other than [`CompressionStrategy::Default`] are tuned
+/// for data of a particular structure. They are backed by a slower
+/// deflate implementation whose levels only go up to `10`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompressionStrategy {
+    /// Searches for repetitions of any kind.
+    #[default]
+    Default,
+    /// Only uses repetitions of at least 5 bytes, for noisy data.
+    Filtered,
+    /// Does not search for repetitions and only encodes single bytes.
+    HuffmanOnly,
+    /// Only searches for runs of the same byte.
+    Rle,
+    /// Uses the fixed codes of the deflate format instead of codes
+    /// tailored to the data.
+    Fixed,
+}
+
+impl CompressionStrategy {
+    fn to_miniz(self) -> i32 {
+        use miniz_oxide::deflate::core::CompressionStrategy as Strategy;
+
+        let strategy = match self {
+            Self::Default => Strategy::Default,
+            Self::Filtered => Strategy::Filtered,
+            Self::HuffmanOnly => Strategy::HuffmanOnly,
+            Self::Rle => Strategy::RLE,
+            Self::Fixed => Strategy::Fixed,
+        };
+        strategy as i32
+    }
+}
 
 /// A zlib inflater for compressing archive files.
 ///
@@ -10,21 +98,65 @@ use katsuba_utils::libdeflater::{CompressionError, CompressionLvl, Compressor};
 /// can be borrowed from the deflater at a time.
 pub struct Deflater {
     compressor: Compressor,
+    level: CompressionLevel,
+    strategy: CompressionStrategy,
+    // The deflater for strategies libdeflate does not support.
+    stream: Option<StreamDeflater>,
     scratch: Vec<u8>,
 }
 
 impl Deflater {
     /// Creates an empty deflater at default compression level.
     pub fn new() -> Self {
+        Self::with_level(CompressionLevel::default())
+    }
+
+    /// Creates an empty deflater at the given compression level.
+    pub fn with_level(level: CompressionLevel) -> Self {
         Self {
-            compressor: Compressor::new(CompressionLvl::best()),
+            compressor: Compressor::new(level.to_lvl()),
+            level,
+            strategy: CompressionStrategy::Default,
+            stream: None,
             scratch: Vec::new(),
         }
     }
 
+    /// Gets the compression level currently in use.
+    #[inline]
+    pub fn level(&self) -> CompressionLevel {
+        self.level
+    }
+
+    /// Changes the compression level for subsequent operations.
+    pub fn set_level(&mut self, level: CompressionLevel) {
+        if self.level != level {
+            self.compressor = Compressor::new(level.to_lvl());
+            self.level = level;
+        }
+    }
+
+    /// Gets the compression strategy currently in use.
+    #[inline]
+    pub fn strategy(&self) -> CompressionStrategy {
+        self.strategy
+    }
+
+    /// Changes the compression strategy for subsequent operations.
+    ///
+    /// This only applies to zlib streams; raw DEFLATE streams from
+    /// [`Deflater::compress_raw`] always use the default strategy.
+    pub fn set_strategy(&mut self, strategy: CompressionStrategy) {
+        self.strategy = strategy;
+    }
+
     /// Compresses a raw buffer into the inner scratch buffer and
     /// returns the subset of the slice occupied by it.
     pub fn compress(&mut self, data: &[u8]) -> Result<&[u8], CompressionError> {
+        if self.strategy != CompressionStrategy::Default {
+            return self.compress_with_strategy(data);
+        }
+
         let max_size = self.compressor.zlib_compress_bound(data.len());
         self.scratch.resize(max_size, 0);
 
@@ -36,12 +168,30 @@ impl Deflater {
         Ok(unsafe { self.scratch.get_unchecked(..real_size) })
     }
 
+    /// Compresses a raw buffer into a raw DEFLATE stream without the
+    /// zlib header and trailer, as used by formats like ZIP.
+    pub fn compress_raw(&mut self, data: &[u8]) -> Result<&[u8], CompressionError> {
+        let max_size = self.compressor.deflate_compress_bound(data.len());
+        self.scratch.resize(max_size, 0);
+
+        let real_size = self.compressor.deflate_compress(data, &mut self.scratch)?;
+        debug_assert!(real_size <= max_size);
+
+        // SAFETY: Same as in `compress`.
+        Ok(unsafe { self.scratch.get_unchecked(..real_size) })
+    }
+
     pub fn compress_into<'a>(
         &mut self,
         out: &'a mut Vec<u8>,
         data: &[u8],
     ) -> Result<&'a [u8], CompressionError> {
         let data_start = out.len();
+        if self.strategy != CompressionStrategy::Default {
+            out.extend_from_slice(self.compress_with_strategy(data)?);
+            return Ok(&out[data_start..]);
+        }
+
         let max_size = self.compressor.zlib_compress_bound(data.len());
 
         // Reserve more memory at the end of the vector and compress into the
@@ -62,6 +212,24 @@ impl Deflater {
             Ok(out.get_unchecked(data_start..))
         }
     }
+
+    fn compress_with_strategy(&mut self, data: &[u8]) -> Result<&[u8], CompressionError> {
+        let stream = self.stream.get_or_insert_with(StreamDeflater::new);
+        stream.reset(self.level, self.strategy);
+
+        let mut out = [0; 16 * 1024];
+        self.scratch.clear();
+        stream
+            .compress(data, true, &mut out, |chunk| {
+                self.scratch.extend_from_slice(chunk);
+                Ok(())
+            })
+            // The output is drained after every step, so the stream
+            // never runs out of space.
+            .map_err(|_| CompressionError::InsufficientSpace)?;
+
+        Ok(&self.scratch)
+    }
 }
 
 impl Default for Deflater {
@@ -69,3 +237,75 @@ impl Default for Deflater {
         Self::new()
     }
 }
+
+// A zlib deflater for data which arrives in chunks.
+//
+// libdeflate only compresses whole buffers at once, so this is backed
+// by miniz_oxide instead. Its levels only go up to 10, higher ones are
+// clamped to that.
+pub(crate) struct StreamDeflater {
+    compressor: Box<CompressorOxide>,
+}
+
+impl StreamDeflater {
+    pub fn new() -> Self {
+        Self {
+            compressor: Box::default(),
+        }
+    }
+
+    // Prepares the deflater for a new stream at the given level and
+    // with the given strategy.
+    pub fn reset(&mut self, level: CompressionLevel, strategy: CompressionStrategy) {
+        let level = level.get().min(10);
+        if strategy == CompressionStrategy::Default {
+            self.compressor.reset();
+            self.compressor
+                .set_format_and_level(DataFormat::Zlib, level);
+        } else {
+            // Strategies can only be chosen on construction.
+            let flags = create_comp_flags_from_zip_params(
+                level.into(),
+                DataFormat::Zlib.to_window_bits(),
+                strategy.to_miniz(),
+            );
+            *self.compressor = CompressorOxide::new(flags);
+        }
+    }
+
+    // Compresses `input` using `out` as the output buffer and passes
+    // every produced piece of data to `sink`.
+    //
+    // With `finish`, the stream is terminated after `input`.
+    pub fn compress<F>(
+        &mut self,
+        mut input: &[u8],
+        finish: bool,
+        out: &mut [u8],
+        mut sink: F,
+    ) -> io::Result<()>
+    where
+        F: FnMut(&[u8]) -> io::Result<()>,
+    {
+        let flush = match finish {
+            true => MZFlush::Finish,
+            false => MZFlush::None,
+        };
+
+        while !input.is_empty() || finish {
+            let res = deflate(&mut self.compressor, input, out, flush);
+            let status = res
+                .status
+                .map_err(|e| io::Error::other(format!("deflate failed: {e:?}")))?;
+
+            input = &input[res.bytes_consumed..];
+            sink(&out[..res.bytes_written])?;
+
+            if status == MZStatus::StreamEnd {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
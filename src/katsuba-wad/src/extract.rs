@@ -0,0 +1,200 @@
+//! Extraction of archive files into pluggable storage.
+//!
+//! Files are handed to an [`ExtractSink`] one at a time, which
+//! decides where their contents end up. [`DirSink`] writes them
+//! into the filesystem and [`MemorySink`] collects them in memory.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    progress::{ProgressSink, Tracker},
+    types as wad_types, Archive, ArchiveError, Inflater,
+};
+
+/// Metadata of an archive file passed to an [`ExtractSink`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntryMetadata {
+    /// The size of the file contents in bytes.
+    pub size: u64,
+    /// The CRC of the data stored in the archive.
+    pub crc: u32,
+    /// Whether the file was stored compressed.
+    pub compressed: bool,
+    /// The UNIX permissions of the archive the file came from.
+    pub mode: u32,
+}
+
+/// A destination for extracted archive files.
+pub trait ExtractSink {
+    /// Stores a file at `path` in the archive.
+    ///
+    /// `reader` yields the decompressed file contents.
+    fn write_entry(
+        &mut self,
+        path: &str,
+        meta: &EntryMetadata,
+        reader: &mut dyn Read,
+    ) -> io::Result<()>;
+
+    /// Called once after all files were written.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Whether `name` stays inside the output directory when joined to it.
+fn is_contained(name: &str) -> bool {
+    Path::new(name)
+        .components()
+        .all(|c| matches!(c, Component::Normal(..) | Component::CurDir))
+}
+
+/// An [`ExtractSink`] writing files into a directory.
+///
+/// Files with paths that would escape the directory are rejected.
+#[derive(Clone, Debug)]
+pub struct DirSink {
+    root: PathBuf,
+}
+
+impl DirSink {
+    /// Creates a sink writing into the directory `root`.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ExtractSink for DirSink {
+    fn write_entry(
+        &mut self,
+        path: &str,
+        _meta: &EntryMetadata,
+        reader: &mut dyn Read,
+    ) -> io::Result<()> {
+        if !is_contained(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("archive file '{path}' escapes the output directory"),
+            ));
+        }
+
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(path)?;
+        io::copy(reader, &mut file)?;
+
+        Ok(())
+    }
+}
+
+/// An [`ExtractSink`] collecting files in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemorySink {
+    /// The extracted files, keyed by their paths in the archive.
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+impl ExtractSink for MemorySink {
+    fn write_entry(
+        &mut self,
+        path: &str,
+        meta: &EntryMetadata,
+        reader: &mut dyn Read,
+    ) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(meta.size as usize);
+        reader.read_to_end(&mut buf)?;
+        self.files.insert(path.to_owned(), buf);
+
+        Ok(())
+    }
+}
+
+/// Extracts all files in `archive` into `sink`, returning the number
+/// of files written.
+///
+/// Unpatched files have no data and will be skipped.
+///
+/// `progress` is notified after every extracted file.
+pub fn extract_with(
+    archive: &Archive,
+    sink: &mut dyn ExtractSink,
+    progress: &mut dyn ProgressSink,
+) -> Result<usize, ArchiveError> {
+    extract_filtered(archive, sink, progress, |_, _| true)
+}
+
+/// Extracts the files in `archive` for which `filter` returns `true`
+/// into `sink`, returning the number of files written.
+///
+/// `filter` is called with the path and the journal entry of every
+/// file, so files can be skipped by name, size or compression state.
+/// Unpatched files are always skipped.
+///
+/// `progress` is notified after every extracted file and only counts
+/// the files accepted by `filter`.
+pub fn extract_filtered<F>(
+    archive: &Archive,
+    sink: &mut dyn ExtractSink,
+    progress: &mut dyn ProgressSink,
+    filter: F,
+) -> Result<usize, ArchiveError>
+where
+    F: Fn(&str, &wad_types::File) -> bool,
+{
+    let files = archive
+        .files()
+        .iter()
+        .filter(|(name, f)| !f.is_unpatched && filter(name, f));
+    let (count, size) = files.clone().fold((0, 0), |(n, s), (_, f)| {
+        (n + 1, s + f.uncompressed_size as u64)
+    });
+
+    let mut tracker = Tracker::new(progress, Some(count), Some(size));
+    let mut inflater = Inflater::new();
+
+    for (name, file) in files {
+        let contents = archive
+            .file_contents(file)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        let mut contents = if file.compressed {
+            inflater.decompress(contents, file.uncompressed_size as usize)?
+        } else {
+            contents
+        };
+
+        let meta = EntryMetadata {
+            size: file.uncompressed_size as u64,
+            crc: file.crc,
+            compressed: file.compressed,
+            mode: archive.mode(),
+        };
+        sink.write_entry(name, &meta, &mut contents)?;
+
+        tracker.advance(name, meta.size);
+    }
+
+    sink.finish()?;
+    tracker.finish();
+
+    Ok(count)
+}
+
+/// Extracts all files in `archive` into the directory `out`, returning
+/// the number of files written.
+///
+/// This is a shorthand for [`extract_with`] and a [`DirSink`].
+pub fn extract_all(
+    archive: &Archive,
+    out: &Path,
+    progress: &mut dyn ProgressSink,
+) -> Result<usize, ArchiveError> {
+    extract_with(archive, &mut DirSink::new(out), progress)
+}
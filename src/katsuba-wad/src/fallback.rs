@@ -0,0 +1,117 @@
+//! Resolution of unpatched placeholder files from other sources.
+//!
+//! Archives may ship placeholder entries whose data is all zeroes,
+//! which the game's patcher fills in later. A [`FallbackSource`]
+//! provides the real contents of such entries, for example from
+//! another copy of the archive.
+
+use crate::{types as wad_types, Archive, ArchiveError, Inflater};
+
+/// A source for the contents of unpatched archive files.
+pub trait FallbackSource {
+    /// Fetches the decompressed contents of the file `name`.
+    ///
+    /// Like the game's patcher, only data matching the CRC and sizes
+    /// recorded in the `expected` journal entry is accepted. Returns
+    /// [`None`] when the source has no such data.
+    fn fetch_fallback(
+        &self,
+        name: &str,
+        expected: &wad_types::File,
+        inflater: &mut Inflater,
+    ) -> Result<Option<Vec<u8>>, ArchiveError>;
+}
+
+// Whether `file` describes the same data as the `expected` entry.
+fn same_data(file: &wad_types::File, expected: &wad_types::File) -> bool {
+    !file.is_unpatched
+        && file.crc == expected.crc
+        && file.compressed == expected.compressed
+        && file.uncompressed_size == expected.uncompressed_size
+        && file.size() == expected.size()
+}
+
+fn inflate(
+    file: &wad_types::File,
+    data: &[u8],
+    inflater: &mut Inflater,
+) -> Result<Vec<u8>, ArchiveError> {
+    if file.compressed {
+        let mut out = vec![0; file.uncompressed_size as usize];
+        inflater.decompress_into(&mut out, data)?;
+        Ok(out)
+    } else {
+        Ok(data.to_vec())
+    }
+}
+
+impl FallbackSource for Archive {
+    fn fetch_fallback(
+        &self,
+        name: &str,
+        expected: &wad_types::File,
+        inflater: &mut Inflater,
+    ) -> Result<Option<Vec<u8>>, ArchiveError> {
+        let Some(file) = self.file_raw(name).filter(|f| same_data(f, expected)) else {
+            return Ok(None);
+        };
+
+        match self.verified_file_contents(file)? {
+            Some(data) => inflate(file, data, inflater).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl FallbackSource for crate::RemoteArchive {
+    fn fetch_fallback(
+        &self,
+        name: &str,
+        expected: &wad_types::File,
+        inflater: &mut Inflater,
+    ) -> Result<Option<Vec<u8>>, ArchiveError> {
+        match self.file_raw(name).filter(|f| same_data(f, expected)) {
+            Some(file) => self.fetch(file, inflater),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Sources are tried in order until one provides the data.
+impl<T: FallbackSource> FallbackSource for [T] {
+    fn fetch_fallback(
+        &self,
+        name: &str,
+        expected: &wad_types::File,
+        inflater: &mut Inflater,
+    ) -> Result<Option<Vec<u8>>, ArchiveError> {
+        for source in self {
+            if let Some(data) = source.fetch_fallback(name, expected, inflater)? {
+                return Ok(Some(data));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Archive {
+    /// Gets the decompressed contents of `file`, resolving unpatched
+    /// files through `fallback`.
+    ///
+    /// Returns [`None`] when the file is unpatched and `fallback` has
+    /// no matching data either.
+    pub fn file_contents_or_fallback(
+        &self,
+        name: &str,
+        file: &wad_types::File,
+        fallback: &(impl FallbackSource + ?Sized),
+        inflater: &mut Inflater,
+    ) -> Result<Option<Vec<u8>>, ArchiveError> {
+        match self.verified_file_contents(file)? {
+            Some(data) => inflate(file, data, inflater).map(Some),
+            None => fallback.fetch_fallback(name, file, inflater),
+        }
+    }
+}
@@ -1,35 +1,68 @@
 //! Utilities for iterating over a subset of archive files chosen
-//! by a UNIX glob pattern.
+//! by UNIX glob patterns.
+//!
+//! Patterns starting with `!` are negated and exclude paths which
+//! would otherwise be matched.
 
 pub use globset::Error as GlobError;
 
 use std::collections::btree_map::Iter;
 
-use globset::{Glob, GlobMatcher};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 
 use crate::{types::File, Archive};
 
 /// A glob matcher for checking archive file strings.
 pub struct Matcher {
-    inner: GlobMatcher,
+    include: GlobSet,
+    exclude: GlobSet,
 }
 
 impl Matcher {
     /// Creates a new glob matcher over the given pattern.
     pub fn new(pattern: &str) -> Result<Self, GlobError> {
-        let inner = Glob::new(pattern)?.compile_matcher();
-        Ok(Self { inner })
+        Self::many(&[pattern])
     }
 
-    /// Checks if a given path is a match to the glob pattern.
+    /// Creates a new glob matcher over a combination of patterns.
+    ///
+    /// A path matches when it matches any of the patterns, but none
+    /// of the negated ones. When only negated patterns are given, all
+    /// other paths match.
+    pub fn many<S: AsRef<str>>(patterns: &[S]) -> Result<Self, GlobError> {
+        let mut include = GlobSetBuilder::new();
+        let mut exclude = GlobSetBuilder::new();
+
+        let mut any_include = false;
+        for pattern in patterns {
+            match pattern.as_ref().strip_prefix('!') {
+                Some(pattern) => exclude.add(Glob::new(pattern)?),
+                None => {
+                    any_include = true;
+                    include.add(Glob::new(pattern.as_ref())?)
+                }
+            };
+        }
+
+        if !any_include {
+            include.add(Glob::new("**")?);
+        }
+
+        Ok(Self {
+            include: include.build()?,
+            exclude: exclude.build()?,
+        })
+    }
+
+    /// Checks if a given path is a match to the glob patterns.
     #[inline]
     pub fn is_match(&self, path: &str) -> bool {
-        self.inner.is_match(path)
+        self.include.is_match(path) && !self.exclude.is_match(path)
     }
 }
 
 /// An iterator that only yields [`Archive`] elements which match
-/// a specified UNIX glob pattern.
+/// specified UNIX glob patterns.
 pub struct GlobIter<'a> {
     archive: Iter<'a, String, File>,
     matcher: Matcher,
@@ -41,10 +74,23 @@ impl<'a> GlobIter<'a> {
     ///
     /// Errors on failure to compile the provided glob pattern.
     pub fn new(archive: &'a Archive, pattern: &str) -> Result<Self, GlobError> {
-        Matcher::new(pattern).map(move |matcher| Self {
+        Matcher::new(pattern).map(move |matcher| Self::with_matcher(archive, matcher))
+    }
+
+    /// Creates a new glob iterator that yields [`Archive`] files
+    /// matching a combination of patterns.
+    ///
+    /// See [`Matcher::many`] for how the patterns are combined.
+    pub fn many<S: AsRef<str>>(archive: &'a Archive, patterns: &[S]) -> Result<Self, GlobError> {
+        Matcher::many(patterns).map(move |matcher| Self::with_matcher(archive, matcher))
+    }
+
+    /// Creates a new glob iterator from an existing [`Matcher`].
+    pub fn with_matcher(archive: &'a Archive, matcher: Matcher) -> Self {
+        Self {
             archive: archive.files().iter(),
             matcher,
-        })
+        }
     }
 }
 
@@ -1,3 +1,8 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Mutex,
+};
+
 use katsuba_utils::libdeflater::{DecompressionError, Decompressor};
 
 /// A zlib inflater for decompressing archive files.
@@ -70,6 +75,25 @@ impl Inflater {
 
         Ok(&self.scratch)
     }
+
+    /// Decompresses a raw DEFLATE stream without zlib header and
+    /// trailer into the internal scratch buffer.
+    ///
+    /// Otherwise, this works like [`Inflater::decompress`].
+    pub fn decompress_raw(
+        &mut self,
+        data: &[u8],
+        size_hint: usize,
+    ) -> Result<&[u8], DecompressionError> {
+        self.scratch.resize(size_hint, 0);
+
+        let written = self.raw.deflate_decompress(data, &mut self.scratch)?;
+        if written != size_hint {
+            return Err(DecompressionError::BadData);
+        }
+
+        Ok(&self.scratch)
+    }
 }
 
 impl Default for Inflater {
@@ -77,3 +101,85 @@ impl Default for Inflater {
         Self::new()
     }
 }
+
+/// A thread-safe pool of [`Inflater`]s for concurrent decompression.
+///
+/// Inflaters are checked out with [`InflaterPool::get`] and return
+/// to the pool when the guard is dropped, so their decompressor
+/// state and scratch buffers are re-used across tasks.
+#[derive(Default)]
+pub struct InflaterPool {
+    idle: Mutex<Vec<Inflater>>,
+    max_idle: Option<usize>,
+}
+
+impl InflaterPool {
+    /// Creates a new, empty pool without a limit on idle inflaters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new pool which keeps at most `max_idle` inflaters
+    /// around after they are returned.
+    ///
+    /// Inflaters returned to a full pool are dropped.
+    pub fn with_max_idle(max_idle: usize) -> Self {
+        Self {
+            idle: Mutex::new(Vec::with_capacity(max_idle)),
+            max_idle: Some(max_idle),
+        }
+    }
+
+    /// Checks out an inflater from the pool, creating a new one
+    /// when none are idle.
+    pub fn get(&self) -> PooledInflater<'_> {
+        let inflater = self.idle.lock().unwrap().pop().unwrap_or_default();
+        PooledInflater {
+            pool: self,
+            inflater: Some(inflater),
+        }
+    }
+
+    /// Gets the number of idle inflaters currently in the pool.
+    pub fn idle(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    fn put(&self, inflater: Inflater) {
+        let mut idle = self.idle.lock().unwrap();
+        if self.max_idle.is_none_or(|max| idle.len() < max) {
+            idle.push(inflater);
+        }
+    }
+}
+
+/// An [`Inflater`] checked out from an [`InflaterPool`].
+///
+/// Returns the inflater to its pool when dropped.
+pub struct PooledInflater<'a> {
+    pool: &'a InflaterPool,
+    inflater: Option<Inflater>,
+}
+
+impl Deref for PooledInflater<'_> {
+    type Target = Inflater;
+
+    fn deref(&self) -> &Self::Target {
+        // The inflater is only taken out on drop.
+        self.inflater.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledInflater<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inflater.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledInflater<'_> {
+    fn drop(&mut self) {
+        if let Some(inflater) = self.inflater.take() {
+            self.pool.put(inflater);
+        }
+    }
+}
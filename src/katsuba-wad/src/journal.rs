@@ -0,0 +1,171 @@
+//! Export and import of archive journals for low-level editing.
+//!
+//! A [`JournalDump`] is an editable copy of the file journal of an
+//! archive. After changing it, e.g. to rename files, it can be
+//! written back with [`rewrite`], which keeps the file data as-is.
+
+use std::{
+    collections::HashSet,
+    io::{Cursor, Write},
+};
+
+use crate::{types as wad_types, Archive, ArchiveError};
+
+/// A journal entry describing a single file in a [`JournalDump`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct JournalEntry {
+    /// The path of the file in the archive.
+    pub name: String,
+    /// The offset of the file data in the source archive.
+    pub offset: u32,
+    /// The uncompressed size of the file contents.
+    pub uncompressed_size: u32,
+    /// The compressed size of the file contents.
+    pub compressed_size: u32,
+    /// Whether the file is stored compressed.
+    pub compressed: bool,
+    /// The CRC32 checksum of the stored file data.
+    pub crc: u32,
+}
+
+impl JournalEntry {
+    fn size(&self) -> u32 {
+        if self.compressed {
+            self.compressed_size
+        } else {
+            self.uncompressed_size
+        }
+    }
+}
+
+/// An editable copy of the header and file journal of an archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct JournalDump {
+    /// The KIWAD format version.
+    pub version: u32,
+    /// The archive flags, only stored for version 2 and later.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub flags: Option<u8>,
+    /// The journal entries in path order.
+    pub files: Vec<JournalEntry>,
+}
+
+impl JournalDump {
+    /// Captures the journal of `archive`.
+    ///
+    /// Offsets refer to the data in `archive` and are translated when
+    /// the journal is written back with [`rewrite`].
+    pub fn from_archive(archive: &Archive) -> Self {
+        let header = archive.header();
+        let files = archive
+            .files()
+            .iter()
+            .map(|(name, file)| JournalEntry {
+                name: name.clone(),
+                offset: file.offset,
+                uncompressed_size: file.uncompressed_size,
+                compressed_size: file.compressed_size,
+                compressed: file.compressed,
+                crc: file.crc,
+            })
+            .collect();
+
+        Self {
+            version: header.version,
+            flags: header.flags,
+            files,
+        }
+    }
+}
+
+fn inconsistent(msg: String) -> ArchiveError {
+    ArchiveError::Inconsistent(msg)
+}
+
+fn encode(journal: &wad_types::Archive) -> Result<Vec<u8>, ArchiveError> {
+    let mut buf = Cursor::new(Vec::new());
+    journal.write(&mut buf)?;
+    Ok(buf.into_inner())
+}
+
+/// Writes `archive` to `out` with its journal replaced by `dump`.
+///
+/// The file data of `archive` is copied verbatim. Entries in `dump`
+/// must refer to data within it and their offsets are moved along
+/// with the data when the size of the journal changes. Entries are
+/// written in path order and names must be unique.
+///
+/// CRCs and sizes are not checked against the data, so this can
+/// produce archives which fail verification when they are edited.
+pub fn rewrite<W: Write>(
+    archive: &Archive,
+    dump: &JournalDump,
+    mut out: W,
+) -> Result<(), ArchiveError> {
+    let raw = archive.raw_archive();
+
+    // Find where the file data starts by re-parsing the journal.
+    let mut cursor = Cursor::new(raw);
+    wad_types::Archive::parse(&mut cursor)?;
+    let data_start = cursor.position() as usize;
+    let data = &raw[data_start..];
+
+    let mut names = HashSet::with_capacity(dump.files.len());
+    for entry in &dump.files {
+        if entry.name.is_empty() || !names.insert(entry.name.as_str()) {
+            return Err(inconsistent(format!(
+                "empty or duplicate name '{}'",
+                entry.name
+            )));
+        }
+
+        let start = entry.offset as usize;
+        let end = start + entry.size() as usize;
+        if start < data_start || end > raw.len() {
+            return Err(inconsistent(format!(
+                "data of '{}' at {start}..{end} is outside of the file data",
+                entry.name
+            )));
+        }
+    }
+
+    let file_count =
+        u32::try_from(dump.files.len()).map_err(|_| inconsistent("too many files".to_owned()))?;
+    let mut journal = wad_types::Archive {
+        header: wad_types::Header {
+            version: dump.version,
+            file_count,
+            flags: (dump.version >= 2).then(|| dump.flags.unwrap_or(0)),
+        },
+        files: dump
+            .files
+            .iter()
+            .map(|e| wad_types::File {
+                offset: e.offset,
+                uncompressed_size: e.uncompressed_size,
+                compressed_size: e.compressed_size,
+                compressed: e.compressed,
+                crc: e.crc,
+                is_unpatched: false,
+                name: e.name.clone(),
+            })
+            .collect(),
+    };
+    journal.files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // Offsets are fixed-size, so the journal size is known after
+    // encoding it once.
+    let journal_size = encode(&journal)?.len();
+    for file in &mut journal.files {
+        let offset = file.offset as usize - data_start + journal_size;
+        file.offset = u32::try_from(offset)
+            .map_err(|_| inconsistent(format!("offset of '{}' overflows", file.name)))?;
+    }
+
+    out.write_all(&encode(&journal)?)?;
+    out.write_all(data)?;
+
+    Ok(())
+}
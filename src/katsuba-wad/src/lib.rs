@@ -13,6 +13,11 @@
 mod archive;
 pub use archive::*;
 
+#[cfg(feature = "async")]
+mod async_archive;
+#[cfg(feature = "async")]
+pub use async_archive::*;
+
 #[cfg(feature = "builder")]
 mod builder;
 #[cfg(feature = "builder")]
@@ -23,9 +28,36 @@ pub mod crc;
 #[cfg(feature = "builder")]
 pub mod deflater;
 
+pub mod extract;
+
+pub mod fallback;
+
 pub mod glob;
 
+pub mod journal;
+
 mod inflater;
 pub use inflater::*;
 
+#[cfg(feature = "builder")]
+pub mod merge;
+
+mod overlay;
+pub use overlay::*;
+
+#[cfg(feature = "builder")]
+pub mod patch;
+
+pub mod progress;
+
+#[cfg(feature = "http")]
+mod remote;
+#[cfg(feature = "http")]
+pub use remote::*;
+
 pub mod types;
+
+pub mod vfs;
+
+#[cfg(feature = "builder")]
+pub mod zip;
@@ -0,0 +1,89 @@
+//! Flattening of layered KIWAD archives into a single output.
+//!
+//! Game clients load `Root.wad` and then layer patch archives on top
+//! of it. The utilities in this module resolve the final view of such
+//! a stack and write it out through an [`ArchiveBuilder`].
+
+use std::collections::BTreeMap;
+
+use katsuba_utils::thiserror::{self, Error};
+
+use crate::{types as wad_types, Archive, ArchiveBuilder, BuilderError};
+
+/// Errors that may occur when merging archives.
+#[derive(Debug, Error)]
+pub enum MergeError {
+    /// Failed to write a file to the output archive.
+    #[error("{0}")]
+    Builder(#[from] BuilderError),
+
+    /// Two archives define the same file and the merge was
+    /// configured to reject that.
+    #[error("file '{0}' is present in more than one input archive")]
+    Conflict(String),
+}
+
+/// The policy to apply when several input archives contain a file
+/// with the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Picks the file from the archive that was given last.
+    ///
+    /// This mirrors how the game client applies patch archives.
+    #[default]
+    TakeNewest,
+
+    /// Keeps the file from the archive that was given first.
+    TakeFirst,
+
+    /// Fails the merge on the first conflicting file.
+    Error,
+}
+
+impl ArchiveBuilder {
+    /// Merges the files of all given `archives` into this builder.
+    ///
+    /// Archives are expected in load order, i.e. later archives are
+    /// considered newer than earlier ones. Name clashes between them
+    /// are resolved according to `policy`.
+    ///
+    /// Unpatched placeholder entries never take precedence over real
+    /// data and are dropped from the output when no archive provides
+    /// contents for them.
+    ///
+    /// File data is copied verbatim without recompressing it.
+    pub fn merge<'a, I>(&mut self, archives: I, policy: ConflictPolicy) -> Result<(), MergeError>
+    where
+        I: IntoIterator<Item = &'a Archive>,
+    {
+        let mut resolved: BTreeMap<&str, (&Archive, &wad_types::File)> = BTreeMap::new();
+
+        for archive in archives {
+            for (name, file) in archive.files() {
+                if file.is_unpatched {
+                    continue;
+                }
+
+                if resolved.contains_key(name.as_str()) {
+                    match policy {
+                        ConflictPolicy::TakeNewest => {}
+                        ConflictPolicy::TakeFirst => continue,
+                        ConflictPolicy::Error => return Err(MergeError::Conflict(name.clone())),
+                    }
+                }
+
+                resolved.insert(name, (archive, file));
+            }
+        }
+
+        for (name, (archive, file)) in resolved {
+            // Unpatched files were filtered above, so this only fails
+            // for malformed archives which do not pass opening.
+            if let Some(data) = archive.file_contents(file) {
+                self.add_raw_entry(name, file, data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
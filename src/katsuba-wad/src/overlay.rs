@@ -0,0 +1,158 @@
+use std::collections::{btree_map, BTreeMap};
+
+use crate::{
+    glob::{GlobError, Matcher},
+    types as wad_types, Archive,
+};
+
+/// A union view over a stack of [`Archive`]s.
+///
+/// Lookups are resolved through the layers in priority order, which
+/// emulates how the game client applies patch archives on top of
+/// `Root.wad`. Layers pushed later take precedence over earlier ones.
+///
+/// Unpatched placeholder files never shadow real data in lower layers.
+#[derive(Default)]
+pub struct OverlayArchive {
+    layers: Vec<Archive>,
+
+    // The resolved view of the stack: a mapping of file names to
+    // the index of the layer that provides them.
+    index: BTreeMap<String, usize>,
+}
+
+impl OverlayArchive {
+    /// Creates an overlay from the given layers in ascending priority.
+    pub fn new(layers: Vec<Archive>) -> Self {
+        let mut this = Self::default();
+        layers.into_iter().for_each(|a| this.push(a));
+        this
+    }
+
+    /// Pushes a new layer on top of the stack.
+    pub fn push(&mut self, archive: Archive) {
+        let layer = self.layers.len();
+
+        for (name, file) in archive.files() {
+            if file.is_unpatched && self.index.contains_key(name) {
+                continue;
+            }
+
+            self.index.insert(name.clone(), layer);
+        }
+
+        self.layers.push(archive);
+    }
+
+    /// Gets the layers of the overlay in ascending priority.
+    #[inline]
+    pub fn layers(&self) -> &[Archive] {
+        &self.layers
+    }
+
+    /// Gets the number of distinct files in the overlay.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether the overlay is empty, i.e. does not contain any files.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub(crate) fn index(&self) -> &BTreeMap<String, usize> {
+        &self.index
+    }
+
+    /// Builds an iterator over the resolved `(path, file)` pairs in
+    /// the overlay, in ascending path order.
+    #[inline]
+    pub fn files(&self) -> OverlayIter<'_> {
+        OverlayIter {
+            layers: &self.layers,
+            index: self.index.iter(),
+        }
+    }
+
+    /// Builds an iterator over resolved `(path, file)` pairs in the
+    /// overlay where the path satisfies the given UNIX glob pattern.
+    pub fn iter_glob(&self, pattern: &str) -> Result<OverlayGlobIter<'_>, GlobError> {
+        Matcher::new(pattern).map(|matcher| OverlayGlobIter {
+            inner: self.files(),
+            matcher,
+        })
+    }
+
+    /// Builds an iterator over resolved `(path, file)` pairs in the
+    /// overlay where the path satisfies a combination of UNIX glob
+    /// patterns, as for [`Archive::iter_glob_many`].
+    pub fn iter_glob_many<S: AsRef<str>>(
+        &self,
+        patterns: &[S],
+    ) -> Result<OverlayGlobIter<'_>, GlobError> {
+        Matcher::many(patterns).map(|matcher| OverlayGlobIter {
+            inner: self.files(),
+            matcher,
+        })
+    }
+
+    /// Gets the archive layer which provides the file of the given name.
+    pub fn layer_of(&self, name: &str) -> Option<&Archive> {
+        self.index.get(name).map(|&layer| &self.layers[layer])
+    }
+
+    /// Gets the raw metadata of a resolved file by its string name.
+    pub fn file_raw(&self, name: &str) -> Option<&wad_types::File> {
+        self.layer_of(name).and_then(|a| a.file_raw(name))
+    }
+
+    /// Extracts the raw contents of a resolved file by its string name.
+    ///
+    /// Like [`Archive::file_contents`], this returns [`None`] for
+    /// unpatched files.
+    pub fn file_contents(&self, name: &str) -> Option<&[u8]> {
+        let archive = self.layer_of(name)?;
+        archive.file_contents(archive.file_raw(name)?)
+    }
+}
+
+/// An iterator over the resolved files of an [`OverlayArchive`].
+pub struct OverlayIter<'a> {
+    layers: &'a [Archive],
+    index: btree_map::Iter<'a, String, usize>,
+}
+
+impl<'a> Iterator for OverlayIter<'a> {
+    type Item = (&'a String, &'a wad_types::File);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.index.next().map(|(name, &layer)| {
+            // The index only refers to files which exist in their layer.
+            let file = self.layers[layer].file_raw(name).unwrap();
+            (name, file)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.index.size_hint()
+    }
+}
+
+/// An iterator that only yields [`OverlayArchive`] files which match
+/// a specified UNIX glob pattern.
+pub struct OverlayGlobIter<'a> {
+    inner: OverlayIter<'a>,
+    matcher: Matcher,
+}
+
+impl<'a> Iterator for OverlayGlobIter<'a> {
+    type Item = (&'a String, &'a wad_types::File);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let matcher = &self.matcher;
+        self.inner.find(|(path, _)| matcher.is_match(path))
+    }
+}
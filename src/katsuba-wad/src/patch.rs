@@ -0,0 +1,532 @@
+//! Compact binary patches between two versions of an archive.
+//!
+//! A patch lists every file of the new archive. Files are either
+//! kept from the old archive, stored in full or described as a
+//! delta against their old contents. The patch body is compressed
+//! as a whole.
+//!
+//! Delta-encoded files are recompressed when a patch is applied,
+//! so the result is equivalent to the new archive in contents but
+//! not necessarily byte for byte.
+
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+    path::Path,
+};
+
+use katsuba_utils::{
+    binrw::{self, binrw, BinReaderExt, BinWriterExt},
+    binrw_ext::{read_prefixed_string, write_prefixed_string},
+    libdeflater::{CompressionError, DecompressionError},
+    thiserror::{self, Error},
+};
+
+use crate::{
+    crc,
+    deflater::Deflater,
+    types::{self as wad_types, is_unpatched_file, CrcMismatch},
+    Archive, ArchiveBuilder, BuilderError, Inflater,
+};
+
+const FORMAT_VERSION: u32 = 2;
+
+// The largest ratio by which DEFLATE can shrink data.
+const MAX_DEFLATE_RATIO: usize = 1032;
+
+// The granularity at which matches between file versions are found.
+const BLOCK_SIZE: usize = 32;
+
+/// Errors that may occur when creating or applying patches.
+#[derive(Debug, Error)]
+pub enum PatchError {
+    /// An I/O error occurred while reading or writing data.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    /// Failed to build the patched archive.
+    #[error("{0}")]
+    Builder(#[from] BuilderError),
+
+    /// Compression of the patch failed.
+    #[error("failed to compress patch: {0}")]
+    Compress(#[from] CompressionError),
+
+    /// Decompression of the patch or an archive file failed.
+    #[error("failed to decompress data: {0}")]
+    Decompress(#[from] DecompressionError),
+
+    /// Failed to parse or serialize the patch.
+    #[error("malformed patch: {0}")]
+    Format(binrw::Error),
+
+    /// The patch data is too large to be represented.
+    #[error("patch too large to represent")]
+    TooLarge,
+
+    /// A file the patch depends on is missing from the old archive.
+    #[error("file '{0}' is missing from the old archive")]
+    MissingFile(String),
+
+    /// A file in the old archive differs from the one the patch was
+    /// created against.
+    #[error("file '{0}' does not match the patch base: {1}")]
+    BaseMismatch(String, CrcMismatch),
+
+    /// A delta instruction refers to data outside of the old file.
+    #[error("delta for '{0}' is out of bounds of the old file")]
+    OutOfBounds(String),
+
+    /// A patched file does not have the expected contents.
+    #[error("patched file '{0}' is corrupted: {1}")]
+    Corrupted(String, CrcMismatch),
+}
+
+impl From<binrw::Error> for PatchError {
+    fn from(value: binrw::Error) -> Self {
+        match value {
+            binrw::Error::Io(e) => Self::Io(e),
+            e => Self::Format(e),
+        }
+    }
+}
+
+#[binrw]
+#[brw(little, magic = b"KDIFF")]
+struct PatchHeader {
+    format_version: u32,
+    archive_version: u32,
+    archive_flags: u8,
+    body_size: u64,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug, PartialEq)]
+enum Instruction {
+    /// Copies a range of bytes from the old file.
+    #[brw(magic = 0_u8)]
+    Copy { offset: u32, len: u32 },
+
+    /// Inserts new bytes into the file.
+    #[brw(magic = 1_u8)]
+    Insert {
+        len: u32,
+        #[br(count = len)]
+        data: Vec<u8>,
+    },
+}
+
+impl Instruction {
+    fn binary_size(&self) -> usize {
+        match self {
+            Self::Copy { .. } => 9,
+            Self::Insert { data, .. } => 5 + data.len(),
+        }
+    }
+}
+
+#[binrw]
+#[brw(little)]
+enum EntryData {
+    /// The file is kept as-is from the old archive.
+    #[brw(magic = 0_u8)]
+    Unchanged { base_crc: u32 },
+
+    /// The file is stored in full, exactly as in the new archive.
+    #[brw(magic = 1_u8)]
+    Full {
+        crc: u32,
+        len: u32,
+        #[br(count = len)]
+        data: Vec<u8>,
+    },
+
+    /// The file is reconstructed from its old contents.
+    #[brw(magic = 2_u8)]
+    Delta {
+        base_crc: u32,
+        // The CRC over the uncompressed contents of the new file.
+        content_crc: u32,
+        count: u32,
+        #[br(count = count)]
+        instructions: Vec<Instruction>,
+    },
+}
+
+#[binrw]
+#[brw(little)]
+struct Entry {
+    #[br(temp)]
+    #[bw(calc(name.len() as u32))]
+    name_len: u32,
+
+    #[br(args(name_len as usize, false), parse_with = read_prefixed_string)]
+    #[bw(args(false), write_with = write_prefixed_string)]
+    name: String,
+
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |&x| x as u8)]
+    compressed: bool,
+    uncompressed_size: u32,
+    compressed_size: u32,
+
+    // Whether the file was unpatched in the new archive. Its data
+    // is then all zeroes and does not match its CRC.
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |&x| x as u8)]
+    unpatched: bool,
+
+    data: EntryData,
+}
+
+#[binrw]
+#[brw(little)]
+struct PatchBody {
+    #[br(temp)]
+    #[bw(calc = entries.len() as u32)]
+    entry_count: u32,
+
+    #[br(count = entry_count)]
+    entries: Vec<Entry>,
+}
+
+// A weak rolling checksum over a window of bytes, as used by rsync.
+#[derive(Clone, Copy)]
+struct Rolling {
+    a: u32,
+    b: u32,
+}
+
+impl Rolling {
+    fn new(block: &[u8]) -> Self {
+        let mut this = Self { a: 0, b: 0 };
+        for (i, &x) in block.iter().enumerate() {
+            this.a = this.a.wrapping_add(x as u32);
+            this.b = this.b.wrapping_add((block.len() - i) as u32 * x as u32);
+        }
+        this
+    }
+
+    fn roll(&mut self, out: u8, new: u8) {
+        self.a = self.a.wrapping_sub(out as u32).wrapping_add(new as u32);
+        self.b = self
+            .b
+            .wrapping_sub(BLOCK_SIZE as u32 * out as u32)
+            .wrapping_add(self.a);
+    }
+
+    fn digest(self) -> u32 {
+        (self.a & 0xFFFF) | (self.b << 16)
+    }
+}
+
+fn push_insert(ops: &mut Vec<Instruction>, data: &[u8]) {
+    if !data.is_empty() {
+        ops.push(Instruction::Insert {
+            len: data.len() as u32,
+            data: data.to_vec(),
+        });
+    }
+}
+
+fn push_copy(ops: &mut Vec<Instruction>, offset: usize, len: usize) {
+    // Merge with a preceding copy of the directly adjacent range.
+    if let Some(Instruction::Copy {
+        offset: prev_offset,
+        len: prev_len,
+    }) = ops.last_mut()
+    {
+        if (*prev_offset + *prev_len) as usize == offset {
+            *prev_len += len as u32;
+            return;
+        }
+    }
+
+    ops.push(Instruction::Copy {
+        offset: offset as u32,
+        len: len as u32,
+    });
+}
+
+// Encodes `new` as a sequence of copies from `old` and insertions.
+//
+// Both files are bounded by `u32::MAX` bytes as archive files.
+fn diff(old: &[u8], new: &[u8]) -> Vec<Instruction> {
+    let mut ops = Vec::new();
+    if old.len() < BLOCK_SIZE || new.len() < BLOCK_SIZE {
+        push_insert(&mut ops, new);
+        return ops;
+    }
+
+    let mut index = HashMap::new();
+    for (i, block) in old.chunks_exact(BLOCK_SIZE).enumerate() {
+        index
+            .entry(Rolling::new(block).digest())
+            .or_insert(i * BLOCK_SIZE);
+    }
+
+    let mut pending = 0;
+    let mut pos = 0;
+    let mut rolling = Rolling::new(&new[..BLOCK_SIZE]);
+    while pos + BLOCK_SIZE <= new.len() {
+        if let Some(&offset) = index.get(&rolling.digest()) {
+            if old[offset..offset + BLOCK_SIZE] == new[pos..pos + BLOCK_SIZE] {
+                let len = BLOCK_SIZE
+                    + old[offset + BLOCK_SIZE..]
+                        .iter()
+                        .zip(&new[pos + BLOCK_SIZE..])
+                        .take_while(|(a, b)| a == b)
+                        .count();
+
+                push_insert(&mut ops, &new[pending..pos]);
+                push_copy(&mut ops, offset, len);
+
+                pos += len;
+                pending = pos;
+                if pos + BLOCK_SIZE <= new.len() {
+                    rolling = Rolling::new(&new[pos..pos + BLOCK_SIZE]);
+                }
+
+                continue;
+            }
+        }
+
+        if pos + BLOCK_SIZE < new.len() {
+            rolling.roll(new[pos], new[pos + BLOCK_SIZE]);
+        }
+        pos += 1;
+    }
+
+    push_insert(&mut ops, &new[pending..]);
+    ops
+}
+
+fn contents<'a>(
+    inflater: &'a mut Inflater,
+    file: &wad_types::File,
+    data: &'a [u8],
+) -> Result<&'a [u8], PatchError> {
+    if file.compressed {
+        Ok(inflater.decompress(data, file.uncompressed_size as usize)?)
+    } else {
+        Ok(data)
+    }
+}
+
+/// Creates a patch which transforms the `old` archive into `new`.
+pub fn create(old: &Archive, new: &Archive) -> Result<Vec<u8>, PatchError> {
+    let mut old_inflater = Inflater::new();
+    let mut new_inflater = Inflater::new();
+
+    let mut entries = Vec::with_capacity(new.len());
+    for (name, file) in new.files() {
+        // Unpatched files have no contents, so we take their raw data.
+        let data = file
+            .extract(new.raw_archive())
+            .ok_or_else(|| PatchError::MissingFile(name.clone()))?;
+
+        let base = old
+            .file_raw(name)
+            .filter(|f| !f.is_unpatched && !file.is_unpatched)
+            .and_then(|f| Some((f, f.extract(old.raw_archive())?)));
+
+        let entry_data = match base {
+            Some((base, base_data))
+                if base.crc == file.crc
+                    && base.compressed == file.compressed
+                    && base_data == data =>
+            {
+                EntryData::Unchanged { base_crc: base.crc }
+            }
+
+            Some((base, base_data)) => {
+                let old_contents = contents(&mut old_inflater, base, base_data)?;
+                let new_contents = contents(&mut new_inflater, file, data)?;
+
+                let instructions = diff(old_contents, new_contents);
+                let delta_size: usize = instructions.iter().map(Instruction::binary_size).sum();
+
+                if delta_size < data.len() {
+                    EntryData::Delta {
+                        base_crc: base.crc,
+                        content_crc: crc::hash(new_contents),
+                        count: instructions.len() as u32,
+                        instructions,
+                    }
+                } else {
+                    EntryData::Full {
+                        crc: file.crc,
+                        len: data.len() as u32,
+                        data: data.to_vec(),
+                    }
+                }
+            }
+
+            None => EntryData::Full {
+                crc: file.crc,
+                len: data.len() as u32,
+                data: data.to_vec(),
+            },
+        };
+
+        entries.push(Entry {
+            name: name.clone(),
+            compressed: file.compressed,
+            uncompressed_size: file.uncompressed_size,
+            compressed_size: file.compressed_size,
+            unpatched: file.is_unpatched,
+            data: entry_data,
+        });
+    }
+
+    let mut body = Cursor::new(Vec::new());
+    body.write_le(&PatchBody { entries })?;
+    let body = body.into_inner();
+
+    let header = new.header();
+    let mut out = Cursor::new(Vec::new());
+    out.write_le(&PatchHeader {
+        format_version: FORMAT_VERSION,
+        archive_version: header.version,
+        archive_flags: header.flags.unwrap_or(0),
+        body_size: body.len() as u64,
+    })?;
+
+    let mut out = out.into_inner();
+    Deflater::new().compress_into(&mut out, &body)?;
+
+    Ok(out)
+}
+
+/// Applies a `patch` to the `old` archive and writes the result to
+/// a new archive at `out`.
+pub fn apply<P: AsRef<Path>>(old: &Archive, patch: &[u8], out: P) -> Result<(), PatchError> {
+    let mut cursor = Cursor::new(patch);
+    let header: PatchHeader = cursor.read_le()?;
+    if header.format_version != FORMAT_VERSION {
+        return Err(PatchError::Format(binrw::Error::AssertFail {
+            pos: 0,
+            message: format!("unsupported patch version {}", header.format_version),
+        }));
+    }
+
+    // Reject sizes the payload cannot possibly inflate to before
+    // allocating memory for them.
+    let payload = &patch[cursor.position() as usize..];
+    let body_size = usize::try_from(header.body_size).or(Err(PatchError::TooLarge))?;
+    if body_size > payload.len().saturating_mul(MAX_DEFLATE_RATIO) {
+        return Err(DecompressionError::BadData.into());
+    }
+
+    let mut inflater = Inflater::new();
+    let body = inflater.decompress(payload, body_size)?;
+    let body: PatchBody = Cursor::new(body).read_le()?;
+
+    let mut builder = ArchiveBuilder::new(header.archive_version, header.archive_flags, out)?;
+    let mut base_inflater = Inflater::new();
+    let mut scratch = Vec::new();
+
+    for entry in body.entries {
+        let name = entry.name;
+
+        let base = |expected: u32| {
+            let file = old
+                .file_raw(&name)
+                .ok_or_else(|| PatchError::MissingFile(name.clone()))?;
+            if file.crc != expected {
+                return Err(PatchError::BaseMismatch(
+                    name.clone(),
+                    CrcMismatch {
+                        expected,
+                        actual: file.crc,
+                    },
+                ));
+            }
+
+            let data = file
+                .extract(old.raw_archive())
+                .ok_or_else(|| PatchError::MissingFile(name.clone()))?;
+            Ok((file, data))
+        };
+
+        match entry.data {
+            EntryData::Unchanged { base_crc } => {
+                let (file, data) = base(base_crc)?;
+                builder.add_raw_entry(&name, file, data)?;
+            }
+
+            EntryData::Full { crc, data, .. } => {
+                // Unpatched files are expected to keep their zeroes.
+                if !(entry.unpatched && is_unpatched_file(&data)) {
+                    let actual = crc::hash(&data);
+                    if actual != crc {
+                        return Err(PatchError::Corrupted(
+                            name,
+                            CrcMismatch {
+                                expected: crc,
+                                actual,
+                            },
+                        ));
+                    }
+                }
+
+                let record = wad_types::File {
+                    offset: 0,
+                    uncompressed_size: entry.uncompressed_size,
+                    compressed_size: entry.compressed_size,
+                    compressed: entry.compressed,
+                    crc,
+                    is_unpatched: false,
+                    name: String::new(),
+                };
+                builder.add_raw_entry(&name, &record, &data)?;
+            }
+
+            EntryData::Delta {
+                base_crc,
+                content_crc,
+                instructions,
+                ..
+            } => {
+                let (file, data) = base(base_crc)?;
+                let old_contents = contents(&mut base_inflater, file, data)?;
+
+                scratch.clear();
+                for op in &instructions {
+                    match op {
+                        Instruction::Copy { offset, len } => {
+                            let (offset, len) = (*offset as usize, *len as usize);
+                            let range = old_contents
+                                .get(offset..offset + len)
+                                .ok_or_else(|| PatchError::OutOfBounds(name.clone()))?;
+                            scratch.extend_from_slice(range);
+                        }
+                        Instruction::Insert { data, .. } => scratch.extend_from_slice(data),
+                    }
+                }
+
+                let actual = crc::hash(&scratch);
+                if actual != content_crc {
+                    return Err(PatchError::Corrupted(
+                        name,
+                        CrcMismatch {
+                            expected: content_crc,
+                            actual,
+                        },
+                    ));
+                }
+
+                if entry.compressed {
+                    builder.add_file_compressed(&name, &scratch)?;
+                } else {
+                    builder.add_file(&name, &scratch)?;
+                }
+            }
+        }
+    }
+
+    builder.finish()?;
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Progress reporting for long-running archive operations.
+//!
+//! Routines which process many archive entries, like
+//! [`extract::extract_all`][crate::extract::extract_all] or the
+//! `ArchiveBuilder`, accept a [`ProgressSink`] to notify frontends
+//! of their progress.
+
+/// A snapshot of the progress of an archive operation.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress<'a> {
+    /// The path of the entry that was just processed.
+    pub entry: &'a str,
+    /// The number of entries processed so far.
+    pub entries_done: usize,
+    /// The total number of entries, if known upfront.
+    pub entries_total: Option<usize>,
+    /// The number of uncompressed bytes processed so far.
+    pub bytes_done: u64,
+    /// The total number of uncompressed bytes, if known upfront.
+    pub bytes_total: Option<u64>,
+}
+
+impl Progress<'_> {
+    /// Gets the completed fraction of the operation in the range
+    /// `0.0..=1.0`, if the totals are known.
+    ///
+    /// Progress is measured in bytes, falling back to entries for
+    /// operations without any data.
+    pub fn fraction(&self) -> Option<f64> {
+        match (self.bytes_total, self.entries_total) {
+            (Some(total), _) if total > 0 => Some(self.bytes_done as f64 / total as f64),
+            (_, Some(total)) if total > 0 => Some(self.entries_done as f64 / total as f64),
+            (Some(_), _) | (_, Some(_)) => Some(1.0),
+            (None, None) => None,
+        }
+    }
+}
+
+/// A receiver of progress updates.
+///
+/// Closures taking a [`Progress`] implement this trait, so they can
+/// be used directly.
+pub trait ProgressSink {
+    /// Called after an entry was processed.
+    fn update(&mut self, progress: &Progress<'_>);
+
+    /// Called once after the operation completed successfully.
+    fn finish(&mut self) {}
+}
+
+impl<F: FnMut(&Progress<'_>)> ProgressSink for F {
+    fn update(&mut self, progress: &Progress<'_>) {
+        self(progress)
+    }
+}
+
+/// A [`ProgressSink`] which discards all updates.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn update(&mut self, _progress: &Progress<'_>) {}
+}
+
+// Tracks the state of an operation and reports it to a sink.
+pub(crate) struct Tracker<'a> {
+    sink: &'a mut dyn ProgressSink,
+    entries_done: usize,
+    entries_total: Option<usize>,
+    bytes_done: u64,
+    bytes_total: Option<u64>,
+}
+
+impl<'a> Tracker<'a> {
+    pub fn new(
+        sink: &'a mut dyn ProgressSink,
+        entries_total: Option<usize>,
+        bytes_total: Option<u64>,
+    ) -> Self {
+        Self {
+            sink,
+            entries_done: 0,
+            entries_total,
+            bytes_done: 0,
+            bytes_total,
+        }
+    }
+
+    pub fn advance(&mut self, entry: &str, bytes: u64) {
+        self.entries_done += 1;
+        self.bytes_done += bytes;
+
+        self.sink.update(&Progress {
+            entry,
+            entries_done: self.entries_done,
+            entries_total: self.entries_total,
+            bytes_done: self.bytes_done,
+            bytes_total: self.bytes_total,
+        });
+    }
+
+    pub fn finish(self) {
+        self.sink.finish();
+    }
+}
@@ -0,0 +1,218 @@
+//! Read-only access to KIWAD archives over HTTP.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::{self, Read},
+};
+
+use crate::{
+    crc,
+    types::{self as wad_types, is_unpatched_file},
+    vfs::{self, ArchiveFs},
+    ArchiveError, Inflater,
+};
+
+// The number of bytes to request for the journal initially.
+//
+// This is doubled until the whole journal was received.
+const INITIAL_JOURNAL_FETCH: u64 = 64 * 1024;
+
+/// A KIWAD archive on a remote HTTP server.
+///
+/// Only the journal is downloaded when opening the archive. The
+/// contents of individual files are then fetched on demand using
+/// HTTP range requests, so archives can be inspected without
+/// downloading them in full.
+///
+/// Unlike [`Archive`][crate::Archive], CRCs are only checked for
+/// the files that are actually fetched.
+pub struct RemoteArchive {
+    agent: ureq::Agent,
+    url: String,
+    header: wad_types::Header,
+    files: BTreeMap<String, wad_types::File>,
+}
+
+impl RemoteArchive {
+    /// Opens the archive at `url` and fetches its journal.
+    pub fn open_http<S: Into<String>>(url: S) -> Result<Self, ArchiveError> {
+        Self::with_agent(ureq::Agent::new(), url)
+    }
+
+    /// Opens the archive at `url` using a pre-configured agent.
+    ///
+    /// This allows customization of timeouts, proxies and the like.
+    pub fn with_agent<S: Into<String>>(agent: ureq::Agent, url: S) -> Result<Self, ArchiveError> {
+        let mut this = Self {
+            agent,
+            url: url.into(),
+            header: wad_types::Header {
+                version: 0,
+                file_count: 0,
+                flags: None,
+            },
+            files: BTreeMap::new(),
+        };
+
+        let mut len = INITIAL_JOURNAL_FETCH;
+        let archive = loop {
+            let data = this.get_range(0, len)?;
+            match wad_types::Archive::parse(io::Cursor::new(&data)) {
+                Ok(archive) => break archive,
+
+                // Fetch more data if the journal was cut off, unless
+                // we already received the whole file.
+                Err(e) if e.is_eof() && data.len() as u64 == len => len *= 2,
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        this.header = archive.header;
+        for mut file in archive.files {
+            let name = std::mem::take(&mut file.name);
+            this.files.insert(name, file);
+        }
+
+        Ok(this)
+    }
+
+    /// Gets the URL of the archive.
+    #[inline]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Gets an immutable reference to the header of this archive.
+    #[inline]
+    pub fn header(&self) -> &wad_types::Header {
+        &self.header
+    }
+
+    /// Gets the number of files in the archive.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether the archive is empty, i.e. does not contain any files.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Gets a raw mapping of archive files from path to file metadata.
+    ///
+    /// See [`Archive::files`][crate::Archive::files] for details.
+    #[inline]
+    pub fn files(&self) -> &BTreeMap<String, wad_types::File> {
+        &self.files
+    }
+
+    /// Gets the raw metadata of an archived file by its string name.
+    pub fn file_raw(&self, name: &str) -> Option<&wad_types::File> {
+        self.files.get(name)
+    }
+
+    /// Fetches the raw, possibly compressed contents of a file and
+    /// validates their CRC.
+    ///
+    /// Returns [`None`] for unpatched files.
+    pub fn fetch_raw(&self, file: &wad_types::File) -> Result<Option<Vec<u8>>, ArchiveError> {
+        let data = self.get_range(file.offset as u64, file.size() as u64)?;
+        if data.len() != file.size() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let actual = crc::hash(&data);
+        if actual == file.crc {
+            Ok(Some(data))
+        } else if is_unpatched_file(&data) {
+            Ok(None)
+        } else {
+            Err(wad_types::CrcMismatch {
+                expected: file.crc,
+                actual,
+            }
+            .into())
+        }
+    }
+
+    /// Fetches and decompresses the contents of a file.
+    ///
+    /// Returns [`None`] for unpatched files.
+    pub fn fetch(
+        &self,
+        file: &wad_types::File,
+        inflater: &mut Inflater,
+    ) -> Result<Option<Vec<u8>>, ArchiveError> {
+        let Some(data) = self.fetch_raw(file)? else {
+            return Ok(None);
+        };
+
+        if file.compressed {
+            let mut out = vec![0; file.uncompressed_size as usize];
+            inflater.decompress_into(&mut out, &data)?;
+            Ok(Some(out))
+        } else {
+            Ok(Some(data))
+        }
+    }
+
+    fn get_range(&self, start: u64, len: u64) -> Result<Vec<u8>, ArchiveError> {
+        let mut data = Vec::new();
+        if len == 0 {
+            return Ok(data);
+        }
+
+        let end = start + len - 1;
+        let res = self
+            .agent
+            .get(&self.url)
+            .set("Range", &format!("bytes={start}-{end}"))
+            .call()
+            .map_err(Box::new)?;
+
+        // Servers without range support reply with the full file,
+        // so we need to skip ahead to the requested data.
+        let skip = if res.status() == 206 { 0 } else { start };
+
+        let mut reader = res.into_reader();
+        io::copy(&mut reader.by_ref().take(skip), &mut io::sink())?;
+        reader.take(len).read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+}
+
+impl ArchiveFs for RemoteArchive {
+    fn open(&self, path: &str) -> io::Result<Cow<'_, [u8]>> {
+        let path = vfs::normalize(path);
+        let file = self.file_raw(path).ok_or_else(|| vfs::not_found(path))?;
+
+        match self.fetch(file, &mut Inflater::new()) {
+            Ok(Some(data)) => Ok(Cow::Owned(data)),
+            Ok(None) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{path}' is unpatched in the archive"),
+            )),
+            Err(ArchiveError::Io(e)) => Err(e),
+            Err(e) => Err(io::Error::other(e)),
+        }
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<vfs::DirEntry>> {
+        vfs::list_dir(&self.files, vfs::normalize(path))
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<vfs::Metadata> {
+        let path = vfs::normalize(path);
+        match self.file_raw(path) {
+            Some(file) => Ok(vfs::Metadata {
+                kind: vfs::EntryKind::File,
+                size: file.uncompressed_size as u64,
+            }),
+            None => vfs::dir_metadata(&self.files, path),
+        }
+    }
+}
@@ -1,5 +1,8 @@
 //! Common types and structures in the KIWAD format.
 
+use std::{fmt, str::FromStr};
+
+use bitflags::bitflags;
 use katsuba_utils::{
     binrw::{
         self, binrw,
@@ -34,6 +37,50 @@ pub struct CrcMismatch {
     pub actual: u32,
 }
 
+bitflags! {
+    /// The flags stored in the header of version 2 archives.
+    ///
+    /// Bits which are not known to this crate are retained as-is.
+    /// They can be queried with [`ArchiveFlags::unknown_bits`].
+    ///
+    /// Flags can be parsed from strings of names separated by `|`,
+    /// such as `"ROOT"`, with unknown bits given in hex like `0x80`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct ArchiveFlags: u8 {
+        /// Set on the client's `Root.wad` archive, which is expected
+        /// to carry it.
+        const ROOT = 1 << 0;
+    }
+}
+
+impl ArchiveFlags {
+    /// Gets the bits set in `self` which do not correspond to any
+    /// known flag.
+    #[inline]
+    pub fn unknown_bits(&self) -> u8 {
+        self.bits() & !Self::all().bits()
+    }
+}
+
+impl FromStr for ArchiveFlags {
+    type Err = bitflags::parser::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        bitflags::parser::from_str(s)
+    }
+}
+
+impl fmt::Display for ArchiveFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("(none)");
+        }
+
+        bitflags::parser::to_writer(self, f)
+    }
+}
+
 /// The header of a KIWAD archive.
 #[binrw]
 #[derive(Clone, Copy, Debug)]
@@ -49,6 +96,15 @@ pub struct Header {
 }
 
 impl Header {
+    /// Gets the archive flags as typed [`ArchiveFlags`].
+    ///
+    /// Archives before version 2 do not store flags and report them
+    /// as empty.
+    #[inline]
+    pub fn archive_flags(&self) -> ArchiveFlags {
+        ArchiveFlags::from_bits_retain(self.flags.unwrap_or(0))
+    }
+
     #[cfg(feature = "builder")]
     fn binary_size(&self) -> usize {
         8 + if self.version >= 2 { 1 } else { 0 }
@@ -110,6 +166,18 @@ impl File {
         }
     }
 
+    /// Gets the ratio of stored to uncompressed size of the file.
+    ///
+    /// Uncompressed and empty files always have a ratio of `1.0`.
+    #[inline]
+    pub fn compression_ratio(&self) -> f64 {
+        if !self.compressed || self.uncompressed_size == 0 {
+            return 1.0;
+        }
+
+        self.compressed_size as f64 / self.uncompressed_size as f64
+    }
+
     /// Extracts this file from the given raw archive bytes.
     ///
     /// When the archive is malformed, this returns [`None`].
@@ -0,0 +1,264 @@
+//! A virtual filesystem interface over packed and unpacked game data.
+//!
+//! Tools written against [`ArchiveFs`] work the same on KIWAD archives
+//! and on directories of extracted files. Paths are always relative
+//! and use `/` as the separator, like file names in an archive.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{Archive, Inflater, OverlayArchive};
+
+/// The kind of an entry in an [`ArchiveFs`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A regular file.
+    File,
+    /// A directory.
+    Dir,
+}
+
+/// Metadata about an entry in an [`ArchiveFs`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    /// The kind of the entry.
+    pub kind: EntryKind,
+    /// The size of the file contents in bytes.
+    ///
+    /// This is always `0` for directories.
+    pub size: u64,
+}
+
+/// An entry yielded by [`ArchiveFs::read_dir`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    /// The name of the entry, without its parent directory.
+    pub name: String,
+    /// The kind of the entry.
+    pub kind: EntryKind,
+}
+
+/// Read-only filesystem operations over game data.
+pub trait ArchiveFs {
+    /// Reads the full, uncompressed contents of the file at `path`.
+    fn open(&self, path: &str) -> io::Result<Cow<'_, [u8]>>;
+
+    /// Lists the entries in the directory at `path`, sorted by name.
+    ///
+    /// An empty path refers to the root directory.
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>>;
+
+    /// Queries metadata for the entry at `path`.
+    fn metadata(&self, path: &str) -> io::Result<Metadata>;
+
+    /// Whether an entry exists at `path`.
+    fn exists(&self, path: &str) -> bool {
+        self.metadata(path).is_ok()
+    }
+}
+
+pub(crate) fn not_found(path: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("'{path}' does not exist"))
+}
+
+pub(crate) fn normalize(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+// Lists the directory at `dir` from a sorted mapping of file paths.
+pub(crate) fn list_dir<V>(files: &BTreeMap<String, V>, dir: &str) -> io::Result<Vec<DirEntry>> {
+    let prefix = if dir.is_empty() {
+        String::new()
+    } else {
+        format!("{dir}/")
+    };
+
+    let mut entries: Vec<DirEntry> = Vec::new();
+    for name in files
+        .range(prefix.clone()..)
+        .map(|(name, _)| name)
+        .take_while(|name| name.starts_with(&prefix))
+    {
+        let (name, kind) = match name[prefix.len()..].split_once('/') {
+            Some((dir, _)) => (dir, EntryKind::Dir),
+            None => (&name[prefix.len()..], EntryKind::File),
+        };
+
+        // Sorted input groups all files of a subdirectory together.
+        if entries.last().is_some_and(|e| e.name == name) {
+            continue;
+        }
+        entries.push(DirEntry {
+            name: name.to_owned(),
+            kind,
+        });
+    }
+
+    if entries.is_empty() && !dir.is_empty() {
+        return Err(not_found(dir));
+    }
+
+    Ok(entries)
+}
+
+pub(crate) fn dir_metadata<V>(files: &BTreeMap<String, V>, path: &str) -> io::Result<Metadata> {
+    let prefix = format!("{path}/");
+    match files.range(prefix.clone()..).next() {
+        Some((name, _)) if name.starts_with(&prefix) => Ok(Metadata {
+            kind: EntryKind::Dir,
+            size: 0,
+        }),
+        _ if path.is_empty() => Ok(Metadata {
+            kind: EntryKind::Dir,
+            size: 0,
+        }),
+        _ => Err(not_found(path)),
+    }
+}
+
+fn read_file<'a>(archive: &'a Archive, path: &str) -> io::Result<Cow<'a, [u8]>> {
+    let file = archive.file_raw(path).ok_or_else(|| not_found(path))?;
+    let contents = archive.file_contents(file).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("'{path}' is unpatched in the archive"),
+        )
+    })?;
+
+    if file.compressed {
+        let mut out = vec![0; file.uncompressed_size as usize];
+        Inflater::new()
+            .decompress_into(&mut out, contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(Cow::Owned(out))
+    } else {
+        Ok(Cow::Borrowed(contents))
+    }
+}
+
+impl ArchiveFs for Archive {
+    fn open(&self, path: &str) -> io::Result<Cow<'_, [u8]>> {
+        read_file(self, normalize(path))
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        list_dir(self.files(), normalize(path))
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let path = normalize(path);
+        match self.file_raw(path) {
+            Some(file) => Ok(Metadata {
+                kind: EntryKind::File,
+                size: file.uncompressed_size as u64,
+            }),
+            None => dir_metadata(self.files(), path),
+        }
+    }
+}
+
+impl ArchiveFs for OverlayArchive {
+    fn open(&self, path: &str) -> io::Result<Cow<'_, [u8]>> {
+        let path = normalize(path);
+        let archive = self.layer_of(path).ok_or_else(|| not_found(path))?;
+        read_file(archive, path)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        list_dir(self.index(), normalize(path))
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let path = normalize(path);
+        match self.file_raw(path) {
+            Some(file) => Ok(Metadata {
+                kind: EntryKind::File,
+                size: file.uncompressed_size as u64,
+            }),
+            None => dir_metadata(self.index(), path),
+        }
+    }
+}
+
+/// An [`ArchiveFs`] over a directory of unpacked files on disk.
+#[derive(Clone, Debug)]
+pub struct DirectoryFs {
+    root: PathBuf,
+}
+
+impl DirectoryFs {
+    /// Creates a filesystem rooted at the given directory.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Gets the root directory of the filesystem.
+    #[inline]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn resolve(&self, path: &str) -> io::Result<PathBuf> {
+        let path = Path::new(normalize(path));
+
+        // Refuse paths which could escape the root directory.
+        if path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(..) | Component::CurDir))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{}' is not a relative path", path.display()),
+            ));
+        }
+
+        Ok(self.root.join(path))
+    }
+}
+
+impl ArchiveFs for DirectoryFs {
+    fn open(&self, path: &str) -> io::Result<Cow<'_, [u8]>> {
+        fs::read(self.resolve(path)?).map(Cow::Owned)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        let mut entries = fs::read_dir(self.resolve(path)?)?
+            .map(|entry| {
+                let entry = entry?;
+                let kind = if entry.file_type()?.is_dir() {
+                    EntryKind::Dir
+                } else {
+                    EntryKind::File
+                };
+
+                Ok(DirEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    kind,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let meta = fs::metadata(self.resolve(path)?)?;
+        Ok(if meta.is_dir() {
+            Metadata {
+                kind: EntryKind::Dir,
+                size: 0,
+            }
+        } else {
+            Metadata {
+                kind: EntryKind::File,
+                size: meta.len(),
+            }
+        })
+    }
+}
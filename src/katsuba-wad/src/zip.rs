@@ -0,0 +1,668 @@
+//! Conversion between KIWAD archives and the ZIP format.
+//!
+//! Many tools understand ZIP but not KIWAD, so archives can be
+//! re-containered into standard ZIP files and back.
+
+use std::{
+    collections::HashSet,
+    io::{self, Cursor, Read, Seek, Write},
+};
+
+use katsuba_utils::{
+    binrw::{self, binrw, BinReaderExt, BinWriterExt},
+    libdeflater::{CompressionError, DecompressionError},
+    thiserror::{self, Error},
+};
+
+use crate::{
+    deflater::Deflater,
+    extract::{self, EntryMetadata, ExtractSink},
+    progress::NoProgress,
+    types::CrcMismatch,
+    Archive, ArchiveBuilder, ArchiveError, BuilderError, Inflater,
+};
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+const VERSION_DEFAULT: u16 = 20;
+const VERSION_ZIP64: u16 = 45;
+
+// Marks file names as UTF-8 encoded.
+const FLAG_UTF8: u16 = 1 << 11;
+
+// DOS timestamp for 1980-01-01 00:00:00, the earliest representable.
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+const EOCD_MAGIC: u32 = 0x06054b50;
+const EOCD_SIZE: usize = 22;
+const ZIP64_LOCATOR_SIZE: usize = 20;
+
+/// Errors that may occur when converting between ZIP and KIWAD.
+#[derive(Debug, Error)]
+pub enum ZipError {
+    /// An I/O error occurred while reading or writing data.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+
+    /// Compression of a file's contents failed.
+    #[error("failed to compress file: {0}")]
+    Compress(#[from] CompressionError),
+
+    /// Decompression of a file's contents failed.
+    #[error("failed to decompress file: {0}")]
+    Decompress(#[from] DecompressionError),
+
+    /// Failed to parse or serialize a ZIP structure.
+    #[error("malformed ZIP structure: {0}")]
+    Format(binrw::Error),
+
+    /// A file is too large to be represented.
+    #[error("file too large to represent")]
+    TooLarge,
+
+    /// The input is not a ZIP file or its structure is corrupted.
+    #[error("input is not a valid ZIP file")]
+    Malformed,
+
+    /// An entry uses a compression method other than store or deflate.
+    #[error("unsupported compression method {0} for '{1}'")]
+    UnsupportedMethod(u16, String),
+
+    /// CRC validation of an entry failed.
+    #[error("'{0}': {1}")]
+    Crc(String, CrcMismatch),
+
+    /// The same path occurs in more than one entry.
+    #[error("duplicate entry '{0}'")]
+    Duplicate(String),
+
+    /// Adding an entry to the output archive failed.
+    #[error("{0}")]
+    Builder(#[from] BuilderError),
+
+    /// Reading a file from the input archive failed.
+    #[error("{0}")]
+    Archive(#[from] ArchiveError),
+}
+
+impl From<binrw::Error> for ZipError {
+    fn from(value: binrw::Error) -> Self {
+        match value {
+            binrw::Error::Io(e) => Self::Io(e),
+            e => Self::Format(e),
+        }
+    }
+}
+
+#[binrw]
+#[brw(little, magic = 0x04034b50_u32)]
+struct LocalHeader {
+    version_needed: u16,
+    flags: u16,
+    method: u16,
+    mod_time: u16,
+    mod_date: u16,
+    crc32: u32,
+    compressed_size: u32,
+    uncompressed_size: u32,
+
+    #[br(temp)]
+    #[bw(calc = name.len() as u16)]
+    name_len: u16,
+    #[br(temp)]
+    #[bw(calc = extra.len() as u16)]
+    extra_len: u16,
+
+    #[br(count = u32::from(name_len))]
+    name: Vec<u8>,
+    #[br(count = u32::from(extra_len))]
+    extra: Vec<u8>,
+}
+
+#[binrw]
+#[brw(little, magic = 0x02014b50_u32)]
+struct CentralHeader {
+    version_made_by: u16,
+    version_needed: u16,
+    flags: u16,
+    method: u16,
+    mod_time: u16,
+    mod_date: u16,
+    crc32: u32,
+    compressed_size: u32,
+    uncompressed_size: u32,
+
+    #[br(temp)]
+    #[bw(calc = name.len() as u16)]
+    name_len: u16,
+    #[br(temp)]
+    #[bw(calc = extra.len() as u16)]
+    extra_len: u16,
+    #[br(temp)]
+    #[bw(calc = 0)]
+    comment_len: u16,
+
+    disk_start: u16,
+    internal_attrs: u16,
+    external_attrs: u32,
+    local_header_offset: u32,
+
+    #[br(count = u32::from(name_len))]
+    name: Vec<u8>,
+    #[br(count = u32::from(extra_len), pad_after = comment_len)]
+    extra: Vec<u8>,
+}
+
+#[binrw]
+#[brw(little, magic = 0x06054b50_u32)]
+struct EndOfCentralDirectory {
+    disk: u16,
+    central_directory_disk: u16,
+    disk_entries: u16,
+    total_entries: u16,
+    central_directory_size: u32,
+    central_directory_offset: u32,
+    comment_len: u16,
+}
+
+#[binrw]
+#[brw(little, magic = 0x06064b50_u32)]
+struct Zip64EndOfCentralDirectory {
+    record_size: u64,
+    version_made_by: u16,
+    version_needed: u16,
+    disk: u32,
+    central_directory_disk: u32,
+    disk_entries: u64,
+    total_entries: u64,
+    central_directory_size: u64,
+    central_directory_offset: u64,
+}
+
+#[binrw]
+#[brw(little, magic = 0x07064b50_u32)]
+struct Zip64Locator {
+    central_directory_disk: u32,
+    end_of_central_directory_offset: u64,
+    total_disks: u32,
+}
+
+struct Entry {
+    name: Vec<u8>,
+    method: u16,
+    crc32: u32,
+    compressed_size: u32,
+    uncompressed_size: u32,
+    offset: u64,
+}
+
+struct Output<W> {
+    out: W,
+    offset: u64,
+    entries: Vec<Entry>,
+}
+
+impl<W: Write + Seek> Output<W> {
+    fn add_entry(
+        &mut self,
+        name: &str,
+        method: u16,
+        crc32: u32,
+        uncompressed_size: usize,
+        data: &[u8],
+    ) -> Result<(), ZipError> {
+        let checked = |x: usize| u32::try_from(x).or(Err(ZipError::TooLarge));
+        if name.len() > u16::MAX as usize {
+            return Err(ZipError::TooLarge);
+        }
+
+        let entry = Entry {
+            name: name.as_bytes().to_vec(),
+            method,
+            crc32,
+            compressed_size: checked(data.len())?,
+            uncompressed_size: checked(uncompressed_size)?,
+            offset: self.offset,
+        };
+
+        let header = LocalHeader {
+            version_needed: VERSION_DEFAULT,
+            flags: FLAG_UTF8,
+            method,
+            mod_time: DOS_TIME,
+            mod_date: DOS_DATE,
+            crc32,
+            compressed_size: entry.compressed_size,
+            uncompressed_size: entry.uncompressed_size,
+            name: entry.name.clone(),
+            extra: Vec::new(),
+        };
+        self.out.write_le(&header)?;
+        self.out.write_all(data)?;
+
+        self.offset += (30 + entry.name.len() + data.len()) as u64;
+        self.entries.push(entry);
+
+        Ok(())
+    }
+}
+
+/// A writer for ZIP files.
+///
+/// Entries are written to the output as they are added, while the
+/// central directory is kept in memory until [`ZipWriter::finish`].
+/// ZIP64 extensions are emitted when the output needs them.
+pub struct ZipWriter<W> {
+    output: Output<W>,
+    deflater: Deflater,
+}
+
+impl<W: Write + Seek> ZipWriter<W> {
+    /// Creates a new ZIP writer that writes to `out`.
+    pub fn new(out: W) -> Self {
+        Self {
+            output: Output {
+                out,
+                offset: 0,
+                entries: Vec::new(),
+            },
+            deflater: Deflater::new(),
+        }
+    }
+
+    /// Adds a file with the given `contents` to the ZIP.
+    ///
+    /// When `compress` is set, the contents will be deflated.
+    /// Otherwise they will be stored as-is.
+    pub fn add_file(
+        &mut self,
+        name: &str,
+        contents: &[u8],
+        compress: bool,
+    ) -> Result<(), ZipError> {
+        let crc32 = crc32fast::hash(contents);
+        if compress {
+            let data = self.deflater.compress_raw(contents)?;
+            self.output
+                .add_entry(name, METHOD_DEFLATED, crc32, contents.len(), data)
+        } else {
+            self.output
+                .add_entry(name, METHOD_STORED, crc32, contents.len(), contents)
+        }
+    }
+
+    /// Adds a file from an already compressed raw DEFLATE stream.
+    ///
+    /// `crc32` and `uncompressed_size` must describe the inflated
+    /// data; they are not validated.
+    pub fn add_deflated(
+        &mut self,
+        name: &str,
+        raw: &[u8],
+        crc32: u32,
+        uncompressed_size: usize,
+    ) -> Result<(), ZipError> {
+        self.output
+            .add_entry(name, METHOD_DEFLATED, crc32, uncompressed_size, raw)
+    }
+
+    /// Writes the central directory and returns the inner writer.
+    pub fn finish(self) -> Result<W, ZipError> {
+        let Output {
+            mut out,
+            offset: cd_offset,
+            entries,
+        } = self.output;
+        let mut cd_size = 0;
+
+        for entry in &entries {
+            let (offset, extra) = match u32::try_from(entry.offset) {
+                Ok(offset) if offset != u32::MAX => (offset, Vec::new()),
+                _ => {
+                    let mut extra = Vec::with_capacity(12);
+                    extra.extend(ZIP64_EXTRA_ID.to_le_bytes());
+                    extra.extend(8_u16.to_le_bytes());
+                    extra.extend(entry.offset.to_le_bytes());
+
+                    (u32::MAX, extra)
+                }
+            };
+
+            let version = if extra.is_empty() {
+                VERSION_DEFAULT
+            } else {
+                VERSION_ZIP64
+            };
+            let header = CentralHeader {
+                version_made_by: version,
+                version_needed: version,
+                flags: FLAG_UTF8,
+                method: entry.method,
+                mod_time: DOS_TIME,
+                mod_date: DOS_DATE,
+                crc32: entry.crc32,
+                compressed_size: entry.compressed_size,
+                uncompressed_size: entry.uncompressed_size,
+                disk_start: 0,
+                internal_attrs: 0,
+                external_attrs: 0,
+                local_header_offset: offset,
+                name: entry.name.clone(),
+                extra,
+            };
+            out.write_le(&header)?;
+
+            cd_size += (46 + header.name.len() + header.extra.len()) as u64;
+        }
+
+        let entries = entries.len();
+        let needs_zip64 = entries >= u16::MAX as usize
+            || cd_size >= u32::MAX as u64
+            || cd_offset >= u32::MAX as u64;
+
+        if needs_zip64 {
+            let eocd_offset = cd_offset + cd_size;
+            out.write_le(&Zip64EndOfCentralDirectory {
+                record_size: 44,
+                version_made_by: VERSION_ZIP64,
+                version_needed: VERSION_ZIP64,
+                disk: 0,
+                central_directory_disk: 0,
+                disk_entries: entries as u64,
+                total_entries: entries as u64,
+                central_directory_size: cd_size,
+                central_directory_offset: cd_offset,
+            })?;
+            out.write_le(&Zip64Locator {
+                central_directory_disk: 0,
+                end_of_central_directory_offset: eocd_offset,
+                total_disks: 1,
+            })?;
+        }
+
+        let entries = u16::try_from(entries).unwrap_or(u16::MAX);
+        out.write_le(&EndOfCentralDirectory {
+            disk: 0,
+            central_directory_disk: 0,
+            disk_entries: entries,
+            total_entries: entries,
+            central_directory_size: u32::try_from(cd_size).unwrap_or(u32::MAX),
+            central_directory_offset: u32::try_from(cd_offset).unwrap_or(u32::MAX),
+            comment_len: 0,
+        })?;
+
+        out.flush()?;
+        Ok(out)
+    }
+}
+
+// Strips the zlib wrapper from a stream, if it is well-formed and
+// does not depend on a preset dictionary.
+fn raw_deflate(zlib: &[u8]) -> Option<&[u8]> {
+    match zlib {
+        [cmf, flg, ..]
+            if zlib.len() >= 6
+                && cmf & 0xF == 8
+                && flg & 0x20 == 0
+                && u16::from_be_bytes([*cmf, *flg]) % 31 == 0 =>
+        {
+            Some(&zlib[2..zlib.len() - 4])
+        }
+        _ => None,
+    }
+}
+
+// Files are stored with the same compression as in the archive.
+impl<W: Write + Seek> ExtractSink for ZipWriter<W> {
+    fn write_entry(
+        &mut self,
+        path: &str,
+        meta: &EntryMetadata,
+        reader: &mut dyn Read,
+    ) -> io::Result<()> {
+        let mut contents = Vec::with_capacity(meta.size as usize);
+        reader.read_to_end(&mut contents)?;
+
+        self.add_file(path, &contents, meta.compressed)
+            .map_err(|e| match e {
+                ZipError::Io(e) => e,
+                e => io::Error::other(e),
+            })
+    }
+}
+
+/// Writes all files in `archive` into a new ZIP file.
+///
+/// Files which are compressed in the archive will be deflated in the
+/// ZIP as well. With `passthrough`, their compressed data is copied
+/// without recompression. Otherwise, it is recompressed at the best
+/// compression level.
+///
+/// Unpatched files have no data and will be skipped.
+pub fn archive_to_zip<W: Write + Seek>(
+    archive: &Archive,
+    out: W,
+    passthrough: bool,
+) -> Result<W, ZipError> {
+    let mut zip = ZipWriter::new(out);
+    if !passthrough {
+        extract::extract_with(archive, &mut zip, &mut NoProgress)?;
+        return zip.finish();
+    }
+
+    let mut inflater = Inflater::new();
+    for (name, file) in archive.files() {
+        let Some(contents) = archive.file_contents(file) else {
+            continue;
+        };
+
+        if !file.compressed {
+            zip.add_file(name, contents, false)?;
+            continue;
+        }
+
+        let size = file.uncompressed_size as usize;
+        let data = inflater.decompress(contents, size)?;
+
+        // Streams which are not plain zlib are recompressed instead.
+        match raw_deflate(contents) {
+            Some(raw) => zip.add_deflated(name, raw, crc32fast::hash(data), size)?,
+            None => zip.add_file(name, data, true)?,
+        }
+    }
+
+    zip.finish()
+}
+
+/// A file entry in a [`ZipReader`].
+pub struct ZipEntry<'a> {
+    /// The path of the entry, with `/` as the separator.
+    pub name: String,
+    /// The compression method of the entry.
+    pub method: u16,
+    /// The CRC32 of the uncompressed data.
+    pub crc32: u32,
+    /// The size of the uncompressed data.
+    pub uncompressed_size: u64,
+    /// The raw, possibly compressed data of the entry.
+    pub data: &'a [u8],
+}
+
+impl ZipEntry<'_> {
+    /// Whether the entry describes a directory rather than a file.
+    #[inline]
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+/// A reader for ZIP files held in memory.
+///
+/// This understands the ZIP64 extensions, but not multi-disk or
+/// encrypted files.
+pub struct ZipReader<'a> {
+    entries: Vec<ZipEntry<'a>>,
+}
+
+// Resolves the fields of a central directory header which were moved
+// into a ZIP64 extra field because they did not fit 32 bits.
+fn apply_zip64_extra(extra: &[u8], fields: [&mut u64; 3]) {
+    let mut rest = extra;
+    while let [a, b, c, d, tail @ ..] = rest {
+        let id = u16::from_le_bytes([*a, *b]);
+        let len = u16::from_le_bytes([*c, *d]) as usize;
+        let Some(body) = tail.get(..len) else {
+            break;
+        };
+
+        if id == ZIP64_EXTRA_ID {
+            let mut values = body
+                .chunks_exact(8)
+                .map(|c| u64::from_le_bytes(c.try_into().unwrap()));
+            for field in fields.into_iter().filter(|f| **f == u32::MAX as u64) {
+                match values.next() {
+                    Some(v) => *field = v,
+                    None => break,
+                }
+            }
+
+            break;
+        }
+
+        rest = &tail[len..];
+    }
+}
+
+impl<'a> ZipReader<'a> {
+    /// Parses the central directory of the ZIP file in `data`.
+    pub fn new(data: &'a [u8]) -> Result<Self, ZipError> {
+        // The end of central directory record may be followed by a
+        // comment of up to `u16::MAX` bytes, so we scan backwards.
+        let last = data
+            .len()
+            .checked_sub(EOCD_SIZE)
+            .ok_or(ZipError::Malformed)?;
+        let eocd_offset = (last.saturating_sub(u16::MAX as usize)..=last)
+            .rev()
+            .find(|&i| data[i..i + 4] == EOCD_MAGIC.to_le_bytes())
+            .ok_or(ZipError::Malformed)?;
+
+        let mut cursor = Cursor::new(data);
+        cursor.set_position(eocd_offset as u64);
+        let eocd: EndOfCentralDirectory = cursor.read_le()?;
+
+        let mut total_entries = eocd.total_entries as u64;
+        let mut cd_offset = eocd.central_directory_offset as u64;
+        if eocd.total_entries == u16::MAX
+            || eocd.central_directory_offset == u32::MAX
+            || eocd.central_directory_size == u32::MAX
+        {
+            let locator_offset = eocd_offset
+                .checked_sub(ZIP64_LOCATOR_SIZE)
+                .ok_or(ZipError::Malformed)?;
+            cursor.set_position(locator_offset as u64);
+            let locator: Zip64Locator = cursor.read_le()?;
+
+            cursor.set_position(locator.end_of_central_directory_offset);
+            let eocd64: Zip64EndOfCentralDirectory = cursor.read_le()?;
+
+            total_entries = eocd64.total_entries;
+            cd_offset = eocd64.central_directory_offset;
+        }
+
+        let mut entries = Vec::new();
+        cursor.set_position(cd_offset);
+        for _ in 0..total_entries {
+            let header: CentralHeader = cursor.read_le()?;
+
+            let mut uncompressed_size = header.uncompressed_size as u64;
+            let mut compressed_size = header.compressed_size as u64;
+            let mut offset = header.local_header_offset as u64;
+            apply_zip64_extra(
+                &header.extra,
+                [&mut uncompressed_size, &mut compressed_size, &mut offset],
+            );
+
+            // The local header may have differently sized variable fields,
+            // so we need to consult it for the start of the entry's data.
+            let mut local = Cursor::new(data);
+            local.set_position(offset);
+            let local: LocalHeader = local.read_le()?;
+
+            let start = offset as usize + 30 + local.name.len() + local.extra.len();
+            let contents = usize::try_from(compressed_size)
+                .ok()
+                .and_then(|size| data.get(start..start.checked_add(size)?))
+                .ok_or(ZipError::Malformed)?;
+
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(&header.name).into_owned(),
+                method: header.method,
+                crc32: header.crc32,
+                uncompressed_size,
+                data: contents,
+            });
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Gets the entries in the ZIP in central directory order.
+    #[inline]
+    pub fn entries(&self) -> &[ZipEntry<'a>] {
+        &self.entries
+    }
+}
+
+/// Adds the files from all given ZIPs to an archive `builder`.
+///
+/// Files will be compressed according to the builder's settings,
+/// regardless of how they were stored in the ZIP. Their data is
+/// validated against the CRCs in the ZIP before that.
+///
+/// Since archives cannot contain the same path twice, duplicate
+/// entries across the ZIPs are an error.
+pub fn zips_to_archive(
+    zips: &[ZipReader<'_>],
+    builder: &mut ArchiveBuilder,
+) -> Result<(), ZipError> {
+    let mut inflater = Inflater::new();
+    let mut seen = HashSet::new();
+
+    for entry in zips.iter().flat_map(|z| z.entries()) {
+        if entry.is_dir() {
+            continue;
+        }
+
+        let name = entry.name.trim_start_matches('/');
+        if !seen.insert(name) {
+            return Err(ZipError::Duplicate(name.to_owned()));
+        }
+
+        let size = usize::try_from(entry.uncompressed_size).or(Err(ZipError::TooLarge))?;
+        let contents = match entry.method {
+            METHOD_STORED => entry.data,
+            METHOD_DEFLATED => inflater.decompress_raw(entry.data, size)?,
+            method => return Err(ZipError::UnsupportedMethod(method, name.to_owned())),
+        };
+
+        let actual = crc32fast::hash(contents);
+        if actual != entry.crc32 {
+            return Err(ZipError::Crc(
+                name.to_owned(),
+                CrcMismatch {
+                    expected: entry.crc32,
+                    actual,
+                },
+            ));
+        }
+
+        builder.add_file_compressed(name, contents)?;
+    }
+
+    Ok(())
+}
@@ -1,4 +1,11 @@
-use katsuba_wad::{Archive, ArchiveError, Inflater};
+use std::{io, time::Duration};
+
+use katsuba_wad::{
+    extract::{self, MemorySink},
+    journal::{self, JournalDump},
+    progress::NoProgress,
+    Archive, ArchiveError, Inflater, InflaterPool, OpenOptions, RetryPolicy,
+};
 
 #[test]
 fn open_mmap() -> Result<(), ArchiveError> {
@@ -10,6 +17,73 @@ fn open_heap() -> Result<(), ArchiveError> {
     Archive::open_heap("tests/data/Test.wad").map(|_| ())
 }
 
+#[test]
+fn open_with_retries() -> Result<(), ArchiveError> {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+
+    OpenOptions::new()
+        .retry(policy)
+        .open_heap("tests/data/Test.wad")?;
+
+    // Missing files are not transient and must fail without retrying.
+    match OpenOptions::new()
+        .retry(policy)
+        .open_heap("tests/data/Missing.wad")
+    {
+        Err(ArchiveError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+        _ => panic!("expected NotFound error"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn case_insensitive_lookup() -> Result<(), ArchiveError> {
+    let mut archive = Archive::open_heap("tests/data/Test.wad")?;
+    assert!(archive.file_raw("SubDir/Subdir_Text1.TXT").is_none());
+
+    archive.set_case_insensitive(true);
+    assert!(archive.file_raw("SubDir/Subdir_Text1.TXT").is_some());
+    assert!(archive.file_raw("text1.txt").is_some());
+    assert!(archive.file_raw("text3.txt").is_none());
+
+    archive.set_case_insensitive(false);
+    assert!(archive.file_raw("SubDir/Subdir_Text1.TXT").is_none());
+
+    let archive = OpenOptions::new()
+        .case_insensitive(true)
+        .open_mmap("tests/data/Test.wad")?;
+    assert!(archive.is_case_insensitive());
+    assert!(archive.file_raw("TEXT1.txt").is_some());
+
+    Ok(())
+}
+
+#[test]
+fn glob_many() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let names = |patterns: &[&str]| -> Vec<String> {
+        archive
+            .iter_glob_many(patterns)
+            .unwrap()
+            .map(|(name, _)| name.clone())
+            .collect()
+    };
+
+    assert_eq!(
+        names(&["*.txt", "!text2.txt"]),
+        ["subdir/subdir_text1.txt", "text1.txt"]
+    );
+    assert_eq!(names(&["subdir/**", "text2.txt"]).len(), 2);
+    assert_eq!(names(&["!**/*.txt"]).len(), archive.len() - 3);
+
+    Ok(())
+}
+
 #[test]
 fn uncompressed() -> Result<(), ArchiveError> {
     let archive = Archive::open_heap("tests/data/Test.wad")?;
@@ -43,6 +117,39 @@ fn subdir() -> Result<(), ArchiveError> {
     Ok(())
 }
 
+#[test]
+fn extract_to_memory() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+
+    let mut sink = MemorySink::default();
+    let written = extract::extract_with(&archive, &mut sink, &mut NoProgress)?;
+
+    assert_eq!(written, sink.files.len());
+    assert_eq!(
+        sink.files["subdir/subdir_text1.txt"],
+        b"this is subdir text1\n"
+    );
+    assert_eq!(sink.files["uncompressed.mp3"], b"uncompressed data\n");
+
+    Ok(())
+}
+
+#[test]
+fn extract_filtered() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+
+    let mut sink = MemorySink::default();
+    let written = extract::extract_filtered(&archive, &mut sink, &mut NoProgress, |name, file| {
+        name.starts_with("subdir/") && file.compressed
+    })?;
+
+    assert_eq!(written, sink.files.len());
+    assert!(sink.files.contains_key("subdir/subdir_text1.txt"));
+    assert!(sink.files.keys().all(|name| name.starts_with("subdir/")));
+
+    Ok(())
+}
+
 #[test]
 fn two_files() -> Result<(), ArchiveError> {
     let archive = Archive::open_heap("tests/data/Test.wad")?;
@@ -83,3 +190,119 @@ fn inflate_twice() -> Result<(), ArchiveError> {
 
     Ok(())
 }
+
+#[test]
+fn inflater_pool() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let pool = InflaterPool::with_max_idle(2);
+
+    let file = archive.file_raw("text1.txt").unwrap();
+    let contents = archive.file_contents(file).unwrap();
+
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                let mut inflater = pool.get();
+                inflater
+                    .decompress(contents, file.uncompressed_size as _)
+                    .unwrap();
+            });
+        }
+    });
+
+    let idle = pool.idle();
+    assert!((1..=2).contains(&idle));
+
+    let mut inflater = pool.get();
+    assert_eq!(pool.idle(), idle - 1);
+    inflater.decompress(contents, file.uncompressed_size as _)?;
+    drop(inflater);
+    assert_eq!(pool.idle(), idle);
+
+    Ok(())
+}
+
+#[test]
+fn deferred_crc_verification() -> Result<(), ArchiveError> {
+    let archive = OpenOptions::new()
+        .verify_crcs(false)
+        .open_heap("tests/data/Test.wad")?;
+
+    for file in archive.files().values() {
+        let contents = archive.verified_file_contents(file)?;
+        assert_eq!(contents, archive.file_contents(file));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn incremental_crc() {
+    let data = b"incremental CRCs are computed in chunks";
+
+    let mut hasher = katsuba_wad::crc::CrcHasher::new();
+    for chunk in data.chunks(7) {
+        hasher.update(chunk);
+    }
+
+    assert_eq!(hasher.finalize(), katsuba_wad::crc::hash(data));
+}
+
+#[test]
+fn archive_flags() {
+    use katsuba_wad::types::ArchiveFlags;
+
+    assert_eq!("ROOT".parse::<ArchiveFlags>().unwrap(), ArchiveFlags::ROOT);
+
+    let flags = "ROOT | 0x80".parse::<ArchiveFlags>().unwrap();
+    assert_eq!(flags.bits(), 0x81);
+    assert_eq!(flags.unknown_bits(), 0x80);
+    assert_eq!(flags.to_string(), "ROOT | 0x80");
+}
+
+#[test]
+fn journal_rename() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let mut dump = JournalDump::from_archive(&archive);
+
+    let entry = dump
+        .files
+        .iter_mut()
+        .find(|e| e.name == "subdir/subdir_text1.txt")
+        .unwrap();
+    entry.name = "a_much_longer_directory_name/renamed.txt".to_owned();
+
+    let mut out = Vec::new();
+    journal::rewrite(&archive, &dump, &mut out)?;
+
+    let renamed = Archive::from_vec(out)?;
+    renamed.verify()?;
+    assert_eq!(renamed.len(), archive.len());
+    assert!(renamed.file_raw("subdir/subdir_text1.txt").is_none());
+
+    let mut inflater = Inflater::new();
+    let file = renamed
+        .file_raw("a_much_longer_directory_name/renamed.txt")
+        .unwrap();
+    let data = inflater.decompress(
+        renamed.file_contents(file).unwrap(),
+        file.uncompressed_size as _,
+    )?;
+    assert_eq!(data, b"this is subdir text1\n");
+
+    Ok(())
+}
+
+#[test]
+fn journal_out_of_range() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let mut dump = JournalDump::from_archive(&archive);
+    dump.files[0].offset = 0;
+
+    assert!(matches!(
+        journal::rewrite(&archive, &dump, io::sink()),
+        Err(ArchiveError::Inconsistent(_))
+    ));
+
+    Ok(())
+}
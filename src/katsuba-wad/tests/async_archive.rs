@@ -0,0 +1,74 @@
+#![cfg(feature = "async")]
+
+use std::{future::Future, io};
+
+use katsuba_wad::{ArchiveError, AsyncArchive, OpenOptions};
+
+const TEST_WAD: &str = "tests/data/Test.wad";
+
+fn block_on<F>(f: F) -> Result<(), ArchiveError>
+where
+    F: Future<Output = Result<(), ArchiveError>>,
+{
+    tokio::runtime::Builder::new_current_thread()
+        .build()?
+        .block_on(f)
+}
+
+#[test]
+fn read_entry() -> Result<(), ArchiveError> {
+    block_on(async {
+        let archive = AsyncArchive::open(TEST_WAD).await?;
+
+        let data = archive.read_entry("subdir/subdir_text1.txt").await?;
+        assert_eq!(data.as_deref(), Some(&b"this is subdir text1\n"[..]));
+
+        let data = archive.read_entry("uncompressed.mp3").await?;
+        assert_eq!(data.as_deref(), Some(&b"uncompressed data\n"[..]));
+
+        assert_eq!(archive.read_entry("missing.txt").await?, None);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn concurrent_reads() -> Result<(), ArchiveError> {
+    block_on(async {
+        let archive = AsyncArchive::open(TEST_WAD).await?;
+
+        let tasks: Vec<_> = ["text1.txt", "subdir/subdir_text1.txt", "uncompressed.mp3"]
+            .into_iter()
+            .map(|name| {
+                let archive = archive.clone();
+                tokio::spawn(async move { archive.read_entry(name).await })
+            })
+            .collect();
+
+        for task in tasks {
+            let data = task.await.map_err(io::Error::other)??;
+            assert!(data.is_some());
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+fn open_with_options() -> Result<(), ArchiveError> {
+    block_on(async {
+        let options = OpenOptions::new().case_insensitive(true).clone();
+        let archive = AsyncArchive::open_with(TEST_WAD, options).await?;
+        assert!(archive.archive().is_case_insensitive());
+
+        let data = archive.read_entry("SubDir/Subdir_Text1.TXT").await?;
+        assert_eq!(data.as_deref(), Some(&b"this is subdir text1\n"[..]));
+
+        match AsyncArchive::open("tests/data/Missing.wad").await {
+            Err(ArchiveError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+            _ => panic!("expected NotFound error"),
+        }
+
+        Ok(())
+    })
+}
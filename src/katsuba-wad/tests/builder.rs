@@ -1,4 +1,14 @@
-use katsuba_wad::{Archive, ArchiveBuilder, Inflater};
+use std::{
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use katsuba_wad::{
+    deflater::{CompressionLevel, CompressionStrategy, Deflater},
+    extract,
+    progress::Progress,
+    Archive, ArchiveBuilder, Inflater, JournalCache, OpenOptions, PreparedFile,
+};
 use tempfile::NamedTempFile;
 
 #[test]
@@ -27,3 +37,421 @@ fn build_and_extract() {
     assert!(!b.compressed);
     assert_eq!(archive.file_contents(b), Some(&b"it does!"[..]));
 }
+
+#[test]
+fn min_compressed_size() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let mut builder = ArchiveBuilder::with_level(2, 0, CompressionLevel::FASTEST, &path).unwrap();
+    builder.set_min_compressed_size(16);
+    builder.add_file_compressed("small.txt", b"tiny").unwrap();
+    builder
+        .add_file_compressed("large.txt", &[b'a'; 64])
+        .unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(file).unwrap();
+    assert!(!archive.file_raw("small.txt").unwrap().compressed);
+    assert!(archive.file_raw("large.txt").unwrap().compressed);
+}
+
+#[test]
+fn compression_strategy() {
+    let contents: Vec<u8> = (0..64 * 1024).map(|i| (i / 97 % 7) as u8).collect();
+
+    let mut sizes = Vec::new();
+    for strategy in [
+        CompressionStrategy::Default,
+        CompressionStrategy::Filtered,
+        CompressionStrategy::HuffmanOnly,
+        CompressionStrategy::Rle,
+        CompressionStrategy::Fixed,
+    ] {
+        let temp = NamedTempFile::new().unwrap();
+        let (file, path) = temp.into_parts();
+
+        let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+        builder.set_compression_strategy(strategy);
+        assert_eq!(builder.compression_strategy(), strategy);
+        builder.add_file_compressed("data.bin", &contents).unwrap();
+        builder
+            .add_file_from_reader("streamed.bin", &contents[..], true)
+            .unwrap();
+        builder.finish().unwrap();
+
+        let archive = Archive::heap(file).unwrap();
+        let mut inflater = Inflater::new();
+        for name in ["data.bin", "streamed.bin"] {
+            let file = archive.file_raw(name).unwrap();
+            assert!(archive.verified_file_contents(file).is_ok());
+            assert_eq!(
+                inflater.decompress(
+                    archive.file_contents(file).unwrap(),
+                    file.uncompressed_size as _
+                ),
+                Ok(&contents[..]),
+                "{strategy:?}"
+            );
+        }
+
+        sizes.push(archive.file_raw("data.bin").unwrap().compressed_size);
+    }
+
+    // Without searching for repetitions, the runs cannot be exploited.
+    assert!(sizes[2] > sizes[0] * 4);
+}
+
+#[test]
+fn from_reader() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let large = vec![b'x'; 200 * 1024];
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder
+        .add_file_from_reader("large.bin", &large[..], false)
+        .unwrap();
+    builder
+        .add_file_from_reader("text.txt", &b"streamed data"[..], true)
+        .unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(file).unwrap();
+    let mut inflater = Inflater::new();
+
+    let a = archive.file_raw("large.bin").unwrap();
+    assert!(!a.compressed);
+    assert_eq!(archive.file_contents(a), Some(&large[..]));
+
+    let b = archive.file_raw("text.txt").unwrap();
+    assert!(b.compressed);
+    assert_eq!(
+        inflater.decompress(archive.file_contents(b).unwrap(), b.uncompressed_size as _),
+        Ok(&b"streamed data"[..])
+    );
+}
+
+#[test]
+fn from_reader_compressed_chunks() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    // Spans several read chunks, the last of which is partial.
+    let large: Vec<u8> = (0..300 * 1024).map(|i| (i % 251) as u8).collect();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder
+        .add_file_from_reader("large.bin", &large[..], true)
+        .unwrap();
+    builder
+        .add_file_from_reader("exact.bin", &large[..128 * 1024], true)
+        .unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(file).unwrap();
+    let mut inflater = Inflater::new();
+
+    for (name, contents) in [
+        ("large.bin", &large[..]),
+        ("exact.bin", &large[..128 * 1024]),
+    ] {
+        let file = archive.file_raw(name).unwrap();
+        assert!(file.compressed);
+        assert!((file.compressed_size as usize) < contents.len());
+        assert_eq!(
+            archive.verified_file_contents(file).unwrap(),
+            archive.file_contents(file)
+        );
+        assert_eq!(
+            inflater.decompress(
+                archive.file_contents(file).unwrap(),
+                file.uncompressed_size as _
+            ),
+            Ok(contents)
+        );
+    }
+}
+
+#[test]
+fn deduplicate_contents() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let placeholder = vec![b'p'; 4096];
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder.add_file("a.bin", &placeholder).unwrap();
+    builder.add_file("b.bin", &placeholder).unwrap();
+    builder
+        .add_file_from_reader("c.bin", &placeholder[..], false)
+        .unwrap();
+    builder.add_file("d.bin", b"unique").unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(file).unwrap();
+
+    let a = archive.file_raw("a.bin").unwrap();
+    let b = archive.file_raw("b.bin").unwrap();
+    let c = archive.file_raw("c.bin").unwrap();
+    assert_eq!(a.offset, b.offset);
+    assert_eq!(a.offset, c.offset);
+    assert_eq!(archive.file_contents(c), Some(&placeholder[..]));
+
+    let d = archive.file_raw("d.bin").unwrap();
+    assert_ne!(a.offset, d.offset);
+    assert_eq!(archive.file_contents(d), Some(&b"unique"[..]));
+}
+
+#[test]
+fn prepared_files() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let mut deflater = Deflater::new();
+    let compressed = PreparedFile::compressed(
+        "a.txt",
+        b"compressed ahead of time".to_vec(),
+        &mut deflater,
+        0,
+    )
+    .unwrap();
+    let music = PreparedFile::compressed("b.mp3", vec![1, 2, 3], &mut deflater, 0).unwrap();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder.add_prepared(compressed).unwrap();
+    builder.add_prepared(music).unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(file).unwrap();
+    let mut inflater = Inflater::new();
+
+    let a = archive.file_raw("a.txt").unwrap();
+    assert!(a.compressed);
+    assert_eq!(
+        inflater.decompress(archive.file_contents(a).unwrap(), a.uncompressed_size as _),
+        Ok(&b"compressed ahead of time"[..])
+    );
+
+    let b = archive.file_raw("b.mp3").unwrap();
+    assert!(!b.compressed);
+    assert_eq!(archive.file_contents(b), Some(&[1, 2, 3][..]));
+}
+
+#[test]
+fn progress() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let added = Arc::new(Mutex::new(Vec::new()));
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder.set_progress({
+        let added = added.clone();
+        move |p: &Progress<'_>| {
+            added
+                .lock()
+                .unwrap()
+                .push((p.entry.to_owned(), p.entries_done, p.bytes_done))
+        }
+    });
+    builder.add_file_compressed("a.txt", b"aaaa").unwrap();
+    builder.add_file("b/c.txt", b"cc").unwrap();
+    builder.finish().unwrap();
+
+    assert_eq!(
+        *added.lock().unwrap(),
+        [("a.txt".to_owned(), 1, 4), ("b/c.txt".to_owned(), 2, 6)]
+    );
+
+    let archive = Archive::heap(file).unwrap();
+    let out = tempfile::tempdir().unwrap();
+
+    let mut fractions = Vec::new();
+    let written = extract::extract_all(&archive, out.path(), &mut |p: &Progress<'_>| {
+        fractions.push(p.fraction().unwrap())
+    })
+    .unwrap();
+
+    assert_eq!(written, 2);
+    assert_eq!(fractions, [4.0 / 6.0, 1.0]);
+    assert_eq!(fs::read(out.path().join("a.txt")).unwrap(), b"aaaa");
+    assert_eq!(fs::read(out.path().join("b/c.txt")).unwrap(), b"cc");
+}
+
+#[test]
+fn stats() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder.add_file("a.XML", b"<a></a>").unwrap();
+    builder.add_file("b/c.xml", b"<c/>").unwrap();
+    builder.add_file("b/.hidden", b"hidden").unwrap();
+    builder.add_file("README", b"").unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(file).unwrap();
+    let stats = archive.stats();
+
+    assert_eq!(stats.total.count, 4);
+    assert_eq!(stats.total.uncompressed_size, 17);
+    assert_eq!(stats.unpatched, 0);
+
+    let xml = &stats.extensions["xml"];
+    assert_eq!((xml.count, xml.stored_size), (2, 11));
+    assert_eq!(stats.extensions[""].count, 2);
+
+    let largest: Vec<_> = stats.largest.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(largest, ["a.XML", "b/.hidden", "b/c.xml", "README"]);
+}
+
+#[test]
+fn raw_entries() {
+    let source = Archive::open_heap("tests/data/Test.wad").unwrap();
+    let file = source.file_raw("subdir/subdir_text1.txt").unwrap();
+    let data = source.file_contents(file).unwrap();
+    assert!(file.compressed);
+
+    let temp = NamedTempFile::new().unwrap();
+    let (out, path) = temp.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder.add_raw_entry("copy.txt", file, data).unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(out).unwrap();
+    let copy = archive.file_raw("copy.txt").unwrap();
+    assert!(copy.compressed);
+    assert_eq!(copy.crc, file.crc);
+    assert_eq!(archive.file_contents(copy), Some(data));
+}
+
+#[test]
+fn reproducible_output() {
+    let build = || {
+        let temp = NamedTempFile::new().unwrap();
+        let (file, path) = temp.into_parts();
+
+        let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+        builder.add_file_compressed("b.txt", &[b'b'; 64]).unwrap();
+        builder.add_file("a/c.txt", b"c").unwrap();
+        builder.finish().unwrap();
+
+        (fs::read(&path).unwrap(), Archive::heap(file).unwrap())
+    };
+
+    let (first, archive) = build();
+    let (second, _) = build();
+    assert_eq!(first, second);
+
+    archive.verify().unwrap();
+}
+
+#[test]
+fn plan() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder.add_file("b.txt", b"second").unwrap();
+    builder.add_file("a.txt", b"first").unwrap();
+
+    let plan = builder.plan().unwrap();
+    let size = builder.planned_size();
+    builder.finish().unwrap();
+
+    assert_eq!(fs::metadata(&path).unwrap().len(), size);
+
+    let archive = Archive::heap(file).unwrap();
+    let names: Vec<_> = plan.files.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["a.txt", "b.txt"]);
+    for planned in &plan.files {
+        let file = archive.file_raw(&planned.name).unwrap();
+        assert_eq!(file.offset, planned.offset);
+    }
+}
+
+#[test]
+fn unpatched_fallback() {
+    let source = Archive::open_heap("tests/data/Test.wad").unwrap();
+    let file = source.file_raw("subdir/subdir_text1.txt").unwrap();
+    let zeroes = vec![0; file.size()];
+
+    let temp = NamedTempFile::new().unwrap();
+    let (out, path) = temp.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder
+        .add_raw_entry("subdir/subdir_text1.txt", file, &zeroes)
+        .unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(out).unwrap();
+    let placeholder = archive.file_raw("subdir/subdir_text1.txt").unwrap();
+    assert!(placeholder.is_unpatched);
+
+    let mut inflater = Inflater::new();
+    let contents = archive
+        .file_contents_or_fallback(
+            "subdir/subdir_text1.txt",
+            placeholder,
+            &[source][..],
+            &mut inflater,
+        )
+        .unwrap();
+    assert_eq!(contents.as_deref(), Some(&b"this is subdir text1\n"[..]));
+}
+
+#[test]
+fn journal_cache() {
+    let source = Archive::open_heap("tests/data/Test.wad").unwrap();
+    let file = source.file_raw("subdir/subdir_text1.txt").unwrap();
+    let zeroes = vec![0; file.size()];
+
+    let temp = NamedTempFile::new().unwrap();
+    let mut builder = ArchiveBuilder::new(2, 0, temp.path()).unwrap();
+    builder
+        .add_raw_entry("subdir/subdir_text1.txt", file, &zeroes)
+        .unwrap();
+    builder.add_file_compressed("text.txt", b"cached").unwrap();
+    builder.finish().unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut options = OpenOptions::new();
+    options
+        .verify_crcs(false)
+        .journal_cache(Some(JournalCache::new(dir.path())));
+
+    let first = options.open_heap(temp.path()).unwrap();
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    assert!(
+        !first
+            .file_raw("subdir/subdir_text1.txt")
+            .unwrap()
+            .is_unpatched
+    );
+
+    // The second open is served from the cache, but still verifies
+    // CRCs and finds the unpatched file.
+    let second = options.verify_crcs(true).open_mmap(temp.path()).unwrap();
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    assert_eq!(second.len(), first.len());
+    assert_eq!(second.header().version, 2);
+    assert!(
+        second
+            .file_raw("subdir/subdir_text1.txt")
+            .unwrap()
+            .is_unpatched
+    );
+
+    let mut inflater = Inflater::new();
+    let text = second.file_raw("text.txt").unwrap();
+    let data = inflater
+        .decompress(
+            second.file_contents(text).unwrap(),
+            text.uncompressed_size as _,
+        )
+        .unwrap();
+    assert_eq!(data, b"cached");
+}
@@ -0,0 +1,28 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use katsuba_wad::{Archive, ArchiveBuilder};
+
+/// Builds an archive at `path` from compressed `files` and opens it.
+pub fn build(path: &Path, files: &[(&str, &[u8])]) -> Archive {
+    build_with(path, files, true)
+}
+
+/// Builds an archive at `path` from `files` and opens it.
+///
+/// Files are stored uncompressed unless `compressed` is set.
+pub fn build_with(path: &Path, files: &[(&str, &[u8])], compressed: bool) -> Archive {
+    let mut builder = ArchiveBuilder::new(2, 0, path).unwrap();
+    for (name, contents) in files {
+        match compressed {
+            true => builder.add_file_compressed(name, contents).unwrap(),
+            false => builder.add_file(name, contents).unwrap(),
+        }
+    }
+    builder.finish().unwrap();
+
+    Archive::open_heap(path).unwrap()
+}
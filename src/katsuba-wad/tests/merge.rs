@@ -0,0 +1,73 @@
+use katsuba_wad::{
+    merge::{ConflictPolicy, MergeError},
+    Archive, ArchiveBuilder,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::*;
+
+fn layers(dir: &TempDir) -> [Archive; 2] {
+    [
+        build(
+            &dir.path().join("Root.wad"),
+            &[("a.txt", &b"old a"[..]), ("b.txt", &b"only in root"[..])],
+        ),
+        build(
+            &dir.path().join("Patch.wad"),
+            &[("a.txt", &b"new a"[..]), ("c.txt", &b"only in patch"[..])],
+        ),
+    ]
+}
+
+fn merge(
+    dir: &TempDir,
+    archives: &[Archive],
+    policy: ConflictPolicy,
+) -> Result<Archive, MergeError> {
+    let out = dir.path().join("Merged.wad");
+
+    let mut builder = ArchiveBuilder::new(2, 0, &out).unwrap();
+    builder.merge(archives, policy)?;
+    builder.finish().unwrap();
+
+    Ok(Archive::open_heap(out).unwrap())
+}
+
+#[test]
+fn take_newest() {
+    let dir = TempDir::new().unwrap();
+    let archives = layers(&dir);
+    let merged = merge(&dir, &archives, ConflictPolicy::TakeNewest).unwrap();
+
+    assert_eq!(merged.len(), 3);
+
+    let a = merged.file_raw("a.txt").unwrap();
+    let expected = archives[1].file_raw("a.txt").unwrap();
+    assert_eq!(a.crc, expected.crc);
+    assert_eq!(merged.file_contents(a), archives[1].file_contents(expected));
+}
+
+#[test]
+fn take_first() {
+    let dir = TempDir::new().unwrap();
+    let archives = layers(&dir);
+    let merged = merge(&dir, &archives, ConflictPolicy::TakeFirst).unwrap();
+
+    assert_eq!(merged.len(), 3);
+
+    let a = merged.file_raw("a.txt").unwrap();
+    let expected = archives[0].file_raw("a.txt").unwrap();
+    assert_eq!(merged.file_contents(a), archives[0].file_contents(expected));
+}
+
+#[test]
+fn error_on_conflict() {
+    let dir = TempDir::new().unwrap();
+    let archives = layers(&dir);
+
+    match merge(&dir, &archives, ConflictPolicy::Error) {
+        Err(MergeError::Conflict(name)) => assert_eq!(name, "a.txt"),
+        _ => panic!("expected a conflict on 'a.txt'"),
+    }
+}
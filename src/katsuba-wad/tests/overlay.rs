@@ -0,0 +1,37 @@
+use katsuba_wad::OverlayArchive;
+use tempfile::TempDir;
+
+mod common;
+use common::*;
+
+#[test]
+fn layered_lookup() {
+    let dir = TempDir::new().unwrap();
+    let overlay = OverlayArchive::new(vec![
+        build_with(
+            &dir.path().join("Root.wad"),
+            &[("a.txt", &b"root a"[..]), ("b.txt", &b"root b"[..])],
+            false,
+        ),
+        build_with(
+            &dir.path().join("Patch.wad"),
+            &[("a.txt", &b"patch a"[..]), ("c/d.txt", &b"patch d"[..])],
+            false,
+        ),
+    ]);
+
+    assert_eq!(overlay.len(), 3);
+    assert_eq!(overlay.file_contents("a.txt"), Some(&b"patch a"[..]));
+    assert_eq!(overlay.file_contents("b.txt"), Some(&b"root b"[..]));
+    assert_eq!(overlay.file_contents("missing.txt"), None);
+
+    let names: Vec<_> = overlay.files().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["a.txt", "b.txt", "c/d.txt"]);
+
+    let globbed: Vec<_> = overlay
+        .iter_glob("c/*")
+        .unwrap()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(globbed, ["c/d.txt"]);
+}
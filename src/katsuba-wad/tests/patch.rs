@@ -0,0 +1,107 @@
+use katsuba_wad::{
+    patch::{self, PatchError},
+    Archive, ArchiveBuilder, Inflater,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::*;
+
+fn read(archive: &Archive, name: &str) -> Option<Vec<u8>> {
+    let file = archive.file_raw(name)?;
+    let data = archive.file_contents(file)?;
+
+    Some(if file.compressed {
+        Inflater::new()
+            .decompress(data, file.uncompressed_size as _)
+            .unwrap()
+            .to_vec()
+    } else {
+        data.to_vec()
+    })
+}
+
+#[test]
+fn create_and_apply() {
+    let dir = TempDir::new().unwrap();
+
+    let old_data: Vec<u8> = (0..64 * 1024).map(|i| (i * 7 % 251) as u8).collect();
+    let mut new_data = old_data.clone();
+    new_data[1000..1010].copy_from_slice(b"patched!!!");
+    new_data.extend_from_slice(b"appended data");
+
+    let old = build(
+        &dir.path().join("Old.wad"),
+        &[
+            ("big.bin", &old_data[..]),
+            ("same.txt", &b"unchanged"[..]),
+            ("removed.txt", &b"gone"[..]),
+        ],
+    );
+    let new = build(
+        &dir.path().join("New.wad"),
+        &[
+            ("big.bin", &new_data[..]),
+            ("same.txt", &b"unchanged"[..]),
+            ("added.txt", &b"fresh"[..]),
+        ],
+    );
+
+    let data = patch::create(&old, &new).unwrap();
+    assert!(data.len() < new_data.len() / 4);
+
+    let out = dir.path().join("Patched.wad");
+    patch::apply(&old, &data, &out).unwrap();
+    let patched = Archive::open_heap(&out).unwrap();
+
+    assert_eq!(patched.len(), 3);
+    assert_eq!(read(&patched, "big.bin"), Some(new_data));
+    assert_eq!(
+        read(&patched, "same.txt").as_deref(),
+        Some(&b"unchanged"[..])
+    );
+    assert_eq!(read(&patched, "added.txt").as_deref(), Some(&b"fresh"[..]));
+    assert_eq!(read(&patched, "removed.txt"), None);
+}
+
+#[test]
+fn unpatched_files() {
+    let dir = TempDir::new().unwrap();
+
+    let source = Archive::open_heap("tests/data/Test.wad").unwrap();
+    let file = source.file_raw("subdir/subdir_text1.txt").unwrap();
+    let zeroes = vec![0; file.size()];
+
+    let old = build(&dir.path().join("Old.wad"), &[("a.txt", &b"a"[..])]);
+
+    let new_path = dir.path().join("New.wad");
+    let mut builder = ArchiveBuilder::new(2, 0, &new_path).unwrap();
+    builder
+        .add_raw_entry("subdir/subdir_text1.txt", file, &zeroes)
+        .unwrap();
+    builder.finish().unwrap();
+    let new = Archive::open_heap(&new_path).unwrap();
+
+    let data = patch::create(&old, &new).unwrap();
+    let out = dir.path().join("Patched.wad");
+    patch::apply(&old, &data, &out).unwrap();
+
+    let patched = Archive::open_heap(&out).unwrap();
+    let file = patched.file_raw("subdir/subdir_text1.txt").unwrap();
+    assert!(file.is_unpatched);
+}
+
+#[test]
+fn oversized_body() {
+    let dir = TempDir::new().unwrap();
+
+    let old = build(&dir.path().join("Old.wad"), &[("a.txt", &b"a"[..])]);
+    let new = build(&dir.path().join("New.wad"), &[("a.txt", &b"b"[..])]);
+    let mut data = patch::create(&old, &new).unwrap();
+
+    // Claim a body far larger than the payload could inflate to.
+    data[14..22].copy_from_slice(&(1_u64 << 40).to_le_bytes());
+
+    let res = patch::apply(&old, &data, dir.path().join("Patched.wad"));
+    assert!(matches!(res, Err(PatchError::Decompress(..))));
+}
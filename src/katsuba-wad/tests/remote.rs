@@ -0,0 +1,122 @@
+#![cfg(feature = "http")]
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use katsuba_wad::{vfs::ArchiveFs, Archive, Inflater, RemoteArchive};
+
+const TEST_WAD: &str = "tests/data/Test.wad";
+
+// Reads the requested byte range from the headers of a request.
+fn requested_range(stream: &TcpStream) -> Option<(usize, usize)> {
+    let mut range = None;
+    for line in BufReader::new(stream).lines() {
+        let line = line.ok()?;
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("range") {
+                let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+                range = Some((start.parse().ok()?, end.parse().ok()?));
+            }
+        }
+    }
+
+    range
+}
+
+// Serves `TEST_WAD` from a local HTTP server and returns its URL.
+//
+// Without `ranges`, the server ignores range requests and replies
+// with the whole file, like servers without range support do.
+fn serve(ranges: bool) -> String {
+    let data = fs::read(TEST_WAD).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/Test.wad", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+
+            let (status, body) = match requested_range(&stream).filter(|_| ranges) {
+                Some((start, end)) => {
+                    let end = data.len().min(end + 1);
+                    ("206 Partial Content", &data[start.min(end)..end])
+                }
+                None => ("200 OK", &data[..]),
+            };
+
+            // Clients may hang up early when they got what they need.
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .and_then(|()| stream.write_all(body));
+        }
+    });
+
+    url
+}
+
+fn assert_matches_local(remote: &RemoteArchive) {
+    let local = Archive::open_heap(TEST_WAD).unwrap();
+    assert!(remote.files().keys().eq(local.files().keys()));
+
+    let mut inflater = Inflater::new();
+    for (name, file) in local.files() {
+        let Some(contents) = local.file_contents(file) else {
+            continue;
+        };
+        let expected = if file.compressed {
+            inflater
+                .decompress(contents, file.uncompressed_size as _)
+                .unwrap()
+                .to_vec()
+        } else {
+            contents.to_vec()
+        };
+
+        let actual = remote.fetch(file, &mut inflater).unwrap();
+        assert_eq!(actual.as_deref(), Some(&expected[..]), "{name}");
+    }
+}
+
+#[test]
+fn range_requests() {
+    let remote = RemoteArchive::open_http(serve(true)).unwrap();
+    assert_matches_local(&remote);
+}
+
+#[test]
+fn without_range_support() {
+    let remote = RemoteArchive::open_http(serve(false)).unwrap();
+    assert_matches_local(&remote);
+}
+
+#[test]
+fn archive_fs() {
+    let remote = RemoteArchive::open_http(serve(true)).unwrap();
+
+    let data = remote.open("subdir/subdir_text1.txt").unwrap();
+    assert_eq!(&data[..], b"this is subdir text1\n");
+    assert_eq!(
+        remote.metadata("subdir/subdir_text1.txt").unwrap().size,
+        data.len() as u64
+    );
+
+    let local = Archive::open_heap(TEST_WAD).unwrap();
+    assert_eq!(
+        remote.read_dir("subdir").unwrap(),
+        local.read_dir("subdir").unwrap()
+    );
+    assert!(remote.open("missing.txt").is_err());
+}
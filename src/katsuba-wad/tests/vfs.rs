@@ -0,0 +1,70 @@
+use std::fs;
+
+use katsuba_wad::{
+    vfs::{ArchiveFs, DirectoryFs, EntryKind},
+    Archive, ArchiveBuilder,
+};
+use tempfile::TempDir;
+
+const FILES: &[(&str, &[u8])] = &[
+    ("a.txt", b"hello"),
+    ("data/b.txt", b"compressed contents"),
+    ("data/nested/c.txt", b"nested"),
+];
+
+fn check(fs: &dyn ArchiveFs) {
+    let root: Vec<_> = fs
+        .read_dir("")
+        .unwrap()
+        .into_iter()
+        .map(|e| (e.name, e.kind))
+        .collect();
+    assert_eq!(
+        root,
+        [
+            ("a.txt".to_owned(), EntryKind::File),
+            ("data".to_owned(), EntryKind::Dir)
+        ]
+    );
+
+    let data: Vec<_> = fs
+        .read_dir("data")
+        .unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    assert_eq!(data, ["b.txt", "nested"]);
+
+    for (name, contents) in FILES {
+        assert_eq!(&*fs.open(name).unwrap(), *contents);
+
+        let meta = fs.metadata(name).unwrap();
+        assert_eq!(meta.kind, EntryKind::File);
+        assert_eq!(meta.size, contents.len() as u64);
+    }
+
+    assert_eq!(fs.metadata("data/nested").unwrap().kind, EntryKind::Dir);
+    assert!(!fs.exists("missing.txt"));
+    assert!(fs.read_dir("missing").is_err());
+}
+
+#[test]
+fn archive_and_directory() {
+    let dir = TempDir::new().unwrap();
+
+    let path = dir.path().join("Test.wad");
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    for (name, contents) in FILES {
+        builder.add_file_compressed(name, contents).unwrap();
+    }
+    builder.finish().unwrap();
+    check(&Archive::open_heap(&path).unwrap());
+
+    let root = dir.path().join("Test");
+    for (name, contents) in FILES {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    check(&DirectoryFs::new(root));
+}
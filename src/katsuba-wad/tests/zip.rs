@@ -0,0 +1,87 @@
+use std::io::Cursor;
+
+use katsuba_wad::{zip, Archive, ArchiveBuilder, Inflater};
+use tempfile::NamedTempFile;
+
+fn u16_at(buf: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes(buf[at..at + 2].try_into().unwrap())
+}
+
+fn u32_at(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+#[test]
+fn archive_to_zip() {
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder
+        .add_file_compressed("a/b.txt", b"compressed compressed compressed")
+        .unwrap();
+    builder.add_file("c.txt", b"stored").unwrap();
+    builder.finish().unwrap();
+    let archive = Archive::heap(file).unwrap();
+
+    for passthrough in [false, true] {
+        let out = zip::archive_to_zip(&archive, Cursor::new(Vec::new()), passthrough)
+            .unwrap()
+            .into_inner();
+
+        // The first entry is sorted first in the archive.
+        assert_eq!(u32_at(&out, 0), 0x04034b50);
+        assert_eq!(u16_at(&out, 8), 8);
+        assert_eq!(
+            u32_at(&out, 14),
+            crc32fast::hash(b"compressed compressed compressed")
+        );
+        assert_eq!(&out[30..37], b"a/b.txt");
+
+        // The end of central directory record lists both entries.
+        let eocd = out.len() - 22;
+        assert_eq!(u32_at(&out, eocd), 0x06054b50);
+        assert_eq!(u16_at(&out, eocd + 10), 2);
+    }
+}
+
+#[test]
+fn zip_round_trip() {
+    let source = NamedTempFile::new().unwrap();
+    let (file, path) = source.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder
+        .add_file_compressed("a/b.txt", b"compressed compressed compressed")
+        .unwrap();
+    builder.add_file("c.mp3", b"stored").unwrap();
+    builder.finish().unwrap();
+    let archive = Archive::heap(file).unwrap();
+
+    let buf = zip::archive_to_zip(&archive, Cursor::new(Vec::new()), true)
+        .unwrap()
+        .into_inner();
+    let zip = zip::ZipReader::new(&buf).unwrap();
+    assert_eq!(zip.entries().len(), 2);
+
+    let dest = NamedTempFile::new().unwrap();
+    let (file, path) = dest.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    zip::zips_to_archive(&[zip], &mut builder).unwrap();
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(file).unwrap();
+    let mut inflater = Inflater::new();
+
+    let a = archive.file_raw("a/b.txt").unwrap();
+    assert!(a.compressed);
+    assert_eq!(
+        inflater.decompress(archive.file_contents(a).unwrap(), a.uncompressed_size as _),
+        Ok(&b"compressed compressed compressed"[..])
+    );
+
+    let c = archive.file_raw("c.mp3").unwrap();
+    assert!(!c.compressed);
+    assert_eq!(archive.file_contents(c), Some(&b"stored"[..]));
+}
@@ -12,6 +12,11 @@ pub use io::*;
 mod processor;
 pub use processor::*;
 
+mod select;
+pub use select::*;
+
+pub mod summary;
+
 /// The CLI interface for the Katsuba application.
 #[derive(Debug, Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -23,6 +28,11 @@ pub struct Cli {
 
     #[clap(flatten)]
     pub verbosity: args::Verbosity,
+
+    /// Prints a summary footer with elapsed time, processed inputs,
+    /// written outputs and the number of warnings.
+    #[clap(long, global = true)]
+    pub summary: bool,
 }
 
 /// The top-level commands supported by Katsuba.
@@ -30,11 +40,13 @@ pub struct Cli {
 pub enum KatsubaCommand {
     Bcd(bcd::Bcd),
     Cs(cs::ClientSig),
+    Doctor(doctor::Doctor),
     Hash(hash::Hash),
     Nav(nav::Nav),
     Op(op::ObjectProperty),
     Poi(poi::Poi),
     Wad(wad::Wad),
+    World(world::World),
 }
 
 impl Command for KatsubaCommand {
@@ -42,11 +54,13 @@ impl Command for KatsubaCommand {
         match self {
             Self::Bcd(bcd) => bcd.handle(),
             Self::Cs(cs) => cs.handle(),
+            Self::Doctor(doctor) => doctor.handle(),
             Self::Hash(hash) => hash.handle(),
             Self::Nav(nav) => nav.handle(),
             Self::Op(op) => op.handle(),
             Self::Poi(poi) => poi.handle(),
             Self::Wad(wad) => wad.handle(),
+            Self::World(world) => world.handle(),
         }
     }
 }
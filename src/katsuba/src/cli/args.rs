@@ -1,4 +1,7 @@
 use clap::{ArgAction, Args};
+use simple_logger::SimpleLogger;
+
+use super::summary::CountingLogger;
 
 /// Configures the verbosity of the builtin logger.
 #[derive(Clone, Copy, Debug, Args)]
@@ -12,9 +15,19 @@ pub struct Verbosity {
 
 impl Verbosity {
     /// Configures the global logger based on the settings.
-    pub fn setup(self) {
+    ///
+    /// With `count_warnings`, warnings are counted for the summary
+    /// even when they are not printed.
+    pub fn setup(self, count_warnings: bool) {
         let level = self.log_level();
-        simple_logger::init_with_level(level).unwrap();
+        if !count_warnings {
+            simple_logger::init_with_level(level).unwrap();
+            return;
+        }
+
+        let inner = SimpleLogger::new().with_level(level.to_level_filter());
+        log::set_boxed_logger(Box::new(CountingLogger { inner })).unwrap();
+        log::set_max_level(level.max(log::Level::Warn).to_level_filter());
     }
 
     fn log_level(self) -> log::Level {
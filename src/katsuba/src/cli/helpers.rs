@@ -1,27 +1,25 @@
-use std::path::PathBuf;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
 
-use katsuba_executor::Executor;
+use katsuba_executor::{Buffer, Executor, Task};
+use katsuba_types::TypeList;
 
-use super::OutputSource;
+use super::{OutputSource, Selection};
 use crate::utils;
 
-/// Helper function to be used with [`Executor::write_with`] for mapping
-/// any serializable `T` value to an output source.
-pub fn write_as_json<T: serde::Serialize>(
-    ex: &Executor,
-    inpath: Option<PathBuf>,
-    value: T,
-    out: OutputSource,
-) -> eyre::Result<()> {
+// Resolves the file path an output should be written to, if any.
+fn output_path(inpath: Option<PathBuf>, out: OutputSource) -> eyre::Result<Option<PathBuf>> {
     match (out, inpath) {
-        (OutputSource::Stdout, _) => utils::serialize_to_output_source(ex, None, &value),
-        (OutputSource::File(path), _) => utils::serialize_to_output_source(ex, Some(path), &value),
+        (OutputSource::Stdout, _) => Ok(None),
+        (OutputSource::File(path), _) => Ok(Some(path)),
         (OutputSource::Dir(mut out, suffix), Some(path)) => {
             // Create a file named after the input in the output directory.
             let infile = path.with_extension(suffix);
             out.push(infile.file_name().unwrap());
 
-            utils::serialize_to_output_source(ex, Some(out), &value)
+            Ok(Some(out))
         }
 
         (OutputSource::Dir(..), None) => Err(eyre::eyre!(
@@ -29,3 +27,58 @@ pub fn write_as_json<T: serde::Serialize>(
         )),
     }
 }
+
+/// Helper function to be used with [`Executor::write_with`] for mapping
+/// any serializable `T` value to an output source.
+pub fn write_as_json<T: serde::Serialize>(
+    ex: &Executor,
+    inpath: Option<PathBuf>,
+    value: T,
+    out: OutputSource,
+) -> eyre::Result<()> {
+    let out = output_path(inpath, out)?;
+    utils::serialize_to_output_source(ex, out, &value)
+}
+
+/// Like [`write_as_json`], but prunes values according to `selection`
+/// before they are written.
+pub fn write_selected_as_json<T: serde::Serialize>(
+    selection: Selection,
+) -> impl FnMut(&Executor, Option<PathBuf>, T, OutputSource) -> eyre::Result<()> {
+    move |ex, inpath, value, out| {
+        if selection.is_empty() {
+            return write_as_json(ex, inpath, value, out);
+        }
+
+        let value = selection.apply(serde_json::to_value(value)?)?;
+        write_as_json(ex, inpath, value, out)
+    }
+}
+
+/// Helper function to be used with [`Executor::write_with`] for writing
+/// raw binary data to an output source.
+pub fn write_bytes(
+    ex: &Executor,
+    inpath: Option<PathBuf>,
+    data: Vec<u8>,
+    out: OutputSource,
+) -> eyre::Result<()> {
+    match output_path(inpath, out)? {
+        Some(path) => {
+            let task = Task::create_file(path, Buffer::owned(data), 0o666);
+            for pending in ex.dispatch(task) {
+                pending?;
+            }
+        }
+
+        None => io::stdout().lock().write_all(&data)?,
+    }
+
+    Ok(())
+}
+
+/// Reads all the given type list paths and merges them into a single
+/// [`TypeList`] instance.
+pub fn merge_type_lists(paths: Vec<PathBuf>) -> eyre::Result<TypeList> {
+    katsuba_pipeline::merge_type_lists(&paths).map_err(Into::into)
+}
@@ -70,6 +70,12 @@ impl InputsOutputs {
         Ok((inputs, outputs))
     }
 
+    /// Gets the raw input string and output path without evaluating
+    /// them, for commands which read their inputs from elsewhere.
+    pub fn into_raw(self) -> (String, PathBuf) {
+        (self.input, self.output)
+    }
+
     fn input_source(&self) -> eyre::Result<InputSource> {
         // First, check for a hyphen which indicates read from stdin.
         if self.input == HYPHEN {
@@ -8,7 +8,7 @@ use eyre::Context;
 use katsuba_executor::{Buffer, Executor};
 
 use self::sealed::Missing;
-use super::{InputSource, OutputSource};
+use super::{summary, InputSource, OutputSource};
 use crate::utils;
 
 mod sealed {
@@ -22,6 +22,14 @@ pub enum Reader<'a> {
 }
 
 impl Reader<'_> {
+    /// Gets a human-readable name for the input source.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Stdin(..) => "<stdin>".to_owned(),
+            Self::File(path, _) => path.display().to_string(),
+        }
+    }
+
     /// Gets the data in the reader as a [`Buffer`], if possible.
     pub fn get_buffer(&mut self, ex: &Executor) -> eyre::Result<Buffer<'_>> {
         match self {
@@ -92,6 +100,7 @@ pub enum Bias {
 /// Processes input sources and maps them to output sources.
 pub struct Processor<R, W> {
     bias: Bias,
+    memory_budget: Option<usize>,
     reader_fn: R,
     writer_fn: W,
 }
@@ -105,11 +114,21 @@ impl Processor<Missing, Missing> {
     pub fn new(bias: Bias) -> eyre::Result<Self> {
         Ok(Self {
             bias,
+            memory_budget: None,
             reader_fn: Missing,
             writer_fn: Missing,
         })
     }
 
+    /// Limits the memory held by pending I/O tasks to `budget` bytes.
+    ///
+    /// See [`Executor::set_memory_budget`] for details.
+    #[inline]
+    pub fn with_memory_budget(mut self, budget: Option<usize>) -> Self {
+        self.memory_budget = budget;
+        self
+    }
+
     /// Configures a callback for reading an input source into an arbitrary
     /// type for further processing.
     #[inline]
@@ -119,6 +138,7 @@ impl Processor<Missing, Missing> {
     {
         Processor {
             bias: self.bias,
+            memory_budget: self.memory_budget,
             reader_fn: f,
             writer_fn: Missing,
         }
@@ -136,6 +156,7 @@ where
     {
         Processor {
             bias: self.bias,
+            memory_budget: self.memory_budget,
             reader_fn: self.reader_fn,
             writer_fn: f,
         }
@@ -152,6 +173,7 @@ where
 
         let mut buf = io::Cursor::new(Vec::new());
         stdin.read_to_end(buf.get_mut())?;
+        summary::record_input(buf.get_ref().len() as u64);
 
         Ok(Reader::Stdin(buf))
     }
@@ -159,6 +181,7 @@ where
     fn file<'a>(&self, path: &'a Path) -> eyre::Result<Reader<'a>> {
         let file = fs::File::open(path)
             .with_context(|| format!("failed to open file '{}'", path.display()))?;
+        summary::record_input(file.metadata().map(|m| m.len()).unwrap_or(0));
 
         Ok(Reader::File(path, io::BufReader::new(file)))
     }
@@ -172,6 +195,7 @@ where
             Bias::Current => Executor::current(),
             Bias::Threaded => Executor::get()?,
         };
+        executor.set_memory_budget(self.memory_budget);
 
         match (input, output) {
             (InputSource::Stdin, out) => {
@@ -192,6 +216,7 @@ where
                 // When processing multiple input files, we ignore the bias.
                 if let Bias::Current = self.bias {
                     executor = Executor::get()?;
+                    executor.set_memory_budget(self.memory_budget);
                 }
 
                 // Create the specified out directory if it doesn't exist.
@@ -0,0 +1,108 @@
+use clap::Args;
+use serde_json::Value;
+
+/// Options for pruning deserialized values before they are written.
+///
+/// Paths use JSON Pointer syntax, e.g. `/collisions/0/geometry`
+/// selects the geometry of the first collision shape. Paths not
+/// starting with `/` use dotted syntax instead, where the same path
+/// is written as `collisions[0].geometry`.
+#[derive(Clone, Debug, Default, Args)]
+pub struct Selection {
+    /// Only writes the subtree at this path.
+    ///
+    /// It is an error when the path does not exist in an output.
+    #[clap(long)]
+    select: Option<String>,
+
+    /// Removes the subtree at this path from the output.
+    ///
+    /// May be given multiple times. Paths are resolved before
+    /// `--select` is applied, and missing paths are ignored.
+    #[clap(long)]
+    exclude: Vec<String>,
+}
+
+// Converts a dotted path to a JSON Pointer, if it isn't one already.
+fn to_pointer(path: &str) -> String {
+    if path.is_empty() || path.starts_with('/') {
+        return path.to_owned();
+    }
+
+    let mut out = String::with_capacity(path.len() + 1);
+    for part in path.split('.') {
+        for segment in part.split('[') {
+            let segment = segment.strip_suffix(']').unwrap_or(segment);
+            if !segment.is_empty() {
+                out.push('/');
+                out.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+            }
+        }
+    }
+
+    out
+}
+
+// Removes the value at `path` from `value`, if it exists.
+fn remove(value: &mut Value, path: &str) {
+    let Some((parent, key)) = path.rsplit_once('/') else {
+        return;
+    };
+    let key = key.replace("~1", "/").replace("~0", "~");
+
+    match value.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.remove(&key);
+        }
+        Some(Value::Array(vec)) => {
+            if let Ok(idx) = key.parse::<usize>() {
+                if idx < vec.len() {
+                    vec.remove(idx);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Selection {
+    /// Whether no pruning is requested.
+    pub fn is_empty(&self) -> bool {
+        self.select.is_none() && self.exclude.is_empty()
+    }
+
+    /// Gets the `--select` path in dotted syntax when it is the only
+    /// pruning requested.
+    ///
+    /// Such paths can be resolved on values directly before they are
+    /// converted to JSON.
+    pub fn value_path(&self) -> Option<&str> {
+        self.select
+            .as_deref()
+            .filter(|p| self.exclude.is_empty() && !p.is_empty() && !p.starts_with('/'))
+    }
+
+    /// Applies the selection to `value`.
+    pub fn apply(&self, mut value: Value) -> eyre::Result<Value> {
+        // Remove later elements first, so that excluding multiple
+        // indices of the same array does not shift the others.
+        let segments = |path: &str| {
+            path.split('/')
+                .map(|s| (s.parse::<usize>().ok(), s.to_owned()))
+                .collect::<Vec<_>>()
+        };
+        let mut exclude: Vec<_> = self.exclude.iter().map(|p| to_pointer(p)).collect();
+        exclude.sort_by_cached_key(|path| std::cmp::Reverse(segments(path)));
+        for path in exclude {
+            remove(&mut value, &path);
+        }
+
+        match &self.select {
+            Some(path) => value
+                .pointer_mut(&to_pointer(path))
+                .map(Value::take)
+                .ok_or_else(|| eyre::eyre!("selected path '{path}' does not exist")),
+            None => Ok(value),
+        }
+    }
+}
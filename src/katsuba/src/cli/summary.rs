@@ -0,0 +1,82 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use katsuba_executor::Metrics;
+use log::{Level, Log, Metadata, Record};
+
+static INPUTS_READ: AtomicU64 = AtomicU64::new(0);
+static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+static WARNINGS: AtomicU64 = AtomicU64::new(0);
+
+/// Accounts for an input source of `size` bytes being read.
+pub fn record_input(size: u64) {
+    INPUTS_READ.fetch_add(1, Ordering::Relaxed);
+    BYTES_READ.fetch_add(size, Ordering::Relaxed);
+}
+
+/// A logger which counts emitted warnings before handing records
+/// off to the `inner` logger.
+pub struct CountingLogger<L> {
+    pub inner: L,
+}
+
+impl<L: Log> Log for CountingLogger<L> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= Level::Warn || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if record.level() == Level::Warn {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{value:.2} {}", UNITS[unit]),
+    }
+}
+
+/// Prints a summary of the work done by the process to stderr.
+pub fn print(elapsed: Duration) {
+    let metrics = Metrics::snapshot();
+
+    eprintln!("Summary:");
+    eprintln!("  Elapsed: {:.3}s", elapsed.as_secs_f64());
+    eprintln!(
+        "  Inputs read: {} ({})",
+        INPUTS_READ.load(Ordering::Relaxed),
+        human_bytes(BYTES_READ.load(Ordering::Relaxed))
+    );
+    eprintln!(
+        "  Files written: {} ({})",
+        metrics.files_written,
+        human_bytes(metrics.bytes_written)
+    );
+    eprintln!("  Directories created: {}", metrics.dirs_created);
+    if metrics.tasks_failed != 0 {
+        eprintln!("  Failed writes: {}", metrics.tasks_failed);
+    }
+    eprintln!("  Warnings: {}", WARNINGS.load(Ordering::Relaxed));
+}
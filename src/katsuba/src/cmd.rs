@@ -1,10 +1,12 @@
 pub mod bcd;
 pub mod cs;
+pub mod doctor;
 pub mod hash;
 pub mod nav;
 pub mod op;
 pub mod poi;
 pub mod wad;
+pub mod world;
 
 /// Represents a command in the Katsuba application.
 pub trait Command {
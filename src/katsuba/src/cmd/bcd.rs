@@ -1,8 +1,10 @@
+use std::{fs, io, path::PathBuf};
+
 use clap::{Args, Subcommand};
 use katsuba_bcd::Bcd as BcdFile;
 
 use super::Command;
-use crate::cli::{helpers, Bias, InputsOutputs, Processor};
+use crate::cli::{helpers, Bias, InputsOutputs, Processor, Selection};
 
 /// Subcommand for working with BCD data.
 #[derive(Debug, Args)]
@@ -14,19 +16,78 @@ pub struct Bcd {
 #[derive(Debug, Subcommand)]
 enum BcdCommand {
     /// Deserializes given Binary Collision Data files into JSON format.
-    De(InputsOutputs),
+    De {
+        #[clap(flatten)]
+        args: InputsOutputs,
+
+        #[clap(flatten)]
+        selection: Selection,
+    },
+
+    /// Serializes JSON files produced by the `de` command back into
+    /// binary Collision Data files.
+    Ser {
+        #[clap(flatten)]
+        args: InputsOutputs,
+
+        /// Orthonormalizes shape rotations and recomputes mesh normals
+        /// before writing.
+        ///
+        /// Recommended for imported or hand-edited geometry.
+        #[clap(long)]
+        fix: bool,
+    },
+
+    /// Emits a JSON Schema describing the output of `bcd de`.
+    ///
+    /// The schema documents units, axis conventions and the meaning
+    /// of collision flags for consumers of the JSON data.
+    Schema {
+        /// The optional file to write the schema to.
+        ///
+        /// If missing, the schema will be printed to stdout.
+        #[clap(short)]
+        output: Option<PathBuf>,
+    },
 }
 
 impl Command for Bcd {
     fn handle(self) -> eyre::Result<()> {
         match self.command {
-            BcdCommand::De(args) => {
+            BcdCommand::De { args, selection } => {
                 let (inputs, outputs) = args.evaluate("de.json")?;
                 Processor::new(Bias::Current)?
                     .read_with(|r, _| BcdFile::parse(r).map_err(Into::into))
-                    .write_with(helpers::write_as_json)
+                    .write_with(helpers::write_selected_as_json(selection))
                     .process(inputs, outputs)
             }
+
+            BcdCommand::Ser { args, fix } => {
+                let (inputs, outputs) = args.evaluate("ser.bcd")?;
+                Processor::new(Bias::Current)?
+                    .read_with(move |r, _| {
+                        let mut bcd: BcdFile = serde_json::from_reader(r)?;
+                        if fix {
+                            bcd.fix_geometry();
+                        }
+
+                        let mut out = io::Cursor::new(Vec::new());
+                        bcd.write(&mut out)?;
+                        Ok(out.into_inner())
+                    })
+                    .write_with(helpers::write_bytes)
+                    .process(inputs, outputs)
+            }
+
+            BcdCommand::Schema { output } => {
+                let schema = serde_json::to_string_pretty(&katsuba_bcd::schema())?;
+                match output {
+                    Some(path) => fs::write(path, schema)?,
+                    None => println!("{schema}"),
+                }
+
+                Ok(())
+            }
         }
     }
 }
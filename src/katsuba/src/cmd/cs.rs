@@ -1,4 +1,8 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
 
 use clap::{Args, Subcommand};
 use eyre::Context;
@@ -63,13 +67,16 @@ impl Command for ClientSig {
             }
 
             ClientSigCommand::Decrypt { path, output } => {
-                let signature = fs::read(&path)
+                let signature = fs::File::open(&path)
                     .with_context(|| format!("failed to read file '{}'", path.display()))?;
-                let decrypted_signature = private_key
-                    .decrypt_sig(&signature)
-                    .context("received invalid Client Signature file")?;
+                let mut output = BufWriter::new(fs::File::create(output)?);
 
-                fs::write(output, decrypted_signature)?;
+                // Decrypt chunk by chunk so large files don't need to be
+                // held in memory all at once.
+                let mut reader = private_key.decrypt_reader(BufReader::new(signature));
+                io::copy(&mut reader, &mut output)
+                    .context("received invalid Client Signature file")?;
+                output.flush()?;
             }
         }
 
@@ -0,0 +1,266 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ::serde::Serialize;
+use clap::Args;
+use eyre::Context;
+use katsuba_object_property::serde;
+use katsuba_types::TypeList;
+use katsuba_wad::{Archive, Inflater, OpenOptions};
+
+use super::{op::guess, Command};
+use crate::cli::helpers::merge_type_lists;
+
+/// Checks that Katsuba can work with the files of a game installation.
+///
+/// All KIWAD archives in the directory are opened and a few entries
+/// of each are sampled. When type lists are available, sampled
+/// ObjectProperty files are deserialized with a guessed serializer
+/// configuration.
+///
+/// The result is a compatibility report, which helps to quickly see
+/// whether a game patch broke anything. The command fails when any
+/// problems were found.
+#[derive(Debug, Args)]
+pub struct Doctor {
+    /// The root directory of the game installation.
+    game_dir: PathBuf,
+
+    /// Type list files to deserialize sampled files with.
+    ///
+    /// When missing, JSON files with `types` in their name are
+    /// searched for in the game directory.
+    #[clap(short, long)]
+    type_lists: Vec<PathBuf>,
+
+    /// A glob pattern for the archive entries to sample.
+    #[clap(long, default_value = "**/*.xml")]
+    glob: String,
+
+    /// The maximum number of entries to sample per archive.
+    #[clap(long, default_value_t = 4)]
+    samples: usize,
+
+    /// Prints the report as JSON.
+    #[clap(long)]
+    json: bool,
+}
+
+/// The outcome of checking a single sampled entry.
+#[derive(Serialize)]
+struct SampleReport {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The outcome of checking a single archive.
+#[derive(Serialize)]
+struct ArchiveReport {
+    path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    files: usize,
+    samples: Vec<SampleReport>,
+}
+
+impl ArchiveReport {
+    fn problems(&self) -> usize {
+        let failed = self.samples.iter().filter(|s| s.error.is_some()).count();
+        failed + self.error.is_some() as usize
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    type_lists: Vec<PathBuf>,
+    archives: Vec<ArchiveReport>,
+}
+
+// Collects files under `root` which satisfy `pred`, in path order.
+fn find_files(root: &Path, pred: impl Fn(&Path) -> bool) -> eyre::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in walkdir::WalkDir::new(root) {
+        let entry = entry.context("failed to query game directory")?;
+        if entry.file_type().is_file() && pred(entry.path()) {
+            paths.push(entry.into_path());
+        }
+    }
+
+    paths.sort();
+    Ok(paths)
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+fn is_type_list(path: &Path) -> bool {
+    has_extension(path, "json")
+        && path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().to_lowercase().contains("types"))
+}
+
+impl Doctor {
+    fn check_archive(&self, path: PathBuf, types: Option<&Arc<TypeList>>) -> ArchiveReport {
+        let mut report = ArchiveReport {
+            path,
+            error: None,
+            files: 0,
+            samples: Vec::new(),
+        };
+
+        // CRCs are only checked for the sampled entries to keep this
+        // fast on big installations.
+        let archive = match OpenOptions::new()
+            .verify_crcs(false)
+            .open_mmap(&report.path)
+        {
+            Ok(archive) => archive,
+            Err(e) => {
+                report.error = Some(e.to_string());
+                return report;
+            }
+        };
+        report.files = archive.len();
+
+        match archive.iter_glob(&self.glob) {
+            Ok(files) => {
+                let files: Vec<_> = files.filter(|(_, f)| !f.is_unpatched).collect();
+
+                // Spread the samples evenly over the matching entries.
+                let step = (files.len() / self.samples.max(1)).max(1);
+                let mut inflater = Inflater::new();
+                for (name, file) in files.into_iter().step_by(step).take(self.samples) {
+                    let error = check_entry(&archive, file, types, &mut inflater)
+                        .err()
+                        .map(|e| e.to_string());
+
+                    report.samples.push(SampleReport {
+                        name: name.clone(),
+                        error,
+                    });
+                }
+            }
+            Err(e) => report.error = Some(e.to_string()),
+        }
+
+        report
+    }
+}
+
+// Extracts an entry and deserializes it, if type lists are available.
+fn check_entry(
+    archive: &Archive,
+    file: &katsuba_wad::types::File,
+    types: Option<&Arc<TypeList>>,
+    inflater: &mut Inflater,
+) -> eyre::Result<()> {
+    let Some(contents) = archive.verified_file_contents(file)? else {
+        return Ok(());
+    };
+    let data = if file.compressed {
+        inflater.decompress(contents, file.uncompressed_size as _)?
+    } else {
+        contents
+    };
+
+    if let Some(types) = types {
+        let report = guess::try_guess(serde::SerializerOptions::default(), types.clone(), data)?;
+        report.value?;
+    }
+
+    Ok(())
+}
+
+fn write_report<W: Write>(mut writer: W, report: &Report) -> io::Result<()> {
+    if report.type_lists.is_empty() {
+        writeln!(writer, "No type lists found, skipping deserialization.")?;
+    } else {
+        writeln!(writer, "Type lists:")?;
+        for path in &report.type_lists {
+            writeln!(writer, "  {}", path.display())?;
+        }
+    }
+    writeln!(writer)?;
+
+    writeln!(writer, "Archives:")?;
+    for archive in &report.archives {
+        let path = archive.path.display();
+        if let Some(e) = &archive.error {
+            writeln!(writer, "  {path}: FAILED: {e}")?;
+            continue;
+        }
+
+        let ok = archive.samples.len() - archive.problems();
+        writeln!(
+            writer,
+            "  {path}: {} files, {ok}/{} samples ok",
+            archive.files,
+            archive.samples.len()
+        )?;
+        for sample in &archive.samples {
+            if let Some(e) = &sample.error {
+                writeln!(writer, "    {}: {e}", sample.name)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Command for Doctor {
+    fn handle(self) -> eyre::Result<()> {
+        if !self.game_dir.is_dir() {
+            eyre::bail!("'{}' is not a directory", self.game_dir.display());
+        }
+
+        let type_lists = if self.type_lists.is_empty() {
+            find_files(&self.game_dir, is_type_list)?
+        } else {
+            self.type_lists.clone()
+        };
+        let types = if type_lists.is_empty() {
+            None
+        } else {
+            Some(Arc::new(merge_type_lists(type_lists.clone())?))
+        };
+
+        let archives = find_files(&self.game_dir, |p| has_extension(p, "wad"))?
+            .into_iter()
+            .map(|path| {
+                log::info!("Checking '{}'", path.display());
+                self.check_archive(path, types.as_ref())
+            })
+            .collect();
+
+        let report = Report {
+            type_lists,
+            archives,
+        };
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        if self.json {
+            serde_json::to_writer_pretty(&mut stdout, &report)?;
+            writeln!(stdout)?;
+        } else {
+            write_report(&mut stdout, &report)?;
+        }
+
+        let problems: usize = report.archives.iter().map(ArchiveReport::problems).sum();
+        if report.archives.is_empty() {
+            eyre::bail!("no archives found in '{}'", self.game_dir.display());
+        }
+        if problems > 0 {
+            eyre::bail!("found {problems} problems");
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
+use std::io;
+
 use clap::{Args, Subcommand, ValueEnum};
 use katsuba_nav::{NavigationGraph, ZoneNavigationGraph};
 
 use super::Command;
-use crate::cli::{helpers, Bias, InputsOutputs, Processor};
+use crate::cli::{helpers, Bias, InputsOutputs, Processor, Selection};
 
 /// Subcommand for working with NAV data.
 #[derive(Debug, Args)]
@@ -27,25 +29,69 @@ enum FileType {
 #[derive(Debug, Subcommand)]
 enum NavCommand {
     /// Deserializes given Navigation Graph files into JSON format.
-    De(InputsOutputs),
+    De {
+        #[clap(flatten)]
+        args: InputsOutputs,
+
+        #[clap(flatten)]
+        selection: Selection,
+    },
+
+    /// Serializes JSON files produced by the `de` command back into
+    /// binary Navigation Graph files.
+    ///
+    /// Node identifiers and link references are validated before
+    /// anything is written.
+    Ser(InputsOutputs),
 }
 
 impl Command for Nav {
     fn handle(self) -> eyre::Result<()> {
         match self.command {
-            NavCommand::De(args) => {
+            NavCommand::De { args, selection } => {
                 let (inputs, outputs) = args.evaluate("de.json")?;
                 let processor = Processor::new(Bias::Current)?;
 
                 match self.file_type {
                     FileType::Nav => processor
                         .read_with(|r, _| NavigationGraph::parse(r).map_err(Into::into))
-                        .write_with(helpers::write_as_json)
+                        .write_with(helpers::write_selected_as_json(selection))
                         .process(inputs, outputs),
 
                     FileType::ZoneNav => processor
                         .read_with(|r, _| ZoneNavigationGraph::parse(r).map_err(Into::into))
-                        .write_with(helpers::write_as_json)
+                        .write_with(helpers::write_selected_as_json(selection))
+                        .process(inputs, outputs),
+                }
+            }
+
+            NavCommand::Ser(args) => {
+                let (inputs, outputs) = args.evaluate("ser.nav")?;
+                let processor = Processor::new(Bias::Current)?;
+
+                match self.file_type {
+                    FileType::Nav => processor
+                        .read_with(|r, _| {
+                            let graph: NavigationGraph = serde_json::from_reader(r)?;
+                            graph.validate()?;
+
+                            let mut out = io::Cursor::new(Vec::new());
+                            graph.write(&mut out)?;
+                            Ok(out.into_inner())
+                        })
+                        .write_with(helpers::write_bytes)
+                        .process(inputs, outputs),
+
+                    FileType::ZoneNav => processor
+                        .read_with(|r, _| {
+                            let graph: ZoneNavigationGraph = serde_json::from_reader(r)?;
+                            graph.validate()?;
+
+                            let mut out = io::Cursor::new(Vec::new());
+                            graph.write(&mut out)?;
+                            Ok(out.into_inner())
+                        })
+                        .write_with(helpers::write_bytes)
                         .process(inputs, outputs),
                 }
             }
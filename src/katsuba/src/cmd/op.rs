@@ -1,20 +1,36 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Instant};
 
-use clap::{Args, Subcommand};
-use katsuba_object_property::serde;
+use ::serde::Serialize;
+use clap::{Args, Subcommand, ValueEnum};
+use katsuba_object_property::{
+    serde,
+    value::{self, Value, WithHashes, WithReferences},
+};
+use katsuba_pipeline::DeserializeJob;
 use katsuba_types::PropertyFlags;
 
 use super::Command;
-use crate::cli::{helpers, Bias, InputsOutputs, Processor};
+use crate::cli::{helpers, Bias, InputSource, InputsOutputs, Processor, Selection};
 
-mod guess;
+mod archive;
+mod diff;
+pub mod guess;
+mod index;
+mod locale;
+mod parallel;
+mod roundtrip;
+mod scan;
+mod ser;
+mod stats;
+mod table;
 mod utils;
+mod xml;
 
 /// Subcommand for working with ObjectProperty serialization.
 #[derive(Debug, Args)]
 pub struct ObjectProperty {
     #[clap(subcommand)]
-    command: ObjectPropertyCommand,
+    command: Option<ObjectPropertyCommand>,
 
     /// A list of paths to JSON type list files to use.
     ///
@@ -32,18 +48,29 @@ pub struct ObjectProperty {
     /// These flags are configuration bits for the serializer
     /// instance and influence how data is interpreted.
     ///
+    /// Either a raw integer or a comma-separated list of flag names
+    /// like `STATEFUL_FLAGS,WITH_COMPRESSION`. See `--list-flags`
+    /// for all known flags.
+    ///
     /// When in doubt what to pick, try 0 or using the guess command.
-    #[clap(short, long, default_value_t = 0)]
-    flags: u32,
+    #[clap(short, long, default_value = "0", value_parser = utils::parse_flags)]
+    flags: serde::SerializerFlags,
+
+    /// Prints all known serializer flags and their meanings.
+    #[clap(long, exclusive = true)]
+    list_flags: bool,
 
     /// Property filter mask to use.
     ///
     /// This mask can be used to conditionally exclude properties
     /// of an object from the serialization.
     ///
+    /// Either a raw integer or a list of flag names separated by `|`
+    /// or `,`, like `TRANSMIT|PRIVILEGED_TRANSMIT`.
+    ///
     /// When in doubt what to pick, try the default value or 0.
-    #[clap(short, long, default_value_t = 0x18)]
-    mask: u32,
+    #[clap(short, long, default_value = "24", value_parser = utils::parse_mask)]
+    mask: PropertyFlags,
 
     /// Whether the object is serialized shallow.
     ///
@@ -67,6 +94,11 @@ pub struct ObjectProperty {
     /// Whether we should use only the djb2 hash (Pirate101)
     #[clap(short, long, default_value_t = false)]
     djb2_only: bool,
+
+    /// A named serializer configuration to use instead of picking
+    /// flags, mask, shallow mode and hashing individually.
+    #[clap(long, value_enum, conflicts_with_all = ["flags", "mask", "shallow", "djb2_only"])]
+    preset: Option<Preset>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -76,9 +108,199 @@ enum ObjectPropertyCommand {
         #[clap(flatten)]
         args: InputsOutputs,
 
+        /// Reads the inputs from this KIWAD archive instead of files.
+        ///
+        /// The input is then a glob pattern for the archive entries to
+        /// deserialize, like `ObjectData/**`, and the output must be a
+        /// directory. Results are written in the archive's layout.
+        #[clap(long)]
+        wad: Option<PathBuf>,
+
         /// Skips properties with unknown types during deserialization.
         #[clap(short, long, default_value_t = false)]
         ignore_unknown_types: bool,
+
+        /// How to treat data left unread after deserialization.
+        ///
+        /// Leftover data usually hints at a wrong serializer config
+        /// or outdated type lists.
+        #[clap(long, value_enum, default_value_t = Strictness::Lenient)]
+        strictness: Strictness,
+
+        /// Recovers from errors in individual properties instead of
+        /// failing the whole object.
+        ///
+        /// Failing values are replaced by an object holding the error
+        /// under `$__error` and reported as warnings. Only supported
+        /// in deep mode.
+        ///
+        /// List elements cannot be recovered individually since
+        /// they carry no sizes, so one bad element replaces the
+        /// whole list.
+        #[clap(long)]
+        tolerant: bool,
+
+        /// Only outputs these properties of the root object, given as
+        /// a comma-separated list of names like `m_name,m_templateID`.
+        ///
+        /// In deep mode, all other properties are skipped without
+        /// decoding them, which speeds up bulk extraction.
+        #[clap(long, value_delimiter = ',')]
+        only: Vec<String>,
+
+        /// Skips all objects of these types, given as a comma-separated
+        /// list of type names or hashes.
+        ///
+        /// Skipped objects are output as null without decoding them,
+        /// e.g. to leave out huge geometry data. Only supported in
+        /// deep mode.
+        #[clap(long, value_delimiter = ',')]
+        skip_type: Vec<String>,
+
+        /// Replaces the string keys in localized properties with their
+        /// text from the language tables in this `Locale` directory or
+        /// KIWAD archive.
+        #[clap(long)]
+        locale: Option<PathBuf>,
+
+        /// Deduplicates identical sub-objects in the output.
+        ///
+        /// The first occurrence of a repeated object is tagged with
+        /// an `$id` field and all later ones are written as JSON
+        /// references of the form `{"$ref": id}`.
+        #[clap(long)]
+        intern: bool,
+
+        /// Annotates every object with the name of its type under
+        /// `$__name` and the hashes of its properties under
+        /// `$__hashes`.
+        ///
+        /// The annotations are ignored by the ser command. Not
+        /// supported with `--intern`.
+        #[clap(long, conflicts_with = "intern")]
+        with_hashes: bool,
+
+        /// Emits dynamic containers holding a single element as that
+        /// element rather than a one-element array.
+        #[clap(long)]
+        collapse_single_element: bool,
+
+        /// Wraps values from dynamic containers in an object naming
+        /// the container kind under `$__container` and holding the
+        /// elements under `$__items`.
+        ///
+        /// This keeps the output unambiguous for round trips and
+        /// overrides `--collapse-single-element`.
+        #[clap(long)]
+        annotate_containers: bool,
+
+        /// Emits enum values as an object holding the variant name
+        /// under `name` and its integral value under `value`.
+        ///
+        /// The name is null for variants unknown to the type lists.
+        #[clap(long)]
+        enum_names: bool,
+
+        /// Keeps properties missing from the type lists as raw data
+        /// under their hash instead of failing.
+        ///
+        /// The ser command writes them back unchanged in deep mode.
+        #[clap(long)]
+        preserve_unknown: bool,
+
+        /// Fills in the type list defaults for omitted delta-encoded
+        /// properties instead of emitting null.
+        ///
+        /// The ser command marks such values as present, so the
+        /// output no longer round trips to the same bytes.
+        #[clap(long)]
+        fill_defaults: bool,
+
+        /// Prints profiling data to stderr after all inputs were
+        /// processed.
+        ///
+        /// This lists the time, decompressed size and object count of
+        /// every input, along with a histogram of the encountered
+        /// object types.
+        #[clap(long)]
+        stats: bool,
+
+        /// Adds the bit offset and length of every property value to
+        /// the root object under `$__spans`, keyed by property path.
+        ///
+        /// Offsets are relative to the object data after removing its
+        /// framing and decompressing it. Not supported with `--intern`.
+        #[clap(long, conflicts_with_all = ["intern", "table"])]
+        annotate: bool,
+
+        /// Logs every primitive read from the object data to stderr.
+        #[cfg(feature = "trace")]
+        #[clap(long)]
+        trace: bool,
+
+        /// The format to write deserialized objects in.
+        #[clap(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+
+        /// Writes the list of objects at this path, like
+        /// `m_templates`, as a CSV table instead.
+        ///
+        /// The objects must all have the same type. Every leaf value
+        /// becomes a column named after its path within the object.
+        #[clap(long, conflicts_with_all = ["intern", "with_hashes", "format"])]
+        table: Option<String>,
+
+        #[clap(flatten)]
+        selection: Selection,
+    },
+
+    /// Serializes JSON as produced by the de command back into
+    /// ObjectProperty binary state.
+    ///
+    /// The serializer options of the base command are used. Objects
+    /// are identified by their `$__type` hash and must match the
+    /// type lists. Interned output is not supported.
+    Ser {
+        #[clap(flatten)]
+        args: InputsOutputs,
+
+        /// Frames the output as a persistent game file.
+        ///
+        /// This prepends the `BINd` magic and implies deep mode with
+        /// stateful serializer flags, as the game expects.
+        #[clap(long)]
+        bind: bool,
+    },
+
+    /// Compares the objects in two files and prints their
+    /// differences.
+    ///
+    /// Every line names the path of a property that was added (+),
+    /// removed (-) or changed (~) from the first to the second file,
+    /// along with the values as JSON.
+    Diff {
+        /// Path to the original file.
+        a: PathBuf,
+
+        /// Path to the modified file.
+        b: PathBuf,
+
+        /// Skips properties with unknown types during deserialization.
+        #[clap(short, long, default_value_t = false)]
+        ignore_unknown_types: bool,
+    },
+
+    /// Verifies that a file survives deserialization and
+    /// serialization unchanged.
+    ///
+    /// The object is re-serialized with the options of the base
+    /// command and compared to the original bytes. On mismatch, the
+    /// first diverging bit and the property containing it are
+    /// reported. Files with the `BINd` magic are handled like in
+    /// `ser --bind`.
+    Roundtrip {
+        /// Path to the file to verify.
+        input: PathBuf,
     },
 
     /// Attempts to deserialize ObjectProperty binary state
@@ -92,7 +314,31 @@ enum ObjectPropertyCommand {
     /// success or failure and you may want to tweak it manually.
     Guess {
         /// Path to the file to deserialize.
-        path: PathBuf,
+        #[clap(
+            required_unless_present_any = ["wad", "recursive"],
+            conflicts_with_all = ["wad", "recursive"]
+        )]
+        path: Option<PathBuf>,
+
+        /// Guesses every file under this directory instead of reading
+        /// a single file, and summarizes the distinct configurations
+        /// found among them.
+        #[clap(long, conflicts_with = "wad")]
+        recursive: Option<PathBuf>,
+
+        /// Samples entries from this KIWAD archive instead of reading
+        /// a single file, and reports the most common configuration
+        /// among them.
+        #[clap(long)]
+        wad: Option<PathBuf>,
+
+        /// A glob pattern for selecting the archive entries to sample.
+        #[clap(long, requires = "wad", default_value = "**/*.xml")]
+        glob: String,
+
+        /// The maximum number of archive entries to sample.
+        #[clap(long, requires = "wad", default_value_t = 32)]
+        samples: usize,
 
         /// Whether the deserialized value should be pretty-printed
         /// on success.
@@ -103,53 +349,485 @@ enum ObjectPropertyCommand {
         #[clap(short, long)]
         quiet: bool,
     },
+
+    /// Deserializes many objects into a searchable index.
+    ///
+    /// Every leaf value becomes one JSON line holding the source file,
+    /// the type of the object it belongs to, its property path and the
+    /// value itself. The output can be searched with common tools or
+    /// loaded into a database.
+    Index {
+        /// Path to the directory to index.
+        #[clap(required_unless_present = "wad", conflicts_with = "wad")]
+        path: Option<PathBuf>,
+
+        /// Indexes entries from this KIWAD archive instead.
+        #[clap(long)]
+        wad: Option<PathBuf>,
+
+        /// A glob pattern for selecting the archive entries to index.
+        #[clap(long, requires = "wad", default_value = "**/*.xml")]
+        glob: String,
+
+        /// Only indexes objects for which this condition holds.
+        ///
+        /// Conditions look like `<path> <op> <value>`, where the path
+        /// is a dotted property path and the operator is one of `==`,
+        /// `!=`, `<`, `<=`, `>`, `>=` or `contains`. Quoted values are
+        /// strings, e.g. `m_objectName contains 'Raven'`.
+        ///
+        /// May be given multiple times, in which case all conditions
+        /// must hold.
+        #[clap(long = "where")]
+        conditions: Vec<index::Condition>,
+
+        /// The file to write the index to. Defaults to stdout.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// Skips properties with unknown types during deserialization.
+        #[clap(short, long, default_value_t = false)]
+        ignore_unknown_types: bool,
+    },
+
+    /// Scans a binary blob for references to known type hashes
+    /// without deserializing it.
+    ///
+    /// This helps to find out which classes unknown data uses.
+    /// Matches are found heuristically, so expect some noise on
+    /// large inputs. Compressed data is inflated according to the
+    /// serializer options first.
+    Scan {
+        /// Path to the file to scan.
+        path: PathBuf,
+
+        /// Checks for hashes at every bit offset instead of every
+        /// byte, to cover compact bit-packed data.
+        #[clap(short, long)]
+        bit_aligned: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// JSON with type hashes under `$__type`.
+    Json,
+    /// The XML dialect of the game's own serializer, for comparing
+    /// against files shipped with the client.
+    ///
+    /// Not supported with `--intern` or output selections.
+    Xml,
+    /// YAML in the same shape as JSON.
+    Yaml,
+    /// TOML in the same shape as JSON.
+    ///
+    /// TOML has no null value, so objects with null pointers cannot
+    /// be written.
+    Toml,
+    /// CBOR in the same shape as JSON, for consumption by programs.
+    Cbor,
+    /// MessagePack in the same shape as JSON, for consumption by
+    /// programs.
+    Msgpack,
+}
+
+impl Format {
+    // Encodes a value in this format. XML is rendered separately since
+    // it needs the type list.
+    fn encode<T: Serialize>(self, value: &T) -> eyre::Result<Vec<u8>> {
+        Ok(match self {
+            Self::Json => serde_json::to_vec(value)?,
+            Self::Yaml => serde_yaml::to_string(value)?.into_bytes(),
+            Self::Toml => toml::to_string(value)?.into_bytes(),
+            Self::Cbor => {
+                let mut out = Vec::new();
+                ciborium::into_writer(value, &mut out)?;
+                out
+            }
+            Self::Msgpack => rmp_serde::to_vec_named(value)?,
+            Self::Xml => unreachable!("XML is rendered from the type list"),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Preset {
+    /// Persistent game files with stateful flags in deep mode.
+    GameFile,
+    /// Shallow state as sent over the network.
+    NetworkShallow,
+    /// Persistent game files of Pirate101.
+    Pirate101,
+}
+
+impl Preset {
+    fn options(self) -> serde::SerializerOptions {
+        match self {
+            Self::GameFile => serde::SerializerOptions::game_file(),
+            Self::NetworkShallow => serde::SerializerOptions::network_shallow(),
+            Self::Pirate101 => serde::SerializerOptions::pirate101(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Strictness {
+    /// Silently ignores leftover data.
+    Lenient,
+    /// Logs a warning for leftover data.
+    Warn,
+    /// Fails deserialization on leftover data.
+    Strict,
+}
+
+impl From<Strictness> for serde::Strictness {
+    fn from(value: Strictness) -> Self {
+        match value {
+            Strictness::Lenient => Self::Lenient,
+            Strictness::Warn => Self::Warn,
+            Strictness::Strict => Self::Strict,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Output {
+    Plain(Value),
+    Interned(WithReferences),
+    Json(serde_json::Value),
+}
+
+// Deserializes an object and reports the errors recovered from in
+// tolerant mode.
+fn deserialize(job: &mut DeserializeJob, buf: &[u8]) -> eyre::Result<Value> {
+    let obj = job.deserialize(buf)?;
+    for diag in job.take_diagnostics() {
+        log::warn!(
+            "Failed to deserialize '{}' at bit {}: {}",
+            diag.path,
+            diag.offset,
+            diag.reason
+        );
+    }
+
+    Ok(obj)
+}
+
+// Adds the bit spans of all properties to the root of `json`.
+fn add_spans(json: &mut serde_json::Value, spans: Vec<serde::Span>) {
+    let serde_json::Value::Object(root) = json else {
+        return;
+    };
+
+    let spans = spans
+        .into_iter()
+        .map(|span| (span.path, serde_json::json!([span.offset, span.len])))
+        .collect();
+    root.insert("$__spans".to_owned(), serde_json::Value::Object(spans));
 }
 
 impl Command for ObjectProperty {
     fn handle(self) -> eyre::Result<()> {
-        let type_list = Arc::new(utils::merge_type_lists(self.type_lists)?);
-        let mut options = serde::SerializerOptions {
-            flags: serde::SerializerFlags::from_bits_truncate(self.flags),
-            property_mask: PropertyFlags::from_bits_truncate(self.mask),
-            shallow: self.shallow,
-            manual_compression: self.zlib_manual,
-            djb2_only: self.djb2_only,
-            ..Default::default()
+        if self.list_flags {
+            utils::list_flags();
+            return Ok(());
+        }
+        let Some(command) = self.command else {
+            eyre::bail!("a subcommand is required");
         };
 
-        match self.command {
+        let type_list = Arc::new(helpers::merge_type_lists(self.type_lists)?);
+        let mut options = match self.preset {
+            Some(preset) => serde::SerializerOptions {
+                manual_compression: self.zlib_manual,
+                ..preset.options()
+            },
+            None => serde::SerializerOptions {
+                flags: self.flags,
+                property_mask: self.mask,
+                shallow: self.shallow,
+                manual_compression: self.zlib_manual,
+                djb2_only: self.djb2_only,
+                ..Default::default()
+            },
+        };
+
+        log::info!(
+            "Using property mask {}",
+            utils::format_mask(options.property_mask)
+        );
+
+        match command {
             ObjectPropertyCommand::De {
                 args,
+                wad,
                 ignore_unknown_types,
+                strictness,
+                tolerant,
+                only,
+                skip_type,
+                locale,
+                intern,
+                with_hashes,
+                collapse_single_element,
+                annotate_containers,
+                enum_names,
+                preserve_unknown,
+                fill_defaults,
+                stats,
+                annotate,
+                #[cfg(feature = "trace")]
+                trace,
+                format,
+                table,
+                selection,
             } => {
-                let (inputs, outputs) = args.evaluate("de.xml")?;
-
                 options.skip_unknown_types = ignore_unknown_types;
-                let mut de = serde::Serializer::new(options, type_list)?;
+                options.strictness = strictness.into();
+                options.tolerant = tolerant;
+                if !only.is_empty() {
+                    options.only = Some(Arc::new(only.into_iter().collect()));
+                }
+                if !skip_type.is_empty() {
+                    let names = skip_type
+                        .iter()
+                        .map(|t| utils::resolve_type_name(&type_list, t))
+                        .collect::<eyre::Result<_>>()?;
+                    options.skip_types = Some(Arc::new(names));
+                }
+                if let Some(locale) = locale {
+                    options.locale = Some(Arc::new(locale::Strings::load(&locale)?));
+                }
+                options.collapse_single_element = collapse_single_element;
+                options.annotate_containers = annotate_containers;
+                options.enum_names = enum_names;
+                options.preserve_unknown = preserve_unknown;
+                options.fill_defaults = fill_defaults;
+                options.record_spans = annotate;
+                if format == Format::Xml && (intern || !selection.is_empty()) {
+                    eyre::bail!("XML output does not support interning or selections");
+                }
+                if table.is_some() && !selection.is_empty() {
+                    eyre::bail!("table output does not support selections");
+                }
+                if annotate && (format == Format::Xml || !selection.is_empty()) {
+                    eyre::bail!("annotated output does not support XML or selections");
+                }
 
-                Processor::new(Bias::Current)?
-                    .read_with(move |mut r, ex| {
-                        let buf = r.get_buffer(ex)?;
-                        let mut buf: &[u8] = &buf;
+                // Every worker thread gets its own job sharing the type list.
+                let make_job = || -> eyre::Result<DeserializeJob> {
+                    Ok(DeserializeJob::new(options.clone(), type_list.clone())?)
+                };
+                // Inputs are named so that they can be told apart in stats.
+                let stats = stats.then(stats::Stats::default);
+                let de = |job: &mut DeserializeJob, name: &str, buf: &[u8]| {
+                    #[cfg(feature = "trace")]
+                    if trace {
+                        let name = name.to_owned();
+                        serde::trace::set_sink(Some(Box::new(
+                            move |read: &serde::trace::Read<'_>| eprintln!("{name}: {read}"),
+                        )));
+                    }
+
+                    let start = Instant::now();
+                    let obj = deserialize(job, buf)?;
+                    if let Some(stats) = &stats {
+                        stats.record(name, start.elapsed(), job.data_len(), &obj);
+                    }
+                    Ok::<_, eyre::Report>(obj)
+                };
+                let to_xml = |job: &mut DeserializeJob, name: &str, buf: &[u8]| {
+                    let obj = de(job, name, buf)?;
+                    Ok::<_, eyre::Report>(xml::render(&type_list, &obj))
+                };
+                let to_table = |job: &mut DeserializeJob, name: &str, buf: &[u8], path: &str| {
+                    let obj = de(job, name, buf)?;
+                    let list = obj
+                        .get_path(path)
+                        .ok_or_else(|| eyre::eyre!("table path '{path}' does not exist"))?;
+                    let table = list.to_table().ok_or_else(|| {
+                        eyre::eyre!("'{path}' is not a list of objects of the same type")
+                    })?;
+
+                    table::render_csv(&table)
+                };
 
-                        // If the data starts with the `BINd` magic, it is a game file.
-                        // These always use a fixed base config so we set it here.
-                        if buf.get(0..4) == Some(serde::BIND_MAGIC) {
-                            de.parts.options.shallow = false;
-                            de.parts.options.flags = serde::SerializerFlags::STATEFUL_FLAGS;
+                // Dotted selections are resolved on the value directly,
+                // which avoids converting the whole object to JSON.
+                let (path, selection) = match selection.value_path() {
+                    Some(path) => (Some(path.to_owned()), Selection::default()),
+                    None => (None, selection),
+                };
+                let to_output = |job: &mut DeserializeJob, name: &str, buf: &[u8]| {
+                    let mut obj = de(job, name, buf)?;
+                    if let Some(path) = &path {
+                        obj = obj
+                            .get_path(path)
+                            .cloned()
+                            .ok_or_else(|| eyre::eyre!("selected path '{path}' does not exist"))?;
+                    }
 
-                            buf = buf.get(4..).unwrap();
+                    Ok::<_, eyre::Report>(if intern {
+                        Output::Interned(WithReferences(value::intern(obj)))
+                    } else if with_hashes || annotate {
+                        let mut json = if with_hashes {
+                            serde_json::to_value(WithHashes::new(&obj, &type_list))?
+                        } else {
+                            serde_json::to_value(&obj)?
+                        };
+                        if annotate {
+                            add_spans(&mut json, job.take_spans());
                         }
+                        Output::Json(json)
+                    } else {
+                        Output::Plain(obj)
+                    })
+                };
+
+                // Every format but JSON is encoded up front, so they all
+                // share the same writer.
+                let to_bytes = |job: &mut DeserializeJob, name: &str, buf: &[u8]| {
+                    if let Some(path) = &table {
+                        return to_table(job, name, buf, path);
+                    }
+                    if format == Format::Xml {
+                        return to_xml(job, name, buf);
+                    }
+
+                    let output = to_output(job, name, buf)?;
+                    if selection.is_empty() {
+                        format.encode(&output)
+                    } else {
+                        format.encode(&selection.apply(serde_json::to_value(output)?)?)
+                    }
+                };
+
+                let res = if let Some(wad) = wad {
+                    let (pattern, out) = args.into_raw();
+                    archive::convert_entries(&wad, &pattern, out, "de.xml", make_job, to_bytes)
+                } else {
+                    // Only plain JSON output is written from values directly.
+                    let json = format == Format::Json && table.is_none();
+                    let (inputs, outputs) = args.evaluate("de.xml")?;
+                    match (inputs, json) {
+                        (InputSource::Files(paths), true) => parallel::process_files(
+                            paths,
+                            outputs,
+                            make_job,
+                            to_output,
+                            helpers::write_selected_as_json(selection),
+                        ),
+                        (InputSource::Files(paths), false) => parallel::process_files(
+                            paths,
+                            outputs,
+                            make_job,
+                            to_bytes,
+                            helpers::write_bytes,
+                        ),
+
+                        (inputs, true) => {
+                            let mut job = make_job()?;
+                            Processor::new(Bias::Current)?
+                                .read_with(|mut r, ex| {
+                                    let name = r.name();
+                                    to_output(&mut job, &name, &r.get_buffer(ex)?)
+                                })
+                                .write_with(helpers::write_selected_as_json(selection))
+                                .process(inputs, outputs)
+                        }
+                        (inputs, false) => {
+                            let mut job = make_job()?;
+                            Processor::new(Bias::Current)?
+                                .read_with(|mut r, ex| {
+                                    let name = r.name();
+                                    to_bytes(&mut job, &name, &r.get_buffer(ex)?)
+                                })
+                                .write_with(helpers::write_bytes)
+                                .process(inputs, outputs)
+                        }
+                    }
+                };
+
+                if let Some(stats) = &stats {
+                    stats.print(&type_list);
+                }
+                res
+            }
+
+            ObjectPropertyCommand::Ser { args, bind } => {
+                let (inputs, outputs) = args.evaluate("ser.xml")?;
+
+                if bind {
+                    options.shallow = false;
+                    options.flags |= serde::SerializerFlags::STATEFUL_FLAGS;
+                }
+                let mut serializer = serde::Serializer::new(options, type_list)?;
 
-                        de.deserialize::<serde::PropertyClass>(buf)
-                            .map_err(Into::into)
+                Processor::new(Bias::Current)?
+                    .read_with(move |mut r, ex| {
+                        let buf = r.get_buffer(ex)?;
+                        ser::serialize(&mut serializer, &buf, bind)
                     })
-                    .write_with(helpers::write_as_json)
+                    .write_with(helpers::write_bytes)
                     .process(inputs, outputs)
             }
 
-            ObjectPropertyCommand::Guess { path, quiet } => {
-                guess::guess(options, type_list, path, quiet)
+            ObjectPropertyCommand::Diff {
+                a,
+                b,
+                ignore_unknown_types,
+            } => {
+                options.skip_unknown_types = ignore_unknown_types;
+                let mut job = DeserializeJob::new(options, type_list)?;
+
+                diff::diff(&mut job, a, b)
+            }
+
+            ObjectPropertyCommand::Roundtrip { input } => {
+                let mut serializer = serde::Serializer::new(options, type_list)?;
+                roundtrip::roundtrip(&mut serializer, &input)
+            }
+
+            ObjectPropertyCommand::Guess {
+                path,
+                recursive,
+                wad,
+                glob,
+                samples,
+                quiet,
+            } => match (path, recursive, wad) {
+                (_, _, Some(wad)) => guess::guess_wad(options, type_list, wad, &glob, samples),
+                (_, Some(dir), None) => guess::guess_dir(options, type_list, dir),
+                (Some(path), None, None) => guess::guess(options, type_list, path, quiet),
+                (None, None, None) => unreachable!(),
+            },
+
+            ObjectPropertyCommand::Index {
+                path,
+                wad,
+                glob,
+                conditions,
+                output,
+                ignore_unknown_types,
+            } => {
+                options.skip_unknown_types = ignore_unknown_types;
+                let make_job = || -> eyre::Result<DeserializeJob> {
+                    Ok(DeserializeJob::new(options.clone(), type_list.clone())?)
+                };
+
+                let opts = index::IndexOptions { conditions, output };
+                match (path, wad) {
+                    (_, Some(wad)) => {
+                        index::index_archive(make_job, &type_list, &wad, &glob, &opts)
+                    }
+                    (Some(path), None) => index::index_dir(make_job, &type_list, &path, &opts),
+                    (None, None) => unreachable!(),
+                }
+            }
+
+            ObjectPropertyCommand::Scan { path, bit_aligned } => {
+                scan::scan(options, type_list, path, bit_aligned)
             }
         }
     }
@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use eyre::Context;
+use katsuba_wad::{
+    extract::{DirSink, EntryMetadata, ExtractSink},
+    Archive, Inflater,
+};
+
+use super::parallel;
+
+/// Converts all entries in the archive at `path` which match `pattern`
+/// on worker threads and writes the results under `out`, mirroring the
+/// archive layout.
+///
+/// Every worker owns a state created by `make`. `convert` receives the
+/// name of every entry along with its data. Entries which fail to
+/// convert are reported and skipped.
+pub fn convert_entries<S, C>(
+    path: &Path,
+    pattern: &str,
+    out: PathBuf,
+    suffix: &'static str,
+    mut make: impl FnMut() -> eyre::Result<S>,
+    convert: C,
+) -> eyre::Result<()>
+where
+    S: Send,
+    C: Fn(&mut S, &str, &[u8]) -> eyre::Result<Vec<u8>> + Sync,
+{
+    if out.as_os_str() == "-" {
+        eyre::bail!("archive inputs need an output directory");
+    }
+
+    let archive = Archive::open_mmap(path)
+        .with_context(|| format!("failed to open archive at '{}'", path.display()))?;
+    let files: Vec<_> = archive
+        .iter_glob(pattern)?
+        .filter(|(_, f)| !f.is_unpatched)
+        .collect();
+
+    let mut sink = DirSink::new(out);
+    let mut failed = 0;
+    parallel::convert_all(
+        &files,
+        || Ok((make()?, Inflater::new())),
+        |(state, inflater), (name, file)| {
+            let contents = archive
+                .file_contents(file)
+                .ok_or_else(|| eyre::eyre!("file is unpatched"))?;
+            let data = if file.compressed {
+                inflater.decompress(contents, file.uncompressed_size as _)?
+            } else {
+                contents
+            };
+
+            convert(state, name, data)
+        },
+        |(name, file), res| {
+            // A single bad entry should not abort a whole bulk conversion.
+            let data = match res {
+                Ok(data) => data,
+                Err(e) => {
+                    log::error!("Failed to convert '{name}': {e}");
+                    failed += 1;
+                    return Ok(());
+                }
+            };
+
+            let meta = EntryMetadata {
+                size: data.len() as u64,
+                crc: file.crc,
+                compressed: file.compressed,
+                mode: archive.mode(),
+            };
+            let path = Path::new(name).with_extension(suffix);
+            sink.write_entry(&path.to_string_lossy(), &meta, &mut data.as_slice())
+                .map_err(Into::into)
+        },
+    )?;
+
+    if failed > 0 {
+        eyre::bail!("failed to convert {failed} of {} entries", files.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use eyre::Context;
+use katsuba_object_property::value::{Change, Value};
+use katsuba_pipeline::DeserializeJob;
+
+fn to_json(value: &Value) -> eyre::Result<String> {
+    serde_json::to_string(value).map_err(Into::into)
+}
+
+/// Prints the structural differences between the objects in the files
+/// at `a` and `b`, one change per line.
+pub fn diff(job: &mut DeserializeJob, a: PathBuf, b: PathBuf) -> eyre::Result<()> {
+    let va = job
+        .deserialize_file(&a)
+        .with_context(|| format!("failed to deserialize '{}'", a.display()))?;
+    let vb = job
+        .deserialize_file(&b)
+        .with_context(|| format!("failed to deserialize '{}'", b.display()))?;
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    for (path, change) in va.diff(&vb) {
+        let path = if path.is_empty() { "<root>" } else { &path };
+        match change {
+            Change::Added(v) => writeln!(stdout, "+ {path}: {}", to_json(v)?)?,
+            Change::Removed(v) => writeln!(stdout, "- {path}: {}", to_json(v)?)?,
+            Change::Changed(old, new) => {
+                writeln!(stdout, "~ {path}: {} -> {}", to_json(old)?, to_json(new)?)?
+            }
+        }
+    }
+
+    Ok(())
+}
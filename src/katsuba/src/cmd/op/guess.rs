@@ -1,21 +1,30 @@
 use std::{
+    cmp::Reverse,
+    collections::HashMap,
     fs,
     io::{self, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
+use eyre::Context;
 use katsuba_object_property::{
     serde::{self, BIND_MAGIC},
     Value,
 };
 use katsuba_types::TypeList;
+use katsuba_wad::{Archive, Inflater};
 
 use crate::utils;
 
-struct Report {
-    value: Result<Value, serde::Error>,
-    opts: serde::SerializerOptions,
+/// The outcome of a deserialization attempt with guessed options.
+pub struct Report {
+    /// The deserialized value or the error that occurred.
+    pub value: Result<Value, serde::Error>,
+    /// The serializer configuration that was used.
+    pub opts: serde::SerializerOptions,
+    /// The confidence in the options guessed by trial, if known.
+    pub confidence: Option<serde::GuessConfidence>,
 }
 
 pub fn guess(
@@ -24,7 +33,8 @@ pub fn guess(
     path: PathBuf,
     quiet: bool,
 ) -> eyre::Result<()> {
-    let report = try_guess(opts, types, path)?;
+    let data = fs::read(path)?;
+    let report = try_guess(opts, types, &data)?;
 
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
@@ -40,49 +50,203 @@ pub fn guess(
     Ok(())
 }
 
-fn try_guess(
+/// Guesses a consensus configuration from a sample of entries in an
+/// archive which match a glob pattern.
+pub fn guess_wad(
     opts: serde::SerializerOptions,
     types: Arc<TypeList>,
-    path: PathBuf,
-) -> eyre::Result<Report> {
-    let data = fs::read(path)?;
-    let mut data = data.as_slice();
+    wad: PathBuf,
+    pattern: &str,
+    samples: usize,
+) -> eyre::Result<()> {
+    let archive = Archive::open_mmap(&wad)
+        .with_context(|| format!("failed to open archive at '{}'", wad.display()))?;
+    let mut inflater = Inflater::new();
 
-    let mut de = serde::Serializer::with_guessed_options_from_base(opts, types, data)?;
-    let mut res;
+    // Tally the successful configurations by their distinct settings.
+    let mut tally: HashMap<_, (usize, serde::SerializerOptions)> = HashMap::new();
+    let mut failed = Vec::new();
+    let mut sampled = 0;
 
-    if data.get(0..4) == Some(BIND_MAGIC) {
-        data = data.get(4..).unwrap();
+    for (name, file) in archive.iter_glob(pattern)?.take(samples) {
+        let Some(contents) = archive.file_contents(file) else {
+            continue;
+        };
+        let data = if file.compressed {
+            inflater.decompress(contents, file.uncompressed_size as _)?
+        } else {
+            contents
+        };
+
+        sampled += 1;
+        match try_guess(opts.clone(), types.clone(), data) {
+            Ok(Report {
+                value: Ok(..),
+                opts,
+                ..
+            }) => {
+                tally.entry(config_key(&opts)).or_insert((0, opts)).0 += 1;
+            }
+            Ok(Report { value: Err(e), .. }) => failed.push((name, e.to_string())),
+            Err(e) => failed.push((name, e.to_string())),
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let succeeded = sampled - failed.len();
+    writeln!(stdout, "Sampled {sampled} entries, {succeeded} succeeded.")?;
+
+    match tally.into_values().max_by_key(|(count, _)| *count) {
+        Some((count, opts)) => {
+            writeln!(stdout, "Consensus shared by {count}/{succeeded} entries.")?;
+            writeln!(stdout)?;
+
+            write_config(
+                &mut stdout,
+                &Report {
+                    value: Ok(Value::Empty),
+                    opts,
+                    confidence: None,
+                },
+            )?;
+        }
+        None => writeln!(stdout, "No configuration could be found.")?,
+    }
+
+    if !failed.is_empty() {
+        writeln!(stdout)?;
+        writeln!(stdout, "Failures:")?;
+        for (name, e) in failed {
+            writeln!(stdout, "  {name}: {e}")?;
+        }
     }
 
-    // First, try to deserialize with the current config.
-    res = de.deserialize::<serde::PropertyClass>(data);
-    if res.is_ok() {
-        return Ok(Report {
-            value: res,
-            opts: de.parts.options,
-        });
+    Ok(())
+}
+
+/// Guesses the configuration of every file under a directory and
+/// prints the distinct configurations found along with their counts.
+pub fn guess_dir(
+    opts: serde::SerializerOptions,
+    types: Arc<TypeList>,
+    dir: PathBuf,
+) -> eyre::Result<()> {
+    // Tally the successful configurations by their distinct settings.
+    let mut tally: HashMap<_, (usize, bool, serde::SerializerOptions)> = HashMap::new();
+    let mut failed = Vec::new();
+    let mut total = 0;
+
+    for entry in walkdir::WalkDir::new(&dir) {
+        let entry = entry.context("failed to query input directory")?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        total += 1;
+        let path = entry.path();
+        let data = fs::read(path)?;
+        let bind = data.starts_with(BIND_MAGIC);
+        match try_guess(opts.clone(), types.clone(), &data) {
+            Ok(Report {
+                value: Ok(..),
+                opts,
+                ..
+            }) => {
+                let key = (bind, config_key(&opts));
+                tally.entry(key).or_insert((0, bind, opts)).0 += 1;
+            }
+            Ok(Report { value: Err(e), .. }) => failed.push((relative(&dir, path), e.to_string())),
+            Err(e) => failed.push((relative(&dir, path), e.to_string())),
+        }
     }
 
-    // If that doesn't work, retry with human readable enums if that's realistic.
-    if !opts.shallow && !opts.flags.contains(serde::SerializerFlags::STATEFUL_FLAGS) {
-        de.parts.options.flags |= serde::SerializerFlags::HUMAN_READABLE_ENUMS;
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    writeln!(stdout, "Guessed {total} files:")?;
 
-        res = de.deserialize::<serde::PropertyClass>(data);
-        if res.is_ok() {
-            return Ok(Report {
-                value: res,
-                opts: de.parts.options,
-            });
+    let mut clusters: Vec<_> = tally.into_values().collect();
+    clusters.sort_by_key(|(count, ..)| Reverse(*count));
+    for (count, bind, opts) in clusters {
+        writeln!(stdout, "  {count} files: {}", describe_config(&opts, bind))?;
+    }
+    if !failed.is_empty() {
+        writeln!(stdout, "  {} failures", failed.len())?;
+
+        writeln!(stdout)?;
+        writeln!(stdout, "Failures:")?;
+        for (name, e) in failed {
+            writeln!(stdout, "  {name}: {e}")?;
         }
+    }
+
+    Ok(())
+}
+
+// Identifies a configuration by its guessable settings.
+fn config_key(opts: &serde::SerializerOptions) -> (bool, u32, bool, u32) {
+    (
+        opts.shallow,
+        opts.flags.bits(),
+        opts.manual_compression,
+        opts.property_mask.bits(),
+    )
+}
+
+// Summarizes a configuration in a single line, e.g. `shallow + COMPACT_LENGTH_PREFIXES`.
+fn describe_config(opts: &serde::SerializerOptions, bind: bool) -> String {
+    let mut parts = vec![match (bind, opts.shallow) {
+        (true, _) => "BINd deep".to_owned(),
+        (false, true) => "shallow".to_owned(),
+        (false, false) => "deep".to_owned(),
+    }];
+
+    // BINd files always use stateful flags, so they aren't worth noting.
+    let mut flags = opts.flags;
+    if bind {
+        flags.remove(serde::SerializerFlags::STATEFUL_FLAGS);
+    }
+    parts.extend(flags.iter_names().map(|(name, _)| name.to_owned()));
 
-        // This didn't work, so reset the bit.
-        de.parts.options.flags &= !serde::SerializerFlags::HUMAN_READABLE_ENUMS;
+    if opts.manual_compression {
+        parts.push("manual compression".to_owned());
+    }
+    if opts.shallow {
+        parts.push(format!(
+            "mask {}",
+            super::utils::format_mask(opts.property_mask)
+        ));
+    }
+
+    parts.join(" + ")
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Deserializes `data` with serializer options guessed from it.
+pub fn try_guess(
+    opts: serde::SerializerOptions,
+    types: Arc<TypeList>,
+    mut data: &[u8],
+) -> eyre::Result<Report> {
+    let (mut de, confidence) =
+        serde::Serializer::with_guessed_options_and_confidence(opts, types, data)?;
+
+    if data.get(0..4) == Some(BIND_MAGIC) {
+        data = data.get(4..).unwrap();
     }
 
     Ok(Report {
-        value: res,
+        value: de.deserialize::<serde::PropertyClass>(data),
         opts: de.parts.options,
+        confidence: Some(confidence),
     })
 }
 
@@ -110,6 +274,17 @@ fn write_config<W: Write>(mut writer: W, report: &Report) -> io::Result<()> {
     )?;
     writeln!(writer, "  Property mask: {:?}", report.opts.property_mask)?;
 
+    if let Some(confidence) = &report.confidence {
+        writeln!(writer)?;
+        writeln!(writer, "Confidence:")?;
+        writeln!(writer, "  Property mask: {:?}", confidence.property_mask)?;
+        writeln!(
+            writer,
+            "  Human-readable enums: {:?}",
+            confidence.human_readable_enums
+        )?;
+    }
+
     Ok(())
 }
 
@@ -0,0 +1,307 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use eyre::Context;
+use katsuba_object_property::Value;
+use katsuba_pipeline::DeserializeJob;
+use katsuba_types::TypeList;
+use katsuba_wad::{Archive, Inflater};
+use serde::Serialize;
+use serde_json::Value as Json;
+
+use super::parallel;
+
+/// A comparison operator in a [`Condition`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+/// A filter on the property values of indexed objects, written like
+/// `m_objectName contains 'Raven'`.
+#[derive(Clone, Debug)]
+pub struct Condition {
+    path: String,
+    op: Op,
+    literal: Json,
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(3, char::is_whitespace);
+        let (Some(path), Some(op), Some(literal)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err("expected a condition like `<path> <op> <value>`".to_owned());
+        };
+
+        let op = match op {
+            "==" | "=" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "contains" => Op::Contains,
+            _ => return Err(format!("unknown operator '{op}'")),
+        };
+
+        // Quoted literals are always strings, everything else is
+        // tried as a JSON number or boolean first.
+        let literal = literal.trim();
+        let quoted = ['\'', '"']
+            .iter()
+            .find_map(|&q| literal.strip_prefix(q)?.strip_suffix(q));
+        let literal = match quoted {
+            Some(s) => Json::String(s.to_owned()),
+            None => serde_json::from_str(literal).unwrap_or_else(|_| Json::String(literal.into())),
+        };
+
+        Ok(Self {
+            path: path.to_owned(),
+            op,
+            literal,
+        })
+    }
+}
+
+impl Condition {
+    /// Checks whether the condition holds for the object `root`.
+    ///
+    /// Conditions on paths which don't exist never hold.
+    pub fn matches(&self, root: &Value) -> bool {
+        let Some(value) = root.get_path(&self.path) else {
+            return false;
+        };
+        let Ok(value) = serde_json::to_value(value) else {
+            return false;
+        };
+
+        let ordering = || match (value.as_f64(), self.literal.as_f64()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        };
+
+        match self.op {
+            Op::Eq => json_eq(&value, &self.literal),
+            Op::Ne => !json_eq(&value, &self.literal),
+            Op::Lt => ordering().is_some_and(|o| o.is_lt()),
+            Op::Le => ordering().is_some_and(|o| o.is_le()),
+            Op::Gt => ordering().is_some_and(|o| o.is_gt()),
+            Op::Ge => ordering().is_some_and(|o| o.is_ge()),
+            Op::Contains => match (&value, &self.literal) {
+                (Json::String(s), Json::String(needle)) => s.contains(needle.as_str()),
+                (Json::Array(items), literal) => items.iter().any(|v| json_eq(v, literal)),
+                _ => false,
+            },
+        }
+    }
+}
+
+// Compares JSON values, treating numbers of different types as equal
+// when they have the same value.
+fn json_eq(a: &Json, b: &Json) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// A single row of the index.
+#[derive(Serialize)]
+struct Row<'a> {
+    file: &'a str,
+    #[serde(rename = "type")]
+    ty: &'a str,
+    path: &'a str,
+    value: &'a Value,
+}
+
+// Writes one row per leaf value in `value` as JSON lines.
+//
+// `ty` is the type name of the object the value belongs to.
+fn write_rows(
+    out: &mut Vec<u8>,
+    types: &TypeList,
+    file: &str,
+    ty: &str,
+    path: &mut String,
+    value: &Value,
+) -> eyre::Result<()> {
+    let len = path.len();
+    match value {
+        Value::Shared(v) => write_rows(out, types, file, ty, path, v)?,
+
+        Value::Object { hash, obj } => {
+            let name = types.0.get(hash).map(|t| t.name.to_string());
+            let ty = name.unwrap_or_else(|| hash.to_string());
+            for (key, v) in obj.iter() {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                write_rows(out, types, file, &ty, path, v)?;
+                path.truncate(len);
+            }
+        }
+
+        Value::List(list) => {
+            for (idx, v) in list.iter().enumerate() {
+                let _ = write!(path, "[{idx}]");
+                write_rows(out, types, file, ty, path, v)?;
+                path.truncate(len);
+            }
+        }
+
+        // Null objects hold no values worth indexing.
+        Value::Empty => {}
+
+        value => {
+            let row = Row {
+                file,
+                ty,
+                path,
+                value,
+            };
+            serde_json::to_writer(&mut *out, &row)?;
+            out.push(b'\n');
+        }
+    }
+
+    Ok(())
+}
+
+/// Options for building an index over many objects.
+pub struct IndexOptions {
+    /// The conditions every indexed object must satisfy.
+    pub conditions: Vec<Condition>,
+    /// The file to write the index to, or stdout.
+    pub output: Option<PathBuf>,
+}
+
+// Deserializes `data` and renders its rows, if it passes the conditions.
+fn index_one(
+    job: &mut DeserializeJob,
+    types: &TypeList,
+    opts: &IndexOptions,
+    name: &str,
+    data: &[u8],
+) -> eyre::Result<Vec<u8>> {
+    let value = job.deserialize(data)?;
+
+    let mut out = Vec::new();
+    if opts.conditions.iter().all(|c| c.matches(&value)) {
+        write_rows(&mut out, types, name, "", &mut String::new(), &value)?;
+    }
+
+    Ok(out)
+}
+
+fn write_index(
+    opts: &IndexOptions,
+    f: impl FnOnce(&mut dyn Write) -> eyre::Result<()>,
+) -> eyre::Result<()> {
+    match &opts.output {
+        Some(path) => {
+            let file = fs::File::create(path)
+                .with_context(|| format!("failed to create '{}'", path.display()))?;
+            let mut writer = BufWriter::new(file);
+            f(&mut writer)?;
+            writer.flush().map_err(Into::into)
+        }
+        None => f(&mut io::stdout().lock()),
+    }
+}
+
+// Collects the rows produced for every item and reports failures.
+fn sink_rows<'a>(
+    writer: &'a mut dyn Write,
+) -> impl FnMut(&str, eyre::Result<Vec<u8>>) -> eyre::Result<()> + 'a {
+    move |name, res| {
+        match res {
+            Ok(rows) => writer.write_all(&rows)?,
+            Err(e) => log::warn!("Skipping '{name}': {e}"),
+        }
+        Ok(())
+    }
+}
+
+/// Indexes every file in the directory tree at `root`.
+pub fn index_dir(
+    make: impl FnMut() -> eyre::Result<DeserializeJob>,
+    types: &TypeList,
+    root: &Path,
+    opts: &IndexOptions,
+) -> eyre::Result<()> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(root) {
+        let entry = entry.context("failed to query input directory")?;
+        if entry.file_type().is_file() {
+            let name = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            files.push((name.display().to_string(), entry.into_path()));
+        }
+    }
+    files.sort();
+
+    write_index(opts, |writer| {
+        let mut sink = sink_rows(writer);
+        parallel::convert_all(
+            &files,
+            make,
+            |job, (name, path)| {
+                let data = fs::read(path)?;
+                index_one(job, types, opts, name, &data)
+            },
+            |(name, _), res| sink(name, res),
+        )
+    })
+}
+
+/// Indexes the entries in the archive at `path` which match `pattern`.
+pub fn index_archive(
+    mut make: impl FnMut() -> eyre::Result<DeserializeJob>,
+    types: &TypeList,
+    path: &Path,
+    pattern: &str,
+    opts: &IndexOptions,
+) -> eyre::Result<()> {
+    let archive = Archive::open_mmap(path)
+        .with_context(|| format!("failed to open archive at '{}'", path.display()))?;
+    let files: Vec<_> = archive
+        .iter_glob(pattern)?
+        .filter(|(_, f)| !f.is_unpatched)
+        .collect();
+
+    write_index(opts, |writer| {
+        let mut sink = sink_rows(writer);
+        parallel::convert_all(
+            &files,
+            || Ok((make()?, Inflater::new())),
+            |(job, inflater), (name, file)| {
+                let contents = archive
+                    .file_contents(file)
+                    .ok_or_else(|| eyre::eyre!("file is unpatched"))?;
+                let data = if file.compressed {
+                    inflater.decompress(contents, file.uncompressed_size as _)?
+                } else {
+                    contents
+                };
+
+                index_one(job, types, opts, name, data)
+            },
+            |(name, _), res| sink(name, res),
+        )
+    })
+}
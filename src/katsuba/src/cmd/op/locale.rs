@@ -0,0 +1,79 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use eyre::Context;
+use katsuba_lang::LangTable;
+use katsuba_object_property::serde::LocaleProvider;
+use katsuba_wad::{Archive, Inflater};
+
+/// The localized strings of all language tables in a `Locale`
+/// directory or archive.
+#[derive(Debug, Default)]
+pub struct Strings(HashMap<String, String>);
+
+impl Strings {
+    /// Loads every `.lang` table in the directory tree or the KIWAD
+    /// archive at `path`.
+    ///
+    /// When a key is defined more than once, the first definition
+    /// in path order wins. Tables which fail to parse are skipped.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let mut strings = Self::default();
+
+        if path.is_dir() {
+            let mut files = Vec::new();
+            for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+                let entry = entry.context("failed to query locale directory")?;
+                if entry.path().extension().is_some_and(|e| e == "lang") {
+                    files.push(entry.into_path());
+                }
+            }
+
+            for file in files {
+                let name = file.display().to_string();
+                let data = fs::read(&file).with_context(|| format!("failed to read '{name}'"))?;
+                strings.add(&name, LangTable::parse(&data).map_err(Into::into));
+            }
+        } else {
+            let archive = Archive::open_mmap(path)
+                .with_context(|| format!("failed to open archive at '{}'", path.display()))?;
+
+            let mut inflater = Inflater::new();
+            for (name, file) in archive.iter_glob("**/*.lang")? {
+                let Some(contents) = archive.file_contents(file) else {
+                    continue;
+                };
+                let table: eyre::Result<_> = match file.compressed {
+                    true => inflater
+                        .decompress(contents, file.uncompressed_size as _)
+                        .map_err(Into::into)
+                        .and_then(|data| LangTable::parse(data).map_err(Into::into)),
+                    false => LangTable::parse(contents).map_err(Into::into),
+                };
+                strings.add(name, table);
+            }
+        }
+
+        log::info!("Loaded {} localized strings", strings.0.len());
+        Ok(strings)
+    }
+
+    fn add(&mut self, name: &str, table: eyre::Result<LangTable>) {
+        let table = match table {
+            Ok(table) => table,
+            Err(e) => {
+                log::warn!("Skipping '{name}': {e}");
+                return;
+            }
+        };
+
+        for (key, value) in table.iter_qualified() {
+            self.0.entry(key).or_insert_with(|| value.to_owned());
+        }
+    }
+}
+
+impl LocaleProvider for Strings {
+    fn lookup(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+}
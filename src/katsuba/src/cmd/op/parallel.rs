@@ -0,0 +1,110 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use eyre::Context;
+use katsuba_executor::Executor;
+
+use crate::cli::{summary, OutputSource};
+
+/// Runs `convert` over all `items` on worker threads and hands the
+/// results to `sink` on the calling thread as they come in.
+///
+/// Every worker owns a state created by `make`, such as a serializer
+/// instance. When `sink` fails, the remaining items are abandoned.
+pub fn convert_all<I, S, T, C, K>(
+    items: &[I],
+    mut make: impl FnMut() -> eyre::Result<S>,
+    convert: C,
+    mut sink: K,
+) -> eyre::Result<()>
+where
+    I: Sync,
+    S: Send,
+    T: Send,
+    C: Fn(&mut S, &I) -> eyre::Result<T> + Sync,
+    K: FnMut(&I, eyre::Result<T>) -> eyre::Result<()>,
+{
+    let jobs = katsuba_executor::worker_threads()?.clamp(1, items.len().max(1));
+    let states = (0..jobs)
+        .map(|_| make())
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    let next = &AtomicUsize::new(0);
+    let convert = &convert;
+    thread::scope(|s| {
+        // Bound the channel so finished results don't pile up in memory
+        // when writing them falls behind.
+        let (tx, rx) = mpsc::sync_channel(jobs * 2);
+        for mut state in states {
+            let tx = tx.clone();
+            s.spawn(move || loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(idx) else {
+                    break;
+                };
+
+                // The receiver is gone when the sink failed.
+                if tx.send((idx, convert(&mut state, item))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        for (idx, res) in rx {
+            sink(&items[idx], res)?;
+        }
+        Ok(())
+    })
+}
+
+/// Converts many input files on worker threads and writes the results
+/// into the output directory with `write` through a threaded executor.
+///
+/// `convert` receives the path of every file along with its data.
+pub fn process_files<S, T, C, W>(
+    paths: Vec<PathBuf>,
+    out: OutputSource,
+    make: impl FnMut() -> eyre::Result<S>,
+    convert: C,
+    mut write: W,
+) -> eyre::Result<()>
+where
+    S: Send,
+    T: Send,
+    C: Fn(&mut S, &str, &[u8]) -> eyre::Result<T> + Sync,
+    W: FnMut(&Executor, Option<PathBuf>, T, OutputSource) -> eyre::Result<()>,
+{
+    let OutputSource::Dir(dir, _) = &out else {
+        eyre::bail!("many inputs need an output directory");
+    };
+    fs::create_dir_all(dir)?;
+
+    let executor = Executor::get()?;
+    convert_all(
+        &paths,
+        make,
+        |state, path| {
+            let data = fs::read(path)
+                .with_context(|| format!("failed to read file '{}'", path.display()))?;
+            summary::record_input(data.len() as u64);
+
+            convert(state, &path.to_string_lossy(), &data)
+        },
+        |path, res| write(&executor, Some(path.clone()), res?, out.clone()),
+    )?;
+
+    // Await the completion of all pending writes.
+    for pending in executor.join() {
+        pending?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use std::{fs, path::Path};
+
+use eyre::Context;
+use katsuba_object_property::serde::{
+    self, Event, PropertyClass, SerializerFlags, SerializerOptions, BIND_MAGIC,
+};
+
+// A segment of the path to the property currently being read.
+enum Segment<'a> {
+    Object(Option<&'a str>),
+    List(Option<usize>),
+}
+
+fn render(path: &[Segment<'_>]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        match segment {
+            Segment::Object(Some(name)) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                out.push_str(name);
+            }
+            Segment::List(Some(idx)) => out.push_str(&format!("[{idx}]")),
+            _ => {}
+        }
+    }
+
+    if out.is_empty() {
+        out.push_str("<root>");
+    }
+    out
+}
+
+// Advances the index of the innermost list when one of its elements
+// starts.
+fn next_element(path: &mut [Segment<'_>]) {
+    if let Some(Segment::List(idx)) = path.last_mut() {
+        *idx = Some(idx.map_or(0, |i| i + 1));
+    }
+}
+
+// Gets the number of framing bits that precede the object data in
+// `data`, or `None` if the object data is compressed.
+fn framing_bits(opts: &SerializerOptions, data: &[u8]) -> Option<usize> {
+    if opts.manual_compression {
+        return None;
+    }
+
+    let mut bits = 0;
+    if opts.flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+        bits += u32::BITS as usize;
+    }
+    if opts.flags.contains(SerializerFlags::WITH_COMPRESSION) {
+        if data.get(bits / 8).copied() != Some(0) {
+            return None;
+        }
+        bits += u8::BITS as usize;
+    }
+
+    Some(bits)
+}
+
+// Finds the path of the property whose value contains the bit at
+// `bit` in the object data.
+fn locate(
+    ser: &mut serde::Serializer,
+    data: &[u8],
+    bit: usize,
+) -> Result<Option<String>, serde::Error> {
+    let mut events = ser.events::<PropertyClass>(data)?;
+    let mut path = Vec::new();
+
+    while let Some(event) = events.next_event()? {
+        match event {
+            Event::ObjectStart { .. } => {
+                next_element(&mut path);
+                path.push(Segment::Object(None));
+            }
+            Event::Property { name } => {
+                if let Some(Segment::Object(current)) = path.last_mut() {
+                    *current = Some(name);
+                }
+            }
+            Event::ListStart { .. } => path.push(Segment::List(None)),
+            Event::ObjectEnd | Event::ListEnd => {
+                path.pop();
+            }
+            Event::Value(..) | Event::Str(..) | Event::Null => next_element(&mut path),
+        }
+
+        if events.position() > bit {
+            return Ok(Some(render(&path)));
+        }
+    }
+
+    Ok(None)
+}
+
+// Finds the offset of the first bit that differs between `a` and `b`.
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    match a.iter().zip(b).position(|(x, y)| x != y) {
+        Some(idx) => Some(idx * 8 + (a[idx] ^ b[idx]).trailing_zeros() as usize),
+        None => (a.len() != b.len()).then(|| a.len().min(b.len()) * 8),
+    }
+}
+
+/// Deserializes the object in the file at `path`, serializes it again
+/// and verifies that the result is identical to the original data.
+///
+/// Files starting with [`BIND_MAGIC`] are handled like persistent game
+/// files. On mismatch, the first diverging bit and the path of the
+/// property containing it are reported as an error.
+pub fn roundtrip(ser: &mut serde::Serializer, path: &Path) -> eyre::Result<()> {
+    let data = fs::read(path).with_context(|| format!("failed to read '{}'", path.display()))?;
+
+    let (magic, body) = match data.strip_prefix(BIND_MAGIC) {
+        Some(body) => {
+            ser.parts.options.shallow = false;
+            ser.parts.options.flags |= SerializerFlags::STATEFUL_FLAGS;
+            (BIND_MAGIC.len(), body)
+        }
+        None => (0, &data[..]),
+    };
+    let options = ser.parts.options.clone();
+
+    let value = ser
+        .deserialize::<PropertyClass>(body)
+        .with_context(|| format!("failed to deserialize '{}'", path.display()))?;
+
+    // Deserialization adopts the flags from stateful data, which may
+    // not name themselves as such.
+    if options.flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+        ser.parts.options.flags |= SerializerFlags::STATEFUL_FLAGS;
+    }
+    let effective = ser.parts.options.clone();
+
+    let out = ser
+        .serialize::<PropertyClass>(&value)
+        .with_context(|| format!("failed to serialize '{}'", path.display()))?;
+
+    let Some(bit) = first_difference(body, &out) else {
+        println!(
+            "'{}': {} bytes round-trip identically",
+            path.display(),
+            data.len()
+        );
+        return Ok(());
+    };
+
+    // Walk the original data once more to find the diverging property.
+    ser.parts.options = options;
+    let property = match framing_bits(&effective, body) {
+        Some(framing) if bit >= framing => locate(ser, body, bit - framing)?,
+        Some(_) => Some("<framing>".to_owned()),
+        None => None,
+    };
+
+    let bit = bit + magic * 8;
+    match property {
+        Some(property) => eyre::bail!(
+            "'{}' diverges at bit {bit} (byte {}) in '{property}'",
+            path.display(),
+            bit / 8
+        ),
+        None => eyre::bail!(
+            "'{}' diverges at bit {bit} (byte {})",
+            path.display(),
+            bit / 8
+        ),
+    }
+}
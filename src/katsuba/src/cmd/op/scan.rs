@@ -0,0 +1,57 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use eyre::Context;
+use katsuba_object_property::serde::{self, SerializerFlags};
+use katsuba_types::TypeList;
+
+/// Prints the type hashes referenced by the file at `path`, ordered by
+/// their number of occurrences.
+///
+/// Compressed data is inflated according to `options` first. Files
+/// starting with [`serde::BIND_MAGIC`] are handled like persistent
+/// game files.
+pub fn scan(
+    mut options: serde::SerializerOptions,
+    types: Arc<TypeList>,
+    path: PathBuf,
+    bit_aligned: bool,
+) -> eyre::Result<()> {
+    let data =
+        fs::read(&path).with_context(|| format!("failed to read file '{}'", path.display()))?;
+    let data = match data.strip_prefix(serde::BIND_MAGIC) {
+        Some(body) => {
+            options.flags |= SerializerFlags::STATEFUL_FLAGS;
+            body
+        }
+        None => &data[..],
+    };
+
+    let mut de = serde::Serializer::new(options, types.clone())?;
+    let mut refs = serde::scan_type_hashes(&mut de, data, bit_aligned)
+        .with_context(|| format!("failed to unpack '{}'", path.display()))?;
+    refs.sort_by_key(|r| Reverse(r.offsets.len()));
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    for r in refs {
+        // Hashes are only reported when they are in the type list.
+        let name = &types.0[&r.hash].name;
+        let (bytes, bits) = (r.offsets[0] / 8, r.offsets[0] % 8);
+
+        writeln!(
+            stdout,
+            "{:#010x} {:>6}x  first at {bytes}:{bits}  {name}",
+            r.hash,
+            r.offsets.len(),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use katsuba_object_property::{
+    serde::{self, PropertyClass},
+    Value,
+};
+
+/// Serializes an object from its JSON representation to binary state.
+///
+/// With `bind`, the output is framed as a game file with the
+/// [`serde::BIND_MAGIC`] header.
+pub fn serialize(ser: &mut serde::Serializer, data: &[u8], bind: bool) -> eyre::Result<Vec<u8>> {
+    let value: Value = serde_json::from_slice(data)?;
+    let state = ser.serialize::<PropertyClass>(&value)?;
+
+    Ok(if bind {
+        [serde::BIND_MAGIC, &state].concat()
+    } else {
+        state
+    })
+}
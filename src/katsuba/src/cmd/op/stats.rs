@@ -0,0 +1,104 @@
+use std::{cmp::Reverse, collections::HashMap, sync::Mutex, time::Duration};
+
+use katsuba_object_property::Value;
+use katsuba_types::TypeList;
+
+// The width of the longest bar in the type histogram.
+const BAR_WIDTH: usize = 40;
+
+// Measurements for a single deserialized input.
+struct Entry {
+    name: String,
+    elapsed: Duration,
+    size: usize,
+    objects: usize,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: Vec<Entry>,
+    types: HashMap<u32, usize>,
+}
+
+/// Profiling data collected while deserializing a batch of inputs.
+///
+/// Inputs may be recorded from multiple threads at once.
+#[derive(Default)]
+pub struct Stats(Mutex<Inner>);
+
+// Counts the objects in `value` by their type hashes.
+fn count_objects(value: &Value, types: &mut HashMap<u32, usize>) -> usize {
+    match value {
+        Value::Shared(v) => count_objects(v, types),
+        Value::List(list) => list.iter().map(|v| count_objects(v, types)).sum(),
+        Value::Object { hash, obj } => {
+            *types.entry(*hash).or_default() += 1;
+            1 + obj.values().map(|v| count_objects(v, types)).sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+impl Stats {
+    /// Records the input `name`, which took `elapsed` to deserialize
+    /// from `size` bytes of object data into `value`.
+    pub fn record(&self, name: &str, elapsed: Duration, size: usize, value: &Value) {
+        let mut inner = self.0.lock().unwrap();
+        let objects = count_objects(value, &mut inner.types);
+        inner.entries.push(Entry {
+            name: name.to_owned(),
+            elapsed,
+            size,
+            objects,
+        });
+    }
+
+    /// Prints the collected stats to stderr, naming object types
+    /// through `types`.
+    pub fn print(&self, types: &TypeList) {
+        let mut inner = self.0.lock().unwrap();
+
+        // The slowest inputs are the most interesting ones.
+        inner.entries.sort_by_key(|e| Reverse(e.elapsed));
+
+        eprintln!("Inputs:");
+        eprintln!("  {:>10}  {:>12}  {:>8}  Name", "Time", "Size", "Objects");
+        for entry in &inner.entries {
+            eprintln!(
+                "  {:>8.3}ms  {:>12}  {:>8}  {}",
+                entry.elapsed.as_secs_f64() * 1000.0,
+                entry.size,
+                entry.objects,
+                entry.name
+            );
+        }
+
+        let total: Duration = inner.entries.iter().map(|e| e.elapsed).sum();
+        let size: usize = inner.entries.iter().map(|e| e.size).sum();
+        eprintln!(
+            "  {:>8.3}ms  {size:>12}  {:>8}  <total>",
+            total.as_secs_f64() * 1000.0,
+            inner.types.values().sum::<usize>()
+        );
+
+        let mut histogram: Vec<_> = inner
+            .types
+            .iter()
+            .map(|(hash, count)| {
+                let name = match types.0.get(hash) {
+                    Some(t) => t.name.to_string(),
+                    None => format!("{hash:#010x}"),
+                };
+                (name, *count)
+            })
+            .collect();
+        histogram.sort_by(|(na, ca), (nb, cb)| cb.cmp(ca).then_with(|| na.cmp(nb)));
+
+        let max = histogram.first().map_or(1, |(_, count)| *count);
+        eprintln!("Types:");
+        for (name, count) in histogram {
+            let bar = "#".repeat((count * BAR_WIDTH).div_ceil(max));
+            eprintln!("  {count:>8}  {bar:<BAR_WIDTH$}  {name}");
+        }
+    }
+}
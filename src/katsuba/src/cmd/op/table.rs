@@ -0,0 +1,42 @@
+use std::borrow::Cow;
+
+use katsuba_object_property::{value::Table, Value};
+
+/// Renders a [`Table`] as CSV with a header row of column paths.
+pub fn render_csv(table: &Table<'_>) -> eyre::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(&table.columns)?;
+    for row in &table.rows {
+        let cells = row.iter().map(|cell| match cell {
+            Some(value) => cell_text(value),
+            None => Ok(Cow::Borrowed("")),
+        });
+        let cells = cells.collect::<eyre::Result<Vec<_>>>()?;
+        writer.write_record(cells.iter().map(|c| c.as_bytes()))?;
+    }
+
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+// Formats a leaf value for a spreadsheet cell.
+//
+// Scalars and strings are written as-is, compound leaf types fall
+// back to their JSON representation.
+fn cell_text(value: &Value) -> eyre::Result<Cow<'_, str>> {
+    let text = match value {
+        Value::Unsigned(v) => v.to_string(),
+        Value::Signed(v) | Value::Enum(v) => v.to_string(),
+        Value::Float(v) => v.to_string(),
+        Value::Bool(v) => v.to_string(),
+        Value::NamedEnum {
+            name: Some(name), ..
+        } => name.to_string(),
+        Value::NamedEnum { value, .. } => value.to_string(),
+        Value::String(s) => return Ok(String::from_utf8_lossy(&s.0)),
+        Value::WString(s) => String::from_utf16_lossy(&s.0),
+        value => serde_json::to_string(value)?,
+    };
+
+    Ok(Cow::Owned(text))
+}
@@ -1,27 +1,63 @@
-use std::{fs, io::BufReader, path::PathBuf};
+use katsuba_object_property::serde::SerializerFlags;
+use katsuba_types::{PropertyFlags, TypeList};
 
-use eyre::Context;
-use katsuba_types::TypeList;
+/// Resolves a type given by either its hash or its name to the name,
+/// validating it against the type list.
+pub fn resolve_type_name(types: &TypeList, s: &str) -> eyre::Result<String> {
+    let type_def = match s.parse::<u32>() {
+        Ok(hash) => types.0.get(&hash),
+        Err(_) => types.0.values().find(|t| t.name == s),
+    };
 
-/// Reads all the given type list paths and merges them into a single
-/// [`TypeList`] instance.
-pub fn merge_type_lists(paths: Vec<PathBuf>) -> eyre::Result<TypeList> {
-    let (first, rest) = paths
-        .split_first()
-        .ok_or_else(|| eyre::eyre!("at least one type list is required for deserialization"))?;
+    type_def
+        .map(|t| t.name.to_string())
+        .ok_or_else(|| eyre::eyre!("type '{s}' is not in the type lists"))
+}
 
-    let first = fs::File::open(first)
-        .with_context(|| format!("failed to open type list at '{}'", first.display()))?;
-    let mut list = TypeList::from_reader(BufReader::new(first))?;
+/// Parses serializer flags from either a raw integer or a list of
+/// flag names separated by `,` or `|`.
+pub fn parse_flags(s: &str) -> Result<SerializerFlags, String> {
+    match s.parse::<u32>() {
+        Ok(bits) => Ok(SerializerFlags::from_bits_truncate(bits)),
+        Err(_) => bitflags::parser::from_str(&s.replace(',', "|")).map_err(|e| format!("{e}")),
+    }
+}
 
-    // Merge remaining type lists into `list`.
-    for path in rest {
-        let file = fs::File::open(path)
-            .with_context(|| format!("failed to open type list at '{}'", path.display()))?;
-        let next = TypeList::from_reader(BufReader::new(file))?;
+/// Parses a property mask from either a raw integer or a list of
+/// flag names separated by `,` or `|`.
+pub fn parse_mask(s: &str) -> Result<PropertyFlags, String> {
+    match s.parse::<u32>() {
+        Ok(bits) => Ok(PropertyFlags::from_bits_retain(bits)),
+        Err(_) => bitflags::parser::from_str(&s.replace(',', "|")).map_err(|e| format!("{e}")),
+    }
+}
 
-        list.merge(next);
+/// Formats a property mask as its flag names, e.g.
+/// `TRANSMIT | PRIVILEGED_TRANSMIT`.
+pub fn format_mask(mask: PropertyFlags) -> String {
+    if mask.is_empty() {
+        return "0".to_owned();
     }
 
-    Ok(list)
+    let mut out = String::new();
+    bitflags::parser::to_writer(&mask, &mut out).unwrap();
+    out
+}
+
+/// Prints all known serializer flags along with their meanings.
+pub fn list_flags() {
+    for (name, flag) in SerializerFlags::all().iter_names() {
+        println!("{name:<25} {:#04x}  {}", flag.bits(), describe_flag(flag));
+    }
+}
+
+fn describe_flag(flag: SerializerFlags) -> &'static str {
+    match flag {
+        SerializerFlags::STATEFUL_FLAGS => "the serializer flags are stored in the data",
+        SerializerFlags::COMPACT_LENGTH_PREFIXES => "small length prefixes are compressed",
+        SerializerFlags::HUMAN_READABLE_ENUMS => "enums are encoded as strings",
+        SerializerFlags::WITH_COMPRESSION => "the data is zlib-compressed",
+        SerializerFlags::FORBID_DELTA_ENCODE => "delta-encoded values must be present",
+        _ => "",
+    }
 }
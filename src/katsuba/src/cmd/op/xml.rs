@@ -0,0 +1,149 @@
+use std::fmt::Write;
+
+use katsuba_object_property::{value::Object, Value};
+use katsuba_types::{Property, TypeList};
+
+/// Renders a deserialized object in the XML dialect of the game's own
+/// serializer.
+///
+/// Objects become `Class` elements named after their type and every
+/// property becomes an element holding its value. Dynamic containers
+/// repeat the property element once per value and compound leaf types
+/// like vectors are written as comma-separated components.
+pub fn render(types: &TypeList, value: &Value) -> Vec<u8> {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Objects>\n");
+    if let Value::Object { hash, obj } = value.unshared() {
+        write_class(types, &mut out, *hash, obj, 1);
+    }
+    out.push_str("</Objects>\n");
+
+    out.into_bytes()
+}
+
+fn indent(out: &mut String, depth: usize) {
+    out.extend(std::iter::repeat_n('\t', depth));
+}
+
+fn escape(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn write_class(types: &TypeList, out: &mut String, hash: u32, obj: &Object, depth: usize) {
+    let type_def = types.0.get(&hash);
+
+    indent(out, depth);
+    out.push_str("<Class Name=\"");
+    match type_def {
+        Some(t) => escape(out, &t.name),
+        None => {
+            let _ = write!(out, "{hash}");
+        }
+    }
+    out.push_str("\">\n");
+
+    for (name, value) in obj {
+        let property = type_def.and_then(|t| t.properties.iter().find(|p| p.name == name.as_str()));
+        match value.unshared() {
+            Value::List(list) => {
+                for element in list.iter() {
+                    write_property(types, out, name, property, element, depth + 1);
+                }
+            }
+            // The game has no notation for properties it doesn't know.
+            Value::Unknown { .. } => {}
+            value => write_property(types, out, name, property, value, depth + 1),
+        }
+    }
+
+    indent(out, depth);
+    out.push_str("</Class>\n");
+}
+
+fn write_property(
+    types: &TypeList,
+    out: &mut String,
+    name: &str,
+    property: Option<&Property>,
+    value: &Value,
+    depth: usize,
+) {
+    indent(out, depth);
+    let _ = write!(out, "<{name}>");
+
+    match value.unshared() {
+        Value::Object { hash, obj } => {
+            out.push('\n');
+            write_class(types, out, *hash, obj, depth + 1);
+            indent(out, depth);
+        }
+        value => write_leaf(out, property, value),
+    }
+
+    let _ = writeln!(out, "</{name}>");
+}
+
+fn write_leaf(out: &mut String, property: Option<&Property>, value: &Value) {
+    let _ = match value {
+        Value::Empty => Ok(()),
+        Value::Unsigned(v) => write!(out, "{v}"),
+        Value::Signed(v) => write!(out, "{v}"),
+        Value::Float(v) => write!(out, "{v}"),
+        Value::Bool(v) => write!(out, "{}", *v as u8),
+        Value::String(v) => {
+            escape(out, &v.to_string());
+            Ok(())
+        }
+        Value::WString(v) => {
+            escape(out, &v.to_string());
+            Ok(())
+        }
+
+        // Enums are written by name like the game does, when the
+        // variant is known.
+        Value::Enum(v) => match property.and_then(|p| p.encode_enum_variant(*v).ok()) {
+            Some(name) => {
+                escape(out, &name);
+                Ok(())
+            }
+            None => write!(out, "{v}"),
+        },
+        Value::NamedEnum { name, value } => match name {
+            Some(name) => {
+                escape(out, name);
+                Ok(())
+            }
+            None => write!(out, "{value}"),
+        },
+
+        Value::Color(c) => write!(out, "{},{},{},{}", c.r, c.g, c.b, c.a),
+        Value::Vec3(v) => write!(out, "{},{},{}", v.x, v.y, v.z),
+        Value::Quat(q) => write!(out, "{},{},{},{}", q.x, q.y, q.z, q.w),
+        Value::Euler(e) => write!(out, "{},{},{}", e.pitch, e.roll, e.yaw),
+        Value::Mat3x3(m) => {
+            let mut components = m.i.iter().chain(&m.j).chain(&m.k);
+            if let Some(first) = components.next() {
+                let _ = write!(out, "{first}");
+            }
+            components.try_for_each(|c| write!(out, ",{c}"))
+        }
+        Value::PointInt(p) => write!(out, "{},{}", p.x, p.y),
+        Value::PointFloat(p) => write!(out, "{},{}", p.x, p.y),
+        Value::SizeInt(s) => write!(out, "{},{}", s.width, s.height),
+        Value::RectInt(r) => write!(out, "{},{},{},{}", r.left, r.top, r.right, r.bottom),
+        Value::RectFloat(r) => write!(out, "{},{},{},{}", r.left, r.top, r.right, r.bottom),
+
+        // Nested lists and objects are handled by the callers.
+        Value::List(..) | Value::Object { .. } | Value::Shared(..) | Value::Unknown { .. } => {
+            Ok(())
+        }
+    };
+}
@@ -2,7 +2,7 @@ use clap::{Args, Subcommand};
 use katsuba_poi::Poi as PoiFile;
 
 use super::Command;
-use crate::cli::{helpers, Bias, InputsOutputs, Processor};
+use crate::cli::{helpers, Bias, InputsOutputs, Processor, Selection};
 
 /// Subcommand for working with POI data.
 #[derive(Debug, Args)]
@@ -14,17 +14,23 @@ pub struct Poi {
 #[derive(Debug, Subcommand)]
 enum PoiCommand {
     /// Deserializes given Point of Interest files into JSON format.
-    De(InputsOutputs),
+    De {
+        #[clap(flatten)]
+        args: InputsOutputs,
+
+        #[clap(flatten)]
+        selection: Selection,
+    },
 }
 
 impl Command for Poi {
     fn handle(self) -> eyre::Result<()> {
         match self.command {
-            PoiCommand::De(args) => {
+            PoiCommand::De { args, selection } => {
                 let (inputs, outputs) = args.evaluate("de.json")?;
                 Processor::new(Bias::Current)?
                     .read_with(|r, _| PoiFile::parse(r).map_err(Into::into))
-                    .write_with(helpers::write_as_json)
+                    .write_with(helpers::write_selected_as_json(selection))
                     .process(inputs, outputs)
             }
         }
@@ -1,20 +1,57 @@
 use std::{
     fs,
+    io::{self, BufWriter, Read, Write},
     path::{Path, PathBuf},
 };
 
-use clap::{Args, Subcommand};
+use clap::{Args, Subcommand, ValueEnum};
 use eyre::Context;
-use katsuba_wad::{Archive, ArchiveBuilder};
+use katsuba_wad::{
+    glob::Matcher,
+    journal::{self, JournalDump},
+    merge::ConflictPolicy,
+    patch,
+    types::ArchiveFlags,
+    vfs::ArchiveFs,
+    zip, Archive, ArchiveBuilder, InflaterPool, JournalCache, OpenOptions,
+};
 
 use super::Command;
-use crate::cli::{Bias, InputsOutputs, Processor, Reader};
+use crate::{
+    cli::{Bias, InputsOutputs, Processor, Reader},
+    utils,
+};
 
 mod extract;
+use extract::{ExtractContext, ExtractOptions, ManifestAlgorithm};
+
+mod grep;
+use grep::GrepOptions;
+
+mod list;
+use list::ListOptions;
+
+mod metadata;
+
+mod pack;
+use pack::{PackJob, PackManifest};
+
+mod stats;
+
+mod strings;
+use strings::StringsOptions;
 
 /// Subcommand for working with KIWAD archives.
 #[derive(Debug, Args)]
 pub struct Wad {
+    /// Caches parsed archive journals in the user cache directory.
+    ///
+    /// Reopening unchanged archives then skips parsing the journal
+    /// and verifying CRCs, which speeds up repeated commands on
+    /// huge archives.
+    #[clap(long, global = true, env = "KATSUBA_JOURNAL_CACHE")]
+    journal_cache: bool,
+
     #[clap(subcommand)]
     command: WadCommand,
 }
@@ -22,6 +59,9 @@ pub struct Wad {
 #[derive(Debug, Subcommand)]
 enum WadCommand {
     /// Packs a directory into a KIWAD archive.
+    ///
+    /// Packing the same directory with the same options always
+    /// produces a byte-identical archive.
     Pack {
         /// The path to the input directory to pack.
         ///
@@ -29,17 +69,77 @@ enum WadCommand {
         /// subdirectories and files will be added to the archive.
         ///
         /// Note that this does not follow symbolic links.
-        input: PathBuf,
+        ///
+        /// When a manifest is given, this defaults to the root directory
+        /// it specifies.
+        #[clap(required_unless_present = "manifest")]
+        input: Option<PathBuf>,
+
+        /// A TOML manifest controlling how files are packed.
+        ///
+        /// The manifest may set the archive `version`, `flags` and
+        /// compression `level`, which take precedence over the command
+        /// line. It may also specify `exclude` patterns, an `order` of
+        /// patterns for the file data, a default for `compress`, and
+        /// `[[rule]]` tables overriding `compress` and `level` for files
+        /// matching their `glob`.
+        #[clap(long)]
+        manifest: Option<PathBuf>,
 
         /// Specifies flags which should be set on the newly created
         /// KIWAD archive.
         ///
+        /// Flags are given either as a number or as names separated
+        /// by `|`, such as `ROOT`.
+        ///
         /// Unless you know what you're doing, the use of this option
         /// is generally not recommended. The only exception to that
-        /// rule is when repacking Root.wad, in which case a value of
-        /// 1 must be set.
-        #[clap(short, default_value_t = 0)]
-        flags: u8,
+        /// rule is when repacking Root.wad, in which case `ROOT` must
+        /// be set.
+        #[clap(short, default_value = "0", value_parser = parse_archive_flags)]
+        flags: ArchiveFlags,
+
+        /// The zlib compression level to use, from 0 (none) to 12 (best).
+        ///
+        /// Lower levels are faster to compress at the cost of bigger
+        /// archive files.
+        #[clap(short, long, default_value_t = 12, value_parser = clap::value_parser!(u8).range(0..=12))]
+        level: u8,
+
+        /// Files smaller than this many bytes are stored uncompressed.
+        #[clap(long, default_value_t = 0)]
+        min_compressed_size: usize,
+
+        /// Records the mode and modification time of every packed file
+        /// in a sidecar manifest next to the output archive.
+        ///
+        /// The sidecar is named like the archive with a `.meta.json`
+        /// extension and can be restored with `wad unpack`.
+        #[clap(long)]
+        record_metadata: bool,
+
+        /// Re-opens the archive after packing and checks the file
+        /// journal and the CRCs of all files before replacing the
+        /// output.
+        ///
+        /// When verification fails, the previous output is kept.
+        #[clap(long)]
+        verify: bool,
+
+        /// Keeps watching the input directory after packing and
+        /// rebuilds the archive whenever files change.
+        ///
+        /// The archive is replaced atomically after each rebuild.
+        #[clap(long)]
+        watch: bool,
+
+        /// The number of threads to compress files on.
+        ///
+        /// Defaults to the number of worker threads configured for
+        /// Katsuba. The resulting archive is the same regardless of
+        /// this setting.
+        #[clap(short, long)]
+        jobs: Option<usize>,
 
         /// The optional output file to write the archive to.
         ///
@@ -53,17 +153,325 @@ enum WadCommand {
     Unpack {
         #[clap(flatten)]
         args: InputsOutputs,
+
+        /// Writes a manifest with digests of all extracted files next
+        /// to the output directory of each archive.
+        ///
+        /// Digests are computed on the worker threads as files are
+        /// written, so this adds no extra pass over the data.
+        #[clap(long, value_enum)]
+        emit_manifest: Option<ManifestAlgorithm>,
+
+        /// Verifies the CRC of every file as it is extracted, instead
+        /// of checking the whole archive upfront.
+        ///
+        /// This saves one pass over the archive data. Decompression
+        /// additionally validates the zlib checksum of compressed files.
+        #[clap(long)]
+        verify_on_extract: bool,
+
+        /// Restores the mode and modification time of extracted files
+        /// from the sidecar manifest of each archive, if present.
+        ///
+        /// Files without recorded metadata take on the mode of the
+        /// archive file itself.
+        #[clap(long)]
+        restore_metadata: bool,
+
+        /// Skips files which already exist in the output directory
+        /// with the expected size and contents.
+        ///
+        /// This makes it cheap to re-run an interrupted extraction,
+        /// since only missing or corrupted files are written again.
+        /// Skipped files keep their current metadata.
+        #[clap(long)]
+        resume: bool,
+
+        /// Only extracts files whose path matches these glob patterns.
+        ///
+        /// May be given multiple times. Patterns starting with `!`
+        /// exclude the files they match.
+        #[clap(short, long = "glob")]
+        glob: Vec<String>,
+
+        /// Limits the memory held by decompressed files which are
+        /// waiting to be written, in MiB.
+        ///
+        /// Extraction pauses until pending writes complete when the
+        /// budget is exhausted. Unlimited by default.
+        #[clap(long, value_name = "MIB")]
+        memory_budget: Option<usize>,
+
+        /// Archives to take the contents of unpatched files from.
+        ///
+        /// Like the game's patcher, only files with the CRC and sizes
+        /// recorded in the unpacked archive are used. May be given
+        /// multiple times, in which case the archives are searched in
+        /// order.
+        #[clap(long)]
+        fallback: Vec<PathBuf>,
+    },
+
+    /// Lists the files in a KIWAD archive.
+    ///
+    /// Every line holds the uncompressed size, the stored size, the
+    /// compression ratio, the CRC and the path of a file.
+    List {
+        /// The path to the archive to list.
+        input: PathBuf,
+
+        #[clap(flatten)]
+        opts: ListOptions,
+    },
+
+    /// Prints statistics about the files in a KIWAD archive.
+    ///
+    /// This includes total sizes, sizes grouped by file extension and
+    /// the largest files, to audit what dominates the archive size.
+    Stats {
+        /// The path to the archive to inspect.
+        input: PathBuf,
+
+        /// Prints the statistics as JSON instead of tables.
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Searches the decompressed contents of files in a KIWAD archive.
+    ///
+    /// Every match is printed as the path of the file and the byte
+    /// offset of the match, separated by a colon.
+    Grep {
+        /// The path to the archive to search.
+        input: PathBuf,
+
+        #[clap(flatten)]
+        opts: GrepOptions,
+    },
+
+    /// Extracts the strings of all language tables in a KIWAD archive
+    /// into one JSON object.
+    ///
+    /// Keys are qualified with the name of their table, as in
+    /// `Table_Key`. Conflicting definitions of the same key are
+    /// reported, keeping the first one in path order.
+    Strings {
+        /// The path to the archive to scan.
+        input: PathBuf,
+
+        #[clap(flatten)]
+        opts: StringsOptions,
+    },
+
+    /// Prints the contents of a single file in a KIWAD archive.
+    ///
+    /// Compressed files are decompressed before they are written.
+    Cat {
+        /// The path to the archive, or "-" to read it from stdin.
+        input: String,
+
+        /// The path of the file inside the archive.
+        path: String,
+
+        /// An optional output file for the contents.
+        ///
+        /// Defaults to "-" for printing to stdout.
+        #[clap(short, default_value = "-")]
+        output: PathBuf,
+    },
+
+    /// Merges several KIWAD archives into a single one.
+    ///
+    /// This is useful for flattening Root.wad and the patch archives
+    /// layered on top of it by the game client.
+    Merge {
+        /// The archives to merge, in load order.
+        ///
+        /// Archives given later are considered newer than the ones
+        /// before them.
+        #[clap(required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// The policy for resolving files present in more than one
+        /// input archive.
+        #[clap(short, long, value_enum, default_value_t = MergePolicy::TakeNewest)]
+        policy: MergePolicy,
+
+        /// The output file to write the merged archive to.
+        #[clap(short)]
+        output: PathBuf,
+    },
+
+    /// Converts a KIWAD archive into a standard ZIP file.
+    ///
+    /// Unpatched files have no data and are left out of the ZIP.
+    ToZip {
+        /// The path to the archive to convert.
+        input: PathBuf,
+
+        /// The path to the ZIP file to create.
+        output: PathBuf,
+
+        /// Copies already compressed file data into the ZIP without
+        /// recompressing it.
+        ///
+        /// This is considerably faster, but keeps the compression
+        /// ratio of the original archive.
+        #[clap(short, long)]
+        passthrough: bool,
+    },
+
+    /// Converts ZIP files into a KIWAD archive.
+    ///
+    /// Every file in the ZIP is recompressed for the archive, with
+    /// the exception of formats that are always stored uncompressed.
+    FromZip {
+        /// The path to the ZIP file to convert.
+        ///
+        /// When this is a directory, all ZIP files directly in it are
+        /// combined into one archive. They must not contain the same
+        /// paths.
+        input: PathBuf,
+
+        /// The path to the archive to create.
+        output: PathBuf,
+
+        /// Specifies flags which should be set on the newly created
+        /// KIWAD archive.
+        ///
+        /// See the pack command for details.
+        #[clap(short, default_value = "0", value_parser = parse_archive_flags)]
+        flags: ArchiveFlags,
+    },
+
+    /// Creates and applies binary patches between archive versions.
+    Patch {
+        #[clap(subcommand)]
+        command: PatchCommand,
+    },
+
+    /// Exports and imports the file journal of an archive as JSON.
+    Journal {
+        #[clap(subcommand)]
+        command: JournalCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum PatchCommand {
+    /// Creates a patch which transforms one archive into another.
+    ///
+    /// Unchanged files are referenced by the patch and modified
+    /// files are stored as compact deltas where possible.
+    Create {
+        /// The path to the old version of the archive.
+        old: PathBuf,
+
+        /// The path to the new version of the archive.
+        new: PathBuf,
+
+        /// The path to the patch file to create.
+        patch: PathBuf,
+    },
+
+    /// Applies a patch to an archive, producing its new version.
+    Apply {
+        /// The path to the old version of the archive.
+        old: PathBuf,
+
+        /// The path to the patch file to apply.
+        patch: PathBuf,
+
+        /// The path to the patched archive to create.
+        output: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum JournalCommand {
+    /// Exports the full journal of an archive as JSON.
+    ///
+    /// This includes the names, offsets, sizes, compression state
+    /// and CRCs of all files.
+    Export {
+        /// The path to the archive to export the journal of.
+        input: PathBuf,
+
+        /// The file to write the JSON journal to.
+        ///
+        /// If missing, the journal is printed to stdout.
+        #[clap(short)]
+        output: Option<PathBuf>,
     },
+
+    /// Rewrites the journal of an archive from an edited JSON export.
+    ///
+    /// File data is copied as-is, which allows for changes such as
+    /// renaming entries. All entries must still point into the file
+    /// data of the input archive.
+    Import {
+        /// The path to the archive to rewrite the journal of.
+        input: PathBuf,
+
+        /// The path to the JSON journal to import.
+        journal: PathBuf,
+
+        /// The path to the archive to create.
+        output: PathBuf,
+    },
+}
+
+/// Conflict resolution strategies for merging archives.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum MergePolicy {
+    /// Take the file from the archive given last.
+    TakeNewest,
+    /// Take the file from the archive given first.
+    TakeFirst,
+    /// Fail when a file is present in more than one archive.
+    Error,
+}
+
+impl From<MergePolicy> for ConflictPolicy {
+    fn from(value: MergePolicy) -> Self {
+        match value {
+            MergePolicy::TakeNewest => Self::TakeNewest,
+            MergePolicy::TakeFirst => Self::TakeFirst,
+            MergePolicy::Error => Self::Error,
+        }
+    }
 }
 
 impl Command for Wad {
     fn handle(self) -> eyre::Result<()> {
+        let mut open = OpenOptions::new();
+        if self.journal_cache {
+            open.journal_cache(JournalCache::default_location());
+        }
+
         match self.command {
             WadCommand::Pack {
                 input,
+                manifest: manifest_path,
                 flags,
+                level,
+                min_compressed_size,
+                record_metadata,
+                verify,
+                watch,
+                jobs,
                 output,
             } => {
+                let manifest = match &manifest_path {
+                    Some(path) => PackManifest::load(path)?,
+                    None => PackManifest::default(),
+                };
+                let input = match (input, &manifest_path) {
+                    (Some(input), _) => input,
+                    (None, Some(path)) => manifest.root(path),
+                    (None, None) => unreachable!(),
+                };
+
                 if !input.is_dir() {
                     eyre::bail!("input for packing must be a directory");
                 }
@@ -82,46 +490,291 @@ impl Command for Wad {
                     }
                 };
 
-                let mut builder = ArchiveBuilder::new(2, flags, &output).with_context(|| {
-                    format!("failed to build output archive at '{}'", output.display())
-                })?;
-
-                for entry in walkdir::WalkDir::new(&input) {
-                    let entry = entry.context("failed to query input directory")?;
-                    if !entry
-                        .metadata()
-                        .context("failed to obtain metadata for path")?
-                        .is_file()
-                    {
-                        continue;
-                    }
-
-                    let path = entry.path();
-                    let contents = fs::read(path)
-                        .with_context(|| format!("failed to read file at '{}'", path.display()))?;
+                let job = PackJob {
+                    input,
+                    output,
+                    manifest,
+                    flags: flags.bits(),
+                    level,
+                    min_compressed_size,
+                    record_metadata,
+                    verify,
+                    jobs: match jobs {
+                        Some(jobs) => jobs,
+                        None => katsuba_executor::worker_threads()?,
+                    },
+                };
 
-                    builder.add_file_compressed(path.strip_prefix(&input).unwrap(), &contents)?;
+                if watch {
+                    job.watch()
+                } else {
+                    job.run()
                 }
-
-                builder.finish()?;
-
-                Ok(())
             }
 
-            WadCommand::Unpack { args } => {
+            WadCommand::Unpack {
+                args,
+                emit_manifest,
+                verify_on_extract,
+                restore_metadata,
+                resume,
+                glob: patterns,
+                memory_budget,
+                fallback,
+            } => {
                 let (inputs, outputs) = args.evaluate("")?;
+                let matcher = Matcher::many(&patterns)?;
+                let fallback = fallback
+                    .iter()
+                    .map(|path| open_archive(&open, path))
+                    .collect::<eyre::Result<Vec<_>>>()?;
+                let inflaters = InflaterPool::new();
+                let cx = ExtractContext {
+                    inflaters: &inflaters,
+                    matcher: &matcher,
+                    fallback: &fallback,
+                    opts: ExtractOptions {
+                        manifest: emit_manifest,
+                        verify: verify_on_extract,
+                        restore_metadata,
+                        resume,
+                    },
+                };
+
+                let mut options = open.clone();
+                options.verify_crcs(!verify_on_extract);
+
                 Processor::new(Bias::Threaded)?
+                    .with_memory_budget(memory_budget.map(|mib| mib * 1024 * 1024))
                     .read_with(move |r, _| {
                         let res = match r {
-                            Reader::Stdin(buf) => Archive::from_vec(buf.into_inner()),
-                            Reader::File(_, f) => Archive::mmap(f.into_inner()),
+                            Reader::Stdin(buf) => options.from_vec(buf.into_inner()),
+                            Reader::File(_, f) => options.mmap(f.into_inner()),
                         };
 
                         res.map_err(Into::into)
                     })
-                    .write_with(extract::extract_archive)
+                    .write_with(|ex, inpath, archive, out| {
+                        extract::extract_archive(ex, inpath, archive, out, &cx)
+                    })
                     .process(inputs, outputs)
             }
+
+            WadCommand::List { input, opts } => {
+                let archive = open_archive(&open, &input)?;
+                list::list_archive(&archive, &opts)
+            }
+
+            WadCommand::Stats { input, json } => {
+                let archive = open_archive(&open, &input)?;
+                stats::print_stats(&archive, json)
+            }
+
+            WadCommand::Grep { input, opts } => {
+                let archive = open_archive(&open, &input)?;
+                grep::grep_archive(&archive, &opts)
+            }
+
+            WadCommand::Strings { input, opts } => {
+                let archive = open_archive(&open, &input)?;
+                strings::extract_strings(&archive, &opts)
+            }
+
+            WadCommand::Cat {
+                input,
+                path,
+                output,
+            } => {
+                let archive = if input == "-" {
+                    let mut buf = Vec::new();
+                    utils::stdin_reader().read_to_end(&mut buf)?;
+                    Archive::from_vec(buf).context("failed to read archive from stdin")?
+                } else {
+                    open_archive(&open, Path::new(&input))?
+                };
+
+                let contents = ArchiveFs::open(&archive, &path)
+                    .with_context(|| format!("failed to read '{path}' from archive"))?;
+
+                if output.as_os_str() == "-" {
+                    io::stdout().lock().write_all(&contents)?;
+                } else {
+                    fs::write(&output, contents).with_context(|| {
+                        format!("failed to write file to '{}'", output.display())
+                    })?;
+                }
+
+                Ok(())
+            }
+
+            WadCommand::Merge {
+                inputs,
+                policy,
+                output,
+            } => {
+                let archives = inputs
+                    .iter()
+                    .map(|path| open_archive(&open, path))
+                    .collect::<eyre::Result<Vec<_>>>()?;
+
+                // Inherit the format of the base archive for the output.
+                let header = archives[0].header();
+                let mut builder =
+                    ArchiveBuilder::new(header.version, header.flags.unwrap_or(0), &output)
+                        .with_context(|| {
+                            format!("failed to build output archive at '{}'", output.display())
+                        })?;
+
+                builder.merge(&archives, policy.into())?;
+                builder.finish()?;
+
+                Ok(())
+            }
+
+            WadCommand::ToZip {
+                input,
+                output,
+                passthrough,
+            } => {
+                let archive = open_archive(&open, &input)?;
+                let out = fs::File::create(&output)
+                    .map(BufWriter::new)
+                    .with_context(|| format!("failed to create ZIP at '{}'", output.display()))?;
+
+                zip::archive_to_zip(&archive, out, passthrough)?;
+
+                Ok(())
+            }
+
+            WadCommand::FromZip {
+                input,
+                output,
+                flags,
+            } => {
+                let paths = if input.is_dir() {
+                    let mut paths = Vec::new();
+                    for entry in fs::read_dir(&input).context("failed to query input directory")? {
+                        let path = entry?.path();
+                        if path.is_file() && path.extension().is_some_and(|e| e == "zip") {
+                            paths.push(path);
+                        }
+                    }
+
+                    paths.sort();
+                    paths
+                } else {
+                    vec![input]
+                };
+
+                let buffers = paths
+                    .iter()
+                    .map(|path| {
+                        fs::read(path)
+                            .with_context(|| format!("failed to read ZIP at '{}'", path.display()))
+                    })
+                    .collect::<eyre::Result<Vec<_>>>()?;
+                let zips = buffers
+                    .iter()
+                    .zip(&paths)
+                    .map(|(buf, path)| {
+                        zip::ZipReader::new(buf)
+                            .with_context(|| format!("failed to parse ZIP at '{}'", path.display()))
+                    })
+                    .collect::<eyre::Result<Vec<_>>>()?;
+
+                let mut builder =
+                    ArchiveBuilder::new(2, flags.bits(), &output).with_context(|| {
+                        format!("failed to build output archive at '{}'", output.display())
+                    })?;
+                zip::zips_to_archive(&zips, &mut builder)?;
+                builder.finish()?;
+
+                Ok(())
+            }
+
+            WadCommand::Patch { command } => match command {
+                PatchCommand::Create {
+                    old,
+                    new,
+                    patch: path,
+                } => {
+                    let old = open_archive(&open, &old)?;
+                    let new = open_archive(&open, &new)?;
+
+                    let data = patch::create(&old, &new)?;
+                    fs::write(&path, data)
+                        .with_context(|| format!("failed to write patch to '{}'", path.display()))
+                }
+
+                PatchCommand::Apply {
+                    old,
+                    patch: path,
+                    output,
+                } => {
+                    let old = open_archive(&open, &old)?;
+                    let data = fs::read(&path)
+                        .with_context(|| format!("failed to read patch at '{}'", path.display()))?;
+
+                    patch::apply(&old, &data, &output)?;
+
+                    Ok(())
+                }
+            },
+
+            WadCommand::Journal { command } => match command {
+                JournalCommand::Export { input, output } => {
+                    let archive = open_archive(&open, &input)?;
+                    let dump = JournalDump::from_archive(&archive);
+
+                    match output {
+                        Some(path) => {
+                            let data = serde_json::to_vec_pretty(&dump)?;
+                            fs::write(&path, data).with_context(|| {
+                                format!("failed to write journal to '{}'", path.display())
+                            })
+                        }
+                        None => {
+                            println!("{}", serde_json::to_string_pretty(&dump)?);
+                            Ok(())
+                        }
+                    }
+                }
+
+                JournalCommand::Import {
+                    input,
+                    journal: path,
+                    output,
+                } => {
+                    let archive = open_archive(&open, &input)?;
+                    let data = fs::read(&path).with_context(|| {
+                        format!("failed to read journal at '{}'", path.display())
+                    })?;
+                    let dump: JournalDump = serde_json::from_slice(&data)
+                        .with_context(|| format!("invalid journal at '{}'", path.display()))?;
+
+                    let file = fs::File::create(&output).with_context(|| {
+                        format!("failed to create archive at '{}'", output.display())
+                    })?;
+                    let mut writer = BufWriter::new(file);
+                    journal::rewrite(&archive, &dump, &mut writer)?;
+                    writer.flush()?;
+
+                    Ok(())
+                }
+            },
         }
     }
 }
+
+fn parse_archive_flags(s: &str) -> Result<ArchiveFlags, String> {
+    match s.parse::<u8>() {
+        Ok(bits) => Ok(ArchiveFlags::from_bits_retain(bits)),
+        Err(_) => s.parse().map_err(|e| format!("{e}")),
+    }
+}
+
+fn open_archive(options: &OpenOptions, path: &Path) -> eyre::Result<Archive> {
+    options
+        .open_mmap(path)
+        .with_context(|| format!("failed to open archive at '{}'", path.display()))
+}
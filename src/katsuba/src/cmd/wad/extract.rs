@@ -1,13 +1,116 @@
 use std::{
     env,
+    fmt::Write,
+    fs,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
+use clap::ValueEnum;
+use eyre::Context;
 use katsuba_executor::{Buffer, Executor, Task};
-use katsuba_wad::{Archive, Inflater};
+use katsuba_wad::{crc, fallback::FallbackSource, glob::Matcher, Archive, Inflater, InflaterPool};
+use sha2::{Digest, Sha256};
 
+use super::metadata::MetadataManifest;
 use crate::{cli::OutputSource, utils::DirectoryTree};
 
+/// Digest algorithms for integrity manifests of extracted files.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ManifestAlgorithm {
+    /// SHA-256, in the format of `sha256sum`.
+    Sha256,
+}
+
+impl ManifestAlgorithm {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> String {
+        let digest = match self {
+            Self::Sha256 => Sha256::digest(data),
+        };
+
+        digest.iter().fold(String::new(), |mut s, b| {
+            let _ = write!(s, "{b:02x}");
+            s
+        })
+    }
+}
+
+// Digests of extracted files, collected from the worker threads.
+type ManifestEntries = Arc<Mutex<Vec<(String, String)>>>;
+
+fn write_manifest(
+    ex: &Executor,
+    algorithm: ManifestAlgorithm,
+    entries: &ManifestEntries,
+    out: &Path,
+) -> eyre::Result<()> {
+    // All tasks must be done before we have the complete manifest.
+    for pending in ex.join() {
+        pending?;
+    }
+
+    let mut entries = entries.lock().unwrap();
+    entries.sort_unstable();
+
+    let mut manifest = String::new();
+    for (path, digest) in entries.iter() {
+        writeln!(manifest, "{digest}  {path}")?;
+    }
+
+    let task = Task::create_file(
+        out.with_extension(algorithm.extension()),
+        Buffer::owned(manifest.into_bytes()),
+        0o666,
+    );
+    for pending in ex.dispatch(task) {
+        pending?;
+    }
+
+    Ok(())
+}
+
+/// Options controlling how archives are extracted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExtractOptions {
+    /// The algorithm for an integrity manifest of the extracted files.
+    pub manifest: Option<ManifestAlgorithm>,
+    /// Whether CRCs are verified as files are extracted.
+    pub verify: bool,
+    /// Whether file metadata is restored from a sidecar manifest.
+    pub restore_metadata: bool,
+    /// Whether files which already exist with the right contents are
+    /// skipped.
+    pub resume: bool,
+}
+
+/// The state shared by the extraction of all archives in a run.
+pub struct ExtractContext<'a> {
+    /// The pool of inflaters for decompressing files.
+    pub inflaters: &'a InflaterPool,
+    /// Selects the files to extract.
+    pub matcher: &'a Matcher,
+    /// Archives to resolve unpatched files from.
+    pub fallback: &'a [Archive],
+    /// Options controlling the extraction.
+    pub opts: ExtractOptions,
+}
+
+// Reads the file at `path` if it exists with the given size.
+fn read_existing(path: &Path, size: u32) -> Option<Vec<u8>> {
+    let meta = fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() != size as u64 {
+        return None;
+    }
+
+    fs::read(path).ok()
+}
+
 struct SafeArchiveDrop<'a> {
     ex: &'a Executor,
     archive: Archive,
@@ -27,14 +130,22 @@ fn fetch_file_contents<'a>(
     archive: &'a Archive,
     inflater: &mut Inflater,
     file: &katsuba_wad::types::File,
+    verify: bool,
 ) -> eyre::Result<Option<Buffer<'a>>> {
-    if file.is_unpatched {
-        return Ok(None);
-    }
+    let contents = if verify {
+        match archive.verified_file_contents(file)? {
+            Some(contents) => contents,
+            None => return Ok(None),
+        }
+    } else {
+        if file.is_unpatched {
+            return Ok(None);
+        }
 
-    let contents = archive
-        .file_contents(file)
-        .ok_or_else(|| eyre::eyre!("missing file contents in archive"))?;
+        archive
+            .file_contents(file)
+            .ok_or_else(|| eyre::eyre!("missing file contents in archive"))?
+    };
 
     match file.compressed {
         true => {
@@ -53,10 +164,15 @@ fn fetch_file_contents<'a>(
     }
 }
 
-fn create_directory_tree(ex: &Executor, archive: &Archive, out: &Path) -> eyre::Result<()> {
+fn create_directory_tree(
+    ex: &Executor,
+    archive: &Archive,
+    matcher: &Matcher,
+    out: &Path,
+) -> eyre::Result<()> {
     // Pre-compute the directory structure we need to create.
     let mut tree = DirectoryTree::new();
-    for file in archive.files().keys() {
+    for file in archive.files().keys().filter(|f| matcher.is_match(f)) {
         tree.add(file.as_ref());
     }
 
@@ -77,12 +193,41 @@ fn create_directory_tree(ex: &Executor, archive: &Archive, out: &Path) -> eyre::
     Ok(())
 }
 
+// Records the digest of a file which is already extracted.
+fn record_digest(opts: ExtractOptions, entries: &ManifestEntries, name: &str, contents: &[u8]) {
+    if let Some(algorithm) = opts.manifest {
+        let digest = algorithm.digest(contents);
+        entries.lock().unwrap().push((name.to_owned(), digest));
+    }
+}
+
 pub fn extract_archive(
     ex: &Executor,
     inpath: Option<PathBuf>,
     archive: Archive,
     out: OutputSource,
+    cx: &ExtractContext<'_>,
 ) -> eyre::Result<()> {
+    let ExtractContext {
+        inflaters,
+        matcher,
+        fallback,
+        opts,
+    } = *cx;
+
+    let metadata = match &inpath {
+        Some(inpath) if opts.restore_metadata => {
+            let path = MetadataManifest::sidecar_path(inpath);
+            if path.is_file() {
+                MetadataManifest::load(&path)?
+            } else {
+                log::warn!("No metadata found at '{}'", path.display());
+                MetadataManifest::default()
+            }
+        }
+        _ => MetadataManifest::default(),
+    };
+
     // Determine the output directory for the archive files.
     // Since we can't print here, we use the cwd instead.
     let input_stem = inpath.as_ref().and_then(|p| p.file_stem()).unwrap();
@@ -93,7 +238,7 @@ pub fn extract_archive(
     out.push(input_stem);
 
     // First, create all the directories for the output files.
-    create_directory_tree(ex, &archive, &out)?;
+    create_directory_tree(ex, &archive, matcher, &out)?;
 
     // This guard ensures we can safely share references into `archive`
     // with the pool without risking dangling in the case of an error.
@@ -103,26 +248,85 @@ pub fn extract_archive(
     // Next, we do the extraction of data out of the archive on the
     // current thread while simultaneously dispatching the file I/O
     // operations to the executor.
-    let mut inflater = Inflater::new();
-    for (path, file) in sad.archive.files() {
-        let path = out.join(path);
+    let mut inflater = inflaters.get();
+    let entries = ManifestEntries::default();
+    for (name, file) in sad.archive.files() {
+        if !matcher.is_match(name) {
+            continue;
+        }
+
+        let path = out.join(name);
+
+        // When resuming, files that are already in place are skipped.
+        // Uncompressed files can be checked against the journal CRC
+        // directly, compressed ones are compared after decompression.
+        let existing = match opts.resume {
+            true => read_existing(&path, file.uncompressed_size),
+            false => None,
+        };
+        if let Some(existing) = &existing {
+            if !file.compressed && crc::hash(existing) == file.crc {
+                log::debug!("Skipping extracted file '{}'", path.display());
+                record_digest(opts, &entries, name, existing);
+                continue;
+            }
+        }
 
         // SAFETY: We can never end up with dangling references into
         // `archive` because `sad` joins all pending tasks on drop.
-        let buffer = match fetch_file_contents(ex, &sad.archive, &mut inflater, file)? {
+        let buffer = fetch_file_contents(ex, &sad.archive, &mut inflater, file, opts.verify)
+            .with_context(|| format!("failed to extract '{name}'"))?;
+        let buffer = match buffer {
             Some(buf) => buf,
-            None => {
-                log::warn!("Skipping unpatched file '{}'", path.display());
-                continue;
-            }
+            None => match fallback
+                .fetch_fallback(name, file, &mut inflater)
+                .with_context(|| format!("failed to resolve unpatched '{name}'"))?
+            {
+                Some(data) => Buffer::owned(data),
+                None => {
+                    log::warn!("Skipping unpatched file '{}'", path.display());
+                    continue;
+                }
+            },
         };
+        if existing.is_some_and(|existing| existing[..] == buffer[..]) {
+            log::debug!("Skipping extracted file '{}'", path.display());
+            record_digest(opts, &entries, name, &buffer);
+            continue;
+        }
         let buffer = unsafe { buffer.extend_lifetime() };
 
-        let task = Task::create_file(path, buffer, mode);
+        // Files without recorded metadata inherit the archive's mode.
+        let meta = metadata.files.get(name).cloned();
+        let mut task = Task::create_file(path, buffer, mode);
+        if let Some(meta) = &meta {
+            task = meta.apply_mtime(task);
+        }
+
+        if opts.manifest.is_some() || meta.as_ref().is_some_and(|m| m.mode.is_some()) {
+            let digest = opts.manifest.map(|algorithm| (algorithm, entries.clone()));
+            let name = name.clone();
+
+            task = task.inspect_with(move |path, contents| {
+                if let Some((algorithm, entries)) = digest {
+                    let digest = algorithm.digest(contents);
+                    entries.lock().unwrap().push((name, digest));
+                }
+
+                if let Some(meta) = meta {
+                    meta.apply_mode(path);
+                }
+            });
+        }
+
         for pending in ex.dispatch(task) {
             pending?;
         }
     }
 
+    if let Some(algorithm) = opts.manifest {
+        write_manifest(ex, algorithm, &entries, &out)?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,80 @@
+use std::io::{self, Write};
+
+use clap::Args;
+use katsuba_wad::{Archive, Inflater};
+use regex::bytes::{Regex, RegexBuilder};
+
+/// Options for searching the contents of archive files.
+#[derive(Debug, Args)]
+pub struct GrepOptions {
+    /// The pattern to search for.
+    ///
+    /// This is a regular expression unless `--fixed` is given.
+    pattern: String,
+
+    /// Only searches files whose path matches this glob pattern.
+    #[clap(short, long, default_value = "**/*")]
+    glob: String,
+
+    /// Interprets the pattern as a literal string.
+    #[clap(short = 'F', long)]
+    fixed: bool,
+
+    /// Matches the pattern case-insensitively.
+    #[clap(short, long)]
+    ignore_case: bool,
+
+    /// Only prints the paths of files with at least one match.
+    #[clap(short = 'l', long)]
+    files_with_matches: bool,
+}
+
+impl GrepOptions {
+    fn regex(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.fixed {
+            regex::escape(&self.pattern)
+        } else {
+            self.pattern.clone()
+        };
+
+        // Archive files are mostly binary, so match bytes rather
+        // than Unicode scalar values.
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_case)
+            .unicode(false)
+            .build()
+    }
+}
+
+/// Searches the files in `archive` and prints `path:offset` for every
+/// match in their decompressed contents.
+pub fn grep_archive(archive: &Archive, opts: &GrepOptions) -> eyre::Result<()> {
+    let regex = opts.regex()?;
+    let mut inflater = Inflater::new();
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    for (name, file) in archive.iter_glob(&opts.glob)? {
+        let Some(contents) = archive.file_contents(file) else {
+            continue;
+        };
+        let data = if file.compressed {
+            inflater.decompress(contents, file.uncompressed_size as _)?
+        } else {
+            contents
+        };
+
+        if opts.files_with_matches {
+            if regex.is_match(data) {
+                writeln!(stdout, "{name}")?;
+            }
+        } else {
+            for m in regex.find_iter(data) {
+                writeln!(stdout, "{name}:{}", m.start())?;
+            }
+        }
+    }
+
+    Ok(())
+}
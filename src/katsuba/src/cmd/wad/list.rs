@@ -0,0 +1,177 @@
+use std::cmp::Ordering;
+
+use clap::{Args, ValueEnum};
+use katsuba_wad::{
+    glob::Matcher,
+    types::File,
+    vfs::{ArchiveFs, EntryKind},
+    Archive,
+};
+
+/// The key to sort listed archive files by.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SortKey {
+    /// Sort by path in the archive.
+    Name,
+    /// Sort by uncompressed file size.
+    Size,
+    /// Sort by compression ratio.
+    Ratio,
+    /// Sort by the CRC value.
+    Crc,
+}
+
+/// Options for filtering and ordering listed archive files.
+#[derive(Debug, Args)]
+pub struct ListOptions {
+    /// The key to sort the listed files by.
+    #[clap(short, long, value_enum, default_value_t = SortKey::Name)]
+    sort: SortKey,
+
+    /// Reverses the sorting order.
+    #[clap(short, long)]
+    reverse: bool,
+
+    /// Only lists files with at least this uncompressed size in bytes.
+    #[clap(long)]
+    min_size: Option<u32>,
+
+    /// Only lists files with at most this uncompressed size in bytes.
+    #[clap(long)]
+    max_size: Option<u32>,
+
+    /// Only lists files which are stored compressed.
+    #[clap(long)]
+    compressed_only: bool,
+
+    /// Only lists unpatched placeholder files.
+    #[clap(long)]
+    unpatched_only: bool,
+
+    /// Only lists files whose path matches these glob patterns.
+    ///
+    /// May be given multiple times. Patterns starting with `!` exclude
+    /// the files they match.
+    #[clap(short, long = "glob")]
+    glob: Vec<String>,
+
+    /// Renders the files as a tree of their directories.
+    ///
+    /// Directories without any listed files are left out. Sorting
+    /// options do not apply to the tree.
+    #[clap(long, conflicts_with_all = ["sort", "reverse"])]
+    tree: bool,
+}
+
+impl ListOptions {
+    fn matches(&self, file: &File) -> bool {
+        self.min_size
+            .is_none_or(|min| file.uncompressed_size >= min)
+            && self
+                .max_size
+                .is_none_or(|max| file.uncompressed_size <= max)
+            && (!self.compressed_only || file.compressed)
+            && (!self.unpatched_only || file.is_unpatched)
+    }
+
+    fn compare(&self, (a_name, a): (&str, &File), (b_name, b): (&str, &File)) -> Ordering {
+        let ord = match self.sort {
+            SortKey::Name => a_name.cmp(b_name),
+            SortKey::Size => a.uncompressed_size.cmp(&b.uncompressed_size),
+            SortKey::Ratio => a.compression_ratio().total_cmp(&b.compression_ratio()),
+            SortKey::Crc => a.crc.cmp(&b.crc),
+        };
+
+        if self.reverse {
+            ord.reverse()
+        } else {
+            ord
+        }
+    }
+}
+
+// Renders the listed files under `dir` into `out`, one line each.
+fn render_tree(
+    archive: &Archive,
+    opts: &ListOptions,
+    matcher: &Matcher,
+    dir: &str,
+    depth: usize,
+    out: &mut Vec<String>,
+) -> eyre::Result<()> {
+    let indent = "  ".repeat(depth);
+    for entry in archive.read_dir(dir)? {
+        let name = entry.name;
+        let path = format!("{dir}{name}");
+
+        match entry.kind {
+            EntryKind::Dir => {
+                let mut children = Vec::new();
+                render_tree(
+                    archive,
+                    opts,
+                    matcher,
+                    &format!("{path}/"),
+                    depth + 1,
+                    &mut children,
+                )?;
+
+                if !children.is_empty() {
+                    out.push(format!("{indent}{name}/"));
+                    out.append(&mut children);
+                }
+            }
+
+            EntryKind::File => {
+                let listed = archive
+                    .file_raw(&path)
+                    .is_some_and(|file| opts.matches(file) && matcher.is_match(&path));
+                if listed {
+                    out.push(format!("{indent}{name}"));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the files in `archive` which match the given options.
+pub fn list_archive(archive: &Archive, opts: &ListOptions) -> eyre::Result<()> {
+    if opts.tree {
+        let matcher = Matcher::many(&opts.glob)?;
+        let mut lines = Vec::new();
+        render_tree(archive, opts, &matcher, "", 0, &mut lines)?;
+
+        lines.iter().for_each(|line| println!("{line}"));
+        return Ok(());
+    }
+
+    let mut files: Vec<_> = archive
+        .iter_glob_many(&opts.glob)?
+        .map(|(name, file)| (name.as_str(), file))
+        .filter(|(_, file)| opts.matches(file))
+        .collect();
+
+    // Archive files are already ordered by name, so stable sorting
+    // keeps a predictable order between entries with equal keys.
+    files.sort_by(|&a, &b| opts.compare(a, b));
+
+    for (name, file) in files {
+        println!(
+            "{:>10} {:>10} {:>6.3} {:08x} {}{}",
+            file.uncompressed_size,
+            file.size(),
+            file.compression_ratio(),
+            file.crc,
+            name,
+            if file.is_unpatched {
+                " (unpatched)"
+            } else {
+                ""
+            },
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use eyre::Context;
+use katsuba_executor::Task;
+use serde::{Deserialize, Serialize};
+
+/// Filesystem metadata of a single packed file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FileMetadata {
+    /// The UNIX permission bits of the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// The time of last modification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<SystemTime>,
+}
+
+impl FileMetadata {
+    /// Captures the relevant metadata of a file in the filesystem.
+    pub fn from_fs(meta: &fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(meta.permissions().mode() & 0o7777)
+        };
+        #[cfg(not(unix))]
+        let mode = None;
+
+        Self {
+            mode,
+            mtime: meta.modified().ok(),
+        }
+    }
+
+    /// Attaches the modification time to a task creating the file.
+    pub fn apply_mtime(&self, task: Task) -> Task {
+        match self.mtime {
+            Some(mtime) => task.with_mtime(mtime),
+            None => task,
+        }
+    }
+
+    /// Applies the mode to a written file.
+    ///
+    /// This happens after creation so that the mode is not subject
+    /// to the process umask. Failures are only logged.
+    pub fn apply_mode(&self, path: &Path) {
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::PermissionsExt;
+
+            if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(mode)) {
+                log::warn!("Failed to set mode of '{}': {e}", path.display());
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = path;
+    }
+}
+
+/// A sidecar manifest storing file metadata which KIWAD archives
+/// cannot represent.
+///
+/// It is written next to an archive by `wad pack` and picked up by
+/// `wad unpack` to restore the files as they were.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MetadataManifest {
+    /// Metadata for every file, keyed by its path in the archive.
+    pub files: BTreeMap<String, FileMetadata>,
+}
+
+impl MetadataManifest {
+    /// Gets the path of the sidecar manifest for an archive.
+    pub fn sidecar_path(archive: &Path) -> PathBuf {
+        archive.with_extension("meta.json")
+    }
+
+    /// Loads the manifest from the given path.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let data = fs::read(path)
+            .with_context(|| format!("failed to read metadata at '{}'", path.display()))?;
+        serde_json::from_slice(&data)
+            .with_context(|| format!("failed to parse metadata at '{}'", path.display()))
+    }
+
+    /// Saves the manifest to the given path.
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+        fs::write(path, data)
+            .with_context(|| format!("failed to write metadata to '{}'", path.display()))
+    }
+
+    /// Records the metadata of a file at `path` under `name`.
+    pub fn record(&mut self, name: &str, path: &Path) -> eyre::Result<()> {
+        let meta = fs::metadata(path)
+            .with_context(|| format!("failed to query metadata of '{}'", path.display()))?;
+        self.files
+            .insert(name.to_owned(), FileMetadata::from_fs(&meta));
+
+        Ok(())
+    }
+}
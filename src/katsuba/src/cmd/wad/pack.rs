@@ -0,0 +1,502 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
+use eyre::Context;
+use glob::{MatchOptions, Pattern};
+use katsuba_wad::{
+    deflater::{CompressionLevel, Deflater},
+    Archive, ArchiveBuilder, PreparedFile,
+};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Deserialize;
+
+use super::metadata::MetadataManifest;
+
+// How long to wait for more changes before rebuilding in watch mode.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+// The number of files per thread to compress in one batch. Batching
+// bounds the amount of compressed data held in memory at once.
+const BATCH_PER_JOB: usize = 8;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A rule which overrides packing settings for matching files.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rule {
+    /// The glob pattern for files the rule applies to.
+    glob: String,
+    /// Whether matching files are compressed.
+    compress: Option<bool>,
+    /// The compression level for matching files.
+    level: Option<u8>,
+}
+
+/// A manifest describing how a directory is packed into an archive.
+///
+/// Paths and patterns are relative to the root directory and use `/`
+/// as the separator.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackManifest {
+    /// The root directory to pack, relative to the manifest.
+    root: Option<PathBuf>,
+
+    /// The KIWAD format version of the archive.
+    version: Option<u32>,
+    /// The flags of the archive.
+    flags: Option<u8>,
+    /// The default compression level.
+    level: Option<u8>,
+
+    /// Whether files are compressed unless a rule says otherwise.
+    compress: Option<bool>,
+
+    /// Patterns for files which are left out of the archive.
+    #[serde(default)]
+    exclude: Vec<String>,
+
+    /// Patterns which define the order of file data in the archive.
+    ///
+    /// Files matching earlier patterns are stored first. Files that
+    /// match none of them come last. Ties are ordered by path.
+    #[serde(default)]
+    order: Vec<String>,
+
+    /// Per-file overrides, where later rules take precedence.
+    #[serde(default, rename = "rule")]
+    rules: Vec<Rule>,
+}
+
+/// A file to add to the archive.
+pub struct PackEntry {
+    /// The path to the file on disk.
+    pub path: PathBuf,
+    /// The name of the file in the archive.
+    pub name: String,
+    /// Whether the file should be compressed.
+    pub compress: bool,
+    /// An override for the compression level, if any.
+    pub level: Option<u8>,
+}
+
+fn compile(patterns: &[String]) -> eyre::Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|p| Pattern::new(p).with_context(|| format!("invalid pattern '{p}' in manifest")))
+        .collect()
+}
+
+impl PackManifest {
+    /// Loads a manifest from a TOML file.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("failed to read manifest at '{}'", path.display()))?;
+        toml::from_str(&data)
+            .with_context(|| format!("failed to parse manifest at '{}'", path.display()))
+    }
+
+    /// Resolves the root directory of a manifest loaded from `path`.
+    pub fn root(&self, path: &Path) -> PathBuf {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        match &self.root {
+            Some(root) => dir.join(root),
+            None => dir.to_owned(),
+        }
+    }
+
+    /// The archive version, or `version` if unspecified.
+    pub fn version_or(&self, version: u32) -> u32 {
+        self.version.unwrap_or(version)
+    }
+
+    /// The archive flags, or `flags` if unspecified.
+    pub fn flags_or(&self, flags: u8) -> u8 {
+        self.flags.unwrap_or(flags)
+    }
+
+    /// The default compression level, or `level` if unspecified.
+    pub fn level_or(&self, level: u8) -> u8 {
+        self.level.unwrap_or(level)
+    }
+
+    /// Collects the files under `root` to pack, in archive order.
+    pub fn collect(&self, root: &Path) -> eyre::Result<Vec<PackEntry>> {
+        let exclude = compile(&self.exclude)?;
+        let order = compile(&self.order)?;
+        let rules = self
+            .rules
+            .iter()
+            .map(|r| {
+                Pattern::new(&r.glob)
+                    .map(|p| (p, r))
+                    .with_context(|| format!("invalid pattern '{}' in manifest", r.glob))
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let mut entries = Vec::new();
+        for entry in walkdir::WalkDir::new(root) {
+            let entry = entry.context("failed to query input directory")?;
+            if !entry
+                .metadata()
+                .context("failed to obtain metadata for path")?
+                .is_file()
+            {
+                continue;
+            }
+
+            let path = entry.into_path();
+            let name = path
+                .strip_prefix(root)
+                .unwrap()
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if exclude.iter().any(|p| p.matches_with(&name, MATCH_OPTIONS)) {
+                continue;
+            }
+
+            let mut compress = self.compress.unwrap_or(true);
+            let mut level = None;
+            for (pattern, rule) in &rules {
+                if pattern.matches_with(&name, MATCH_OPTIONS) {
+                    compress = rule.compress.unwrap_or(compress);
+                    level = rule.level.or(level);
+                }
+            }
+
+            let rank = order
+                .iter()
+                .position(|p| p.matches_with(&name, MATCH_OPTIONS))
+                .unwrap_or(order.len());
+
+            entries.push((
+                rank,
+                PackEntry {
+                    path,
+                    name,
+                    compress,
+                    level,
+                },
+            ));
+        }
+
+        entries.sort_by(|(ra, a), (rb, b)| ra.cmp(rb).then_with(|| a.name.cmp(&b.name)));
+        Ok(entries.into_iter().map(|(_, e)| e).collect())
+    }
+}
+
+/// A configured run of packing a directory into an archive.
+pub struct PackJob {
+    /// The directory to pack.
+    pub input: PathBuf,
+    /// The path to the archive to create.
+    pub output: PathBuf,
+    /// The manifest controlling how files are packed.
+    pub manifest: PackManifest,
+    /// The archive flags, unless set by the manifest.
+    pub flags: u8,
+    /// The compression level, unless set by the manifest.
+    pub level: u8,
+    /// Files smaller than this are stored uncompressed.
+    pub min_compressed_size: usize,
+    /// Whether to write a metadata sidecar for the archive.
+    pub record_metadata: bool,
+    /// Whether to check the built archive before replacing the output.
+    pub verify: bool,
+    /// The number of threads to compress files on.
+    pub jobs: usize,
+}
+
+fn compression_level(level: u8) -> eyre::Result<CompressionLevel> {
+    CompressionLevel::new(level)
+        .ok_or_else(|| eyre::eyre!("compression level must be in range 0..=12"))
+}
+
+// Reads and compresses a file on a worker thread.
+fn prepare(
+    entry: &PackEntry,
+    deflater: &mut Deflater,
+    default_level: CompressionLevel,
+    min_compressed_size: usize,
+) -> eyre::Result<PreparedFile> {
+    let path = &entry.path;
+    let contents =
+        fs::read(path).with_context(|| format!("failed to read file at '{}'", path.display()))?;
+
+    if !entry.compress {
+        return PreparedFile::uncompressed(&entry.name, contents).map_err(Into::into);
+    }
+
+    let level = match entry.level {
+        Some(level) => compression_level(level)?,
+        None => default_level,
+    };
+    deflater.set_level(level);
+
+    PreparedFile::compressed(&entry.name, contents, deflater, min_compressed_size)
+        .map_err(Into::into)
+}
+
+impl PackJob {
+    // The path the archive is built at before it replaces the output.
+    fn partial_output(&self) -> PathBuf {
+        let mut path = OsString::from(&self.output);
+        path.push(".part");
+        path.into()
+    }
+
+    // The files written by packing, which must never be packed
+    // themselves when the output is inside the input directory.
+    fn own_files(&self) -> [PathBuf; 3] {
+        [
+            self.output.clone(),
+            self.partial_output(),
+            MetadataManifest::sidecar_path(&self.output),
+        ]
+        .map(|p| absolute(&p))
+    }
+
+    /// Packs the input directory into the output archive.
+    ///
+    /// The archive is built next to the output and only replaces it
+    /// once it is complete, so readers never observe a partial file.
+    ///
+    /// Identical input trees always produce byte-identical archives:
+    /// files are added in a fixed order regardless of the filesystem
+    /// and the thread count, and no timestamps or other data from the
+    /// environment end up in the archive.
+    pub fn run(&self) -> eyre::Result<()> {
+        let own = self.own_files();
+        let mut entries = self.manifest.collect(&self.input)?;
+        entries.retain(|e| !own.contains(&absolute(&e.path)));
+
+        let mut metadata = MetadataManifest::default();
+        if self.record_metadata {
+            for entry in &entries {
+                metadata.record(&entry.name, &entry.path)?;
+            }
+        }
+
+        let partial = self.partial_output();
+        if let Err(e) = self.write(&partial, &entries) {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+
+        if self.record_metadata {
+            metadata.save(&MetadataManifest::sidecar_path(&self.output))?;
+        }
+
+        Ok(())
+    }
+
+    // Builds the archive at `partial` and moves it to the output.
+    fn write(&self, partial: &Path, entries: &[PackEntry]) -> eyre::Result<()> {
+        let manifest = &self.manifest;
+        let mut builder = ArchiveBuilder::with_level(
+            manifest.version_or(2),
+            manifest.flags_or(self.flags),
+            compression_level(manifest.level_or(self.level))?,
+            partial,
+        )
+        .with_context(|| {
+            format!(
+                "failed to build output archive at '{}'",
+                self.output.display()
+            )
+        })?;
+        builder.set_min_compressed_size(self.min_compressed_size);
+
+        if self.jobs > 1 {
+            self.add_parallel(&mut builder, entries)?;
+        } else {
+            add_sequential(&mut builder, entries)?;
+        }
+
+        builder.finish()?;
+        if self.verify {
+            verify_archive(partial, entries)?;
+        }
+
+        fs::rename(partial, &self.output)
+            .with_context(|| format!("failed to write archive to '{}'", self.output.display()))
+    }
+
+    // Compresses files on multiple threads, but adds them to the
+    // archive in their original order so the output is identical to
+    // a sequential run.
+    fn add_parallel(
+        &self,
+        builder: &mut ArchiveBuilder,
+        entries: &[PackEntry],
+    ) -> eyre::Result<()> {
+        let default_level = compression_level(self.manifest.level_or(self.level))?;
+        let min_compressed_size = builder.min_compressed_size();
+
+        for batch in entries.chunks(self.jobs * BATCH_PER_JOB) {
+            let next = &AtomicUsize::new(0);
+            let mut prepared: Vec<_> = thread::scope(|s| {
+                let workers: Vec<_> = (0..self.jobs)
+                    .map(|_| {
+                        s.spawn(move || {
+                            let mut deflater = Deflater::with_level(default_level);
+                            let mut done = Vec::new();
+
+                            loop {
+                                let idx = next.fetch_add(1, Ordering::Relaxed);
+                                let Some(entry) = batch.get(idx) else {
+                                    break done;
+                                };
+
+                                let file = prepare(
+                                    entry,
+                                    &mut deflater,
+                                    default_level,
+                                    min_compressed_size,
+                                );
+                                done.push((idx, file));
+                            }
+                        })
+                    })
+                    .collect();
+
+                workers
+                    .into_iter()
+                    .flat_map(|w| w.join().unwrap())
+                    .collect()
+            });
+
+            prepared.sort_unstable_by_key(|(idx, _)| *idx);
+            for (_, file) in prepared {
+                builder.add_prepared(file?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Whether a filesystem event should trigger a rebuild. Our own
+    // writes to the output are ignored for when it's in the input.
+    fn is_relevant(&self, event: &notify::Event) -> bool {
+        let partial = self.partial_output();
+        let sidecar = MetadataManifest::sidecar_path(&self.output);
+
+        !matches!(event.kind, EventKind::Access(..))
+            && event.paths.iter().any(|p| {
+                !p.ends_with(&self.output) && !p.ends_with(&partial) && !p.ends_with(&sidecar)
+            })
+    }
+
+    /// Packs the input directory, then watches it for changes and
+    /// rebuilds the archive whenever files are modified.
+    ///
+    /// Bursts of changes are collected into a single rebuild. A failed
+    /// rebuild is reported and the previous archive is kept. This runs
+    /// until the process is interrupted.
+    pub fn watch(&self) -> eyre::Result<()> {
+        self.run()?;
+        log::info!("Packed '{}'", self.output.display());
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).context("failed to create watcher")?;
+        watcher
+            .watch(&self.input, RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch '{}'", self.input.display()))?;
+
+        while let Ok(event) = rx.recv() {
+            let event = event.context("failed to watch input directory")?;
+            if !self.is_relevant(&event) {
+                continue;
+            }
+
+            // Wait for the burst of changes to settle.
+            while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+
+            match self.run() {
+                Ok(()) => log::info!("Repacked '{}'", self.output.display()),
+                Err(e) => log::error!("Failed to repack archive: {e:?}"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Makes `path` absolute with symlinks in its parent resolved, so
+// paths can be compared even when the file doesn't exist yet.
+fn absolute(path: &Path) -> PathBuf {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+
+    match (fs::canonicalize(parent), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.to_owned(),
+    }
+}
+
+// Re-opens a freshly built archive and checks that it is consistent
+// and holds exactly the packed files.
+fn verify_archive(path: &Path, entries: &[PackEntry]) -> eyre::Result<()> {
+    let archive = Archive::open_heap(path)
+        .with_context(|| format!("failed to re-open built archive '{}'", path.display()))?;
+    archive
+        .verify()
+        .context("verification of built archive failed")?;
+
+    if archive.len() != entries.len() {
+        eyre::bail!(
+            "verification of built archive failed: expected {} files, found {}",
+            entries.len(),
+            archive.len()
+        );
+    }
+    if let Some(missing) = entries.iter().find(|e| archive.file_raw(&e.name).is_none()) {
+        eyre::bail!(
+            "verification of built archive failed: '{}' is missing",
+            missing.name
+        );
+    }
+
+    Ok(())
+}
+
+fn add_sequential(builder: &mut ArchiveBuilder, entries: &[PackEntry]) -> eyre::Result<()> {
+    for entry in entries {
+        let path = &entry.path;
+        let contents = fs::read(path)
+            .with_context(|| format!("failed to read file at '{}'", path.display()))?;
+
+        match (entry.compress, entry.level) {
+            (false, _) => builder.add_file(&entry.name, &contents)?,
+            (true, None) => builder.add_file_compressed(&entry.name, &contents)?,
+            (true, Some(level)) => {
+                builder.add_file_compressed_with(
+                    &entry.name,
+                    &contents,
+                    compression_level(level)?,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
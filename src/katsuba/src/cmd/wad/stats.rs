@@ -0,0 +1,59 @@
+use std::cmp::Reverse;
+
+use katsuba_wad::{Archive, ArchiveStats};
+
+/// Prints statistics about the files in `archive`, either as tables
+/// or as JSON.
+pub fn print_stats(archive: &Archive, json: bool) -> eyre::Result<()> {
+    let stats = archive.stats();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        let header = archive.header();
+        println!("version:      {}", header.version);
+        println!("flags:        {}", header.archive_flags());
+        print_tables(&stats);
+    }
+
+    Ok(())
+}
+
+fn print_tables(stats: &ArchiveStats) {
+    let total = &stats.total;
+    println!(
+        "files:        {} ({} unpatched)",
+        total.count, stats.unpatched
+    );
+    println!("stored:       {}", total.stored_size);
+    println!("uncompressed: {}", total.uncompressed_size);
+    println!("ratio:        {:.3}", total.compression_ratio());
+
+    // Show the extensions which take up the most space first.
+    let mut extensions: Vec<_> = stats.extensions.iter().collect();
+    extensions.sort_by_key(|(_, ext)| Reverse(ext.stored_size));
+
+    println!();
+    println!(
+        "{:<12} {:>8} {:>12} {:>12} {:>6}",
+        "extension", "files", "stored", "uncompressed", "ratio"
+    );
+    for (ext, size) in extensions {
+        println!(
+            "{:<12} {:>8} {:>12} {:>12} {:>6.3}",
+            if ext.is_empty() { "(none)" } else { ext },
+            size.count,
+            size.stored_size,
+            size.uncompressed_size,
+            size.compression_ratio(),
+        );
+    }
+
+    println!();
+    println!("{:>12} {:>12} largest files", "stored", "uncompressed");
+    for entry in &stats.largest {
+        println!(
+            "{:>12} {:>12} {}",
+            entry.stored_size, entry.uncompressed_size, entry.name
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use clap::Args;
+use eyre::Context;
+use katsuba_lang::LangTable;
+use katsuba_wad::{types as wad_types, Archive, Inflater};
+
+/// Options for extracting the string tables of an archive.
+#[derive(Debug, Args)]
+pub struct StringsOptions {
+    /// Only considers files whose path matches this glob pattern.
+    #[clap(short, long, default_value = "**/*.lang")]
+    glob: String,
+
+    /// The number of threads to parse tables on.
+    ///
+    /// Defaults to the number of worker threads configured for
+    /// Katsuba.
+    #[clap(short, long)]
+    jobs: Option<usize>,
+
+    /// The file to write the JSON mapping to.
+    ///
+    /// If missing, the mapping is printed to stdout.
+    #[clap(short)]
+    output: Option<PathBuf>,
+}
+
+// Decompresses and parses a single table.
+fn parse_table(
+    archive: &Archive,
+    file: &wad_types::File,
+    inflater: &mut Inflater,
+) -> eyre::Result<LangTable> {
+    let contents = archive
+        .verified_file_contents(file)?
+        .ok_or_else(|| eyre::eyre!("file is unpatched"))?;
+    let data = if file.compressed {
+        inflater.decompress(contents, file.uncompressed_size as _)?
+    } else {
+        contents
+    };
+
+    LangTable::parse(data).map_err(Into::into)
+}
+
+/// Parses all language tables in `archive` and writes one JSON object
+/// mapping every qualified string key to its text.
+///
+/// Tables are processed in path order. When a key is defined more than
+/// once with different texts, the first definition is kept and the
+/// conflict is reported as a warning. Files which fail to parse are
+/// reported and skipped.
+pub fn extract_strings(archive: &Archive, opts: &StringsOptions) -> eyre::Result<()> {
+    let files: Vec<_> = archive
+        .iter_glob(&opts.glob)?
+        .filter(|(_, f)| !f.is_unpatched)
+        .collect();
+    let jobs = match opts.jobs {
+        Some(jobs) => jobs.max(1),
+        None => katsuba_executor::worker_threads()?,
+    };
+
+    let next = &AtomicUsize::new(0);
+    let files = &files;
+    let mut tables: Vec<_> = thread::scope(|s| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                s.spawn(move || {
+                    let mut inflater = Inflater::new();
+                    let mut done = Vec::new();
+
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((_, file)) = files.get(idx) else {
+                            break done;
+                        };
+
+                        done.push((idx, parse_table(archive, file, &mut inflater)));
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap())
+            .collect()
+    });
+    tables.sort_unstable_by_key(|(idx, _)| *idx);
+
+    let mut strings = BTreeMap::new();
+    let mut origins = BTreeMap::new();
+    let mut conflicts = 0;
+    for (idx, table) in tables {
+        let name = files[idx].0;
+        let table = match table {
+            Ok(table) => table,
+            Err(e) => {
+                log::warn!("Skipping '{name}': {e}");
+                continue;
+            }
+        };
+
+        for (key, value) in table.iter_qualified() {
+            match strings.get(&key) {
+                Some(existing) if existing == value => {}
+                Some(_) => {
+                    log::warn!(
+                        "Conflicting definitions of '{key}' in '{}' and '{name}'",
+                        origins[&key]
+                    );
+                    conflicts += 1;
+                }
+                None => {
+                    origins.insert(key.clone(), name);
+                    strings.insert(key, value.to_owned());
+                }
+            }
+        }
+    }
+
+    if conflicts > 0 {
+        log::warn!("Found {conflicts} conflicting string definitions");
+    }
+
+    match &opts.output {
+        Some(path) => {
+            let file = fs::File::create(path)
+                .with_context(|| format!("failed to create '{}'", path.display()))?;
+            write_json(BufWriter::new(file), &strings)
+        }
+        None => write_json(io::stdout().lock(), &strings),
+    }
+}
+
+fn write_json<W: Write>(mut writer: W, strings: &BTreeMap<String, String>) -> eyre::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, strings)?;
+    writeln!(writer)?;
+    writer.flush().map_err(Into::into)
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ::serde::Serialize;
+use clap::{Args, Subcommand};
+use katsuba_bcd::Bcd;
+use katsuba_nav::NavigationGraph;
+use katsuba_object_property::{serde, Value};
+use katsuba_poi::Poi;
+use katsuba_types::TypeList;
+use katsuba_wad::{Archive, Inflater};
+
+use super::Command;
+use crate::cli::{helpers, Bias, InputsOutputs, Processor, Reader};
+
+/// Subcommand for inspecting game world data.
+#[derive(Debug, Args)]
+pub struct World {
+    #[clap(subcommand)]
+    command: WorldCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum WorldCommand {
+    /// Aggregates per-zone information from a WorldData archive into
+    /// a single JSON report.
+    ///
+    /// Files are grouped into zones by their parent directory in the
+    /// archive. The report covers collision, navigation and point of
+    /// interest data as well as the zone's serialized objects. Files
+    /// which fail to parse are skipped with a warning.
+    Summary {
+        #[clap(flatten)]
+        args: InputsOutputs,
+
+        /// Optional type list files for identifying the types of BINd
+        /// objects in the archive.
+        #[clap(short, long)]
+        type_lists: Vec<PathBuf>,
+    },
+}
+
+/// An axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, Serialize)]
+struct Bounds {
+    min: [f32; 3],
+    max: [f32; 3],
+}
+
+impl Bounds {
+    fn extend(this: &mut Option<Self>, p: [f32; 3]) {
+        let b = this.get_or_insert(Self { min: p, max: p });
+        for ((min, max), v) in b.min.iter_mut().zip(&mut b.max).zip(p) {
+            *min = min.min(v);
+            *max = max.max(v);
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct CollisionSummary {
+    shapes: usize,
+    /// Bounds over the origins of all collision shapes.
+    bounds: Option<Bounds>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct NavigationSummary {
+    nodes: usize,
+    links: usize,
+    bounds: Option<Bounds>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct PointSummary {
+    goals: usize,
+    teleporters: usize,
+    teleports_to: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ObjectSummary {
+    path: String,
+    #[serde(rename = "type")]
+    type_name: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ZoneSummary {
+    collision: Option<CollisionSummary>,
+    navigation: Option<NavigationSummary>,
+    points: Option<PointSummary>,
+    objects: Vec<ObjectSummary>,
+}
+
+// Unwraps the result of parsing the file at `path`, or warns about the
+// failure so that the file can be skipped.
+fn parsed<T, E: fmt::Display>(path: &Path, res: Result<T, E>) -> Option<T> {
+    res.map_err(|e| log::warn!("Failed to parse '{}': {e}", path.display()))
+        .ok()
+}
+
+struct Summarizer {
+    inflater: Inflater,
+    de: Option<(serde::Serializer, Arc<TypeList>)>,
+    zones: BTreeMap<String, ZoneSummary>,
+}
+
+impl Summarizer {
+    fn new(types: Option<Arc<TypeList>>) -> eyre::Result<Self> {
+        let de = match types {
+            Some(types) => {
+                let options = serde::SerializerOptions::game_file();
+                Some((serde::Serializer::new(options, types.clone())?, types))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            inflater: Inflater::new(),
+            de,
+            zones: BTreeMap::new(),
+        })
+    }
+
+    fn summarize(mut self, archive: &Archive) -> eyre::Result<BTreeMap<String, ZoneSummary>> {
+        for (path, file) in archive.files() {
+            let path = Path::new(path);
+            let kind = match path.extension().and_then(|e| e.to_str()) {
+                Some(ext @ ("bcd" | "nav" | "poi" | "xml")) => ext,
+                _ => continue,
+            };
+
+            let Some(contents) = archive.file_contents(file) else {
+                log::warn!("Skipping unpatched file '{}'", path.display());
+                continue;
+            };
+            let data = if file.compressed {
+                match self
+                    .inflater
+                    .decompress(contents, file.uncompressed_size as _)
+                {
+                    Ok(data) => data,
+                    Err(e) => {
+                        log::warn!("Failed to decompress '{}': {e}", path.display());
+                        continue;
+                    }
+                }
+            } else {
+                contents
+            };
+
+            let zone = path
+                .parent()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let summary = self.zones.entry(zone).or_default();
+
+            match kind {
+                "bcd" => {
+                    let Some(bcd) = parsed(path, Bcd::parse(io::Cursor::new(data))) else {
+                        continue;
+                    };
+                    let collision = summary.collision.get_or_insert_with(Default::default);
+
+                    collision.shapes += bcd.collisions.len();
+                    for c in &bcd.collisions {
+                        Bounds::extend(&mut collision.bounds, c.geometry.location);
+                    }
+                }
+
+                "nav" => {
+                    let Some(nav) = parsed(path, NavigationGraph::parse(io::Cursor::new(data)))
+                    else {
+                        continue;
+                    };
+                    let navigation = summary.navigation.get_or_insert_with(Default::default);
+
+                    navigation.nodes += nav.nodes.len();
+                    navigation.links += nav.links.len();
+                    for n in &nav.nodes {
+                        Bounds::extend(&mut navigation.bounds, n.location);
+                    }
+                }
+
+                "poi" => {
+                    let Some(poi) = parsed(path, Poi::parse(io::Cursor::new(data))) else {
+                        continue;
+                    };
+                    let points = summary.points.get_or_insert_with(Default::default);
+
+                    points.goals += poi.goals.len();
+                    for teleporter in poi.teleporters.values().flatten() {
+                        points.teleporters += 1;
+                        points.teleports_to.insert(teleporter.destination.clone());
+                    }
+                }
+
+                _ => {
+                    let Some(data) = data.strip_prefix(serde::BIND_MAGIC) else {
+                        continue;
+                    };
+
+                    let type_name = match &mut self.de {
+                        Some((de, types)) => match de.deserialize::<serde::PropertyClass>(data) {
+                            Ok(Value::Object { hash, .. }) => {
+                                types.0.get(&hash).map(|t| t.name.to_string())
+                            }
+                            Ok(..) => None,
+                            Err(e) => {
+                                log::warn!("Failed to deserialize '{}': {e}", path.display());
+                                None
+                            }
+                        },
+                        None => None,
+                    };
+
+                    summary.objects.push(ObjectSummary {
+                        path: path.to_string_lossy().into_owned(),
+                        type_name,
+                    });
+                }
+            }
+        }
+
+        Ok(self.zones)
+    }
+}
+
+impl Command for World {
+    fn handle(self) -> eyre::Result<()> {
+        match self.command {
+            WorldCommand::Summary { args, type_lists } => {
+                let types = if type_lists.is_empty() {
+                    None
+                } else {
+                    Some(Arc::new(helpers::merge_type_lists(type_lists)?))
+                };
+
+                let (inputs, outputs) = args.evaluate("summary.json")?;
+                Processor::new(Bias::Current)?
+                    .read_with(move |r, _| {
+                        let archive = match r {
+                            Reader::Stdin(buf) => Archive::from_vec(buf.into_inner()),
+                            Reader::File(_, f) => Archive::mmap(f.into_inner()),
+                        }?;
+
+                        Summarizer::new(types.clone())?.summarize(&archive)
+                    })
+                    .write_with(helpers::write_as_json)
+                    .process(inputs, outputs)
+            }
+        }
+    }
+}
@@ -4,6 +4,8 @@
     unsafe_op_in_unsafe_fn
 )]
 
+use std::time::Instant;
+
 use clap::Parser;
 
 mod cli;
@@ -21,7 +23,13 @@ fn main() -> eyre::Result<()> {
     color_eyre::install()?;
 
     let cli = Cli::parse();
-    cli.verbosity.setup();
+    cli.verbosity.setup(cli.summary);
+
+    let start = Instant::now();
+    let res = cli.command.handle();
+    if cli.summary {
+        cli::summary::print(start.elapsed());
+    }
 
-    cli.command.handle()
+    res
 }